@@ -0,0 +1,97 @@
+//! Representative inputs shared by the benchmark targets.
+//!
+//! Every generator is deterministic so numbers stay comparable between runs.
+
+#![allow(dead_code)]
+
+use serde::de::{Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+
+/// A byte buffer that is encoded as a CBOR byte string instead of an array of integers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Blob(pub Vec<u8>);
+
+impl Serialize for Blob {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Blob {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Blob, D::Error> {
+        struct BlobVisitor;
+
+        impl<'de> Visitor<'de> for BlobVisitor {
+            type Value = Blob;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt.write_str("a byte string")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Blob, E> {
+                Ok(Blob(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Blob, E> {
+                Ok(Blob(v))
+            }
+        }
+
+        deserializer.deserialize_bytes(BlobVisitor)
+    }
+}
+
+/// A small record, typical for RPC payloads and log lines.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub id: u64,
+    pub name: String,
+    pub email: String,
+    pub active: bool,
+    pub score: f64,
+    pub delta: i32,
+    pub tags: Vec<String>,
+    pub payload: Blob,
+}
+
+/// A record that borrows its strings from the input.
+#[derive(Debug, Deserialize)]
+pub struct BorrowedRecord<'a> {
+    pub id: u64,
+    pub name: &'a str,
+    pub email: &'a str,
+    pub active: bool,
+    pub score: f64,
+    pub delta: i32,
+    #[serde(borrow)]
+    pub tags: Vec<&'a str>,
+    pub payload: &'a [u8],
+}
+
+/// Builds `n` distinct records.
+pub fn records(n: usize) -> Vec<Record> {
+    (0..n)
+        .map(|i| Record {
+            id: i as u64 * 7919,
+            name: format!("user-{}", i),
+            email: format!("user-{}@example.com", i),
+            active: i % 3 != 0,
+            score: i as f64 * 1.25,
+            delta: i as i32 - 500,
+            tags: (0..i % 5).map(|t| format!("tag{}", t)).collect(),
+            payload: Blob((0..64).map(|b| (b ^ i) as u8).collect()),
+        })
+        .collect()
+}
+
+/// Returns a byte string of `len` bytes.
+pub fn large_bytes(len: usize) -> Blob {
+    Blob((0..len).map(|i| (i % 251) as u8).collect())
+}
+
+/// Returns a text string of `len` ASCII characters.
+pub fn large_text(len: usize) -> String {
+    (0..len).map(|i| (b'a' + (i % 26) as u8) as char).collect()
+}
@@ -0,0 +1,71 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde_cbor::{de, ser, Value};
+
+mod corpus;
+
+use crate::corpus::{large_bytes, large_text, records, Blob, BorrowedRecord, Record};
+
+fn typed_struct(c: &mut Criterion) {
+    let records = records(1000);
+    let named = serde_cbor::to_vec(&records).unwrap();
+    let packed = ser::to_vec_packed(&records).unwrap();
+
+    let mut group = c.benchmark_group("typed_struct");
+    group.throughput(Throughput::Bytes(named.len() as u64));
+    group.bench_function("slice/named", |b| {
+        b.iter(|| de::from_slice::<Vec<Record>>(&named).unwrap())
+    });
+    group.bench_function("slice/named_borrowed", |b| {
+        b.iter(|| de::from_slice::<Vec<BorrowedRecord<'_>>>(&named).unwrap())
+    });
+    group.bench_function("reader/named", |b| {
+        b.iter(|| de::from_reader::<Vec<Record>, _>(&named[..]).unwrap())
+    });
+    group.throughput(Throughput::Bytes(packed.len() as u64));
+    group.bench_function("slice/packed", |b| {
+        b.iter(|| de::from_slice::<Vec<Record>>(&packed).unwrap())
+    });
+    group.bench_function("reader/packed", |b| {
+        b.iter(|| de::from_reader::<Vec<Record>, _>(&packed[..]).unwrap())
+    });
+    group.finish();
+}
+
+fn value(c: &mut Criterion) {
+    let named = serde_cbor::to_vec(&records(1000)).unwrap();
+
+    let mut group = c.benchmark_group("value");
+    group.throughput(Throughput::Bytes(named.len() as u64));
+    group.bench_function("slice", |b| {
+        b.iter(|| de::from_slice::<Value>(&named).unwrap())
+    });
+    group.bench_function("reader", |b| {
+        b.iter(|| de::from_reader::<Value, _>(&named[..]).unwrap())
+    });
+    group.finish();
+}
+
+fn large_strings(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large_strings");
+    for &len in &[1024, 64 * 1024, 1024 * 1024] {
+        let bytes = serde_cbor::to_vec(&large_bytes(len)).unwrap();
+        let text = serde_cbor::to_vec(&large_text(len)).unwrap();
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::new("bytes/slice", len), &bytes, |b, input| {
+            b.iter(|| de::from_slice::<&[u8]>(input).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("bytes/reader", len), &bytes, |b, input| {
+            b.iter(|| de::from_reader::<Blob, _>(&input[..]).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("text/slice", len), &text, |b, input| {
+            b.iter(|| de::from_slice::<&str>(input).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("text/reader", len), &text, |b, input| {
+            b.iter(|| de::from_reader::<String, _>(&input[..]).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, typed_struct, value, large_strings);
+criterion_main!(benches);
@@ -0,0 +1,79 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde_cbor::{ser, Value};
+
+mod corpus;
+
+use crate::corpus::{large_bytes, large_text, records};
+
+fn typed_struct(c: &mut Criterion) {
+    let records = records(1000);
+    let len = serde_cbor::to_vec(&records).unwrap().len();
+
+    let mut group = c.benchmark_group("typed_struct");
+    group.throughput(Throughput::Bytes(len as u64));
+    group.bench_function("vec/named", |b| {
+        b.iter(|| serde_cbor::to_vec(&records).unwrap())
+    });
+    group.bench_function("vec/packed", |b| {
+        b.iter(|| ser::to_vec_packed(&records).unwrap())
+    });
+    group.bench_function("writer/named", |b| {
+        b.iter(|| {
+            let mut out = Vec::with_capacity(len);
+            serde_cbor::to_writer(&mut out, &records).unwrap();
+            out
+        })
+    });
+    group.finish();
+}
+
+fn value(c: &mut Criterion) {
+    let value = serde_cbor::value::to_value(records(1000)).unwrap();
+    let len = serde_cbor::to_vec(&value).unwrap().len();
+
+    let mut group = c.benchmark_group("value");
+    group.throughput(Throughput::Bytes(len as u64));
+    group.bench_function("vec", |b| {
+        b.iter(|| serde_cbor::to_vec::<Value>(&value).unwrap())
+    });
+    group.finish();
+}
+
+fn large_strings(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large_strings");
+    for &len in &[1024, 64 * 1024, 1024 * 1024] {
+        let bytes = large_bytes(len);
+        let text = large_text(len);
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::new("bytes", len), &bytes, |b, input| {
+            b.iter(|| serde_cbor::to_vec(input).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("text", len), &text, |b, input| {
+            b.iter(|| serde_cbor::to_vec(input).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn tiny_fields(c: &mut Criterion) {
+    // Short keys and values, where the number of calls to the writer dominates.
+    let map: std::collections::BTreeMap<String, String> = (0..1000)
+        .map(|i| (format!("k{}", i), format!("v{}", i % 10)))
+        .collect();
+    let len = serde_cbor::to_vec(&map).unwrap().len();
+
+    let mut group = c.benchmark_group("tiny_fields");
+    group.throughput(Throughput::Bytes(len as u64));
+    group.bench_function("vec", |b| b.iter(|| serde_cbor::to_vec(&map).unwrap()));
+    group.bench_function("writer", |b| {
+        b.iter(|| {
+            let mut out = std::io::BufWriter::new(Vec::with_capacity(len));
+            serde_cbor::to_writer(&mut out, &map).unwrap();
+            out
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, typed_struct, value, large_strings, tiny_fields);
+criterion_main!(benches);
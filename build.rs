@@ -0,0 +1,31 @@
+use std::env;
+use std::process::Command;
+use std::str;
+
+// `core::error::Error` is stable since Rust 1.81, and serde requires it of the error types in
+// `no_std` builds from then on. Older compilers build without the impl.
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let minor = match rustc_minor_version() {
+        Some(minor) => minor,
+        None => return,
+    };
+    if minor >= 80 {
+        println!("cargo:rustc-check-cfg=cfg(no_core_error)");
+    }
+    if minor < 81 {
+        println!("cargo:rustc-cfg=no_core_error");
+    }
+}
+
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    let version = str::from_utf8(&output.stdout).ok()?;
+    let mut pieces = version.split('.');
+    if pieces.next() != Some("rustc 1") {
+        return None;
+    }
+    pieces.next()?.parse().ok()
+}
@@ -0,0 +1,62 @@
+//! The main loop of a device that receives commands over a serial line and answers them, with
+//! the serial line simulated by slices. No allocations are made, all buffers are arrays.
+
+use serde_cbor::embedded::{self, ChunkWrite, RingBuffer};
+use serde_cbor::Serializer;
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+struct Command {
+    channel: u8,
+    duty: u16,
+}
+
+#[derive(Debug, Serialize)]
+struct Reply {
+    channel: u8,
+    accepted: bool,
+}
+
+fn main() {
+    // {"channel": 1, "duty": 512}, {"channel": 9, "duty": 70000} in the pieces the UART
+    // interrupt would receive, the second duty is too large for a `u16`
+    let received: [&[u8]; 4] = [
+        b"\xa2\x67chan",
+        b"nel\x01\x64duty\x19\x02\x00\xa2\x67channel",
+        b"\x09\x64duty\x1a\x00\x01",
+        b"\x11\x70",
+    ];
+
+    let mut rx_storage = [0; 64];
+    let mut rx = RingBuffer::new(&mut rx_storage);
+    let mut scratch = [0; 16];
+    let mut tx_chunk = [0; 8];
+    let mut tx = ChunkWrite::new(&mut tx_chunk, |chunk: &[u8]| {
+        println!("DMA transfer of {:02x?}", chunk);
+    });
+
+    for bytes in &received {
+        // what the interrupt handler does
+        rx.push(bytes);
+
+        // what the main loop does
+        loop {
+            let command = match embedded::from_ring::<Command>(&mut rx, &mut scratch) {
+                Ok(Some(command)) => command,
+                Ok(None) => break,
+                Err(e) => {
+                    println!("dropping invalid input: {}", e);
+                    rx.clear();
+                    break;
+                }
+            };
+            println!("received {:?}", command);
+            let reply = Reply {
+                channel: command.channel,
+                accepted: command.duty <= 1024,
+            };
+            serde::Serialize::serialize(&reply, &mut Serializer::new(&mut tx)).unwrap();
+            tx.flush();
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Storage for the strings of values decoded from a reader.
+//!
+//! Values decoded from a slice can borrow their strings from it, values decoded from a reader
+//! usually can't because the bytes are gone once the next ones are read. When decoding with
+//! [`from_reader_in`](../de/fn.from_reader_in.html) every string is copied into an [`Arena`]
+//! instead, so `&str` and `&[u8]` fields borrow from the arena and only the arena has to outlive
+//! the value.
+//!
+//! ```
+//! use serde_derive::Deserialize;
+//! use serde_cbor::arena::Arena;
+//!
+//! #[derive(Debug, PartialEq, Deserialize)]
+//! struct Event<'a> {
+//!     kind: &'a str,
+//!     source: &'a str,
+//! }
+//!
+//! let mut stream: &[u8] = b"\xa2\x64kind\x65login\x66source\x63ssh\
+//!                           \xa2\x64kind\x66logout\x66source\x63ssh";
+//! let mut arena = Arena::new();
+//! for expected in &["login", "logout"] {
+//!     let event: Event = serde_cbor::de::from_reader_in(&mut stream, &arena).unwrap();
+//!     assert_eq!(event.kind, *expected);
+//!     // the storage of the arena is reused for the next message
+//!     arena.reset();
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::cmp;
+use std::slice;
+
+const DEFAULT_CHUNK_SIZE: usize = 4 * 1024;
+
+/// A bump allocator for byte strings.
+///
+/// The bytes are copied into chunks that are allocated as needed and freed all at once, by
+/// [`reset`](#method.reset) or when the arena is dropped.
+#[derive(Debug)]
+pub struct Arena {
+    chunks: RefCell<Vec<Vec<u8>>>,
+    chunk_size: usize,
+}
+
+impl Arena {
+    /// Creates an arena that allocates chunks of 4 KiB.
+    pub fn new() -> Arena {
+        Arena::with_chunk_size(DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates an arena that allocates chunks of `chunk_size` bytes, or larger ones for longer
+    /// strings.
+    pub fn with_chunk_size(chunk_size: usize) -> Arena {
+        Arena {
+            chunks: RefCell::new(Vec::new()),
+            chunk_size,
+        }
+    }
+
+    /// The number of bytes stored in the arena.
+    pub fn allocated(&self) -> usize {
+        self.chunks.borrow().iter().map(Vec::len).sum()
+    }
+
+    /// Frees everything stored in the arena, keeping its largest chunk for reuse.
+    pub fn reset(&mut self) {
+        let chunks = self.chunks.get_mut();
+        if let Some(largest) = (0..chunks.len()).max_by_key(|&i| chunks[i].capacity()) {
+            let mut chunk = chunks.swap_remove(largest);
+            chunk.clear();
+            chunks.clear();
+            chunks.push(chunk);
+        }
+    }
+
+    /// Copies `bytes` into the arena.
+    pub(crate) fn alloc(&self, bytes: &[u8]) -> &[u8] {
+        if bytes.is_empty() {
+            return &[];
+        }
+        let mut chunks = self.chunks.borrow_mut();
+        let fits = chunks
+            .last()
+            .is_some_and(|chunk| chunk.capacity() - chunk.len() >= bytes.len());
+        if !fits {
+            let capacity = cmp::max(self.chunk_size, bytes.len());
+            chunks.push(Vec::with_capacity(capacity));
+        }
+        let chunk = chunks.last_mut().expect("a chunk with room for the bytes");
+        let start = chunk.len();
+        chunk.extend_from_slice(bytes);
+        // SAFETY: A chunk never grows past its capacity, so its buffer is never moved. Chunks
+        // are only dropped by `reset` and `drop`, which take `&mut self` and therefore outlive
+        // every slice returned here.
+        unsafe { slice::from_raw_parts(chunk.as_ptr().add(start), bytes.len()) }
+    }
+}
+
+impl Default for Arena {
+    fn default() -> Arena {
+        Arena::new()
+    }
+}
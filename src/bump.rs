@@ -0,0 +1,298 @@
+//! Decoding strings and sequences into `bumpalo` arenas.
+//!
+//! A service that decodes many short-lived messages spends much of its time allocating and
+//! freeing the `String`s and `Vec`s in them. Types implementing [`DeserializeIn`] use `&str`,
+//! `&[u8]` and `&[T]` instead, which are allocated in a `bumpalo::Bump` that is reset once the
+//! messages are handled. [`In`] is the `DeserializeSeed` passing the arena down, so structs can
+//! decode their fields into it as well.
+//!
+//! ```
+//! use bumpalo::Bump;
+//!
+//! let mut bump = Bump::new();
+//! // [["a", "b"], ["c"]]
+//! let input = b"\x82\x82\x61a\x61b\x81\x61c";
+//! for _ in 0..3 {
+//!     let groups: &[&[&str]] = serde_cbor::bump::from_slice(input, &bump).unwrap();
+//!     assert_eq!(groups, [&["a", "b"][..], &["c"][..]]);
+//!     bump.reset();
+//! }
+//! ```
+//!
+//! A struct implements `DeserializeIn` by decoding itself with a visitor that holds the arena:
+//!
+//! ```
+//! use std::fmt;
+//!
+//! use bumpalo::Bump;
+//! use serde::de::{self, Deserializer, MapAccess, Visitor};
+//! use serde_cbor::bump::{DeserializeIn, In};
+//!
+//! struct Event<'b> {
+//!     kind: &'b str,
+//!     tags: &'b [&'b str],
+//! }
+//!
+//! impl<'b> DeserializeIn<'b> for Event<'b> {
+//!     fn deserialize_in<'de, D>(deserializer: D, bump: &'b Bump) -> Result<Self, D::Error>
+//!     where
+//!         D: Deserializer<'de>,
+//!     {
+//!         struct EventVisitor<'b>(&'b Bump);
+//!
+//!         impl<'de, 'b> Visitor<'de> for EventVisitor<'b> {
+//!             type Value = Event<'b>;
+//!
+//!             fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+//!                 f.write_str("an event")
+//!             }
+//!
+//!             fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Event<'b>, A::Error> {
+//!                 let (mut kind, mut tags) = (None, None);
+//!                 while let Some(key) = map.next_key::<String>()? {
+//!                     match &*key {
+//!                         "kind" => kind = Some(map.next_value_seed(In::new(self.0))?),
+//!                         "tags" => tags = Some(map.next_value_seed(In::new(self.0))?),
+//!                         _ => map.next_value::<de::IgnoredAny>().map(drop)?,
+//!                     }
+//!                 }
+//!                 Ok(Event {
+//!                     kind: kind.ok_or_else(|| de::Error::missing_field("kind"))?,
+//!                     tags: tags.unwrap_or(&[]),
+//!                 })
+//!             }
+//!         }
+//!
+//!         deserializer.deserialize_map(EventVisitor(bump))
+//!     }
+//! }
+//!
+//! let bump = Bump::new();
+//! let input = b"\xa2\x64kind\x65login\x64tags\x81\x63ssh";
+//! let event: Event = serde_cbor::bump::from_slice(input, &bump).unwrap();
+//! assert_eq!((event.kind, event.tags), ("login", &["ssh"][..]));
+//! ```
+
+use std::fmt;
+use std::io;
+use std::marker::PhantomData;
+
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
+use serde::de::{self, DeserializeSeed};
+
+use crate::de::{Deserializer, IoRead, SliceRead};
+
+/// A type that can be decoded with its strings and sequences allocated in a `Bump`.
+///
+/// Implemented for `&str`, `&[u8]`, slices and `Option`s of such types and the primitive types
+/// except `u8`, which leaves `&[u8]` to byte strings. The arena never runs destructors, so the
+/// types decoded into it should not need them.
+pub trait DeserializeIn<'b>: Sized {
+    /// Decodes the value, allocating in `bump`.
+    fn deserialize_in<'de, D>(deserializer: D, bump: &'b Bump) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>;
+}
+
+/// The `DeserializeSeed` decoding a `T` into a `Bump`.
+pub struct In<'b, T> {
+    bump: &'b Bump,
+    output: PhantomData<T>,
+}
+
+impl<'b, T> In<'b, T> {
+    /// Decodes a `T` with its allocations in `bump`.
+    pub fn new(bump: &'b Bump) -> In<'b, T> {
+        In {
+            bump,
+            output: PhantomData,
+        }
+    }
+}
+
+impl<'b, T> Clone for In<'b, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'b, T> Copy for In<'b, T> {}
+
+impl<'b, T> fmt::Debug for In<'b, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("In").finish_non_exhaustive()
+    }
+}
+
+impl<'de, 'b, T> DeserializeSeed<'de> for In<'b, T>
+where
+    T: DeserializeIn<'b>,
+{
+    type Value = T;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<T, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        T::deserialize_in(deserializer, self.bump)
+    }
+}
+
+/// Decodes a value from a slice, allocating its strings and sequences in `bump`.
+pub fn from_slice<'b, T>(input: &[u8], bump: &'b Bump) -> crate::Result<T>
+where
+    T: DeserializeIn<'b>,
+{
+    let mut deserializer = Deserializer::new(SliceRead::new(input));
+    let value = In::new(bump).deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Decodes a value from a reader, allocating its strings and sequences in `bump`.
+pub fn from_reader<'b, T, R>(reader: R, bump: &'b Bump) -> crate::Result<T>
+where
+    T: DeserializeIn<'b>,
+    R: io::Read,
+{
+    let mut deserializer = Deserializer::new(IoRead::new(reader));
+    let value = In::new(bump).deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+impl<'b> DeserializeIn<'b> for &'b str {
+    fn deserialize_in<'de, D>(deserializer: D, bump: &'b Bump) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct StrVisitor<'b>(&'b Bump);
+
+        impl<'de, 'b> de::Visitor<'de> for StrVisitor<'b> {
+            type Value = &'b str;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a string")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<&'b str, E> {
+                Ok(self.0.alloc_str(v))
+            }
+        }
+
+        deserializer.deserialize_str(StrVisitor(bump))
+    }
+}
+
+impl<'b> DeserializeIn<'b> for &'b [u8] {
+    fn deserialize_in<'de, D>(deserializer: D, bump: &'b Bump) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct BytesVisitor<'b>(&'b Bump);
+
+        impl<'de, 'b> de::Visitor<'de> for BytesVisitor<'b> {
+            type Value = &'b [u8];
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a byte string")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<&'b [u8], E> {
+                Ok(self.0.alloc_slice_copy(v))
+            }
+        }
+
+        deserializer.deserialize_bytes(BytesVisitor(bump))
+    }
+}
+
+impl<'b, T> DeserializeIn<'b> for &'b [T]
+where
+    T: DeserializeIn<'b> + 'b,
+{
+    fn deserialize_in<'de, D>(deserializer: D, bump: &'b Bump) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct SliceVisitor<'b, T>(&'b Bump, PhantomData<T>);
+
+        impl<'de, 'b, T> de::Visitor<'de> for SliceVisitor<'b, T>
+        where
+            T: DeserializeIn<'b> + 'b,
+        {
+            type Value = &'b [T];
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an array")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<&'b [T], A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                let mut vec = BumpVec::with_capacity_in(seq.size_hint().unwrap_or(0), self.0);
+                while let Some(element) = seq.next_element_seed(In::new(self.0))? {
+                    vec.push(element);
+                }
+                Ok(vec.into_bump_slice())
+            }
+        }
+
+        deserializer.deserialize_seq(SliceVisitor(bump, PhantomData))
+    }
+}
+
+impl<'b, T> DeserializeIn<'b> for Option<T>
+where
+    T: DeserializeIn<'b>,
+{
+    fn deserialize_in<'de, D>(deserializer: D, bump: &'b Bump) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct OptionVisitor<'b, T>(&'b Bump, PhantomData<T>);
+
+        impl<'de, 'b, T> de::Visitor<'de> for OptionVisitor<'b, T>
+        where
+            T: DeserializeIn<'b>,
+        {
+            type Value = Option<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an option")
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Option<T>, E> {
+                Ok(None)
+            }
+
+            fn visit_some<D>(self, deserializer: D) -> Result<Option<T>, D::Error>
+            where
+                D: de::Deserializer<'de>,
+            {
+                T::deserialize_in(deserializer, self.0).map(Some)
+            }
+        }
+
+        deserializer.deserialize_option(OptionVisitor(bump, PhantomData))
+    }
+}
+
+macro_rules! impl_primitive {
+    ($($ty:ty)*) => {
+        $(
+            impl<'b> DeserializeIn<'b> for $ty {
+                fn deserialize_in<'de, D>(deserializer: D, _bump: &'b Bump) -> Result<Self, D::Error>
+                where
+                    D: de::Deserializer<'de>,
+                {
+                    de::Deserialize::deserialize(deserializer)
+                }
+            }
+        )*
+    };
+}
+
+impl_primitive!(bool char u16 u32 u64 u128 i8 i16 i32 i64 i128 f32 f64);
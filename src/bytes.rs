@@ -0,0 +1,181 @@
+//! Integration with the `bytes` crate.
+//!
+//! `BytesMut` implements the [`Write`](../ser/trait.Write.html) trait, so values can be encoded
+//! directly into the buffers of a network stack. When decoding with
+//! [`from_bytes`](../de/fn.from_bytes.html), fields using the [`deserialize`](fn.deserialize.html)
+//! function of this module or of type [`SharedBytes`](struct.SharedBytes.html) are slices of the
+//! input that share its reference count instead of copies.
+//!
+//! ```
+//! use bytes::{Bytes, BytesMut};
+//! use serde_derive::{Deserialize, Serialize};
+//! use serde_cbor::Serializer;
+//!
+//! #[derive(Debug, PartialEq, Deserialize, Serialize)]
+//! struct Packet {
+//!     id: u32,
+//!     #[serde(with = "serde_cbor::bytes")]
+//!     payload: Bytes,
+//! }
+//!
+//! let mut buf = BytesMut::new();
+//! let packet = Packet { id: 1, payload: Bytes::from_static(b"data") };
+//! serde::Serialize::serialize(&packet, &mut Serializer::new(&mut buf)).unwrap();
+//!
+//! let input = buf.freeze();
+//! let decoded: Packet = serde_cbor::de::from_bytes(input.clone()).unwrap();
+//! assert_eq!(decoded, packet);
+//! // The payload points into the input.
+//! assert!(input.as_ptr_range().contains(&decoded.payload.as_ptr()));
+//! ```
+
+use std::cell::RefCell;
+use std::fmt;
+use std::ops::Deref;
+
+use ::bytes::Bytes;
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+thread_local!(static SOURCE: RefCell<Option<Bytes>> = const { RefCell::new(None) });
+
+/// Makes `source` available to `deserialize` while `f` runs.
+pub(crate) fn with_source<T, F>(source: Bytes, f: F) -> T
+where
+    F: FnOnce(&[u8]) -> T,
+{
+    let previous = SOURCE.with(|s| s.replace(Some(source.clone())));
+    let result = f(&source);
+    SOURCE.with(|s| *s.borrow_mut() = previous);
+    result
+}
+
+/// Serializes `Bytes` as a byte string.
+pub fn serialize<S>(bytes: &Bytes, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_bytes(bytes)
+}
+
+/// Deserializes a byte string into `Bytes`.
+///
+/// Byte strings borrowed from the input of [`from_bytes`](../de/fn.from_bytes.html) share the
+/// input's buffer, all others are copied.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Bytes, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_bytes(BytesVisitor)
+}
+
+/// A byte string that shares the buffer it was decoded from.
+///
+/// This is an owned byte string like `serde_bytes::ByteBuf`, but decoding it with
+/// [`from_bytes`](../de/fn.from_bytes.html) does not allocate: it is a view into the input that
+/// keeps the input's buffer alive. A proxy can decode a message, inspect a few fields and forward
+/// the payloads without copying them. Fields of this type need no `#[serde(with)]` attribute.
+///
+/// ```
+/// use bytes::Bytes;
+/// use serde_derive::Deserialize;
+/// use serde_cbor::bytes::SharedBytes;
+///
+/// #[derive(Deserialize)]
+/// struct Message {
+///     route: String,
+///     payload: SharedBytes,
+/// }
+///
+/// // {"route": "a", "payload": h'0102'}
+/// let input = Bytes::from_static(b"\xa2\x65route\x61a\x67payload\x42\x01\x02");
+/// let message: Message = serde_cbor::de::from_bytes(input.clone()).unwrap();
+/// assert_eq!(message.payload, [1, 2][..]);
+/// assert_eq!(message.payload.as_ptr(), input[input.len() - 2..].as_ptr());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SharedBytes(Bytes);
+
+impl SharedBytes {
+    /// Returns the underlying `Bytes`.
+    pub fn into_bytes(self) -> Bytes {
+        self.0
+    }
+}
+
+impl Deref for SharedBytes {
+    type Target = Bytes;
+
+    fn deref(&self) -> &Bytes {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Bytes> for SharedBytes {
+    fn from(bytes: Bytes) -> SharedBytes {
+        SharedBytes(bytes)
+    }
+}
+
+impl From<SharedBytes> for Bytes {
+    fn from(bytes: SharedBytes) -> Bytes {
+        bytes.0
+    }
+}
+
+impl PartialEq<[u8]> for SharedBytes {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.0 == other
+    }
+}
+
+impl Serialize for SharedBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for SharedBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SharedBytes, D::Error> {
+        deserialize(deserializer).map(SharedBytes)
+    }
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Bytes;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a byte string")
+    }
+
+    fn visit_borrowed_bytes<E: de::Error>(self, value: &'de [u8]) -> Result<Bytes, E> {
+        let shared = SOURCE.with(|s| match *s.borrow() {
+            Some(ref source) if contains(source, value) => Some(source.slice_ref(value)),
+            _ => None,
+        });
+        Ok(shared.unwrap_or_else(|| Bytes::copy_from_slice(value)))
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Bytes, E> {
+        Ok(Bytes::copy_from_slice(value))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Bytes, E> {
+        Ok(Bytes::from(value))
+    }
+}
+
+/// Whether `slice` lies within the buffer of `source`.
+fn contains(source: &Bytes, slice: &[u8]) -> bool {
+    let source = source.as_ptr_range();
+    let slice = slice.as_ptr_range();
+    source.start <= slice.start && slice.end <= source.end
+}
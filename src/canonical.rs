@@ -0,0 +1,264 @@
+//! Re-encode items in canonical form without decoding them.
+//!
+//! A signature over a CBOR item can only be checked if the signer and the verifier agree on its
+//! bytes. [`canonicalize`] rewrites any well-formed item into the form that
+//! [`to_vec_canonical`](../ser/fn.to_vec_canonical.html) writes:
+//!
+//! - integers, lengths and tag numbers with their shortest argument,
+//! - floats in the shortest width that holds them exactly, and every `NaN` as `0xf97e00`,
+//! - strings, arrays and maps of indefinite length with a definite length, strings joined from
+//!   their chunks,
+//! - the entries of maps sorted by their canonical keys, shorter keys first.
+//!
+//! The item is walked by its headers like in the [`document`](../document/index.html) module, so
+//! no Rust values or `Value`s are built and unknown tags and simple values are kept. Only the
+//! entries of maps are buffered while their keys are sorted.
+//!
+//! ```
+//! // {_ "bb": 1, "a": [_ 2]} with 1 written in three bytes
+//! let input = b"\xbf\x62bb\x19\x00\x01\x61a\x9f\x02\xff\xff";
+//! let output = serde_cbor::canonical::canonicalize(input).unwrap();
+//! // {"a": [2], "bb": 1}
+//! assert_eq!(output, b"\xa2\x61a\x81\x02\x62bb\x01");
+//! ```
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
+use core::str;
+
+use half::f16;
+
+use crate::de::{ValidAll, RECURSION_LIMIT};
+use crate::document::{header_at, item_end, Entries};
+use crate::error::{Error, ErrorCode, ExpectedSet, Result};
+use crate::header::{Header, MajorType};
+use crate::ser::{BufferedMap, KeyOrder};
+
+/// Re-encodes the item in `input` in canonical form, with map keys in `KeyOrder::LengthFirst`
+/// order as in the canonical encoding of RFC 7049.
+///
+/// Fails if `input` is not a single well-formed item, if a text string is not valid UTF-8 or if
+/// a map has two keys that are the same after re-encoding them.
+pub fn canonicalize(input: &[u8]) -> Result<Vec<u8>> {
+    canonicalize_with_order(input, KeyOrder::LengthFirst)
+}
+
+/// Re-encodes the item in `input` in canonical form with map keys in the given order.
+///
+/// `KeyOrder::Bytewise` gives the deterministic encoding of RFC 8949.
+///
+/// ```
+/// use serde_cbor::canonical::canonicalize_with_order;
+/// use serde_cbor::ser::KeyOrder;
+///
+/// // {"a": 1, 256: 2}
+/// let input = b"\xa2\x61a\x01\x19\x01\x00\x02";
+/// let output = canonicalize_with_order(input, KeyOrder::Bytewise).unwrap();
+/// assert_eq!(output, b"\xa2\x19\x01\x00\x02\x61a\x01");
+/// ```
+pub fn canonicalize_with_order(input: &[u8], order: KeyOrder) -> Result<Vec<u8>> {
+    let canonicalizer = Canonicalizer { input, order };
+    let mut out = Vec::with_capacity(input.len());
+    let end = canonicalizer.item(0, RECURSION_LIMIT, &mut out)?;
+    if end < input.len() {
+        return Err(Error::syntax(ErrorCode::TrailingData, end as u64));
+    }
+    Ok(out)
+}
+
+struct Canonicalizer<'a> {
+    input: &'a [u8],
+    order: KeyOrder,
+}
+
+impl Canonicalizer<'_> {
+    /// Writes the item starting at `start` to `out` and returns its end.
+    fn item(&self, start: usize, depth: u8, out: &mut Vec<u8>) -> Result<usize> {
+        if depth == 0 {
+            return Err(Error::syntax(
+                ErrorCode::RecursionLimitExceeded,
+                start as u64,
+            ));
+        }
+        let header = header_at(self.input, start)?;
+        let major = self.input[start] >> 5;
+        let pos = start + header.size;
+        match (header.major, header.argument) {
+            (MajorType::Unsigned, Some(value)) | (MajorType::Negative, Some(value)) => {
+                write_header(out, major, value);
+                Ok(pos)
+            }
+            (MajorType::Tag, Some(tag)) => {
+                write_header(out, major, tag);
+                self.item(pos, depth - 1, out)
+            }
+            (MajorType::Bytes, _) | (MajorType::Text, _) => self.string(start, header, out),
+            (MajorType::Array, len) => self.array(pos, len, depth, out),
+            (MajorType::Map, len) => self.map(start, pos, len, depth, out),
+            (MajorType::Simple, _) => self.simple(start, header, out),
+            (_, None) => unreachable!("integers and tags always have an argument"),
+        }
+    }
+
+    fn string(&self, start: usize, header: Header, out: &mut Vec<u8>) -> Result<usize> {
+        let mut len = 0;
+        self.chunks(start, header, |offset, chunk| {
+            if header.major == MajorType::Text {
+                if let Err(e) = str::from_utf8(chunk) {
+                    let offset = offset + e.valid_up_to();
+                    return Err(Error::syntax(ErrorCode::InvalidUtf8, offset as u64));
+                }
+            }
+            len += chunk.len() as u64;
+            Ok(())
+        })?;
+        write_header(out, self.input[start] >> 5, len);
+        self.chunks(start, header, |_, chunk| {
+            out.extend_from_slice(chunk);
+            Ok(())
+        })
+    }
+
+    /// Calls `f` with the offset and the contents of every chunk of the string starting at
+    /// `start`, and returns its end.
+    fn chunks<F>(&self, start: usize, header: Header, mut f: F) -> Result<usize>
+    where
+        F: FnMut(usize, &[u8]) -> Result<()>,
+    {
+        let end = item_end(self.input, start, 2)?;
+        let mut pos = start + header.size;
+        if header.argument.is_some() {
+            f(pos, &self.input[pos..end])?;
+            return Ok(end);
+        }
+        // the chunks are checked by `item_end`, the break code is at `end - 1`
+        while pos < end - 1 {
+            let chunk = header_at(self.input, pos)?;
+            let chunk_end = item_end(self.input, pos, 1)?;
+            f(pos + chunk.size, &self.input[pos + chunk.size..chunk_end])?;
+            pos = chunk_end;
+        }
+        Ok(end)
+    }
+
+    fn array(&self, pos: usize, len: Option<u64>, depth: u8, out: &mut Vec<u8>) -> Result<usize> {
+        let count = match len {
+            Some(len) => len,
+            None => {
+                // the header comes first, so the elements are counted before they are written
+                let mut entries = Entries {
+                    input: self.input,
+                    pos,
+                    remaining: None,
+                };
+                let mut count = 0;
+                while let Some(entry) = entries.next()? {
+                    entries.pos = item_end(self.input, entry, depth - 1)?;
+                    count += 1;
+                }
+                count
+            }
+        };
+        write_header(out, 4, count);
+        let mut entries = Entries {
+            input: self.input,
+            pos,
+            remaining: len,
+        };
+        while let Some(entry) = entries.next()? {
+            entries.pos = self.item(entry, depth - 1, out)?;
+        }
+        Ok(entries.pos + usize::from(len.is_none()))
+    }
+
+    fn map(
+        &self,
+        start: usize,
+        pos: usize,
+        len: Option<u64>,
+        depth: u8,
+        out: &mut Vec<u8>,
+    ) -> Result<usize> {
+        let mut map = BufferedMap::default();
+        let mut entries = Entries {
+            input: self.input,
+            pos,
+            remaining: len.map(|len| len.saturating_mul(2)),
+        };
+        while let Some(key) = entries.next()? {
+            let entry_start = map.buf.len();
+            entries.pos = self.item(key, depth - 1, &mut map.buf)?;
+            map.entries.push((entry_start, map.buf.len()));
+            let value = entries.next()?.ok_or_else(|| {
+                Error::syntax(
+                    ErrorCode::UnexpectedCode(ExpectedSet::from_valid::<ValidAll>(), 0xff),
+                    entries.pos as u64,
+                )
+            })?;
+            entries.pos = self.item(value, depth - 1, &mut map.buf)?;
+        }
+        let sorted = map.entries(Some(self.order));
+        if sorted.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(Error::syntax(ErrorCode::DuplicateKey, start as u64));
+        }
+        write_header(out, 5, sorted.len() as u64);
+        for (_, entry) in sorted {
+            out.extend_from_slice(entry);
+        }
+        Ok(entries.pos + usize::from(len.is_none()))
+    }
+
+    fn simple(&self, start: usize, header: Header, out: &mut Vec<u8>) -> Result<usize> {
+        let unexpected = |byte| {
+            Error::syntax(
+                ErrorCode::UnexpectedCode(ExpectedSet::from_valid::<ValidAll>(), byte),
+                start as u64,
+            )
+        };
+        match (header.info, header.argument) {
+            (0..=23, _) => out.push(self.input[start]),
+            (24, Some(value)) if value >= 32 => out.extend_from_slice(&[0xf8, value as u8]),
+            (25, Some(bits)) => write_float(out, f16::from_bits(bits as u16).to_f64()),
+            (26, Some(bits)) => write_float(out, f32::from_bits(bits as u32).into()),
+            (27, Some(bits)) => write_float(out, f64::from_bits(bits)),
+            _ => return Err(unexpected(self.input[start])),
+        }
+        Ok(start + header.size)
+    }
+}
+
+/// Writes a header with the shortest argument holding `value`.
+fn write_header(out: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    if value <= 0x17 {
+        out.push(major | value as u8);
+    } else if value <= u64::from(u8::MAX) {
+        out.extend_from_slice(&[major | 24, value as u8]);
+    } else if value <= u64::from(u16::MAX) {
+        out.push(major | 25);
+        out.extend_from_slice(&(value as u16).to_be_bytes());
+    } else if value <= u64::from(u32::MAX) {
+        out.push(major | 26);
+        out.extend_from_slice(&(value as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+/// Writes `value` in the shortest width that holds it exactly.
+#[allow(clippy::float_cmp)]
+fn write_float(out: &mut Vec<u8>, value: f64) {
+    if value.is_nan() {
+        out.extend_from_slice(&[0xf9, 0x7e, 0x00]);
+    } else if f16::from_f64(value).to_f64() == value {
+        out.push(0xf9);
+        out.extend_from_slice(&f16::from_f64(value).to_bits().to_be_bytes());
+    } else if f64::from(value as f32) == value {
+        out.push(0xfa);
+        out.extend_from_slice(&(value as f32).to_bits().to_be_bytes());
+    } else {
+        out.push(0xfb);
+        out.extend_from_slice(&value.to_bits().to_be_bytes());
+    }
+}
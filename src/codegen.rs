@@ -0,0 +1,235 @@
+//! Generate Rust key tables for integer-keyed structs from a schema.
+//!
+//! Protocols with many integer-keyed maps are easier to maintain when the keys are written down
+//! once. [`key_tables`] turns a schema into a Rust module for each struct, holding a constant per
+//! key and a `KEY_RENAMES` table for
+//! [`CustomDeserializerOptions::set_key_renames`](../de/struct.CustomDeserializerOptions.html#method.set_key_renames).
+//! It is meant to be called from a build script:
+//!
+//! ```no_run
+//! // build.rs
+//! let schema = std::fs::read_to_string("schema.txt").unwrap();
+//! let code = serde_cbor::codegen::key_tables(&schema).unwrap();
+//! let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("keys.rs");
+//! std::fs::write(out, code).unwrap();
+//! ```
+//!
+//! The generated code is then included with
+//! `include!(concat!(env!("OUT_DIR"), "/keys.rs"));`.
+//!
+//! # Schema
+//!
+//! A schema lists structs in a syntax borrowed from CDDL. Every struct has a name and its fields,
+//! each with the unsigned integer key it has on the wire. Entries are separated by commas or line
+//! breaks and comments start with `;`.
+//!
+//! ```text
+//! ; a sensor reading
+//! Reading = {
+//!     temperature: 1,
+//!     humidity: 2,
+//! }
+//! ```
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use crate::error::{Error, Result};
+
+/// Generates the key tables for the structs in `schema`.
+///
+/// See the [module documentation](index.html) for the schema syntax. Fails with an error naming
+/// the line if the schema is malformed, or a struct uses a field name or key twice.
+///
+/// ```
+/// let code = serde_cbor::codegen::key_tables("Reading = { temperature: 1 }").unwrap();
+/// assert!(code.contains("pub const TEMPERATURE: u64 = 1;"));
+/// assert!(code.contains("(serde_cbor::de::WireKey::Integer(1), \"temperature\"),"));
+/// ```
+pub fn key_tables(schema: &str) -> Result<String> {
+    let structs = Parser::new(schema).structs()?;
+    let mut code = String::from("// Generated by serde_cbor::codegen::key_tables, do not edit.\n");
+    for (name, fields) in &structs {
+        generate(&mut code, name, fields).expect("writing to a string");
+    }
+    Ok(code)
+}
+
+/// A struct and its fields with their keys, in the order of the schema.
+type Struct<'s> = (&'s str, Vec<(&'s str, u64)>);
+
+fn generate(code: &mut String, name: &str, fields: &[(&str, u64)]) -> std::fmt::Result {
+    writeln!(code)?;
+    writeln!(code, "/// The packed keys of `{}`.", name)?;
+    writeln!(code, "#[allow(dead_code)]")?;
+    writeln!(code, "pub mod {} {{", snake_case(name))?;
+    for (field, key) in fields {
+        writeln!(code, "    /// The key of `{}`.", field)?;
+        writeln!(
+            code,
+            "    pub const {}: u64 = {};",
+            field.to_ascii_uppercase(),
+            key
+        )?;
+    }
+    writeln!(code)?;
+    writeln!(
+        code,
+        "    /// Maps the keys of `{}` to its field names.",
+        name
+    )?;
+    writeln!(
+        code,
+        "    pub const KEY_RENAMES: &[(serde_cbor::de::WireKey<'static>, &str)] = &["
+    )?;
+    for (field, key) in fields {
+        writeln!(
+            code,
+            "        (serde_cbor::de::WireKey::Integer({}), \"{}\"),",
+            key, field
+        )?;
+    }
+    writeln!(code, "    ];")?;
+    writeln!(code, "}}")
+}
+
+/// Converts a `CamelCase` struct name into a `snake_case` module name.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.char_indices() {
+        if c.is_ascii_uppercase() {
+            if i > 0 && !name[..i].ends_with('_') {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// Splits a schema into tokens, tracking the line for error messages.
+struct Parser<'s> {
+    rest: &'s str,
+    line: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn new(schema: &'s str) -> Parser<'s> {
+        Parser {
+            rest: schema,
+            line: 1,
+        }
+    }
+
+    fn error(&self, msg: std::fmt::Arguments<'_>) -> Error {
+        Error::message(format_args!("schema line {}: {}", self.line, msg))
+    }
+
+    /// Skips whitespace, commas, line breaks and comments.
+    fn skip(&mut self) {
+        loop {
+            let trimmed = self
+                .rest
+                .trim_start_matches(|c: char| c.is_whitespace() && c != '\n' || c == ',');
+            self.rest = trimmed;
+            if let Some(rest) = self.rest.strip_prefix('\n') {
+                self.line += 1;
+                self.rest = rest;
+            } else if self.rest.starts_with(';') {
+                let end = self.rest.find('\n').unwrap_or(self.rest.len());
+                self.rest = &self.rest[end..];
+            } else {
+                return;
+            }
+        }
+    }
+
+    /// Reads the next token: an identifier, a number or a single punctuation character.
+    fn token(&mut self) -> Option<&'s str> {
+        self.skip();
+        let len = match self.rest.chars().next()? {
+            c if c.is_ascii_alphanumeric() || c == '_' => self
+                .rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(self.rest.len()),
+            c => c.len_utf8(),
+        };
+        let (token, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Some(token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<()> {
+        match self.token() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => {
+                Err(self.error(format_args!("expected `{}`, found `{}`", expected, token)))
+            }
+            None => Err(self.error(format_args!("expected `{}`, found the end", expected))),
+        }
+    }
+
+    fn identifier(&mut self, what: &str) -> Result<&'s str> {
+        match self.token() {
+            Some(token) if token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') => {
+                Ok(token)
+            }
+            Some(token) => Err(self.error(format_args!("expected {}, found `{}`", what, token))),
+            None => Err(self.error(format_args!("expected {}, found the end", what))),
+        }
+    }
+
+    fn structs(&mut self) -> Result<Vec<Struct<'s>>> {
+        let mut structs: Vec<Struct<'s>> = Vec::new();
+        loop {
+            self.skip();
+            if self.rest.is_empty() {
+                return Ok(structs);
+            }
+            let name = self.identifier("a struct name")?;
+            if structs.iter().any(|(other, _)| *other == name) {
+                return Err(self.error(format_args!("struct `{}` is defined twice", name)));
+            }
+            self.expect("=")?;
+            self.expect("{")?;
+            let fields = self.fields(name)?;
+            structs.push((name, fields));
+        }
+    }
+
+    fn fields(&mut self, name: &str) -> Result<Vec<(&'s str, u64)>> {
+        let mut fields = Vec::new();
+        let mut names = BTreeSet::new();
+        let mut keys = BTreeSet::new();
+        loop {
+            self.skip();
+            if self.rest.starts_with('}') {
+                self.token();
+                return Ok(fields);
+            }
+            let field = self.identifier("a field name or `}`")?;
+            self.expect(":")?;
+            let key = match self.token().map(str::parse::<u64>) {
+                Some(Ok(key)) => key,
+                _ => {
+                    return Err(self.error(format_args!(
+                        "expected an unsigned integer key for `{}`",
+                        field
+                    )))
+                }
+            };
+            if !names.insert(field.to_ascii_uppercase()) {
+                return Err(self.error(format_args!(
+                    "field `{}` of `{}` is defined twice",
+                    field, name
+                )));
+            }
+            if !keys.insert(key) {
+                return Err(self.error(format_args!("key {} of `{}` is used twice", key, name)));
+            }
+            fields.push((field, key));
+        }
+    }
+}
@@ -0,0 +1,323 @@
+//! Conformance test vectors and a runner for them.
+//!
+//! [`APPENDIX_A`](constant.APPENDIX_A.html) holds the examples of RFC 8949 Appendix A and
+//! [`CANONICAL`](constant.CANONICAL.html) inputs together with their preferred, deterministic
+//! encoding. [`run`](fn.run.html) checks them against the default encoder and decoder,
+//! [`run_with`](fn.run_with.html) against any combination of options.
+//!
+//! ```
+//! let failures = serde_cbor::conformance::run();
+//! assert!(failures.is_empty(), "{:?}", failures);
+//! ```
+//!
+//! Decoded values are compared through their diagnostic notation as produced by
+//! [`diagnostic`](fn.diagnostic.html). A `Value` does not keep whether an item had an indefinite
+//! length, so the `_` markers of the RFC are left out. The examples with the simple values
+//! `undefined`, `simple(16)` and `simple(255)` are not included, they cannot be represented by a
+//! `Value`.
+use std::fmt::Write;
+
+use crate::error::Result;
+use crate::tags::ExpectedEncoding;
+use crate::value::Value;
+
+/// An encoded data item and what it decodes to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Vector {
+    /// The encoded item in hex.
+    pub hex: &'static str,
+    /// The decoded item in diagnostic notation.
+    pub diagnostic: &'static str,
+    /// Whether encoding the decoded item gives back `hex`.
+    ///
+    /// This is not the case for indefinite length items and for floats that are not written with
+    /// their shortest exact encoding.
+    pub roundtrip: bool,
+}
+
+/// An encoded data item and its deterministic encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CanonicalVector {
+    /// The encoded item in hex.
+    pub input: &'static str,
+    /// The item encoded with preferred serialization and sorted map keys, in hex.
+    pub canonical: &'static str,
+}
+
+/// A test vector that was not handled correctly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Failure {
+    /// The encoded item of the vector in hex.
+    pub input: &'static str,
+    /// What went wrong.
+    pub message: String,
+}
+
+macro_rules! vectors {
+    ($($hex:expr, $diagnostic:expr, $roundtrip:expr;)*) => {
+        &[$(Vector { hex: $hex, diagnostic: $diagnostic, roundtrip: $roundtrip }),*]
+    };
+}
+
+macro_rules! canonical_vectors {
+    ($($input:expr, $canonical:expr;)*) => {
+        &[$(CanonicalVector { input: $input, canonical: $canonical }),*]
+    };
+}
+
+/// The examples of RFC 8949 Appendix A.
+pub const APPENDIX_A: &[Vector] = vectors![
+    "00", "0", true;
+    "01", "1", true;
+    "0a", "10", true;
+    "17", "23", true;
+    "1818", "24", true;
+    "1819", "25", true;
+    "1864", "100", true;
+    "1903e8", "1000", true;
+    "1a000f4240", "1000000", true;
+    "1b000000e8d4a51000", "1000000000000", true;
+    "1bffffffffffffffff", "18446744073709551615", true;
+    "c249010000000000000000", "2(h'010000000000000000')", true;
+    "3bffffffffffffffff", "-18446744073709551616", true;
+    "c349010000000000000000", "3(h'010000000000000000')", true;
+    "20", "-1", true;
+    "29", "-10", true;
+    "3863", "-100", true;
+    "3903e7", "-1000", true;
+    "f90000", "0.0", true;
+    "f98000", "-0.0", true;
+    "f93c00", "1.0", true;
+    "fb3ff199999999999a", "1.1", true;
+    "f93e00", "1.5", true;
+    "f97bff", "65504.0", true;
+    "fa47c35000", "100000.0", true;
+    "fa7f7fffff", "3.4028234663852886e38", true;
+    "fb7e37e43c8800759c", "1e300", true;
+    "f90001", "5.960464477539063e-8", true;
+    "f90400", "6.103515625e-5", true;
+    "f9c400", "-4.0", true;
+    "fbc010666666666666", "-4.1", true;
+    "f97c00", "Infinity", true;
+    "f97e00", "NaN", true;
+    "f9fc00", "-Infinity", true;
+    "fa7f800000", "Infinity", false;
+    "fa7fc00000", "NaN", false;
+    "faff800000", "-Infinity", false;
+    "fb7ff0000000000000", "Infinity", false;
+    "fb7ff8000000000000", "NaN", false;
+    "fbfff0000000000000", "-Infinity", false;
+    "f4", "false", true;
+    "f5", "true", true;
+    "f6", "null", true;
+    "c074323031332d30332d32315432303a30343a30305a", "0(\"2013-03-21T20:04:00Z\")", true;
+    "c11a514b67b0", "1(1363896240)", true;
+    "c1fb41d452d9ec200000", "1(1363896240.5)", true;
+    "d74401020304", "23(h'01020304')", true;
+    "d818456449455446", "24(h'6449455446')", true;
+    "d82076687474703a2f2f7777772e6578616d706c652e636f6d", "32(\"http://www.example.com\")", true;
+    "40", "h''", true;
+    "4401020304", "h'01020304'", true;
+    "60", "\"\"", true;
+    "6161", "\"a\"", true;
+    "6449455446", "\"IETF\"", true;
+    "62225c", "\"\\\"\\\\\"", true;
+    "62c3bc", "\"\u{fc}\"", true;
+    "63e6b0b4", "\"\u{6c34}\"", true;
+    "64f0908591", "\"\u{10151}\"", true;
+    "80", "[]", true;
+    "83010203", "[1, 2, 3]", true;
+    "8301820203820405", "[1, [2, 3], [4, 5]]", true;
+    "98190102030405060708090a0b0c0d0e0f101112131415161718181819",
+    "[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25]",
+    true;
+    "a0", "{}", true;
+    "a201020304", "{1: 2, 3: 4}", true;
+    "a26161016162820203", "{\"a\": 1, \"b\": [2, 3]}", true;
+    "826161a161626163", "[\"a\", {\"b\": \"c\"}]", true;
+    "a56161614161626142616361436164614461656145",
+    "{\"a\": \"A\", \"b\": \"B\", \"c\": \"C\", \"d\": \"D\", \"e\": \"E\"}", true;
+    "5f42010243030405ff", "h'0102030405'", false;
+    "7f657374726561646d696e67ff", "\"streaming\"", false;
+    "9fff", "[]", false;
+    "9f018202039f0405ffff", "[1, [2, 3], [4, 5]]", false;
+    "9f01820203820405ff", "[1, [2, 3], [4, 5]]", false;
+    "83018202039f0405ff", "[1, [2, 3], [4, 5]]", false;
+    "83019f0203ff820405", "[1, [2, 3], [4, 5]]", false;
+    "9f0102030405060708090a0b0c0d0e0f101112131415161718181819ff",
+    "[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25]",
+    false;
+    "bf61610161629f0203ffff", "{\"a\": 1, \"b\": [2, 3]}", false;
+    "826161bf61626163ff", "[\"a\", {\"b\": \"c\"}]", false;
+    "bf6346756ef563416d7421ff", "{\"Amt\": -2, \"Fun\": true}", false;
+];
+
+/// Items in non-preferred encodings and with unsorted maps, with their deterministic encoding.
+pub const CANONICAL: &[CanonicalVector] = canonical_vectors![
+    "1817", "17";
+    "190018", "1818";
+    "1a00000100", "190100";
+    "1b0000000100000000", "1b0000000100000000";
+    "3800", "20";
+    "fa3fc00000", "f93e00";
+    "fb3ff8000000000000", "f93e00";
+    "fb7ff8000000000000", "f97e00";
+    "5800", "40";
+    "5f41014102ff", "420102";
+    "9f01ff", "8101";
+    "d9000100", "c100";
+    "a2616201616102", "a2616102616201";
+    "a8f4018120026261610381186404617a0520060a07186408", "a80a071864082006617a056261610381186404812002f401";
+];
+
+/// Checks all test vectors against the default encoder and decoder.
+pub fn run() -> Vec<Failure> {
+    run_with(|input| crate::from_slice(input), crate::to_vec)
+}
+
+/// Checks all test vectors with the given functions for decoding and encoding.
+///
+/// Every vector is decoded and compared to its diagnostic notation. Vectors of
+/// [`APPENDIX_A`](constant.APPENDIX_A.html) marked as `roundtrip` and all vectors of
+/// [`CANONICAL`](constant.CANONICAL.html) are encoded again and compared to the expected bytes.
+pub fn run_with<D, E>(mut decode: D, mut encode: E) -> Vec<Failure>
+where
+    D: FnMut(&[u8]) -> Result<Value>,
+    E: FnMut(&Value) -> Result<Vec<u8>>,
+{
+    let mut failures = Vec::new();
+    let mut check = |input: &'static str, diagnostic: Option<&str>, output: Option<&str>| {
+        let bytes = ExpectedEncoding::Base16
+            .decode(input)
+            .expect("test vectors are valid hex");
+        let value = match decode(&bytes) {
+            Ok(value) => value,
+            Err(e) => return Some(format!("decoding failed: {}", e)),
+        };
+        let actual = self::diagnostic(&value);
+        if let Some(expected) = diagnostic {
+            if actual != expected {
+                return Some(format!("decoded {}, expected {}", actual, expected));
+            }
+        }
+        if let Some(expected) = output {
+            let encoded = match encode(&value) {
+                Ok(encoded) => ExpectedEncoding::Base16.encode(&encoded),
+                Err(e) => return Some(format!("encoding {} failed: {}", actual, e)),
+            };
+            if encoded != expected {
+                return Some(format!(
+                    "encoded {} as {}, expected {}",
+                    actual, encoded, expected
+                ));
+            }
+        }
+        None
+    };
+    for vector in APPENDIX_A {
+        let output = if vector.roundtrip {
+            Some(vector.hex)
+        } else {
+            None
+        };
+        if let Some(message) = check(vector.hex, Some(vector.diagnostic), output) {
+            failures.push(Failure {
+                input: vector.hex,
+                message,
+            });
+        }
+    }
+    for vector in CANONICAL {
+        if let Some(message) = check(vector.input, None, Some(vector.canonical)) {
+            failures.push(Failure {
+                input: vector.input,
+                message,
+            });
+        }
+    }
+    failures
+}
+
+/// Writes `value` in the diagnostic notation of RFC 8949.
+///
+/// Floats are written like Rust's `Debug` does, except for `Infinity`, `-Infinity` and `NaN`.
+/// A `Value::SizedFloat` gets the encoding indicator `_1`, `_2` or `_3` of its width, a
+/// `Value::SizedInteger` the indicator `_i` or `_0` to `_3`.
+pub fn diagnostic(value: &Value) -> String {
+    let mut out = String::new();
+    write_diagnostic(&mut out, value);
+    out
+}
+
+fn write_diagnostic(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Simple(v) => {
+            let _ = write!(out, "simple({})", v);
+        }
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Integer(i) => {
+            let _ = write!(out, "{}", i);
+        }
+        Value::SizedInteger(i, width) => {
+            let indicator = ["i", "0", "1", "2", "3"][*width as usize];
+            let _ = write!(out, "{}_{}", i, indicator);
+        }
+        Value::Float(f) if f.is_nan() => out.push_str("NaN"),
+        Value::Float(f) if f.is_infinite() => {
+            out.push_str(if *f > 0.0 { "Infinity" } else { "-Infinity" })
+        }
+        Value::Float(f) => {
+            let _ = write!(out, "{:?}", f);
+        }
+        Value::SizedFloat(f, width) => {
+            write_diagnostic(out, &Value::Float(*f));
+            let _ = write!(out, "_{}", *width as u8 + 1);
+        }
+        Value::Bytes(bytes) => {
+            let _ = write!(out, "h'{}'", ExpectedEncoding::Base16.encode(bytes));
+        }
+        Value::Text(text) => {
+            out.push('"');
+            for c in text.chars() {
+                match c {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    c if c.is_control() => {
+                        let _ = write!(out, "\\u{:04x}", c as u32);
+                    }
+                    c => out.push(c),
+                }
+            }
+            out.push('"');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_diagnostic(out, item);
+            }
+            out.push(']');
+        }
+        Value::Map(entries) => {
+            out.push('{');
+            for (i, (key, value)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_diagnostic(out, key);
+                out.push_str(": ");
+                write_diagnostic(out, value);
+            }
+            out.push('}');
+        }
+        Value::Tag(tag, value) => {
+            let _ = write!(out, "{}(", tag);
+            write_diagnostic(out, value);
+            out.push(')');
+        }
+        Value::__Hidden => unreachable!(),
+    }
+}
@@ -0,0 +1,164 @@
+//! Encode CBOR at compile time.
+//!
+//! Firmware often sends the same fixed structures, like the protected header of a COSE message.
+//! [`ConstEncoder`] writes them in a `const` item, so they end up as a byte array in the binary
+//! and are never encoded at runtime.
+//!
+//! ```
+//! use serde_cbor::constant::ConstEncoder;
+//!
+//! // The COSE protected header {1: -7}, the ES256 algorithm.
+//! const PROTECTED: [u8; 3] = ConstEncoder::new().map(1).unsigned(1).int(-7).finish();
+//! assert_eq!(PROTECTED, [0xa1, 0x01, 0x26]);
+//!
+//! // ["v1", h'0102', true]
+//! const TEMPLATE: [u8; 8] = ConstEncoder::new()
+//!     .array(3)
+//!     .text("v1")
+//!     .bytes(&[1, 2])
+//!     .bool(true)
+//!     .finish();
+//! assert_eq!(&TEMPLATE, b"\x83\x62v1\x42\x01\x02\xf5");
+//! ```
+//!
+//! Arrays, maps and tags only write their header, the items following it make up their contents.
+//! The encoder does not check that the contents match the declared lengths.
+
+/// Writes CBOR items into an array of `N` bytes in `const` contexts.
+///
+/// Every method consumes the encoder and returns it with the item appended. Writing more than `N`
+/// bytes, or finishing with fewer, fails to compile when the encoder is used in a `const` item and
+/// panics otherwise.
+#[derive(Clone, Copy, Debug)]
+pub struct ConstEncoder<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> ConstEncoder<N> {
+    /// Creates an empty encoder.
+    pub const fn new() -> Self {
+        ConstEncoder {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    /// The number of bytes written so far.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if nothing was written yet.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Writes an unsigned integer.
+    pub const fn unsigned(self, value: u64) -> Self {
+        self.header(0, value)
+    }
+
+    /// Writes a signed integer.
+    pub const fn int(self, value: i64) -> Self {
+        if value >= 0 {
+            self.header(0, value as u64)
+        } else {
+            self.header(1, !value as u64)
+        }
+    }
+
+    /// Writes a byte string.
+    pub const fn bytes(self, value: &[u8]) -> Self {
+        self.header(2, value.len() as u64).raw(value)
+    }
+
+    /// Writes a text string.
+    pub const fn text(self, value: &str) -> Self {
+        self.header(3, value.len() as u64).raw(value.as_bytes())
+    }
+
+    /// Writes the header of an array of `len` items, which have to follow.
+    pub const fn array(self, len: u64) -> Self {
+        self.header(4, len)
+    }
+
+    /// Writes the header of a map of `len` entries, whose keys and values have to follow.
+    pub const fn map(self, len: u64) -> Self {
+        self.header(5, len)
+    }
+
+    /// Writes a tag, the tagged item has to follow.
+    pub const fn tag(self, tag: u64) -> Self {
+        self.header(6, tag)
+    }
+
+    /// Writes a boolean.
+    pub const fn bool(self, value: bool) -> Self {
+        self.byte(if value { 0xf5 } else { 0xf4 })
+    }
+
+    /// Writes `null`.
+    pub const fn null(self) -> Self {
+        self.byte(0xf6)
+    }
+
+    /// Writes bytes that already are encoded CBOR, like the output of another encoder.
+    pub const fn raw(mut self, bytes: &[u8]) -> Self {
+        let mut i = 0;
+        while i < bytes.len() {
+            self = self.byte(bytes[i]);
+            i += 1;
+        }
+        self
+    }
+
+    /// Returns the encoded bytes, which have to fill the array exactly.
+    pub const fn finish(self) -> [u8; N] {
+        assert!(
+            self.len == N,
+            "the encoded bytes do not fill the array of the ConstEncoder"
+        );
+        self.buf
+    }
+
+    const fn header(self, major: u8, value: u64) -> Self {
+        let major = major << 5;
+        if value <= 0x17 {
+            self.byte(major | value as u8)
+        } else if value <= 0xff {
+            self.byte(major | 24).big_endian(value, 1)
+        } else if value <= 0xffff {
+            self.byte(major | 25).big_endian(value, 2)
+        } else if value <= 0xffff_ffff {
+            self.byte(major | 26).big_endian(value, 4)
+        } else {
+            self.byte(major | 27).big_endian(value, 8)
+        }
+    }
+
+    const fn big_endian(mut self, value: u64, len: u32) -> Self {
+        let mut i = len;
+        while i > 0 {
+            i -= 1;
+            self = self.byte((value >> (8 * i)) as u8);
+        }
+        self
+    }
+
+    const fn byte(mut self, byte: u8) -> Self {
+        assert!(
+            self.len < N,
+            "the encoded bytes do not fit into the array of the ConstEncoder"
+        );
+        self.buf[self.len] = byte;
+        self.len += 1;
+        self
+    }
+}
+
+impl<const N: usize> Default for ConstEncoder<N> {
+    fn default() -> Self {
+        ConstEncoder::new()
+    }
+}
@@ -1,8 +1,14 @@
 //! Deserialization.
 
-use core::convert::TryInto;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::{string::String, vec::Vec};
+#[cfg(any(feature = "std", feature = "alloc"))]
+use core::cmp::Ordering;
+use core::convert::{TryFrom, TryInto};
 use core::f32;
+use core::fmt;
 use core::marker::PhantomData;
+use core::ops::RangeInclusive;
 use core::result;
 use core::str;
 use half::f16;
@@ -10,19 +16,25 @@ use serde::de;
 #[cfg(feature = "std")]
 use std::io;
 
+#[cfg(feature = "std")]
+use crate::arena::Arena;
 use crate::error::{Error, ErrorCode, ExpectedSet, Result};
 #[cfg(not(feature = "unsealed_read_write"))]
 use crate::read::EitherLifetime;
 #[cfg(feature = "unsealed_read_write")]
 pub use crate::read::EitherLifetime;
-#[cfg(feature = "std")]
-pub use crate::read::IoRead;
 use crate::read::Offset;
 #[cfg(any(feature = "std", feature = "alloc"))]
 pub use crate::read::SliceRead;
+#[cfg(feature = "std")]
+pub use crate::read::{ArenaRead, IoRead};
 pub use crate::read::{MutSliceRead, Read, SliceReadFixed};
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::ser::KeyOrder;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::tags::ExpectedEncoding;
 #[cfg(feature = "tags")]
-use crate::tags::set_tag;
+use crate::tags::{set_tag, MAP_TAG, SET_TAG};
 /// Decodes a value from CBOR data in a slice.
 ///
 /// # Examples
@@ -55,6 +67,64 @@ where
     Ok(value)
 }
 
+/// Decodes a value from CBOR data in a slice, attaching a dump of the input to errors.
+///
+/// Errors carry the annotated lines of the input around their offset, which are shown when the
+/// error is displayed.
+///
+/// ```
+/// # use serde_cbor::de;
+/// // [1, "a"]
+/// let err = de::from_slice_verbose::<Vec<u8>>(b"\x82\x01\x61a").unwrap_err();
+/// assert!(err.dump().unwrap().contains("> 00000003"));
+/// ```
+#[cfg(feature = "std")]
+pub fn from_slice_verbose<'a, T>(slice: &'a [u8]) -> Result<T>
+where
+    T: de::Deserialize<'a>,
+{
+    from_slice(slice).map_err(|e| e.with_dump(slice))
+}
+
+/// Decodes a value from CBOR data in a slice and reports statistics about its items.
+///
+/// The statistics are collected while decoding, so the input is only parsed once.
+///
+/// ```
+/// # use serde_cbor::de;
+/// // [1, "abc", [_ ]]
+/// let v = b"\x83\x01\x63abc\x9f\xff";
+/// let (value, stats): (serde_cbor::Value, _) = de::from_slice_with_stats(v).unwrap();
+/// assert_eq!(stats.major_types, [1, 0, 0, 1, 2, 0, 0, 0]);
+/// assert_eq!(stats.max_depth, 1);
+/// assert_eq!(stats.largest_string, 3);
+/// assert_eq!(stats.indefinite_items, 1);
+/// ```
+#[cfg(feature = "std")]
+pub fn from_slice_with_stats<'a, T>(slice: &'a [u8]) -> Result<(T, DecodeStats)>
+where
+    T: de::Deserialize<'a>,
+{
+    let mut deserializer = Deserializer::from_slice(slice);
+    deserializer.enable_stats();
+    let value = de::Deserialize::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok((value, deserializer.stats.unwrap_or_default()))
+}
+
+/// Decodes a value from CBOR data in a `Bytes` buffer.
+///
+/// Byte strings decoded with [`serde_cbor::bytes::deserialize`](../bytes/fn.deserialize.html)
+/// share the buffer of `input` instead of being copied, so the result does not borrow from the
+/// input.
+#[cfg(feature = "bytes")]
+pub fn from_bytes<T>(input: ::bytes::Bytes) -> Result<T>
+where
+    T: de::DeserializeOwned,
+{
+    crate::bytes::with_source(input, |slice| from_slice(slice))
+}
+
 // When the "std" feature is enabled there should be little to no need to ever use this function,
 // as `from_slice` covers all use cases (at the expense of being less efficient).
 /// Decode a value from CBOR data in a mutable slice.
@@ -72,6 +142,32 @@ where
     Ok(value)
 }
 
+/// Decodes a sequence of values from CBOR data in a mutable slice.
+///
+/// This is the streaming counterpart of [`from_mut_slice`](fn.from_mut_slice.html): indefinite byte
+/// and text strings in every item are reassembled in place, so all items can borrow from the slice
+/// at the same time without any allocation.
+///
+/// ```
+/// use serde_cbor::de;
+///
+/// // (_ "ab" "c"), (_ "d" "ef"), "g"
+/// let mut input = *b"\x7f\x62ab\x61c\xff\x7f\x61d\x62ef\xff\x61g";
+/// let mut words = [""; 3];
+/// for (word, item) in words.iter_mut().zip(de::from_mut_slice_iter(&mut input)) {
+///     *word = item.unwrap();
+/// }
+/// assert_eq!(words, ["abc", "def", "g"]);
+/// ```
+pub fn from_mut_slice_iter<'a, T>(
+    slice: &'a mut [u8],
+) -> StreamDeserializer<'a, MutSliceRead<'a>, T>
+where
+    T: de::Deserialize<'a>,
+{
+    Deserializer::from_mut_slice(slice).into_iter()
+}
+
 // When the "std" feature is enabled there should be little to no need to ever use this function,
 // as `from_slice` covers all use cases and is much more reliable (at the expense of being less
 // efficient).
@@ -92,6 +188,38 @@ where
     Ok(value)
 }
 
+/// Returns the size of the scratch buffer needed to decode `slice` with
+/// [`from_slice_with_scratch`](fn.from_slice_with_scratch.html).
+///
+/// Only indefinite length strings are copied into the scratch buffer, so this is the length of the
+/// longest of them. The input is checked to be a single well-formed item, but no value is built.
+///
+/// ```
+/// use serde_cbor::de;
+///
+/// // (_ "foo" "bar")
+/// let input = b"\x7f\x63foo\x63bar\xff";
+/// let mut scratch = [0; 4];
+/// let err = de::from_slice_with_scratch::<String>(input, &mut scratch).unwrap_err();
+/// assert_eq!(err.scratch_needed(), Some(6));
+/// assert_eq!(err.scratch_available(), Some(4));
+///
+/// let mut scratch = [0; 16];
+/// let needed = de::scratch_size(input).unwrap();
+/// assert_eq!(needed, 6);
+/// let value: String = de::from_slice_with_scratch(input, &mut scratch[..needed]).unwrap();
+/// assert_eq!(value, "foobar");
+/// ```
+pub fn scratch_size(slice: &[u8]) -> Result<usize> {
+    let mut deserializer = Deserializer::from_slice_with_scratch(slice, &mut []);
+    deserializer.enable_stats();
+    deserializer.skip_value()?;
+    deserializer.end()?;
+    Ok(deserializer
+        .stats()
+        .map_or(0, |stats| stats.largest_indefinite_string))
+}
+
 /// Decodes a value from CBOR data in a reader.
 ///
 /// # Examples
@@ -113,11 +241,14 @@ where
 /// let value: &str = de::from_reader(&v[..]).unwrap();
 /// assert_eq!(value, "foobar");
 /// ```
+///
+/// The reader does not have to be `Send`, readers that share state through `Rc` or `RefCell` can
+/// be used as well.
 #[cfg(feature = "std")]
 pub fn from_reader<T, R>(reader: R) -> Result<T>
 where
     T: de::DeserializeOwned,
-    R: io::Read + Send,
+    R: io::Read,
 {
     let mut deserializer = Deserializer::from_reader(reader);
     let value = de::Deserialize::deserialize(&mut deserializer)?;
@@ -125,12 +256,98 @@ where
     Ok(value)
 }
 
+/// Decodes a value from CBOR data in a reader, copying its strings into an arena.
+///
+/// Unlike `from_reader` this can decode types that borrow strings, like `&str` and `&[u8]`
+/// fields, which then point into the arena. See the [`arena` module](../arena/index.html).
+///
+/// Like `from_reader_one` no byte past the end of the value is consumed and trailing data is not
+/// checked for, so further messages can be read from the same reader, each into a reset arena.
+///
+/// ```
+/// use serde_cbor::arena::Arena;
+/// use serde_cbor::de;
+///
+/// let arena = Arena::new();
+/// let words: Vec<&str> = de::from_reader_in(&b"\x82\x63one\x7f\x62tw\x61o\xff"[..], &arena).unwrap();
+/// assert_eq!(words, ["one", "two"]);
+/// assert_eq!(arena.allocated(), 6);
+/// ```
+#[cfg(feature = "std")]
+pub fn from_reader_in<'a, T, R>(reader: R, arena: &'a Arena) -> Result<T>
+where
+    T: de::Deserialize<'a>,
+    R: io::Read,
+{
+    let mut deserializer = Deserializer::from_reader_in(reader, arena);
+    de::Deserialize::deserialize(&mut deserializer)
+}
+
+/// Decodes a single value from a reader and leaves the reader positioned right after it.
+///
+/// Unlike `from_reader` this does not check for trailing data, and no byte past the end of the
+/// value is consumed. This allows reading consecutive messages off a long-lived stream. Pass the
+/// reader by `&mut` to keep using it afterwards.
+///
+/// # Examples
+///
+/// ```
+/// # use serde_cbor::de;
+/// use std::io::Read;
+///
+/// let mut stream: &[u8] = &[0x01, 0x62, 0x68, 0x69, 0xff];
+/// let first: u8 = de::from_reader_one(&mut stream).unwrap();
+/// let second: String = de::from_reader_one(&mut stream).unwrap();
+/// assert_eq!((first, second.as_str()), (1, "hi"));
+///
+/// let mut rest = Vec::new();
+/// stream.read_to_end(&mut rest).unwrap();
+/// assert_eq!(rest, [0xff]);
+/// ```
+#[cfg(feature = "std")]
+pub fn from_reader_one<T, R>(reader: R) -> Result<T>
+where
+    T: de::DeserializeOwned,
+    R: io::Read,
+{
+    let mut deserializer = Deserializer::from_reader(reader);
+    de::Deserialize::deserialize(&mut deserializer)
+}
+
+/// The nesting depth at which decoding fails with `RecursionLimitExceeded`.
+pub(crate) const RECURSION_LIMIT: u8 = 128;
+
+/// Statistics about the items of a decoded input.
+///
+/// They are collected by a deserializer after [`enable_stats`](struct.Deserializer.html#method.enable_stats)
+/// and by [`from_slice_with_stats`](fn.from_slice_with_stats.html). Items that are skipped, like
+/// unknown struct fields, are counted as well.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecodeStats {
+    /// The number of items of each major type, indexed by the major type.
+    ///
+    /// The chunks of indefinite length strings and the break codes are not counted.
+    pub major_types: [u64; 8],
+    /// The deepest nesting of arrays, maps and tags, where top level items have depth 0.
+    pub max_depth: usize,
+    /// The length in bytes of the longest byte or text string.
+    pub largest_string: usize,
+    /// The length in bytes of the longest indefinite length byte or text string, which is the
+    /// size of the scratch buffer needed to decode them.
+    pub largest_indefinite_string: usize,
+    /// The number of indefinite length strings, arrays and maps.
+    pub indefinite_items: u64,
+}
+
 /// A Serde `Deserialize`r of CBOR data.
 #[derive(Debug)]
 pub struct Deserializer<R, O = DefaultDeserializerOptions> {
     read: R,
     remaining_depth: u8,
     options: O,
+    /// The struct the map parsed next belongs to, if any.
+    struct_format: Option<StructFormat>,
+    stats: Option<DecodeStats>,
 }
 
 #[cfg(feature = "std")]
@@ -144,6 +361,178 @@ where
     }
 }
 
+#[cfg(feature = "std")]
+impl<'a, R> Deserializer<ArenaRead<'a, R>>
+where
+    R: io::Read,
+{
+    /// Constructs a `Deserializer` which reads from a `Read`er and borrows strings from `arena`.
+    pub fn from_reader_in(reader: R, arena: &'a Arena) -> Deserializer<ArenaRead<'a, R>> {
+        Deserializer::new(ArenaRead::new(reader, arena))
+    }
+}
+
+/// The struct a map is decoded into and the field formats accepted for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct StructFormat {
+    name: &'static str,
+    fields: &'static [&'static str],
+    accept_named: bool,
+    accept_packed: bool,
+}
+
+/// Fails if `key` is not one of the fields of `known`, see
+/// `DeserializerOptions::deny_unknown_fields`.
+fn check_field(known: Option<StructFormat>, key: WireKey<'_>) -> Result<()> {
+    let format = match known {
+        Some(format) => format,
+        None => return Ok(()),
+    };
+    match key {
+        WireKey::Text(key) if !format.fields.contains(&key) => Err(Error::message(format_args!(
+            "unknown field `{}` in struct `{}`",
+            key, format.name
+        ))),
+        WireKey::Integer(index) if index >= format.fields.len() as u64 => Err(Error::message(
+            format_args!("unknown field {} in struct `{}`", index, format.name),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// The options for looking up the text keys of struct fields in `parse_field_key`.
+struct FieldLookup {
+    renames: &'static [(WireKey<'static>, &'static str)],
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    normalization: KeyNormalization,
+    known: Option<StructFormat>,
+}
+
+impl FieldLookup {
+    fn new<O: DeserializerOptions>(options: &O, known: Option<StructFormat>) -> FieldLookup {
+        FieldLookup {
+            renames: options.key_renames(),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            normalization: options.key_normalization(),
+            known,
+        }
+    }
+
+    /// Replaces and checks the text key `key`. Keys that are not replaced are deserialized by
+    /// `visit`.
+    fn text<'de, K, F>(&self, seed: K, key: &str, visit: F) -> Result<K::Value>
+    where
+        K: de::DeserializeSeed<'de>,
+        F: FnOnce(K) -> Result<K::Value>,
+    {
+        let renamed = self
+            .renames
+            .iter()
+            .find(|(old, _)| *old == WireKey::Text(key));
+        if let Some(&(_, new)) = renamed {
+            check_field(self.known, WireKey::Text(new))?;
+            return seed.deserialize(de::value::BorrowedStrDeserializer::new(new));
+        }
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if let Some(key) = self.normalization.apply(key) {
+                check_field(self.known, WireKey::Text(&key))?;
+                return seed.deserialize(de::value::StringDeserializer::new(key));
+            }
+        }
+        check_field(self.known, WireKey::Text(key))?;
+        visit(seed)
+    }
+
+    /// Fails for a byte string key that is not UTF-8, and so no field name, if unknown fields
+    /// are denied.
+    fn check_bytes(&self) -> Result<()> {
+        match self.known {
+            Some(format) => Err(Error::message(format_args!(
+                "unknown byte string field in struct `{}`",
+                format.name
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A position in the input of a deserializer to return to.
+///
+/// See [`Deserializer::snapshot`](struct.Deserializer.html#method.snapshot).
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    offset: u64,
+    remaining_depth: u8,
+    struct_format: Option<StructFormat>,
+}
+
+#[cfg(feature = "std")]
+impl Snapshot {
+    /// The byte offset of the position.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R, O> Deserializer<IoRead<R>, O>
+where
+    R: io::Read + io::Seek,
+{
+    /// Records the current position, to return to it with [`rewind`](#method.rewind).
+    ///
+    /// Together they allow speculative decoding, like trying several versions of a schema in
+    /// turn, without buffering the whole input.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use serde_derive::Deserialize;
+    /// use serde_cbor::Deserializer;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct V2 {
+    ///     name: String,
+    ///     email: String,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct V1 {
+    ///     name: String,
+    /// }
+    ///
+    /// // {"name": "Ann"}
+    /// let input = Cursor::new(b"\xa1\x64name\x63Ann".to_vec());
+    /// let mut deserializer = Deserializer::from_reader(input);
+    /// let snapshot = deserializer.snapshot();
+    /// let v2: Result<V2, _> = serde::Deserialize::deserialize(&mut deserializer);
+    /// assert!(v2.is_err());
+    /// deserializer.rewind(snapshot).unwrap();
+    /// let v1: V1 = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(v1.name, "Ann");
+    /// ```
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            offset: self.read.position(),
+            remaining_depth: self.remaining_depth,
+            struct_format: self.struct_format,
+        }
+    }
+
+    /// Seeks the reader back, or forward, to a position recorded with
+    /// [`snapshot`](#method.snapshot).
+    ///
+    /// This also resets the nesting depth, so the snapshot can be returned to after any error. The
+    /// [`stats`](#method.stats) keep counting the items decoded in between.
+    pub fn rewind(&mut self, snapshot: Snapshot) -> Result<()> {
+        self.read.seek_to(snapshot.offset)?;
+        self.remaining_depth = snapshot.remaining_depth;
+        self.struct_format = snapshot.struct_format;
+        Ok(())
+    }
+}
+
 #[cfg(any(feature = "std", feature = "alloc"))]
 impl<'a> Deserializer<SliceRead<'a>> {
     /// Constructs a `Deserializer` which reads from a slice.
@@ -164,6 +553,64 @@ impl<'a> Deserializer<MutSliceRead<'a>> {
     }
 }
 
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a, O> Deserializer<SliceRead<'a>, O>
+where
+    O: DeserializerOptions,
+{
+    /// The part of the slice that has not been parsed yet.
+    ///
+    /// ```
+    /// use serde_cbor::Deserializer;
+    ///
+    /// // "foo" followed by 7
+    /// let input = b"\x63foo\x07";
+    /// let mut deserializer = Deserializer::from_slice(input);
+    /// let value: String = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(value, "foo");
+    /// assert_eq!(deserializer.remaining(), b"\x07");
+    /// assert_eq!(deserializer.consumed(), 4);
+    /// ```
+    pub fn remaining(&self) -> &'a [u8] {
+        self.read.remaining()
+    }
+
+    /// The number of bytes of the slice parsed so far.
+    pub fn consumed(&self) -> usize {
+        self.read.byte_offset()
+    }
+}
+
+impl<'a, O> Deserializer<MutSliceRead<'a>, O>
+where
+    O: DeserializerOptions,
+{
+    /// The part of the slice that has not been parsed yet.
+    pub fn remaining(&self) -> &[u8] {
+        self.read.remaining()
+    }
+
+    /// The number of bytes of the slice parsed so far.
+    pub fn consumed(&self) -> usize {
+        self.read.byte_offset()
+    }
+}
+
+impl<'a, 'b, O> Deserializer<SliceReadFixed<'a, 'b>, O>
+where
+    O: DeserializerOptions,
+{
+    /// The part of the slice that has not been parsed yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.read.remaining()
+    }
+
+    /// The number of bytes of the slice parsed so far.
+    pub fn consumed(&self) -> usize {
+        self.read.byte_offset()
+    }
+}
+
 impl<'a, 'b> Deserializer<SliceReadFixed<'a, 'b>> {
     #[doc(hidden)]
     pub fn from_slice_with_scratch(
@@ -197,439 +644,2458 @@ pub trait DeserializerOptions {
         false
     }
 
+    /// Names of structs whose fields are only accepted in the packed format.
     #[inline]
-    fn to_custom(&self) -> CustomDeserializerOptions {
-        CustomDeserializerOptions {
-            accept_named: self.accept_named(),
-            accept_packed: self.accept_packed(),
-            accept_standard_enums: self.accept_standard_enums(),
-            accept_legacy_enums: self.accept_legacy_enums(),
-        }
+    fn packed_structs(&self) -> &'static [&'static str] {
+        &[]
     }
-}
-
-/// Default Deserializer Options
-#[derive(Debug)]
-pub struct DefaultDeserializerOptions;
-
-/// Custom Deserializer Options
-#[derive(Debug)]
-pub struct CustomDeserializerOptions {
-    accept_named: bool,
-    accept_packed: bool,
-    accept_standard_enums: bool,
-    accept_legacy_enums: bool,
-}
 
-impl DeserializerOptions for CustomDeserializerOptions {
+    /// Names of structs whose fields are only accepted in the named format.
     #[inline]
-    fn accept_named(&self) -> bool {
-        self.accept_named
+    fn named_structs(&self) -> &'static [&'static str] {
+        &[]
     }
+
+    /// Struct field keys that are replaced by another name before they are matched.
     #[inline]
-    fn accept_packed(&self) -> bool {
-        self.accept_packed
+    fn key_renames(&self) -> &'static [(WireKey<'static>, &'static str)] {
+        &[]
     }
+
+    /// Accept byte strings holding the name of a struct field or enum variant.
     #[inline]
-    fn accept_standard_enums(&self) -> bool {
-        self.accept_standard_enums
+    fn accept_byte_string_keys(&self) -> bool {
+        false
     }
+
+    /// Fail on struct field keys that are not one of the fields, with an error naming the key
+    /// and the struct.
+    ///
+    /// Unlike `#[serde(deny_unknown_fields)]` this applies to every struct and needs no change to
+    /// the types. Only text and unsigned integer keys are checked.
     #[inline]
-    fn accept_legacy_enums(&self) -> bool {
-        self.accept_legacy_enums
+    fn deny_unknown_fields(&self) -> bool {
+        false
     }
-}
 
-impl CustomDeserializerOptions {
-    #[allow(missing_docs)]
-    pub fn new() -> Self {
-        DefaultDeserializerOptions.to_custom()
+    /// Report definite length arrays and maps that are not read to the end with the number of
+    /// elements they declare and the index of the first unread one.
+    ///
+    /// A type that stops reading a collection early, like a tuple decoded from a longer array,
+    /// fails with trailing data otherwise. The error is placed at the first unread element, the
+    /// key for a map. Pairs beyond the declared length of a map can't be told apart from the
+    /// items following it and are still reported where they end up being read.
+    #[inline]
+    fn strict_lengths(&self) -> bool {
+        false
     }
 
-    /// Accept named variants and fields.
-    pub fn set_accept_named_format(mut self, new: bool) -> Self {
-        self.accept_named = new;
-        self
+    /// Decode `undefined` (simple value 23) as `None` for `Option`s.
+    ///
+    /// JavaScript encoders write `undefined` for missing values. Without this option only `null`
+    /// is `None`, and `undefined` is handed to the type inside the `Option`.
+    #[inline]
+    fn undefined_as_none(&self) -> bool {
+        false
     }
 
-    /// Accept numbered variants and fields.
-    pub fn set_accept_packed_format(mut self, new: bool) -> Self {
-        self.accept_packed = new;
-        self
+    /// Decode a `null`, or an `undefined` with `undefined_as_none`, wrapped in tags as `None` for
+    /// `Option`s.
+    ///
+    /// Looking past the tags needs the input to be a slice, with a reader tagged nulls are
+    /// handed to the type inside the `Option` as before.
+    #[inline]
+    fn tagged_null_as_none(&self) -> bool {
+        false
     }
 
-    /// Accept the new enum format used by `serde_cbor` versions >= v0.10.
-    pub fn set_accept_standard_enums(mut self, new: bool) -> Self {
-        self.accept_standard_enums = new;
-        self
+    /// The largest number of chunks an indefinite length string, or elements an indefinite
+    /// length array or map, may have. Map entries are counted in pairs.
+    ///
+    /// Each chunk costs a header and some bookkeeping, so millions of empty chunks take a long
+    /// time to decode although they add nothing to the value. Definite length items and the
+    /// lazy `array_iter` and `map_iter`, which hand every element to the caller, are not
+    /// limited. Unlimited by default.
+    #[inline]
+    fn max_indefinite_chunks(&self) -> Option<usize> {
+        None
     }
 
-    /// Accept the old enum format used by `serde_cbor` versions <= v0.9.
-    pub fn set_accept_legacy_enums(mut self, new: bool) -> Self {
-        self.accept_legacy_enums = new;
-        self
+    /// How simple values other than `false`, `true`, `null` and `undefined` are decoded, both
+    /// the ones in the initial byte and the two byte form.
+    ///
+    /// These values are unassigned, so they are rejected by default. Two byte simple values
+    /// below 32 are not well-formed and always rejected.
+    #[inline]
+    fn simple_values(&self) -> SimpleValues {
+        SimpleValues::Reject
     }
-}
 
-impl Default for CustomDeserializerOptions {
+    /// Resolves the enum variant identifiers read from the input to variant names.
+    ///
+    /// Without a resolver identifiers are handed to the enum as they are.
     #[inline]
-    fn default() -> Self {
-        Self::new()
+    fn variant_resolver(&self) -> Option<VariantResolver> {
+        None
     }
-}
-
-impl DeserializerOptions for DefaultDeserializerOptions {}
 
-impl<'de, R> Deserializer<R>
-where
-    R: Read<'de>,
-{
-    /// Constructs a `Deserializer` from one of the possible serde_cbor input sources.
-    ///
-    /// `from_slice` and `from_reader` should normally be used instead of this method.
+    /// How struct field keys are normalized before they are matched.
+    #[cfg(any(feature = "std", feature = "alloc"))]
     #[inline]
-    pub fn new(read: R) -> Self {
-        Deserializer::new_with_options(read, DefaultDeserializerOptions)
+    fn key_normalization(&self) -> KeyNormalization {
+        KeyNormalization::NONE
     }
-}
 
-impl<'de, R, O> Deserializer<R, O>
-where
-    R: Read<'de>,
-    O: DeserializerOptions,
-{
-    /// Constructs a `Deserializer` from one of the possible serde_cbor input sources.
+    /// Require the keys of every map to appear in this order of their encodings, as they do in
+    /// canonical input.
     ///
-    /// `from_slice` and `from_reader` should normally be used instead of this method.
+    /// A key that is not greater than the one before it fails with an error at its offset, so
+    /// duplicate keys are rejected as well. This includes the maps inside values that are
+    /// skipped, like the ones of unknown struct fields. Readers of streams keep a copy of the
+    /// previous key of each map, readers that keep neither their input nor a copy fail on maps
+    /// of more than one entry.
+    #[cfg(any(feature = "std", feature = "alloc"))]
     #[inline]
-    pub fn new_with_options(read: R, options: O) -> Self {
-        Deserializer {
-            read,
-            remaining_depth: 128,
-            options,
-        }
+    fn key_order(&self) -> Option<KeyOrder> {
+        None
     }
 
-    /// Don't accept named variants and fields.
+    /// Accept floats without a fractional part for integer types.
     #[inline]
-    pub fn disable_named_format(self) -> Deserializer<R, CustomDeserializerOptions> {
-        Deserializer {
-            read: self.read,
-            remaining_depth: self.remaining_depth,
-            options: self.options.to_custom().set_accept_named_format(false),
-        }
+    fn accept_integral_floats(&self) -> bool {
+        false
     }
 
-    /// Don't accept numbered variants and fields.
+    /// Accept text strings containing a decimal number for integer and float types.
     #[inline]
-    pub fn disable_packed_format(self) -> Deserializer<R, CustomDeserializerOptions> {
-        Deserializer {
-            read: self.read,
-            remaining_depth: self.remaining_depth,
-            options: self.options.to_custom().set_accept_packed_format(false),
-        }
+    fn accept_numeric_strings(&self) -> bool {
+        false
     }
 
-    /// Don't accept the new enum format used by `serde_cbor` versions >= v0.10.
+    /// Accept byte strings of up to eight bytes holding a big-endian unsigned integer for
+    /// integer types.
     #[inline]
-    pub fn disable_standard_enums(self) -> Deserializer<R, CustomDeserializerOptions> {
-        Deserializer {
-            read: self.read,
-            remaining_depth: self.remaining_depth,
-            options: self.options.to_custom().set_accept_standard_enums(false),
-        }
+    fn accept_integer_bytes(&self) -> bool {
+        false
     }
 
-    /// Don't accept the old enum format used by `serde_cbor` versions <= v0.9.
+    /// Accept unsigned integers holding a Unicode code point for `char`.
     #[inline]
-    pub fn disable_legacy_enums(self) -> Deserializer<R, CustomDeserializerOptions> {
-        Deserializer {
-            read: self.read,
-            remaining_depth: self.remaining_depth,
-            options: self.options.to_custom().set_accept_legacy_enums(false),
-        }
-    }
-
-    /// This method should be called after a value has been deserialized to ensure there is no
-    /// trailing data in the input source.
-    pub fn end(&mut self) -> Result<()> {
-        match self.next()? {
-            Some(_) => Err(self.error(ErrorCode::TrailingData)),
-            None => Ok(()),
-        }
+    fn accept_code_point_chars(&self) -> bool {
+        false
     }
 
-    /// Turn a CBOR deserializer into an iterator over values of type T.
-    #[allow(clippy::should_implement_trait)] // Trait doesn't allow unconstrained T.
-    pub fn into_iter<T>(self) -> StreamDeserializer<'de, R, T, O>
-    where
-        T: de::Deserialize<'de>,
-    {
-        StreamDeserializer {
-            de: self,
-            output: PhantomData,
-            lifetime: PhantomData,
-        }
+    /// Decode text strings tagged with an expected encoding (tags 21 to 23) for byte strings.
+    #[inline]
+    fn decode_expected_encodings(&self) -> bool {
+        false
     }
 
+    /// Report the input as human readable to the types being deserialized.
+    ///
+    /// Some types like `std::net::IpAddr` choose between a text and a binary representation
+    /// based on this flag. Enable it to decode data written with the text representations.
     #[inline]
-    fn next(&mut self) -> Result<Option<u8>> {
-        self.read.next()
+    fn human_readable(&self) -> bool {
+        false
     }
 
+    /// Decode floats that are not written with their shortest exact width as
+    /// `Value::SizedFloat`, so encoding the `Value` again gives back the same bytes.
     #[inline]
-    fn peek(&mut self) -> Result<Option<u8>> {
-        self.read.peek()
+    fn preserve_float_widths(&self) -> bool {
+        false
     }
 
+    /// Visit half precision floats with `visit_f32` instead of `visit_f64`.
+    ///
+    /// Every half converts to an `f32` exactly, infinities stay infinite and `NaN`s stay `NaN`s
+    /// of the same sign, so a visitor that only takes `f32`s sees the value as it was written.
+    /// Single and double precision floats are visited with `visit_f64` as before.
     #[inline]
-    fn consume(&mut self) {
-        self.read.discard();
+    fn half_floats_as_f32(&self) -> bool {
+        false
     }
 
+    /// Decode integers whose header is longer than necessary as `Value::SizedInteger`, so
+    /// encoding the `Value` again gives back the same bytes.
     #[inline]
-    fn error(&self, reason: ErrorCode) -> Error {
-        let offset = self.read.offset();
-        Error::syntax(reason, offset)
+    fn preserve_integer_widths(&self) -> bool {
+        false
     }
 
+    /// How text strings are checked to be valid UTF-8.
     #[inline]
-    fn parse_uint(&mut self, magnitude: u8) -> Result<u64> {
-        let mut buf = [0; 8];
-        let bytes = 1 << (magnitude - 1);
-        let buf_view = &mut buf[8 - bytes..];
-        self.read.read_into(buf_view)?;
-        Ok(u64::from_be_bytes(buf))
+    fn utf8_validation(&self) -> Utf8Validation {
+        Utf8Validation::STRICT
     }
 
     #[inline]
-    fn parse_u8(&mut self) -> Result<u8> {
-        match self.next()? {
-            Some(byte) => Ok(byte),
-            None => Err(self.error(ErrorCode::EofWhileParsingValue)),
+    fn to_custom(&self) -> CustomDeserializerOptions {
+        CustomDeserializerOptions {
+            accept_named: self.accept_named(),
+            accept_packed: self.accept_packed(),
+            accept_standard_enums: self.accept_standard_enums(),
+            accept_legacy_enums: self.accept_legacy_enums(),
+            packed_structs: self.packed_structs(),
+            named_structs: self.named_structs(),
+            key_renames: self.key_renames(),
+            accept_byte_string_keys: self.accept_byte_string_keys(),
+            deny_unknown_fields: self.deny_unknown_fields(),
+            strict_lengths: self.strict_lengths(),
+            undefined_as_none: self.undefined_as_none(),
+            tagged_null_as_none: self.tagged_null_as_none(),
+            max_indefinite_chunks: self.max_indefinite_chunks(),
+            simple_values: self.simple_values(),
+            variant_resolver: self.variant_resolver(),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            key_normalization: self.key_normalization(),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            key_order: self.key_order(),
+            accept_integral_floats: self.accept_integral_floats(),
+            accept_numeric_strings: self.accept_numeric_strings(),
+            accept_integer_bytes: self.accept_integer_bytes(),
+            accept_code_point_chars: self.accept_code_point_chars(),
+            decode_expected_encodings: self.decode_expected_encodings(),
+            human_readable: self.human_readable(),
+            preserve_float_widths: self.preserve_float_widths(),
+            half_floats_as_f32: self.half_floats_as_f32(),
+            preserve_integer_widths: self.preserve_integer_widths(),
+            utf8_validation: self.utf8_validation(),
         }
     }
+}
 
-    fn parse_bytes<V>(&mut self, len: Option<usize>, visitor: V) -> Result<V::Value>
-    where
-        V: de::Visitor<'de>,
-    {
-        let read = if let Some(len) = len {
-            self.read.read(len)
-        } else {
-            self.read_indefinite_bytes()
-        }?;
-        match read {
-            EitherLifetime::Long(buf) => visitor.visit_borrowed_bytes(buf),
-            EitherLifetime::Short(buf) => visitor.visit_bytes(buf),
-        }
-    }
+/// A struct field key as it appears in the input, see `DeserializerOptions::key_renames`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WireKey<'a> {
+    /// A text string key.
+    Text(&'a str),
+    /// An unsigned integer key, as used by the packed format.
+    Integer(u64),
+}
 
-    #[cold]
-    fn read_indefinite_bytes(&mut self) -> Result<EitherLifetime<'_, 'de>> {
-        self.read.clear_buffer();
-        loop {
-            let byte = self.parse_u8()?;
-            let len = match byte {
-                0x40..=0x57 => byte as usize - 0x40,
-                0x58..=0x5b => {
-                    let len = self.parse_uint(byte - 0x57)?;
-                    if len > usize::max_value() as u64 {
-                        return Err(self.error(ErrorCode::LengthOutOfRange));
-                    }
-                    len as usize
-                }
-                0xff => break,
-                _ => return Err(self.error(ErrorCode::UnexpectedCode(ExpectedSet::STRING, byte))),
-            };
+/// Maps an enum variant identifier from the input to one of `variants`, the variant names of the
+/// enum in declaration order, or returns `None` to reject it.
+///
+/// See `DeserializerOptions::variant_resolver`.
+pub type VariantResolver =
+    fn(variants: &'static [&'static str], key: WireKey<'_>) -> Option<&'static str>;
 
-            self.read.read_to_buffer(len)?;
-        }
+/// Resolves variant names to themselves and variant indices by their position in `variants`.
+///
+/// This accepts the variant identifiers written by both the named and the packed format.
+pub fn resolve_variant_by_index(
+    variants: &'static [&'static str],
+    key: WireKey<'_>,
+) -> Option<&'static str> {
+    match key {
+        WireKey::Text(name) => variants.iter().copied().find(|variant| *variant == name),
+        WireKey::Integer(index) => usize::try_from(index)
+            .ok()
+            .and_then(|index| variants.get(index).copied()),
+    }
+}
 
-        Ok(self.read.take_buffer())
+/// Resolves variant names to themselves and rejects variant indices.
+pub fn resolve_variant_by_name(
+    variants: &'static [&'static str],
+    key: WireKey<'_>,
+) -> Option<&'static str> {
+    match key {
+        WireKey::Text(name) => variants.iter().copied().find(|variant| *variant == name),
+        WireKey::Integer(_) => None,
     }
+}
 
-    #[inline]
-    fn convert_str<'a>(buf: &'a [u8], offset: u64) -> Result<&'a str> {
-        match str::from_utf8(buf) {
-            Ok(s) => Ok(s),
-            Err(_) => Err(Error::syntax(ErrorCode::InvalidUtf8, offset)),
+/// How simple values other than `false`, `true`, `null` and `undefined` are decoded, see
+/// `DeserializerOptions::simple_values`.
+#[derive(Clone, Copy, Debug)]
+pub enum SimpleValues {
+    /// Simple values are an unexpected code error. This is the default.
+    Reject,
+    /// Simple values are decoded as their number, and as `Value::Simple` by a `Value`.
+    Number,
+    /// Simple values are replaced by the simple value the function returns, or rejected if it
+    /// returns `None`. The replacements 20 to 23 are decoded as `false`, `true`, `null` and
+    /// `undefined`, all others as with `Number`.
+    Map(fn(u8) -> Option<u8>),
+}
+
+impl PartialEq for SimpleValues {
+    fn eq(&self, other: &SimpleValues) -> bool {
+        match (*self, *other) {
+            (SimpleValues::Reject, SimpleValues::Reject) => true,
+            (SimpleValues::Number, SimpleValues::Number) => true,
+            (SimpleValues::Map(a), SimpleValues::Map(b)) => a as usize == b as usize,
+            _ => false,
         }
     }
+}
+
+impl Eq for SimpleValues {}
+
+/// How struct field keys are normalized, see `DeserializerOptions::key_normalization`.
+///
+/// The normalizations are combined with `|`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyNormalization(u8);
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl KeyNormalization {
+    /// Keys are matched as they are. This is the default.
+    pub const NONE: KeyNormalization = KeyNormalization(0);
+
+    /// Leading and trailing whitespace is removed.
+    pub const TRIM: KeyNormalization = KeyNormalization(1);
+
+    /// Keys are lowercased.
+    pub const LOWERCASE: KeyNormalization = KeyNormalization(2);
+
+    /// Dashes are replaced by underscores, which turns kebab-case into snake_case.
+    pub const KEBAB_TO_SNAKE: KeyNormalization = KeyNormalization(4);
+
+    /// Returns whether all normalizations in `other` are enabled.
+    pub const fn contains(self, other: KeyNormalization) -> bool {
+        self.0 & other.0 == other.0
+    }
 
-    #[inline]
-    fn parse_str<V>(&mut self, len: Option<usize>, visitor: V) -> Result<V::Value>
-    where
-        V: de::Visitor<'de>,
-    {
-        let offset = self.read.offset();
-        let read = if let Some(len) = len {
-            self.read.read(len)
+    /// Returns the normalized key, or `None` if the key does not change.
+    fn apply(self, key: &str) -> Option<String> {
+        #[cfg(not(feature = "std"))]
+        use alloc::string::ToString;
+
+        let mut normalized = key;
+        if self.contains(KeyNormalization::TRIM) {
+            normalized = normalized.trim();
+        }
+        let mut normalized = if self.contains(KeyNormalization::LOWERCASE) {
+            normalized.to_lowercase()
         } else {
-            self.read_indefinite_str()
-        }?;
-        match read {
-            EitherLifetime::Long(buf) => {
-                let s = Self::convert_str(buf, offset)?;
-                visitor.visit_borrowed_str(s)
-            }
-            EitherLifetime::Short(buf) => {
-                let s = Self::convert_str(buf, offset)?;
-                visitor.visit_str(s)
-            }
+            normalized.to_string()
+        };
+        if self.contains(KeyNormalization::KEBAB_TO_SNAKE) {
+            normalized = normalized.replace('-', "_");
+        }
+        if normalized == key {
+            None
+        } else {
+            Some(normalized)
         }
     }
+}
 
-    #[cold]
-    fn read_indefinite_str(&mut self) -> Result<EitherLifetime<'_, 'de>> {
-        self.read.clear_buffer();
-        loop {
-            let byte = self.parse_u8()?;
-            let len = match byte {
-                0x60..=0x77 => byte as usize - 0x60,
-                0x78..=0x7b => {
-                    let len = self.parse_uint(byte - 0x77)?;
-                    if len > usize::max_value() as u64 {
-                        return Err(self.error(ErrorCode::LengthOutOfRange));
-                    }
-                    len as usize
-                }
-                0xff => break,
-                _ => return Err(self.error(ErrorCode::UnexpectedCode(ExpectedSet::STRING, byte))),
-            };
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl core::ops::BitOr for KeyNormalization {
+    type Output = KeyNormalization;
 
-            self.read.read_to_buffer(len)?;
-        }
+    fn bitor(self, other: KeyNormalization) -> KeyNormalization {
+        KeyNormalization(self.0 | other.0)
+    }
+}
 
-        Ok(self.read.take_buffer())
+/// How text strings are checked to be valid UTF-8, see `DeserializerOptions::utf8_validation`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Utf8Validation(Utf8Mode);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Utf8Mode {
+    Strict,
+    #[cfg_attr(not(any(feature = "std", feature = "alloc")), allow(dead_code))]
+    Lossy,
+    Trusted,
+}
+
+impl Utf8Validation {
+    /// Text strings that are not valid UTF-8 are an error. This is the default.
+    pub const STRICT: Utf8Validation = Utf8Validation(Utf8Mode::Strict);
+
+    /// Invalid sequences in text strings are replaced with U+FFFD REPLACEMENT CHARACTER.
+    ///
+    /// Such strings are decoded into a new `String`, so they cannot be borrowed from the input.
+    /// Where a string is not handed to the type being deserialized, like for struct field names,
+    /// `char` or numbers in text strings, invalid UTF-8 is still an error.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub const LOSSY: Utf8Validation = Utf8Validation(Utf8Mode::Lossy);
+
+    /// Text strings are not validated at all.
+    ///
+    /// This saves the time spent on validation when the input comes from a trusted producer,
+    /// like another process of the same service.
+    ///
+    /// # Safety
+    ///
+    /// Every text string in the input decoded with these options has to be valid UTF-8.
+    /// Otherwise the `str`s handed to the types being deserialized are invalid, which is
+    /// undefined behavior.
+    pub const unsafe fn trusted() -> Utf8Validation {
+        Utf8Validation(Utf8Mode::Trusted)
     }
+}
 
-    #[cfg(feature = "tags")]
-    fn handle_tagged_value<V, Valid>(&mut self, tag: u64, visitor: V) -> Result<V::Value>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.recursion_checked(|d| {
-            set_tag(Some(tag));
-            let r = visitor.visit_newtype_struct(d);
-            set_tag(None);
-            r
-        })
+/// Default Deserializer Options
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DefaultDeserializerOptions;
+
+/// Custom Deserializer Options
+///
+/// The options are plain data, they can be copied and shared between threads freely. Variant
+/// resolvers are compared by their address.
+#[derive(Clone, Copy, Debug)]
+pub struct CustomDeserializerOptions {
+    accept_named: bool,
+    accept_packed: bool,
+    accept_standard_enums: bool,
+    accept_legacy_enums: bool,
+    packed_structs: &'static [&'static str],
+    named_structs: &'static [&'static str],
+    key_renames: &'static [(WireKey<'static>, &'static str)],
+    accept_byte_string_keys: bool,
+    deny_unknown_fields: bool,
+    strict_lengths: bool,
+    undefined_as_none: bool,
+    tagged_null_as_none: bool,
+    max_indefinite_chunks: Option<usize>,
+    simple_values: SimpleValues,
+    variant_resolver: Option<VariantResolver>,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    key_normalization: KeyNormalization,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    key_order: Option<KeyOrder>,
+    accept_integral_floats: bool,
+    accept_numeric_strings: bool,
+    accept_integer_bytes: bool,
+    accept_code_point_chars: bool,
+    decode_expected_encodings: bool,
+    human_readable: bool,
+    preserve_float_widths: bool,
+    half_floats_as_f32: bool,
+    preserve_integer_widths: bool,
+    utf8_validation: Utf8Validation,
+}
+
+impl DeserializerOptions for CustomDeserializerOptions {
+    #[inline]
+    fn accept_named(&self) -> bool {
+        self.accept_named
+    }
+    #[inline]
+    fn accept_packed(&self) -> bool {
+        self.accept_packed
+    }
+    #[inline]
+    fn accept_standard_enums(&self) -> bool {
+        self.accept_standard_enums
+    }
+    #[inline]
+    fn accept_legacy_enums(&self) -> bool {
+        self.accept_legacy_enums
+    }
+    #[inline]
+    fn packed_structs(&self) -> &'static [&'static str] {
+        self.packed_structs
+    }
+    #[inline]
+    fn named_structs(&self) -> &'static [&'static str] {
+        self.named_structs
+    }
+    #[inline]
+    fn key_renames(&self) -> &'static [(WireKey<'static>, &'static str)] {
+        self.key_renames
+    }
+    #[inline]
+    fn accept_byte_string_keys(&self) -> bool {
+        self.accept_byte_string_keys
+    }
+    #[inline]
+    fn deny_unknown_fields(&self) -> bool {
+        self.deny_unknown_fields
+    }
+    #[inline]
+    fn strict_lengths(&self) -> bool {
+        self.strict_lengths
+    }
+    #[inline]
+    fn undefined_as_none(&self) -> bool {
+        self.undefined_as_none
+    }
+    #[inline]
+    fn tagged_null_as_none(&self) -> bool {
+        self.tagged_null_as_none
+    }
+    #[inline]
+    fn max_indefinite_chunks(&self) -> Option<usize> {
+        self.max_indefinite_chunks
+    }
+    #[inline]
+    fn simple_values(&self) -> SimpleValues {
+        self.simple_values
+    }
+    #[inline]
+    fn variant_resolver(&self) -> Option<VariantResolver> {
+        self.variant_resolver
+    }
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    fn key_normalization(&self) -> KeyNormalization {
+        self.key_normalization
+    }
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    fn key_order(&self) -> Option<KeyOrder> {
+        self.key_order
+    }
+    #[inline]
+    fn accept_integral_floats(&self) -> bool {
+        self.accept_integral_floats
+    }
+    #[inline]
+    fn accept_numeric_strings(&self) -> bool {
+        self.accept_numeric_strings
+    }
+    #[inline]
+    fn accept_integer_bytes(&self) -> bool {
+        self.accept_integer_bytes
+    }
+    #[inline]
+    fn accept_code_point_chars(&self) -> bool {
+        self.accept_code_point_chars
+    }
+    #[inline]
+    fn decode_expected_encodings(&self) -> bool {
+        self.decode_expected_encodings
+    }
+    #[inline]
+    fn human_readable(&self) -> bool {
+        self.human_readable
+    }
+    #[inline]
+    fn preserve_float_widths(&self) -> bool {
+        self.preserve_float_widths
+    }
+    #[inline]
+    fn half_floats_as_f32(&self) -> bool {
+        self.half_floats_as_f32
+    }
+    #[inline]
+    fn preserve_integer_widths(&self) -> bool {
+        self.preserve_integer_widths
+    }
+    #[inline]
+    fn utf8_validation(&self) -> Utf8Validation {
+        self.utf8_validation
+    }
+}
+
+impl CustomDeserializerOptions {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        DefaultDeserializerOptions.to_custom()
+    }
+
+    /// Accept named variants and fields.
+    pub fn set_accept_named_format(mut self, new: bool) -> Self {
+        self.accept_named = new;
+        self
+    }
+
+    /// Accept numbered variants and fields.
+    pub fn set_accept_packed_format(mut self, new: bool) -> Self {
+        self.accept_packed = new;
+        self
+    }
+
+    /// Accept the new enum format used by `serde_cbor` versions >= v0.10.
+    pub fn set_accept_standard_enums(mut self, new: bool) -> Self {
+        self.accept_standard_enums = new;
+        self
+    }
+
+    /// Accept the old enum format used by `serde_cbor` versions <= v0.9.
+    pub fn set_accept_legacy_enums(mut self, new: bool) -> Self {
+        self.accept_legacy_enums = new;
+        self
+    }
+
+    /// Accept only numbered fields for the structs with these names.
+    ///
+    /// This overrides `set_accept_named_format` and `set_accept_packed_format` for a
+    /// size-critical inner struct that is encoded packed inside an otherwise named document.
+    pub fn set_packed_structs(mut self, names: &'static [&'static str]) -> Self {
+        self.packed_structs = names;
+        self
+    }
+
+    /// Accept only named fields for the structs with these names.
+    pub fn set_named_structs(mut self, names: &'static [&'static str]) -> Self {
+        self.named_structs = names;
+        self
+    }
+
+    /// Replace struct field keys before they are matched against the fields.
+    ///
+    /// Every entry maps a key as found in the input to the name of a field. This allows
+    /// migrating the keys used on the wire without changing the Rust types, for example to read
+    /// documents written with an older schema or in the packed format.
+    ///
+    /// ```
+    /// use serde_derive::Deserialize;
+    /// use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead, WireKey};
+    ///
+    /// #[derive(Debug, PartialEq, Deserialize)]
+    /// struct User {
+    ///     name: String,
+    ///     age: u8,
+    /// }
+    ///
+    /// const RENAMES: &[(WireKey, &str)] = &[
+    ///     (WireKey::Text("username"), "name"),
+    ///     (WireKey::Integer(1), "age"),
+    /// ];
+    /// let options = CustomDeserializerOptions::new().set_key_renames(RENAMES);
+    ///
+    /// // {"username": "ann", 1: 30}
+    /// let input = b"\xa2\x68username\x63ann\x01\x18\x1e";
+    /// let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    /// let user: User = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(user, User { name: "ann".to_owned(), age: 30 });
+    /// ```
+    pub fn set_key_renames(mut self, renames: &'static [(WireKey<'static>, &'static str)]) -> Self {
+        self.key_renames = renames;
+        self
+    }
+
+    /// Accept byte strings holding the name of a struct field or enum variant.
+    ///
+    /// Some producers key the maps of structs with byte strings instead of text strings. The
+    /// bytes are matched against the names like text would be.
+    ///
+    /// ```
+    /// use serde_derive::Deserialize;
+    /// use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead};
+    ///
+    /// #[derive(Debug, PartialEq, Deserialize)]
+    /// struct Reading {
+    ///     id: u8,
+    ///     value: u16,
+    /// }
+    ///
+    /// let options = CustomDeserializerOptions::new().set_accept_byte_string_keys(true);
+    /// // {h'6964': 1, h'76616c7565': 500}
+    /// let input = b"\xa2\x42id\x01\x45value\x19\x01\xf4";
+    /// let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    /// let reading: Reading = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(reading, Reading { id: 1, value: 500 });
+    /// ```
+    pub fn set_accept_byte_string_keys(mut self, new: bool) -> Self {
+        self.accept_byte_string_keys = new;
+        self
+    }
+
+    /// Fail on struct field keys that are not one of the fields.
+    ///
+    /// ```
+    /// use serde_derive::Deserialize;
+    /// use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead};
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Point {
+    ///     x: i8,
+    ///     y: i8,
+    /// }
+    ///
+    /// let options = CustomDeserializerOptions::new().set_deny_unknown_fields(true);
+    /// // {"x": 1, "z": 2, "y": 3}
+    /// let input = b"\xa3\x61x\x01\x61z\x02\x61y\x03";
+    /// let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    /// let err = <Point as serde::Deserialize>::deserialize(&mut deserializer).unwrap_err();
+    /// assert_eq!(err.to_string(), "unknown field `z` in struct `Point` at offset 4");
+    /// ```
+    pub fn set_deny_unknown_fields(mut self, new: bool) -> Self {
+        self.deny_unknown_fields = new;
+        self
+    }
+
+    /// Report collections that are not read to the end with their element counts.
+    ///
+    /// ```
+    /// use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead};
+    ///
+    /// let options = CustomDeserializerOptions::new().set_strict_lengths(true);
+    /// // [[1, 2, 3]]
+    /// let input = b"\x81\x83\x01\x02\x03";
+    /// let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    /// let err = <[(u8, u8); 1] as serde::Deserialize>::deserialize(&mut deserializer).unwrap_err();
+    /// assert_eq!(err.unread_element(), Some(2));
+    /// assert_eq!(err.declared_elements(), Some(3));
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "array of 3 elements has element 2 left unread at offset 4"
+    /// );
+    /// ```
+    pub fn set_strict_lengths(mut self, new: bool) -> Self {
+        self.strict_lengths = new;
+        self
+    }
+
+    /// Decode `undefined` as `None` for `Option`s.
+    ///
+    /// ```
+    /// use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead};
+    ///
+    /// let options = CustomDeserializerOptions::new()
+    ///     .set_undefined_as_none(true)
+    ///     .set_tagged_null_as_none(true);
+    /// // [undefined, 1(null), 2]
+    /// let input = b"\x83\xf7\xc1\xf6\x02";
+    /// let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    /// let values: Vec<Option<u8>> = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(values, [None, None, Some(2)]);
+    /// ```
+    pub fn set_undefined_as_none(mut self, new: bool) -> Self {
+        self.undefined_as_none = new;
+        self
+    }
+
+    /// Decode nulls wrapped in tags as `None` for `Option`s.
+    pub fn set_tagged_null_as_none(mut self, new: bool) -> Self {
+        self.tagged_null_as_none = new;
+        self
+    }
+
+    /// Allow indefinite length items at most `max` chunks or elements.
+    ///
+    /// ```
+    /// use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead};
+    ///
+    /// let options = CustomDeserializerOptions::new().set_max_indefinite_chunks(2);
+    /// // (_ "a", "b", "c")
+    /// let input = b"\x7f\x61a\x61b\x61c\xff";
+    /// let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    /// let err = <String as serde::Deserialize>::deserialize(&mut deserializer).unwrap_err();
+    /// assert_eq!(err.to_string(), "indefinite length item has more than 2 chunks at offset 6");
+    /// ```
+    pub fn set_max_indefinite_chunks(mut self, max: usize) -> Self {
+        self.max_indefinite_chunks = Some(max);
+        self
+    }
+
+    /// Decode simple values according to `policy`.
+    ///
+    /// ```
+    /// use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SimpleValues, SliceRead};
+    ///
+    /// // [simple(16), simple(99)]
+    /// let input = b"\x82\xf0\xf8\x63";
+    /// let decode = |policy| {
+    ///     let options = CustomDeserializerOptions::new().set_simple_values(policy);
+    ///     let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    ///     <Vec<Option<u8>> as serde::Deserialize>::deserialize(&mut deserializer)
+    /// };
+    /// assert!(decode(SimpleValues::Reject).is_err());
+    /// assert_eq!(decode(SimpleValues::Number).unwrap(), [Some(16), Some(99)]);
+    /// // simple(99) stands for null in this protocol
+    /// let policy = SimpleValues::Map(|n| if n == 99 { Some(22) } else { Some(n) });
+    /// assert_eq!(decode(policy).unwrap(), [Some(16), None]);
+    /// ```
+    pub fn set_simple_values(mut self, policy: SimpleValues) -> Self {
+        self.simple_values = policy;
+        self
+    }
+
+    /// Resolve enum variant identifiers with `resolver`, for example
+    /// [`resolve_variant_by_name`](fn.resolve_variant_by_name.html) to reject variant indices.
+    ///
+    /// ```
+    /// use serde_derive::Deserialize;
+    /// use serde_cbor::de::{self, CustomDeserializerOptions, Deserializer, SliceRead};
+    ///
+    /// #[derive(Debug, PartialEq, Deserialize)]
+    /// enum Shape {
+    ///     Circle,
+    ///     Square,
+    /// }
+    ///
+    /// let decode = |input: &[u8]| {
+    ///     let options =
+    ///         CustomDeserializerOptions::new().set_variant_resolver(de::resolve_variant_by_name);
+    ///     let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    ///     <Shape as serde::Deserialize>::deserialize(&mut deserializer)
+    /// };
+    /// assert_eq!(decode(b"\x66Square").unwrap(), Shape::Square);
+    /// assert!(decode(b"\x01").is_err());
+    /// ```
+    pub fn set_variant_resolver(mut self, resolver: VariantResolver) -> Self {
+        self.variant_resolver = Some(resolver);
+        self
+    }
+
+    /// Normalize struct field keys before they are matched against the fields.
+    ///
+    /// This reads documents produced with other naming conventions without renaming every
+    /// field. Keys replaced by `set_key_renames` are not normalized.
+    ///
+    /// ```
+    /// use serde_derive::Deserialize;
+    /// use serde_cbor::de::{CustomDeserializerOptions, Deserializer, KeyNormalization, SliceRead};
+    ///
+    /// #[derive(Debug, PartialEq, Deserialize)]
+    /// struct Config {
+    ///     max_size: u8,
+    /// }
+    ///
+    /// let normalization = KeyNormalization::LOWERCASE | KeyNormalization::KEBAB_TO_SNAKE;
+    /// let options = CustomDeserializerOptions::new().set_key_normalization(normalization);
+    /// // {"Max-Size": 8}
+    /// let input = b"\xa1\x68Max-Size\x08";
+    /// let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    /// let config: Config = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(config, Config { max_size: 8 });
+    /// ```
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn set_key_normalization(mut self, new: KeyNormalization) -> Self {
+        self.key_normalization = new;
+        self
+    }
+
+    /// Require the keys of every map to appear in the given order, see
+    /// `DeserializerOptions::key_order`.
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead};
+    /// use serde_cbor::ser::KeyOrder;
+    ///
+    /// let options = CustomDeserializerOptions::new().set_key_order(Some(KeyOrder::LengthFirst));
+    /// // {"a": 1, "bb": 2} and {"bb": 2, "a": 1}
+    /// for (input, ordered) in [(b"\xa2\x61a\x01\x62bb\x02", true), (b"\xa2\x62bb\x02\x61a\x01", false)] {
+    ///     let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    ///     let map: Result<BTreeMap<String, u8>, _> = serde::Deserialize::deserialize(&mut deserializer);
+    ///     assert_eq!(map.is_ok(), ordered);
+    /// }
+    /// ```
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn set_key_order(mut self, new: Option<KeyOrder>) -> Self {
+        self.key_order = new;
+        self
+    }
+
+    /// Accept floats without a fractional part, like `3.0`, for integer types.
+    pub fn set_accept_integral_floats(mut self, new: bool) -> Self {
+        self.accept_integral_floats = new;
+        self
+    }
+
+    /// Accept text strings containing a number, like `"42"` or `"1.5"`, for integer and float
+    /// types.
+    pub fn set_accept_numeric_strings(mut self, new: bool) -> Self {
+        self.accept_numeric_strings = new;
+        self
+    }
+
+    /// Accept byte strings of up to eight bytes holding a big-endian unsigned integer for integer
+    /// types.
+    pub fn set_accept_integer_bytes(mut self, new: bool) -> Self {
+        self.accept_integer_bytes = new;
+        self
+    }
+
+    /// Accept unsigned integers holding a Unicode code point, like `97` for `'a'`, for `char`.
+    pub fn set_accept_code_point_chars(mut self, new: bool) -> Self {
+        self.accept_code_point_chars = new;
+        self
+    }
+
+    /// Decode text strings tagged with an expected encoding (tags 21 to 23) for byte strings.
+    ///
+    /// Data converted from JSON carries byte strings as base64url, base64 or base16 text, with
+    /// the tag telling which encoding was used. With this option the text is decoded back into
+    /// bytes, other values inside these tags are decoded as if they were untagged.
+    ///
+    /// ```
+    /// use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead};
+    ///
+    /// let options = CustomDeserializerOptions::new().set_decode_expected_encodings(true);
+    /// // 23("0102ff")
+    /// let input = b"\xd7\x66\x30\x31\x30\x32\x66\x66";
+    /// let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    /// let bytes: serde_bytes::ByteBuf = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(bytes, [1, 2, 255]);
+    /// ```
+    pub fn set_decode_expected_encodings(mut self, new: bool) -> Self {
+        self.decode_expected_encodings = new;
+        self
+    }
+
+    /// Report the input as human readable, see `DeserializerOptions::human_readable`.
+    pub fn set_human_readable(mut self, new: bool) -> Self {
+        self.human_readable = new;
+        self
+    }
+
+    /// Decode floats that are not written with their shortest exact width as
+    /// `Value::SizedFloat`, see `DeserializerOptions::preserve_float_widths`.
+    pub fn set_preserve_float_widths(mut self, new: bool) -> Self {
+        self.preserve_float_widths = new;
+        self
+    }
+
+    /// Visit half precision floats with `visit_f32`, see
+    /// `DeserializerOptions::half_floats_as_f32`.
+    pub fn set_half_floats_as_f32(mut self, new: bool) -> Self {
+        self.half_floats_as_f32 = new;
+        self
+    }
+
+    /// Decode integers whose header is longer than necessary as `Value::SizedInteger`, see
+    /// `DeserializerOptions::preserve_integer_widths`.
+    pub fn set_preserve_integer_widths(mut self, new: bool) -> Self {
+        self.preserve_integer_widths = new;
+        self
+    }
+
+    /// Choose how text strings are checked to be valid UTF-8.
+    ///
+    /// ```
+    /// use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead, Utf8Validation};
+    ///
+    /// let options = CustomDeserializerOptions::new().set_utf8_validation(Utf8Validation::LOSSY);
+    /// let input = b"\x63a\xffb";
+    /// let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    /// let text: String = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(text, "a\u{fffd}b");
+    /// ```
+    pub fn set_utf8_validation(mut self, new: Utf8Validation) -> Self {
+        self.utf8_validation = new;
+        self
+    }
+}
+
+impl Default for CustomDeserializerOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for CustomDeserializerOptions {
+    fn eq(&self, other: &Self) -> bool {
+        // destructured so that a new option can't be left out of the comparison
+        let CustomDeserializerOptions {
+            accept_named,
+            accept_packed,
+            accept_standard_enums,
+            accept_legacy_enums,
+            packed_structs,
+            named_structs,
+            key_renames,
+            accept_byte_string_keys,
+            deny_unknown_fields,
+            strict_lengths,
+            undefined_as_none,
+            tagged_null_as_none,
+            max_indefinite_chunks,
+            simple_values,
+            variant_resolver,
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            key_normalization,
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            key_order,
+            accept_integral_floats,
+            accept_numeric_strings,
+            accept_integer_bytes,
+            accept_code_point_chars,
+            decode_expected_encodings,
+            human_readable,
+            preserve_float_widths,
+            half_floats_as_f32,
+            preserve_integer_widths,
+            utf8_validation,
+        } = *self;
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        let same_keys =
+            key_normalization == other.key_normalization && key_order == other.key_order;
+        #[cfg(not(any(feature = "std", feature = "alloc")))]
+        let same_keys = true;
+        let same_resolver = match (variant_resolver, other.variant_resolver) {
+            (Some(a), Some(b)) => a as usize == b as usize,
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        accept_named == other.accept_named
+            && accept_packed == other.accept_packed
+            && accept_standard_enums == other.accept_standard_enums
+            && accept_legacy_enums == other.accept_legacy_enums
+            && packed_structs == other.packed_structs
+            && named_structs == other.named_structs
+            && key_renames == other.key_renames
+            && accept_byte_string_keys == other.accept_byte_string_keys
+            && deny_unknown_fields == other.deny_unknown_fields
+            && strict_lengths == other.strict_lengths
+            && undefined_as_none == other.undefined_as_none
+            && tagged_null_as_none == other.tagged_null_as_none
+            && max_indefinite_chunks == other.max_indefinite_chunks
+            && simple_values == other.simple_values
+            && same_resolver
+            && same_keys
+            && accept_integral_floats == other.accept_integral_floats
+            && accept_numeric_strings == other.accept_numeric_strings
+            && accept_integer_bytes == other.accept_integer_bytes
+            && accept_code_point_chars == other.accept_code_point_chars
+            && decode_expected_encodings == other.decode_expected_encodings
+            && human_readable == other.human_readable
+            && preserve_float_widths == other.preserve_float_widths
+            && half_floats_as_f32 == other.half_floats_as_f32
+            && preserve_integer_widths == other.preserve_integer_widths
+            && utf8_validation == other.utf8_validation
+    }
+}
+
+impl Eq for CustomDeserializerOptions {}
+
+impl DeserializerOptions for DefaultDeserializerOptions {}
+
+impl<'de, R> Deserializer<R>
+where
+    R: Read<'de>,
+{
+    /// Constructs a `Deserializer` from one of the possible serde_cbor input sources.
+    ///
+    /// `from_slice` and `from_reader` should normally be used instead of this method.
+    #[inline]
+    pub fn new(read: R) -> Self {
+        Deserializer::new_with_options(read, DefaultDeserializerOptions)
+    }
+}
+
+impl<'de, R, O> Deserializer<R, O>
+where
+    R: Read<'de>,
+    O: DeserializerOptions,
+{
+    /// Constructs a `Deserializer` from one of the possible serde_cbor input sources.
+    ///
+    /// `from_slice` and `from_reader` should normally be used instead of this method.
+    #[inline]
+    pub fn new_with_options(read: R, options: O) -> Self {
+        Deserializer {
+            read,
+            remaining_depth: RECURSION_LIMIT,
+            options,
+            struct_format: None,
+            stats: None,
+        }
+    }
+
+    /// Don't accept named variants and fields.
+    #[inline]
+    pub fn disable_named_format(self) -> Deserializer<R, CustomDeserializerOptions> {
+        Deserializer {
+            read: self.read,
+            remaining_depth: self.remaining_depth,
+            options: self.options.to_custom().set_accept_named_format(false),
+            struct_format: None,
+            stats: self.stats,
+        }
+    }
+
+    /// Don't accept numbered variants and fields.
+    #[inline]
+    pub fn disable_packed_format(self) -> Deserializer<R, CustomDeserializerOptions> {
+        Deserializer {
+            read: self.read,
+            remaining_depth: self.remaining_depth,
+            options: self.options.to_custom().set_accept_packed_format(false),
+            struct_format: None,
+            stats: self.stats,
+        }
+    }
+
+    /// Don't accept the new enum format used by `serde_cbor` versions >= v0.10.
+    #[inline]
+    pub fn disable_standard_enums(self) -> Deserializer<R, CustomDeserializerOptions> {
+        Deserializer {
+            read: self.read,
+            remaining_depth: self.remaining_depth,
+            options: self.options.to_custom().set_accept_standard_enums(false),
+            struct_format: None,
+            stats: self.stats,
+        }
+    }
+
+    /// Don't accept the old enum format used by `serde_cbor` versions <= v0.9.
+    #[inline]
+    pub fn disable_legacy_enums(self) -> Deserializer<R, CustomDeserializerOptions> {
+        Deserializer {
+            read: self.read,
+            remaining_depth: self.remaining_depth,
+            options: self.options.to_custom().set_accept_legacy_enums(false),
+            struct_format: None,
+            stats: self.stats,
+        }
+    }
+
+    /// Report the input as human readable to the types being deserialized.
+    #[inline]
+    pub fn human_readable(self) -> Deserializer<R, CustomDeserializerOptions> {
+        Deserializer {
+            read: self.read,
+            remaining_depth: self.remaining_depth,
+            options: self.options.to_custom().set_human_readable(true),
+            struct_format: None,
+            stats: self.stats,
+        }
+    }
+
+    /// Collects [`DecodeStats`](struct.DecodeStats.html) about the items decoded from now on.
+    pub fn enable_stats(&mut self) {
+        self.stats.get_or_insert_with(DecodeStats::default);
+    }
+
+    /// The statistics collected since [`enable_stats`](#method.enable_stats) was called.
+    pub fn stats(&self) -> Option<&DecodeStats> {
+        self.stats.as_ref()
+    }
+
+    /// Frees the memory of the scratch buffer of the reader beyond `capacity` bytes.
+    ///
+    /// The buffer holds strings that cannot be borrowed from the input, like the strings read from
+    /// an `IoRead`, and keeps the capacity of the longest one. A long-lived deserializer that once
+    /// read a huge string can call this between items to give the memory back.
+    ///
+    /// ```
+    /// use serde_cbor::{Deserializer, Value};
+    ///
+    /// // a text string of 100000 bytes, then 1
+    /// let mut input = vec![0x7a, 0x00, 0x01, 0x86, 0xa0];
+    /// input.resize(100_005, b'x');
+    /// input.push(0x01);
+    /// let mut de = Deserializer::from_reader(&input[..]);
+    /// let _large: Value = serde::Deserialize::deserialize(&mut de).unwrap();
+    /// de.shrink_to(4096);
+    /// let small: Value = serde::Deserialize::deserialize(&mut de).unwrap();
+    /// assert_eq!(small, Value::Integer(1));
+    /// ```
+    pub fn shrink_to(&mut self, capacity: usize) {
+        self.read.shrink_buffer(capacity);
+    }
+
+    /// This method should be called after a value has been deserialized to ensure there is no
+    /// trailing data in the input source.
+    pub fn end(&mut self) -> Result<()> {
+        match self.next()? {
+            Some(_) => Err(self.error(ErrorCode::TrailingData)),
+            None => Ok(()),
+        }
+    }
+
+    /// Unwraps the input source.
+    ///
+    /// No byte past the last decoded item is consumed, so a stream can be read further after
+    /// decoding a value with `IoRead::into_inner`.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.read
+    }
+
+    /// Turn a CBOR deserializer into an iterator over values of type T.
+    #[allow(clippy::should_implement_trait)] // Trait doesn't allow unconstrained T.
+    pub fn into_iter<T>(self) -> StreamDeserializer<'de, R, T, O>
+    where
+        T: de::Deserialize<'de>,
+    {
+        let budget_start = self.read.offset();
+        StreamDeserializer {
+            de: self,
+            max_items: None,
+            max_bytes: None,
+            items: 0,
+            budget_start,
+            scratch_capacity: None,
+            output: PhantomData,
+            lifetime: PhantomData,
+        }
+    }
+
+    /// Lazily iterate over the elements of an array of values of type T.
+    ///
+    /// The array header is consumed immediately, every element is only decoded when the iterator
+    /// is advanced. This allows processing arrays which are too large to be held in memory.
+    ///
+    /// ```
+    /// use serde_cbor::Deserializer;
+    ///
+    /// let data = [0x9f, 0x01, 0x02, 0x03, 0xff];
+    /// let mut de = Deserializer::from_reader(&data[..]);
+    /// let mut sum = 0;
+    /// for item in de.array_iter::<u32>().unwrap() {
+    ///     sum += item.unwrap();
+    /// }
+    /// assert_eq!(sum, 6);
+    /// ```
+    pub fn array_iter<T>(&mut self) -> Result<ArrayIter<'_, 'de, R, T, O>>
+    where
+        T: de::Deserialize<'de>,
+    {
+        let byte = self.parse_u8()?;
+        self.record_item(byte);
+        let len = match byte {
+            0x80..=0x97 => Some(byte as usize - 0x80),
+            0x98..=0x9b => {
+                let len = self.parse_uint(byte - 0x97)?;
+                if len > usize::MAX as u64 {
+                    return Err(self.error(ErrorCode::LengthOutOfRange));
+                }
+                Some(len as usize)
+            }
+            0x9f => None,
+            byte => {
+                return Err(self.error(ErrorCode::UnexpectedCode(ExpectedSet::ARRAY, byte)));
+            }
+        };
+        self.enter_collection()?;
+        Ok(ArrayIter {
+            de: self,
+            len,
+            done: false,
+            output: PhantomData,
+            lifetime: PhantomData,
+        })
+    }
+
+    /// Lazily iterate over the entries of a map with keys of type K and values of type V.
+    ///
+    /// This is the map counterpart to [`array_iter`](#method.array_iter).
+    pub fn map_iter<K, V>(&mut self) -> Result<MapIter<'_, 'de, R, K, V, O>>
+    where
+        K: de::Deserialize<'de>,
+        V: de::Deserialize<'de>,
+    {
+        let byte = self.parse_u8()?;
+        self.record_item(byte);
+        let len = match byte {
+            0xa0..=0xb7 => Some(byte as usize - 0xa0),
+            0xb8..=0xbb => {
+                let len = self.parse_uint(byte - 0xb7)?;
+                if len > usize::MAX as u64 {
+                    return Err(self.error(ErrorCode::LengthOutOfRange));
+                }
+                Some(len as usize)
+            }
+            0xbf => None,
+            byte => {
+                return Err(self.error(ErrorCode::UnexpectedCode(ExpectedSet::MAP, byte)));
+            }
+        };
+        self.enter_collection()?;
+        Ok(MapIter {
+            de: self,
+            len,
+            done: false,
+            output: PhantomData,
+            lifetime: PhantomData,
+        })
+    }
+
+    /// Accounts for a collection that is decoded piecewise by a lazy iterator.
+    ///
+    /// The depth is given back when the iterator is dropped.
+    fn enter_collection(&mut self) -> Result<()> {
+        self.remaining_depth -= 1;
+        if self.remaining_depth == 0 {
+            self.remaining_depth += 1;
+            return Err(self.error(ErrorCode::RecursionLimitExceeded));
+        }
+        Ok(())
+    }
+
+    /// Checks whether a lazily iterated collection has another item and consumes the
+    /// terminating break of indefinite collections.
+    fn collection_has_next(&mut self, len: &mut Option<usize>, eof: ErrorCode) -> Result<bool> {
+        match len {
+            Some(0) => Ok(false),
+            Some(len) => {
+                *len -= 1;
+                Ok(true)
+            }
+            None => match self.peek()? {
+                Some(0xff) => {
+                    self.consume();
+                    Ok(false)
+                }
+                Some(_) => Ok(true),
+                None => Err(self.error(eof)),
+            },
+        }
+    }
+
+    #[inline]
+    fn next(&mut self) -> Result<Option<u8>> {
+        self.read.next()
+    }
+
+    /// Counts the item starting with the initial byte `byte` in the statistics.
+    #[inline]
+    fn record_item(&mut self, byte: u8) {
+        if let Some(stats) = &mut self.stats {
+            stats.major_types[usize::from(byte >> 5)] += 1;
+            let depth = usize::from(RECURSION_LIMIT - self.remaining_depth);
+            stats.max_depth = stats.max_depth.max(depth);
+            if byte < 0xe0 && byte & 0x1f == 0x1f {
+                stats.indefinite_items += 1;
+            }
+        }
+    }
+
+    /// Accounts for a byte or text string of `len` bytes in the statistics.
+    #[inline]
+    fn record_string(&mut self, len: usize) {
+        if let Some(stats) = &mut self.stats {
+            stats.largest_string = stats.largest_string.max(len);
+        }
+    }
+
+    /// Runs `f` to decode an item, placing errors that do not carry an offset yet, like the custom
+    /// errors of visitors, at the start of the item.
+    #[inline]
+    fn placed<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let offset = self.read.offset();
+        f(self).map_err(|e| e.or_offset(offset))
+    }
+
+    fn record_indefinite_string(&mut self, len: usize) {
+        if let Some(stats) = &mut self.stats {
+            stats.largest_string = stats.largest_string.max(len);
+            stats.largest_indefinite_string = stats.largest_indefinite_string.max(len);
+        }
+    }
+
+    #[inline]
+    fn peek(&mut self) -> Result<Option<u8>> {
+        self.read.peek()
+    }
+
+    #[inline]
+    fn consume(&mut self) {
+        self.read.discard();
+    }
+
+    /// Returns the length of the tags before the next item if it is a tagged null, or undefined
+    /// with `undefined_as_none`, and the reader can look that far ahead.
+    fn tagged_null_len(&self) -> Option<usize> {
+        let mut len = 0usize;
+        loop {
+            match self.read.peek_at(len)? {
+                0xc0..=0xd7 => len += 1,
+                byte @ 0xd8..=0xdb => len += 1 + (1 << (byte - 0xd8)),
+                0xf6 => return Some(len),
+                0xf7 if self.options.undefined_as_none() => return Some(len),
+                _ => return None,
+            }
+        }
+    }
+
+    /// Returns the length of the next item if it is a simple value that `SimpleValues::Map` maps
+    /// to null, or undefined with `undefined_as_none`, and the reader can look that far ahead.
+    fn mapped_null_len(&self, byte: u8) -> Option<usize> {
+        let (len, value) = match byte {
+            0xe0..=0xf3 => (1, byte - 0xe0),
+            _ => (2, self.read.peek_at(1).filter(|&value| value >= 32)?),
+        };
+        match self.options.simple_values() {
+            SimpleValues::Map(map) => match map(value)? {
+                22 => Some(len),
+                23 if self.options.undefined_as_none() => Some(len),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Skips the tags of `len` bytes found by `tagged_null_len` and the null after them.
+    fn skip_tagged_null(&mut self, len: usize) -> Result<()> {
+        let mut pos = 0;
+        while pos < len {
+            let byte = self.parse_u8()?;
+            self.record_item(byte);
+            let argument = if byte >= 0xd8 { 1 << (byte - 0xd8) } else { 0 };
+            self.read.read(argument)?;
+            pos += 1 + argument;
+        }
+        let byte = self.parse_u8()?;
+        self.record_item(byte);
+        Ok(())
+    }
+
+    #[inline]
+    fn error(&self, reason: ErrorCode) -> Error {
+        let offset = self.read.offset();
+        Error::syntax(reason, offset)
+    }
+
+    /// Counts another chunk or element of an indefinite length item, see
+    /// `DeserializerOptions::max_indefinite_chunks`.
+    #[inline]
+    fn count_chunk(&self, count: &mut usize) -> Result<()> {
+        *count += 1;
+        match self.options.max_indefinite_chunks() {
+            Some(max) if *count > max => Err(self.error(ErrorCode::TooManyChunks { max })),
+            _ => Ok(()),
+        }
+    }
+
+    /// The error for a definite length array or map of `declared` elements that was left with
+    /// `left` unread elements, see `DeserializerOptions::strict_lengths`.
+    #[cold]
+    fn unread_elements(&self, map: bool, declared: usize, left: usize) -> Error {
+        if !self.options.strict_lengths() {
+            return self.error(ErrorCode::TrailingData);
+        }
+        self.error(ErrorCode::UnreadElements {
+            map,
+            declared,
+            index: declared - left,
+        })
+    }
+
+    #[inline]
+    fn parse_uint(&mut self, magnitude: u8) -> Result<u64> {
+        let mut buf = [0; 8];
+        let bytes = 1 << (magnitude - 1);
+        let buf_view = &mut buf[8 - bytes..];
+        self.read.read_into(buf_view)?;
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    #[inline]
+    fn parse_u8(&mut self) -> Result<u8> {
+        match self.next()? {
+            Some(byte) => Ok(byte),
+            None => Err(self.error(ErrorCode::EofWhileParsingValue)),
+        }
+    }
+
+    fn parse_bytes<V>(&mut self, len: Option<usize>, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let read = if let Some(len) = len {
+            self.record_string(len);
+            self.read.read(len)
+        } else {
+            self.read_indefinite_bytes()
+        }?;
+        match read {
+            EitherLifetime::Long(buf) => visitor.visit_borrowed_bytes(buf),
+            EitherLifetime::Short(buf) => visitor.visit_bytes(buf),
+        }
+    }
+
+    /// Reads a definite length byte string with the initial byte `byte` into a `Vec` that is
+    /// passed to `visit_byte_buf`, without going through the scratch buffer of the reader.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn parse_owned_bytes<V>(&mut self, byte: u8, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.consume();
+        self.record_item(byte);
+        let len = self.parse_length(byte)?;
+        self.record_string(len);
+        let buf = self.read.read_owned(len)?;
+        visitor.visit_byte_buf(buf)
+    }
+
+    /// Decodes a byte string of exactly `len` bytes as a sequence of `u8`, like `[u8; 32]`.
+    ///
+    /// Tuples of other types fail at their first element, the bytes are not widened.
+    fn parse_byte_array<V>(&mut self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let offset = self.read.offset();
+        let byte = self.parse_u8()?;
+        self.record_item(byte);
+        let read = if byte == 0x5f {
+            self.read_indefinite_bytes()
+        } else {
+            let len = self.parse_length(byte)?;
+            self.record_string(len);
+            self.read.read(len)
+        }?;
+        let buf = match read {
+            EitherLifetime::Long(buf) => buf,
+            EitherLifetime::Short(buf) => buf,
+        };
+        if buf.len() != len {
+            return Err(Error::syntax(
+                ErrorCode::ByteArrayLength {
+                    expected: len,
+                    found: buf.len(),
+                },
+                offset,
+            ));
+        }
+        let elements = buf.iter().map(|&byte| ByteElement(byte));
+        visitor.visit_seq(de::value::SeqDeserializer::new(elements))
+    }
+
+    /// Decodes the content of a tag hinting an expected encoding for bytes.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn parse_encoded_bytes<V>(&mut self, encoding: ExpectedEncoding, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let byte = match self.peek()? {
+            Some(byte @ 0x60..=0x7b) | Some(byte @ 0x7f) => byte,
+            _ => return self.parse_value::<_, ValidForBytes>(visitor),
+        };
+        let offset = self.read.offset();
+        let utf8 = self.options.utf8_validation();
+        self.consume();
+        self.record_item(byte);
+        let buf = if byte == 0x7f {
+            self.read_indefinite_str()?
+        } else {
+            let len = self.parse_length(byte)?;
+            self.record_string(len);
+            self.read.read(len)?
+        };
+        let text = match buf {
+            EitherLifetime::Long(buf) => Self::convert_str(buf, offset + 1, utf8)?,
+            EitherLifetime::Short(buf) => Self::convert_str(buf, offset + 1, utf8)?,
+        };
+        match encoding.decode(text) {
+            Some(bytes) => visitor.visit_byte_buf(bytes),
+            None => Err(Error::syntax(ErrorCode::InvalidEncodedText, offset)),
+        }
+    }
+
+    #[cold]
+    fn read_indefinite_bytes(&mut self) -> Result<EitherLifetime<'_, 'de>> {
+        self.read.clear_buffer();
+        let mut total = 0usize;
+        let mut chunks = 0;
+        loop {
+            let byte = self.parse_u8()?;
+            let len = match byte {
+                0x40..=0x57 => byte as usize - 0x40,
+                0x58..=0x5b => {
+                    let len = self.parse_uint(byte - 0x57)?;
+                    if len > usize::max_value() as u64 {
+                        return Err(self.error(ErrorCode::LengthOutOfRange));
+                    }
+                    len as usize
+                }
+                0xff => break,
+                _ => return Err(self.error(ErrorCode::UnexpectedCode(ExpectedSet::STRING, byte))),
+            };
+            self.count_chunk(&mut chunks)?;
+
+            if total == 0 && self.read.peek_at(len) == Some(0xff) {
+                return self.read_single_chunk(len);
+            }
+            total = total.saturating_add(len);
+            self.read.read_to_buffer(len)?;
+        }
+
+        self.record_indefinite_string(total);
+        Ok(self.read.take_buffer())
+    }
+
+    /// Reads the only chunk of an indefinite length string, which is followed by the break code,
+    /// without copying it into the scratch buffer.
+    fn read_single_chunk(&mut self, len: usize) -> Result<EitherLifetime<'_, 'de>> {
+        self.record_indefinite_string(len);
+        match self.read.read(len)? {
+            EitherLifetime::Long(buf) => {
+                self.read.next()?;
+                Ok(EitherLifetime::Long(buf))
+            }
+            EitherLifetime::Short(_) => {
+                self.read.next()?;
+                Ok(self.read.take_buffer())
+            }
+        }
+    }
+
+    #[inline]
+    fn convert_str(buf: &[u8], offset: u64, utf8: Utf8Validation) -> Result<&str> {
+        if utf8.0 == Utf8Mode::Trusted {
+            // SAFETY: whoever created `Utf8Validation::trusted` promised that the input is valid.
+            return Ok(unsafe { str::from_utf8_unchecked(buf) });
+        }
+        match str::from_utf8(buf) {
+            Ok(s) => Ok(s),
+            Err(_) => Err(Error::syntax(ErrorCode::InvalidUtf8, offset)),
+        }
+    }
+
+    /// Visits text that is not valid UTF-8 with the replacement characters of
+    /// `Utf8Validation::LOSSY`, or fails with `error`.
+    #[cold]
+    fn visit_invalid_str<V>(
+        buf: &[u8],
+        error: Error,
+        utf8: Utf8Validation,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            #[cfg(not(feature = "std"))]
+            use alloc::string::String;
+
+            if utf8.0 == Utf8Mode::Lossy {
+                return visitor.visit_string(String::from_utf8_lossy(buf).into_owned());
+            }
+        }
+        let _ = (buf, utf8, visitor);
+        Err(error)
+    }
+
+    #[inline]
+    fn parse_str<V>(&mut self, len: Option<usize>, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let offset = self.read.offset();
+        let utf8 = self.options.utf8_validation();
+        let read = if let Some(len) = len {
+            self.record_string(len);
+            self.read.read(len)
+        } else {
+            self.read_indefinite_str()
+        }?;
+        match read {
+            EitherLifetime::Long(buf) => match Self::convert_str(buf, offset, utf8) {
+                Ok(s) => visitor.visit_borrowed_str(s),
+                Err(e) => Self::visit_invalid_str(buf, e, utf8, visitor),
+            },
+            EitherLifetime::Short(buf) => match Self::convert_str(buf, offset, utf8) {
+                Ok(s) => visitor.visit_str(s),
+                Err(e) => Self::visit_invalid_str(buf, e, utf8, visitor),
+            },
+        }
+    }
+
+    /// Reads a definite length text string with the initial byte `byte` into a `String` that is
+    /// passed to `visit_string`, without going through the scratch buffer of the reader.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn parse_owned_str<V>(&mut self, byte: u8, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.consume();
+        self.record_item(byte);
+        let len = self.parse_length(byte)?;
+        let offset = self.read.offset();
+        let utf8 = self.options.utf8_validation();
+        self.record_string(len);
+        let buf = self.read.read_owned(len)?;
+        if utf8.0 == Utf8Mode::Trusted {
+            // SAFETY: whoever created `Utf8Validation::trusted` promised that the input is valid.
+            return visitor.visit_string(unsafe { String::from_utf8_unchecked(buf) });
+        }
+        match String::from_utf8(buf) {
+            Ok(s) => visitor.visit_string(s),
+            Err(e) => {
+                let error = Error::syntax(ErrorCode::InvalidUtf8, offset);
+                Self::visit_invalid_str(e.as_bytes(), error, utf8, visitor)
+            }
+        }
+    }
+
+    #[cold]
+    fn read_indefinite_str(&mut self) -> Result<EitherLifetime<'_, 'de>> {
+        self.read.clear_buffer();
+        let mut total = 0usize;
+        let mut chunks = 0;
+        loop {
+            let byte = self.parse_u8()?;
+            let len = match byte {
+                0x60..=0x77 => byte as usize - 0x60,
+                0x78..=0x7b => {
+                    let len = self.parse_uint(byte - 0x77)?;
+                    if len > usize::max_value() as u64 {
+                        return Err(self.error(ErrorCode::LengthOutOfRange));
+                    }
+                    len as usize
+                }
+                0xff => break,
+                _ => return Err(self.error(ErrorCode::UnexpectedCode(ExpectedSet::STRING, byte))),
+            };
+            self.count_chunk(&mut chunks)?;
+
+            if total == 0 && self.read.peek_at(len) == Some(0xff) {
+                return self.read_single_chunk(len);
+            }
+            total = total.saturating_add(len);
+            self.read.read_to_buffer(len)?;
+        }
+
+        self.record_indefinite_string(total);
+        Ok(self.read.take_buffer())
+    }
+
+    #[cfg(feature = "tags")]
+    fn handle_tagged_value<V, Valid>(&mut self, tag: u64, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+        Valid: ValidValues,
+    {
+        if Valid::TRANSPARENT_TAG == Some(tag) {
+            return self.recursion_checked(|de| de.parse_value::<_, Valid>(visitor));
+        }
+        self.recursion_checked(|d| {
+            set_tag(Some(tag));
+            let r = visitor.visit_newtype_struct(d);
+            set_tag(None);
+            r
+        })
+    }
+
+    #[cfg(not(feature = "tags"))]
+    fn handle_tagged_value<V, Valid>(&mut self, _tag: u64, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+        Valid: ValidValues,
+    {
+        self.recursion_checked(|de| de.parse_value::<_, Valid>(visitor))
+    }
+
+    #[inline]
+    fn recursion_checked<F, T>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Deserializer<R, O>) -> Result<T>,
+    {
+        self.remaining_depth -= 1;
+        if self.remaining_depth == 0 {
+            return Err(self.error(ErrorCode::RecursionLimitExceeded));
+        }
+        let r = f(self);
+        self.remaining_depth += 1;
+        r
+    }
+
+    /// Reads a map key with `f` and checks that it follows the previous key `last` in `order`.
+    /// The encoded key is kept in `last` for the next one.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn ordered_key<F, T>(&mut self, order: KeyOrder, last: &mut Option<Vec<u8>>, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Deserializer<R, O>) -> Result<T>,
+    {
+        let start = self.read.offset();
+        // readers of slices return the key through `input_range`, others copy it while it is read
+        let copy = self.read.start_copy();
+        let value = f(self);
+        let copied = copy.map(|copy| self.read.end_copy(copy));
+        let value = value?;
+        let key = match copied {
+            Some(key) => key,
+            None => match self.read.input_range(start, self.read.offset()) {
+                Some(key) => key.to_vec(),
+                None => return Err(Error::syntax(ErrorCode::KeyOrderUnavailable, start)),
+            },
+        };
+        if let Some(last) = last {
+            if order.compare(last, &key) != Ordering::Less {
+                return Err(Error::syntax(ErrorCode::UnorderedKey, start));
+            }
+        }
+        *last = Some(key);
+        Ok(value)
+    }
+
+    fn parse_array<V>(&mut self, mut len: Option<usize>, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(offset = self.read.offset(), len = ?len, "array begin");
+        let declared = len.unwrap_or(0);
+        self.recursion_checked(|de| {
+            let value = visitor.visit_seq(SeqAccess {
+                de,
+                len: len.as_mut(),
+                read: 0,
+            })?;
+
+            match len {
+                Some(0) => (),
+                Some(left) => return Err(de.unread_elements(false, declared, left)),
+                None => match de.next()? {
+                    Some(0xff) => (),
+                    Some(_) => return Err(de.error(ErrorCode::TrailingData)),
+                    None => return Err(de.error(ErrorCode::EofWhileParsingArray)),
+                },
+            }
+            #[cfg(feature = "tracing")]
+            tracing::trace!(offset = de.read.offset(), "array end");
+            Ok(value)
+        })
+    }
+
+    fn parse_map<V>(&mut self, mut len: Option<usize>, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(offset = self.read.offset(), len = ?len, "map begin");
+        let structure = self.struct_format.take();
+        let (accept_named, accept_packed) = match structure {
+            Some(format) => (format.accept_named, format.accept_packed),
+            None => (self.options.accept_named(), self.options.accept_packed()),
+        };
+        let declared = len.unwrap_or(0);
+        self.recursion_checked(|de| {
+            let value = visitor.visit_map(MapAccess {
+                de,
+                len: len.as_mut(),
+                read: 0,
+                accept_named,
+                accept_packed,
+                structure,
+                #[cfg(any(feature = "std", feature = "alloc"))]
+                last_key: None,
+            })?;
+
+            match len {
+                Some(0) => (),
+                Some(left) => return Err(de.unread_elements(true, declared, left)),
+                None => match de.next()? {
+                    Some(0xff) => (),
+                    Some(_) => return Err(de.error(ErrorCode::TrailingData)),
+                    None => return Err(de.error(ErrorCode::EofWhileParsingMap)),
+                },
+            }
+            #[cfg(feature = "tracing")]
+            tracing::trace!(offset = de.read.offset(), "map end");
+            Ok(value)
+        })
+    }
+
+    fn parse_enum<V>(&mut self, mut len: Option<usize>, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.recursion_checked(|de| {
+            let value = visitor.visit_enum(VariantAccess {
+                seq: SeqAccess {
+                    de,
+                    len: len.as_mut(),
+                    read: 0,
+                },
+            })?;
+
+            match len {
+                Some(0) => (),
+                Some(_) => return Err(de.error(ErrorCode::TrailingData)),
+                None => match de.next()? {
+                    Some(0xff) => (),
+                    Some(_) => return Err(de.error(ErrorCode::TrailingData)),
+                    None => return Err(de.error(ErrorCode::EofWhileParsingArray)),
+                },
+            }
+            Ok(value)
+        })
+    }
+
+    /// Deserializes an enum in the standard or the legacy format, or a unit variant.
+    fn parse_enum_value<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.peek()? {
+            Some(byte @ 0x80..=0x9b | byte @ 0x9f) => {
+                if !self.options.accept_legacy_enums() {
+                    return Err(self.error(ErrorCode::WrongEnumFormat));
+                }
+                self.consume();
+                self.record_item(byte);
+                match byte {
+                    0x80..=0x9b | 0x9f => {
+                        let len = if byte == 0x9f {
+                            None
+                        } else if byte <= 0x97 {
+                            Some(byte as usize - 0x80)
+                        } else {
+                            let len = self.parse_uint(byte - 0x97)?;
+                            if len > usize::MAX as u64 {
+                                return Err(self.error(ErrorCode::LengthOutOfRange));
+                            }
+                            Some(len as usize)
+                        };
+                        self.check_variant_format()?;
+                        self.parse_enum(len, visitor)
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            Some(0xa1) => {
+                if !self.options.accept_standard_enums() {
+                    return Err(self.error(ErrorCode::WrongEnumFormat));
+                }
+                self.consume();
+                self.record_item(0xa1);
+                self.check_variant_format()?;
+                self.parse_enum_map(visitor)
+            }
+            None => Err(self.error(ErrorCode::EofWhileParsingValue)),
+            _ => {
+                if !self.options.accept_standard_enums() && !self.options.accept_legacy_enums() {
+                    return Err(self.error(ErrorCode::WrongEnumFormat));
+                }
+                self.check_variant_format()?;
+                visitor.visit_enum(UnitVariantAccess { de: self })
+            }
+        }
+    }
+
+    /// Fails if the variant identifier read next is a name while the named format is not
+    /// accepted, or an index while the packed format is not accepted.
+    fn check_variant_format(&mut self) -> Result<()> {
+        let accepted = match self.peek()? {
+            Some(0x00..=0x1b) => self.options.accept_packed(),
+            Some(0x60..=0x7f) => self.options.accept_named(),
+            Some(0x40..=0x5f) if self.options.accept_byte_string_keys() => {
+                self.options.accept_named()
+            }
+            _ => true,
+        };
+        if accepted {
+            Ok(())
+        } else {
+            Err(self.error(ErrorCode::WrongEnumFormat))
+        }
+    }
+
+    fn parse_enum_map<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.recursion_checked(|de| {
+            let mut len = 1;
+            // The variant identifier is checked by `check_variant_format`.
+            let value = visitor.visit_enum(VariantAccessMap {
+                map: MapAccess {
+                    accept_named: true,
+                    accept_packed: true,
+                    structure: None,
+                    de,
+                    len: Some(&mut len),
+                    read: 0,
+                    #[cfg(any(feature = "std", feature = "alloc"))]
+                    last_key: None,
+                },
+            })?;
+
+            if len != 0 {
+                Err(de.error(ErrorCode::TrailingData))
+            } else {
+                Ok(value)
+            }
+        })
+    }
+
+    /// Reads the argument of a header with an additional information value below 28.
+    #[inline]
+    fn parse_argument(&mut self, byte: u8) -> Result<u64> {
+        match byte & 0x1f {
+            info @ 0x00..=0x17 => Ok(u64::from(info)),
+            info => self.parse_uint(info - 0x17),
+        }
+    }
+
+    #[inline]
+    fn parse_length(&mut self, byte: u8) -> Result<usize> {
+        let len = self.parse_argument(byte)?;
+        if len > usize::MAX as u64 {
+            return Err(self.error(ErrorCode::LengthOutOfRange));
+        }
+        Ok(len as usize)
+    }
+
+    /// Deserializes a struct field key, replacing it according to `key_renames` and
+    /// `key_normalization`, and checking that it is one of the fields of `known`.
+    ///
+    /// Text and unsigned integer keys are looked up, and so are byte string keys if
+    /// `accept_byte_string_keys` is set. All other keys are deserialized as usual.
+    fn parse_field_key<K>(&mut self, seed: K, known: Option<StructFormat>) -> Result<K::Value>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        match self.peek()? {
+            Some(byte @ 0x00..=0x1b) => {
+                self.consume();
+                self.record_item(byte);
+                let key = self.parse_argument(byte)?;
+                let renamed = self
+                    .options
+                    .key_renames()
+                    .iter()
+                    .find(|(old, _)| *old == WireKey::Integer(key));
+                if let Some(&(_, new)) = renamed {
+                    check_field(known, WireKey::Text(new))?;
+                    return seed.deserialize(de::value::BorrowedStrDeserializer::new(new));
+                }
+                check_field(known, WireKey::Integer(key))?;
+                seed.deserialize(de::value::U64Deserializer::new(key))
+            }
+            Some(byte @ 0x60..=0x7b) | Some(byte @ 0x7f) => {
+                self.consume();
+                self.record_item(byte);
+                let offset = self.read.offset();
+                let utf8 = self.options.utf8_validation();
+                let lookup = FieldLookup::new(&self.options, known);
+                let buf = if byte == 0x7f {
+                    self.read_indefinite_str()?
+                } else {
+                    let len = self.parse_length(byte)?;
+                    self.record_string(len);
+                    self.read.read(len)?
+                };
+                match buf {
+                    EitherLifetime::Long(buf) => {
+                        let key = Self::convert_str(buf, offset, utf8)?;
+                        lookup.text(seed, key, |seed| {
+                            seed.deserialize(de::value::BorrowedStrDeserializer::new(key))
+                        })
+                    }
+                    EitherLifetime::Short(buf) => {
+                        let key = Self::convert_str(buf, offset, utf8)?;
+                        lookup.text(seed, key, |seed| {
+                            seed.deserialize(de::value::StrDeserializer::new(key))
+                        })
+                    }
+                }
+            }
+            Some(byte @ 0x40..=0x5b) | Some(byte @ 0x5f)
+                if self.options.accept_byte_string_keys() =>
+            {
+                self.consume();
+                self.record_item(byte);
+                let lookup = FieldLookup::new(&self.options, known);
+                let buf = if byte == 0x5f {
+                    self.read_indefinite_bytes()?
+                } else {
+                    let len = self.parse_length(byte)?;
+                    self.record_string(len);
+                    self.read.read(len)?
+                };
+                // the keys are matched as names, so they are only looked up if they are UTF-8
+                match buf {
+                    EitherLifetime::Long(buf) => match str::from_utf8(buf) {
+                        Ok(key) => lookup.text(seed, key, |seed| {
+                            seed.deserialize(de::value::BorrowedBytesDeserializer::new(buf))
+                        }),
+                        Err(_) => {
+                            lookup.check_bytes()?;
+                            seed.deserialize(de::value::BorrowedBytesDeserializer::new(buf))
+                        }
+                    },
+                    EitherLifetime::Short(buf) => match str::from_utf8(buf) {
+                        Ok(key) => lookup.text(seed, key, |seed| {
+                            seed.deserialize(de::value::BytesDeserializer::new(buf))
+                        }),
+                        Err(_) => {
+                            lookup.check_bytes()?;
+                            seed.deserialize(de::value::BytesDeserializer::new(buf))
+                        }
+                    },
+                }
+            }
+            _ => seed.deserialize(self),
+        }
+    }
+
+    /// Decodes a number, applying the coercions enabled in the options.
+    ///
+    /// `float` tells whether the target is a float type, otherwise it is an integer type.
+    fn parse_number<V, Valid>(&mut self, visitor: V, float: bool) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+        Valid: ValidValues,
+    {
+        let integral_floats = !float && self.options.accept_integral_floats();
+        let numeric_strings = self.options.accept_numeric_strings();
+        let integer_bytes = !float && self.options.accept_integer_bytes();
+        if !integral_floats && !numeric_strings && !integer_bytes {
+            return self.parse_value::<_, Valid>(visitor);
+        }
+        match self.peek()? {
+            Some(byte @ 0xf9..=0xfb) if integral_floats => {
+                self.consume();
+                self.record_item(byte);
+                let value = self.parse_float(byte - 0xf9 + 2)?;
+                // the casts truncate, only integral values survive the round trip
+                if (0.0..18_446_744_073_709_551_616.0).contains(&value)
+                    && value as u64 as f64 == value
+                {
+                    visitor.visit_u64(value as u64)
+                } else if (-9_223_372_036_854_775_808.0..0.0).contains(&value)
+                    && value as i64 as f64 == value
+                {
+                    visitor.visit_i64(value as i64)
+                } else {
+                    Err(de::Error::invalid_value(
+                        de::Unexpected::Float(value),
+                        &visitor,
+                    ))
+                }
+            }
+            Some(byte @ 0x60..=0x7b) | Some(byte @ 0x7f) if numeric_strings => {
+                self.consume();
+                self.record_item(byte);
+                let offset = self.read.offset();
+                let utf8 = self.options.utf8_validation();
+                let buf = if byte == 0x7f {
+                    self.read_indefinite_str()?
+                } else {
+                    let len = self.parse_length(byte)?;
+                    self.record_string(len);
+                    self.read.read(len)?
+                };
+                let text = match buf {
+                    EitherLifetime::Long(buf) => Self::convert_str(buf, offset, utf8)?,
+                    EitherLifetime::Short(buf) => Self::convert_str(buf, offset, utf8)?,
+                };
+                let text = text.trim();
+                if float {
+                    match text.parse::<f64>() {
+                        Ok(value) => visitor.visit_f64(value),
+                        Err(_) => Err(de::Error::invalid_value(
+                            de::Unexpected::Str(text),
+                            &visitor,
+                        )),
+                    }
+                } else if let Ok(value) = text.parse::<u64>() {
+                    visitor.visit_u64(value)
+                } else if let Ok(value) = text.parse::<i64>() {
+                    visitor.visit_i64(value)
+                } else {
+                    Err(de::Error::invalid_value(
+                        de::Unexpected::Str(text),
+                        &visitor,
+                    ))
+                }
+            }
+            Some(byte @ 0x40..=0x5b) if integer_bytes => {
+                self.consume();
+                self.record_item(byte);
+                let len = self.parse_length(byte)?;
+                self.record_string(len);
+                if len > 8 {
+                    return Err(de::Error::invalid_length(len, &visitor));
+                }
+                let mut buf = [0; 8];
+                self.read.read_into(&mut buf[8 - len..])?;
+                visitor.visit_u64(u64::from_be_bytes(buf))
+            }
+            _ => self.parse_value::<_, Valid>(visitor),
+        }
+    }
+
+    /// Decodes a text string holding exactly one character.
+    ///
+    /// With `accept_code_point_chars` an unsigned integer holding a Unicode scalar value is
+    /// accepted as well.
+    fn parse_char<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let offset = self.read.offset();
+        match self.peek()? {
+            Some(byte @ 0x60..=0x7b) | Some(byte @ 0x7f) => {
+                self.consume();
+                self.record_item(byte);
+                let str_offset = self.read.offset();
+                let utf8 = self.options.utf8_validation();
+                let buf = if byte == 0x7f {
+                    self.read_indefinite_str()?
+                } else {
+                    let len = self.parse_length(byte)?;
+                    self.record_string(len);
+                    self.read.read(len)?
+                };
+                let text = match buf {
+                    EitherLifetime::Long(buf) => Self::convert_str(buf, str_offset, utf8)?,
+                    EitherLifetime::Short(buf) => Self::convert_str(buf, str_offset, utf8)?,
+                };
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => visitor.visit_char(c),
+                    _ => Err(Error::syntax(
+                        ErrorCode::NotSingleChar {
+                            chars: text.chars().count(),
+                        },
+                        offset,
+                    )),
+                }
+            }
+            Some(byte @ 0x00..=0x1b) if self.options.accept_code_point_chars() => {
+                self.consume();
+                self.record_item(byte);
+                let value = self.parse_argument(byte)?;
+                match u32::try_from(value).ok().and_then(char::from_u32) {
+                    Some(c) => visitor.visit_char(c),
+                    None => Err(Error::syntax(
+                        ErrorCode::NumberOutOfRange {
+                            value: value.into(),
+                            target: "char",
+                        },
+                        offset,
+                    )),
+                }
+            }
+            _ => self.parse_value::<_, ValidForString>(visitor),
+        }
     }
 
-    #[cfg(not(feature = "tags"))]
-    fn handle_tagged_value<V, Valid>(&mut self, _tag: u64, visitor: V) -> Result<V::Value>
+    /// Decodes an integer that has to fit into the type `target` with the bounds `min` and `max`.
+    ///
+    /// Values outside of these bounds fail with an error carrying the value, the target type and
+    /// the offset of the item, instead of the visitor's error without any position.
+    fn parse_ranged<V, Valid>(
+        &mut self,
+        visitor: V,
+        min: i128,
+        max: i128,
+        target: &'static str,
+    ) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
         Valid: ValidValues,
     {
-        self.recursion_checked(|de| de.parse_value::<_, Valid>(visitor))
+        let offset = self.read.offset();
+        if !Valid::INT_NEG {
+            // negative integers are out of range as well, not of an unexpected type
+            if let Some(byte @ 0x20..=0x3b) = self.peek()? {
+                self.consume();
+                self.record_item(byte);
+                let value = -1 - i128::from(self.parse_argument(byte)?);
+                return Err(Error::syntax(
+                    ErrorCode::NumberOutOfRange { value, target },
+                    offset,
+                ));
+            }
+        }
+        let checked = RangeChecked {
+            visitor,
+            range: min..=max,
+        };
+        match self.parse_number::<_, Valid>(checked, false)? {
+            Ok(value) => Ok(value),
+            Err(value) => Err(Error::syntax(
+                ErrorCode::NumberOutOfRange { value, target },
+                offset,
+            )),
+        }
     }
 
-    #[inline]
-    fn recursion_checked<F, T>(&mut self, f: F) -> Result<T>
-    where
-        F: FnOnce(&mut Deserializer<R, O>) -> Result<T>,
-    {
-        self.remaining_depth -= 1;
-        if self.remaining_depth == 0 {
-            return Err(self.error(ErrorCode::RecursionLimitExceeded));
+    /// Skips over the next data item without visiting it.
+    ///
+    /// Only the headers are decoded and no values are built. The input is still checked like by
+    /// `parse_value`: it has to be well-formed, text strings are validated according to
+    /// `DeserializerOptions::utf8_validation` and simple values are accepted according to
+    /// `DeserializerOptions::simple_values`.
+    pub(crate) fn skip_value(&mut self) -> Result<()> {
+        let byte = self.parse_u8()?;
+        self.record_item(byte);
+        match byte {
+            0x00..=0x1b | 0x20..=0x3b => {
+                self.parse_argument(byte)?;
+                Ok(())
+            }
+            0x40..=0x5b | 0x60..=0x7b => {
+                let len = self.parse_length(byte)?;
+                self.record_string(len);
+                self.skip_string(byte, len)
+            }
+            0x5f | 0x7f => {
+                let mut total = 0usize;
+                let mut chunks = 0;
+                loop {
+                    match self.parse_u8()? {
+                        0xff => {
+                            self.record_indefinite_string(total);
+                            return Ok(());
+                        }
+                        chunk if chunk & 0xe0 == byte & 0xe0 && chunk & 0x1f < 0x1c => {
+                            self.count_chunk(&mut chunks)?;
+                            let len = self.parse_length(chunk)?;
+                            total = total.saturating_add(len);
+                            self.skip_string(chunk, len)?;
+                        }
+                        chunk => {
+                            let expected = if byte == 0x5f {
+                                ExpectedSet::BYTES
+                            } else {
+                                ExpectedSet::STRING
+                            };
+                            return Err(self.error(ErrorCode::UnexpectedCode(expected, chunk)));
+                        }
+                    }
+                }
+            }
+            0x80..=0x9b => {
+                let len = self.parse_length(byte)?;
+                self.recursion_checked(|de| {
+                    for _ in 0..len {
+                        de.skip_value()?;
+                    }
+                    Ok(())
+                })
+            }
+            0x9f => self.recursion_checked(|de| {
+                let mut elements = 0;
+                loop {
+                    match de.peek()? {
+                        Some(0xff) => {
+                            de.consume();
+                            return Ok(());
+                        }
+                        Some(_) => {
+                            de.count_chunk(&mut elements)?;
+                            de.skip_value()?;
+                        }
+                        None => return Err(de.error(ErrorCode::EofWhileParsingArray)),
+                    }
+                }
+            }),
+            0xa0..=0xbb => {
+                let len = self.parse_length(byte)?;
+                self.recursion_checked(|de| de.skip_entries(Some(len)))
+            }
+            0xbf => self.recursion_checked(|de| de.skip_entries(None)),
+            0xc0..=0xdb => {
+                self.parse_argument(byte)?;
+                self.recursion_checked(|de| de.skip_value())
+            }
+            0xe0..=0xf3 => self.skip_simple_value(byte, byte - 0xe0),
+            0xf4..=0xf7 => Ok(()),
+            0xf8 => {
+                let value = self.parse_simple()?;
+                self.skip_simple_value(byte, value)
+            }
+            0xf9..=0xfb => {
+                let mut buf = [0; 8];
+                self.read.read_into(&mut buf[..1 << (byte - 0xf8)])
+            }
+            _ => Err(self.error(ErrorCode::UnexpectedCode(
+                ExpectedSet::from_valid::<ValidAll>(),
+                byte,
+            ))),
         }
-        let r = f(self);
-        self.remaining_depth += 1;
-        r
     }
 
-    fn parse_array<V>(&mut self, mut len: Option<usize>, visitor: V) -> Result<V::Value>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.recursion_checked(|de| {
-            let value = visitor.visit_seq(SeqAccess {
-                de,
-                len: len.as_mut(),
-            })?;
-
+    /// Skips the entries of a map with `len` entries, or up to the break code, checking the order
+    /// of the keys for `DeserializerOptions::key_order`.
+    fn skip_entries(&mut self, len: Option<usize>) -> Result<()> {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        let mut last_key = None;
+        let mut entries = 0;
+        loop {
             match len {
-                Some(0) => (),
-                Some(_) => return Err(de.error(ErrorCode::TrailingData)),
-                None => match de.next()? {
-                    Some(0xff) => (),
-                    Some(_) => return Err(de.error(ErrorCode::TrailingData)),
-                    None => return Err(de.error(ErrorCode::EofWhileParsingArray)),
+                Some(len) if entries == len => return Ok(()),
+                Some(_) => entries += 1,
+                None => match self.peek()? {
+                    Some(0xff) => {
+                        self.consume();
+                        return Ok(());
+                    }
+                    Some(_) => self.count_chunk(&mut entries)?,
+                    None => return Err(self.error(ErrorCode::EofWhileParsingMap)),
                 },
             }
-            Ok(value)
-        })
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            match self.options.key_order() {
+                Some(order) => self.ordered_key(order, &mut last_key, |de| de.skip_value())?,
+                None => self.skip_value()?,
+            }
+            #[cfg(not(any(feature = "std", feature = "alloc")))]
+            self.skip_value()?;
+            self.skip_value()?;
+        }
     }
 
-    fn parse_map<V>(&mut self, mut len: Option<usize>, visitor: V) -> Result<V::Value>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.recursion_checked(|de| {
-            let value = visitor.visit_map(MapAccess {
-                de,
-                len: len.as_mut(),
-            })?;
-
-            match len {
-                Some(0) => (),
-                Some(_) => return Err(de.error(ErrorCode::TrailingData)),
-                None => match de.next()? {
-                    Some(0xff) => (),
-                    Some(_) => return Err(de.error(ErrorCode::TrailingData)),
-                    None => return Err(de.error(ErrorCode::EofWhileParsingMap)),
-                },
+    /// Skips a string of `len` bytes, checking that it is valid UTF-8 if `byte` is the header of
+    /// a text string and `Utf8Validation::STRICT` is used.
+    fn skip_string(&mut self, byte: u8, len: usize) -> Result<()> {
+        let offset = self.read.offset();
+        let strict = byte >= 0x60 && self.options.utf8_validation().0 == Utf8Mode::Strict;
+        match self.read.read(len)? {
+            EitherLifetime::Long(buf) if strict => {
+                Self::convert_str(buf, offset, Utf8Validation::STRICT)?;
             }
-            Ok(value)
-        })
+            EitherLifetime::Short(buf) if strict => {
+                Self::convert_str(buf, offset, Utf8Validation::STRICT)?;
+            }
+            _ => {}
+        }
+        Ok(())
     }
 
-    fn parse_enum<V>(&mut self, mut len: Option<usize>, visitor: V) -> Result<V::Value>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.recursion_checked(|de| {
-            let value = visitor.visit_enum(VariantAccess {
-                seq: SeqAccess {
-                    de,
-                    len: len.as_mut(),
-                },
-            })?;
+    /// Checks that the simple value `value` with the initial byte `byte` is accepted by
+    /// `DeserializerOptions::simple_values`.
+    fn skip_simple_value(&mut self, byte: u8, value: u8) -> Result<()> {
+        let accepted = match self.options.simple_values() {
+            SimpleValues::Reject => false,
+            SimpleValues::Number => true,
+            SimpleValues::Map(map) => map(value).is_some(),
+        };
+        if accepted {
+            Ok(())
+        } else {
+            Err(self.error(ErrorCode::UnexpectedCode(
+                ExpectedSet::from_valid::<ValidAll>(),
+                byte,
+            )))
+        }
+    }
 
-            match len {
-                Some(0) => (),
-                Some(_) => return Err(de.error(ErrorCode::TrailingData)),
-                None => match de.next()? {
-                    Some(0xff) => (),
-                    Some(_) => return Err(de.error(ErrorCode::TrailingData)),
-                    None => return Err(de.error(ErrorCode::EofWhileParsingArray)),
-                },
-            }
-            Ok(value)
-        })
+    /// Reads the value of a two byte simple value.
+    fn parse_simple(&mut self) -> Result<u8> {
+        let value = self.parse_u8()?;
+        if value < 32 {
+            return Err(self.error(ErrorCode::UnexpectedCode(
+                ExpectedSet::from_valid::<ValidAll>(),
+                0xf8,
+            )));
+        }
+        Ok(value)
     }
 
-    fn parse_enum_map<V>(&mut self, visitor: V) -> Result<V::Value>
+    /// Visits the simple value `value` with the initial byte `byte` according to
+    /// `DeserializerOptions::simple_values`.
+    #[cold]
+    fn parse_simple_value<V, Valid>(&mut self, byte: u8, value: u8, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
+        Valid: ValidValues,
     {
-        self.recursion_checked(|de| {
-            let mut len = 1;
-            let value = visitor.visit_enum(VariantAccessMap {
-                map: MapAccess {
-                    de,
-                    len: Some(&mut len),
-                },
-            })?;
-
-            if len != 0 {
-                Err(de.error(ErrorCode::TrailingData))
-            } else {
-                Ok(value)
+        let value = match self.options.simple_values() {
+            SimpleValues::Reject => None,
+            SimpleValues::Number => Some(value),
+            SimpleValues::Map(map) => map(value),
+        };
+        match value {
+            Some(20..=21) if Valid::BOOL => visitor.visit_bool(value == Some(21)),
+            Some(22..=23) if Valid::NULL => visitor.visit_unit(),
+            Some(value @ (0..=19 | 24..)) if Valid::INT_POS => {
+                #[cfg(feature = "std")]
+                return crate::value::visit_simple(visitor, value);
+                #[cfg(not(feature = "std"))]
+                visitor.visit_u64(value.into())
             }
-        })
+            _ => Err(self.error(ErrorCode::UnexpectedCode(
+                ExpectedSet::from_valid::<Valid>(),
+                byte,
+            ))),
+        }
     }
 
     #[inline]
@@ -654,117 +3120,112 @@ where
         Valid: ValidValues,
     {
         let byte = self.parse_u8()?;
+        self.record_item(byte);
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::trace_span!("parse_value", offset = self.read.offset() - 1, byte).entered();
         match byte {
             // Major type 0: an unsigned integer
             0x00..=0x1b if Valid::INT_POS => {
-                let value = if byte <= 0x17 {
-                    byte as u64
-                } else {
-                    self.parse_uint(byte - 0x17)?
-                };
+                let value = self.parse_argument(byte)?;
+                #[cfg(feature = "std")]
+                if self.options.preserve_integer_widths() {
+                    let width = crate::value::IntegerWidth::from_header(byte);
+                    return crate::value::visit_sized_integer(visitor, value.into(), width);
+                }
                 visitor.visit_u64(value)
             }
 
             // Major type 1: a negative integer
             0x20..=0x3b if Valid::INT_NEG => {
-                let u_value = if byte <= 0x37 {
-                    (byte - 0x20) as u64
-                } else {
-                    let u_value = self.parse_uint(byte - 0x37)?;
-                    if u_value > i64::max_value() as u64 {
-                        return visitor.visit_i128(-1 - i128::from(u_value));
-                    }
-                    u_value
-                };
+                let u_value = self.parse_argument(byte)?;
+                #[cfg(feature = "std")]
+                if self.options.preserve_integer_widths() {
+                    let width = crate::value::IntegerWidth::from_header(byte);
+                    let value = -1 - i128::from(u_value);
+                    return crate::value::visit_sized_integer(visitor, value, width);
+                }
+                if u_value > i64::MAX as u64 {
+                    return visitor.visit_i128(-1 - i128::from(u_value));
+                }
                 visitor.visit_i64(-1 - u_value as i64)
             }
 
             // Major type 2: a byte string
-            0x40..=0x5b | 0x5f if Valid::BYTES => {
-                let len = if byte == 0x5f {
-                    None
-                } else if byte <= 0x57 {
-                    Some(byte as usize - 0x40)
-                } else {
-                    let len = self.parse_uint(byte - 0x57)?;
-                    if len > usize::max_value() as u64 {
-                        return Err(self.error(ErrorCode::LengthOutOfRange));
-                    }
-                    Some(len as usize)
-                };
-                self.parse_bytes(len, visitor)
+            0x40..=0x5b if Valid::BYTES => {
+                let len = self.parse_length(byte)?;
+                self.parse_bytes(Some(len), visitor)
             }
+            0x5f if Valid::BYTES => self.parse_bytes(None, visitor),
 
             // Major type 3: a text string
-            0x60..=0x7b | 0x7f if Valid::STRING => {
-                let len = if byte == 0x7f {
-                    None
-                } else if byte <= 0x77 {
-                    Some(byte as usize - 0x60)
-                } else {
-                    let len = self.parse_uint(byte - 0x77)?;
-                    if len > usize::max_value() as u64 {
-                        return Err(self.error(ErrorCode::LengthOutOfRange));
-                    }
-                    Some(len as usize)
-                };
-                self.parse_str(len, visitor)
+            0x60..=0x7b if Valid::STRING => {
+                let len = self.parse_length(byte)?;
+                self.parse_str(Some(len), visitor)
             }
+            0x7f if Valid::STRING => self.parse_str(None, visitor),
 
             // Major type 4: an array of data items
-            0x80..=0x9b | 0x9f if Valid::ARRAY => {
-                let len = if byte == 0x9f {
-                    None
-                } else if byte <= 0x97 {
-                    Some(byte as usize - 0x80)
-                } else {
-                    let len = self.parse_uint(byte - 0x97)?;
-                    if len > usize::max_value() as u64 {
-                        return Err(self.error(ErrorCode::LengthOutOfRange));
-                    }
-                    Some(len as usize)
-                };
-                self.parse_array(len, visitor)
+            0x80..=0x9b if Valid::ARRAY => {
+                let len = self.parse_length(byte)?;
+                self.parse_array(Some(len), visitor)
             }
+            0x9f if Valid::ARRAY => self.parse_array(None, visitor),
 
             // Major type 5: a map of pairs of data items
-            0xa0..=0xbb | 0xbf if Valid::MAP => {
-                let len = if byte == 0xbf {
-                    None
-                } else if byte <= 0xb7 {
-                    Some(byte as usize - 0xa0)
-                } else {
-                    let len = self.parse_uint(byte - 0xb7)?;
-                    if len > usize::max_value() as u64 {
-                        return Err(self.error(ErrorCode::LengthOutOfRange));
-                    }
-                    Some(len as usize)
-                };
-                self.parse_map(len, visitor)
+            0xa0..=0xbb if Valid::MAP => {
+                let len = self.parse_length(byte)?;
+                self.parse_map(Some(len), visitor)
             }
+            0xbf if Valid::MAP => self.parse_map(None, visitor),
 
             // Major type 6: optional semantic tagging of other major types
             0xc0..=0xdb => {
-                let tag = if byte <= 0xd7 {
-                    byte as u64 - 0xc0
-                } else {
-                    self.parse_uint(byte - 0xd7)?
-                };
+                let tag = self.parse_argument(byte)?;
                 self.handle_tagged_value::<_, Valid>(tag, visitor)
             }
 
             // Major type 7: floating-point numbers and other simple data types that need no content
             0xf4..=0xf5 if Valid::BOOL => visitor.visit_bool(byte == 0xf5),
             0xf6..=0xf7 if Valid::NULL => visitor.visit_unit(),
-            0xf9..=0xfb if Valid::FLOAT => {
-                let value = self.parse_float(byte - 0xf9 + 2)?;
-                visitor.visit_f64(value)
+            0xe0..=0xf3 => self.parse_simple_value::<_, Valid>(byte, byte - 0xe0, visitor),
+            0xf8 => {
+                let value = self.parse_simple()?;
+                self.parse_simple_value::<_, Valid>(byte, value, visitor)
             }
-            _ => Err(self.error(ErrorCode::UnexpectedCode(
-                ExpectedSet::from_valid::<Valid>(),
-                byte,
-            ))),
+            0xf9..=0xfb if Valid::FLOAT => self.parse_float_value(byte, visitor),
+
+            _ => Err(self.unexpected_code::<Valid>(byte)),
+        }
+    }
+
+    /// Reads a float with the initial byte `byte` and visits it.
+    #[inline]
+    fn parse_float_value<V>(&mut self, byte: u8, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        if byte == 0xf9 && self.options.half_floats_as_f32() {
+            let mut buf = [0; 2];
+            self.read.read_into(&mut buf)?;
+            return visitor.visit_f32(f16::from_be_bytes(buf).to_f32());
+        }
+        let value = self.parse_float(byte - 0xf9 + 2)?;
+        #[cfg(feature = "std")]
+        if self.options.preserve_float_widths() {
+            let width = crate::value::FloatWidth::from_header(byte);
+            return crate::value::visit_sized_float(visitor, value, width);
         }
+        visitor.visit_f64(value)
+    }
+
+    /// The error for an initial byte that does not start any of the `Valid` items.
+    #[cold]
+    fn unexpected_code<Valid: ValidValues>(&self, byte: u8) -> Error {
+        self.error(ErrorCode::UnexpectedCode(
+            ExpectedSet::from_valid::<Valid>(),
+            byte,
+        ))
     }
 }
 
@@ -780,7 +3241,7 @@ where
     where
         V: de::Visitor<'de>,
     {
-        self.parse_value::<_, ValidAll>(visitor)
+        self.placed(|de| de.parse_value::<_, ValidAll>(visitor))
     }
 
     #[inline]
@@ -788,13 +3249,37 @@ where
     where
         V: de::Visitor<'de>,
     {
-        match self.peek()? {
-            Some(0xf6) => {
-                self.consume();
+        self.placed(|de| match de.peek()? {
+            Some(byte @ 0xf6) => {
+                de.consume();
+                de.record_item(byte);
                 visitor.visit_none()
             }
-            _ => visitor.visit_some(self),
-        }
+            Some(byte @ 0xf7) if de.options.undefined_as_none() => {
+                de.consume();
+                de.record_item(byte);
+                visitor.visit_none()
+            }
+            Some(0xc0..=0xdb) if de.options.tagged_null_as_none() => match de.tagged_null_len() {
+                Some(len) => {
+                    de.skip_tagged_null(len)?;
+                    visitor.visit_none()
+                }
+                None => visitor.visit_some(de),
+            },
+            Some(byte @ (0xe0..=0xf3 | 0xf8)) => match de.mapped_null_len(byte) {
+                Some(len) => {
+                    de.consume();
+                    de.record_item(byte);
+                    if len == 2 {
+                        de.parse_u8()?;
+                    }
+                    visitor.visit_none()
+                }
+                None => visitor.visit_some(de),
+            },
+            _ => visitor.visit_some(de),
+        })
     }
 
     #[inline]
@@ -802,7 +3287,7 @@ where
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        self.placed(|de| visitor.visit_newtype_struct(de))
     }
 
     // Unit variants are encoded as just the variant identifier.
@@ -812,132 +3297,124 @@ where
     fn deserialize_enum<V>(
         self,
         _name: &str,
-        _variants: &'static [&'static str],
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        match self.peek()? {
-            Some(byte @ 0x80..=0x9b | byte @ 0x9f) => {
-                if !self.options.accept_legacy_enums() {
-                    return Err(self.error(ErrorCode::WrongEnumFormat));
-                }
-                self.consume();
-                match byte {
-                    0x80..=0x9b | 0x9f => {
-                        let len = if byte == 0x9f {
-                            None
-                        } else if byte <= 0x97 {
-                            Some(byte as usize - 0x80)
-                        } else {
-                            let len = self.parse_uint(byte - 0x97)?;
-                            if len > usize::max_value() as u64 {
-                                return Err(self.error(ErrorCode::LengthOutOfRange));
-                            }
-                            Some(len as usize)
-                        };
-                        self.parse_enum(len, visitor)
-                    }
-                    _ => unreachable!(),
-                }
-            }
-            Some(0xa1) => {
-                if !self.options.accept_standard_enums() {
-                    return Err(self.error(ErrorCode::WrongEnumFormat));
-                }
-                self.consume();
-                self.parse_enum_map(visitor)
-            }
-            None => Err(self.error(ErrorCode::EofWhileParsingValue)),
-            _ => {
-                if !self.options.accept_standard_enums() && !self.options.accept_legacy_enums() {
-                    return Err(self.error(ErrorCode::WrongEnumFormat));
-                }
-                visitor.visit_enum(UnitVariantAccess { de: self })
+        match self.options.variant_resolver() {
+            Some(resolver) => {
+                let visitor = ResolveVariant {
+                    inner: visitor,
+                    variants,
+                    resolver,
+                };
+                self.placed(|de| de.parse_enum_value(visitor))
             }
+            None => self.placed(|de| de.parse_enum_value(visitor)),
         }
     }
 
     #[inline]
     fn is_human_readable(&self) -> bool {
-        false
+        self.options.human_readable()
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.parse_value::<_, ValidForBool>(visitor)
+        self.placed(|de| de.parse_value::<_, ValidForBool>(visitor))
     }
 
     fn deserialize_i8<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_i64(visitor)
+        self.placed(|de| {
+            de.parse_ranged::<_, ValidForSInt>(visitor, i8::MIN.into(), i8::MAX.into(), "i8")
+        })
     }
 
     fn deserialize_i16<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_i64(visitor)
+        self.placed(|de| {
+            de.parse_ranged::<_, ValidForSInt>(visitor, i16::MIN.into(), i16::MAX.into(), "i16")
+        })
     }
 
     fn deserialize_i32<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_i64(visitor)
+        self.placed(|de| {
+            de.parse_ranged::<_, ValidForSInt>(visitor, i32::MIN.into(), i32::MAX.into(), "i32")
+        })
     }
 
     fn deserialize_i64<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.parse_value::<_, ValidForSInt>(visitor)
+        self.placed(|de| de.parse_number::<_, ValidForSInt>(visitor, false))
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_u64(visitor)
+        self.placed(|de| {
+            de.parse_ranged::<_, ValidForUInt>(visitor, u8::MIN.into(), u8::MAX.into(), "u8")
+        })
     }
 
     fn deserialize_u16<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_u64(visitor)
+        self.placed(|de| {
+            de.parse_ranged::<_, ValidForUInt>(visitor, u16::MIN.into(), u16::MAX.into(), "u16")
+        })
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_u64(visitor)
+        self.placed(|de| {
+            de.parse_ranged::<_, ValidForUInt>(visitor, u32::MIN.into(), u32::MAX.into(), "u32")
+        })
     }
 
     fn deserialize_u64<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.parse_value::<_, ValidForUInt>(visitor)
+        self.placed(|de| {
+            de.parse_ranged::<_, ValidForUInt>(visitor, u64::MIN.into(), u64::MAX.into(), "u64")
+        })
     }
 
     fn deserialize_str<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.parse_value::<_, ValidForString>(visitor)
+        self.placed(|de| de.parse_value::<_, ValidForString>(visitor))
     }
 
     fn deserialize_string<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if let Some(byte @ 0x60..=0x7b) = self.peek()? {
+                return self.placed(|de| de.parse_owned_str(byte, visitor));
+            }
+        }
         self.deserialize_str(visitor)
     }
 
@@ -945,32 +3422,58 @@ where
     where
         V: de::Visitor<'de>,
     {
-        self.parse_value::<_, ValidForMap>(visitor)
+        self.placed(|de| de.parse_value::<_, ValidForMap>(visitor))
     }
 
     fn deserialize_struct<V>(
         self,
-        _name: &'static str,
-        _fields: &'static [&'static str],
+        name: &'static str,
+        fields: &'static [&'static str],
         visitor: V,
     ) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_map(visitor)
+        if let Some(0xa0..=0xbb) | Some(0xbf) = self.peek()? {
+            let (accept_named, accept_packed) = if self.options.packed_structs().contains(&name) {
+                (false, true)
+            } else if self.options.named_structs().contains(&name) {
+                (true, false)
+            } else {
+                (self.options.accept_named(), self.options.accept_packed())
+            };
+            self.struct_format = Some(StructFormat {
+                name,
+                fields,
+                accept_named,
+                accept_packed,
+            });
+        }
+        let value = (&mut *self).deserialize_map(visitor);
+        self.struct_format = None;
+        value
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.parse_value::<_, ValidForStringAndUInt>(visitor)
+        if self.options.accept_byte_string_keys() {
+            return self.placed(|de| de.parse_value::<_, ValidForIdentifier>(visitor));
+        }
+        self.placed(|de| de.parse_value::<_, ValidForStringAndUInt>(visitor))
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if let Some(byte @ 0x40..=0x5b) = self.peek()? {
+                return self.placed(|de| de.parse_owned_bytes(byte, visitor));
+            }
+        }
         self.deserialize_bytes(visitor)
     }
 
@@ -978,6 +3481,19 @@ where
     where
         V: de::Visitor<'de>,
     {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if self.options.decode_expected_encodings() {
+                if let Some(byte @ 0xd5..=0xd7) = self.peek()? {
+                    self.consume();
+                    self.record_item(byte);
+                    let encoding = ExpectedEncoding::from_tag(u64::from(byte - 0xc0)).unwrap();
+                    return self.placed(|de| {
+                        de.recursion_checked(|de| de.parse_encoded_bytes(encoding, visitor))
+                    });
+                }
+            }
+        }
         self.parse_value::<_, ValidForBytes>(visitor)
     }
 
@@ -985,14 +3501,14 @@ where
     where
         V: de::Visitor<'de>,
     {
-        self.parse_value::<_, ValidForSeq>(visitor)
+        self.placed(|de| de.parse_value::<_, ValidForSeq>(visitor))
     }
 
     fn deserialize_char<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_str(visitor)
+        self.placed(|de| de.parse_char(visitor))
     }
 
     fn deserialize_f32<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
@@ -1006,14 +3522,14 @@ where
     where
         V: de::Visitor<'de>,
     {
-        self.parse_value::<_, ValidForFloat>(visitor)
+        self.placed(|de| de.parse_number::<_, ValidForFloat>(visitor, true))
     }
 
     fn deserialize_unit<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.parse_value::<_, ValidForUnit>(visitor)
+        self.placed(|de| de.parse_value::<_, ValidForUnit>(visitor))
     }
 
     fn deserialize_unit_struct<V>(
@@ -1027,11 +3543,14 @@ where
         self.deserialize_unit(visitor)
     }
 
-    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> result::Result<V::Value, Self::Error>
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> result::Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        match self.peek()? {
+            Some(0x40..=0x5b) | Some(0x5f) => self.placed(|de| de.parse_byte_array(len, visitor)),
+            _ => self.deserialize_seq(visitor),
+        }
     }
 
     fn deserialize_tuple_struct<V>(
@@ -1050,7 +3569,10 @@ where
     where
         V: de::Visitor<'de>,
     {
-        self.parse_value::<_, ValidAll>(visitor)
+        self.placed(|de| {
+            de.skip_value()?;
+            visitor.visit_unit()
+        })
     }
 
     fn deserialize_i128<V>(self, visitor: V) -> result::Result<V::Value, Self::Error>
@@ -1087,6 +3609,8 @@ trait MakeError {
 struct SeqAccess<'a, R, O> {
     de: &'a mut Deserializer<R, O>,
     len: Option<&'a mut usize>,
+    /// The number of elements of an indefinite length array read so far.
+    read: usize,
 }
 
 impl<'de, 'a, R, O> de::SeqAccess<'de> for SeqAccess<'a, R, O>
@@ -1108,12 +3632,12 @@ where
         } else {
             match self.de.peek()? {
                 Some(0xff) => return Ok(None),
-                Some(_) => (),
+                Some(_) => self.de.count_chunk(&mut self.read)?,
                 None => return Err(self.de.error(ErrorCode::EofWhileParsingArray)),
             }
         }
 
-        let value = seed.deserialize(&mut *self.de)?;
+        let value = self.de.placed(|de| seed.deserialize(de))?;
         Ok(Some(value))
     }
 
@@ -1135,6 +3659,15 @@ where
 struct MapAccess<'a, R, O> {
     de: &'a mut Deserializer<R, O>,
     len: Option<&'a mut usize>,
+    /// The number of entries of an indefinite length map read so far.
+    read: usize,
+    accept_named: bool,
+    accept_packed: bool,
+    /// The struct the map is decoded into, if any.
+    structure: Option<StructFormat>,
+    /// The previous encoded key, for `DeserializerOptions::key_order`.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    last_key: Option<Vec<u8>>,
 }
 
 impl<'de, 'a, R, O> de::MapAccess<'de> for MapAccess<'a, R, O>
@@ -1156,7 +3689,7 @@ where
         } else {
             match self.de.peek()? {
                 Some(0xff) => return Ok(None),
-                Some(_) => (),
+                Some(_) => self.de.count_chunk(&mut self.read)?,
                 None => return Err(self.de.error(ErrorCode::EofWhileParsingMap)),
             }
         }
@@ -1164,27 +3697,58 @@ where
         // TODO: the accept_packed check is broken here. If `accept packed` is `false`
         // the map deserializer will refuse integer keys (which are valid in cbor),
         // erroring with WrongStructFormat.
-        if !self.de.options.accept_named() || !self.de.options.accept_packed() {
+        if !self.accept_named || !self.accept_packed {
             match self.de.peek()? {
-                Some(_byte @ 0x00..=0x1b) if !self.de.options.accept_packed() => {
+                Some(_byte @ 0x00..=0x1b) if !self.accept_packed => {
                     return Err(self.de.error(ErrorCode::WrongStructFormat));
                 }
-                Some(_byte @ 0x60..=0x7f) if !self.de.options.accept_named() => {
+                Some(_byte @ 0x60..=0x7f) if !self.accept_named => {
+                    return Err(self.de.error(ErrorCode::WrongStructFormat));
+                }
+                Some(_byte @ 0x40..=0x5f)
+                    if !self.accept_named && self.de.options.accept_byte_string_keys() =>
+                {
                     return Err(self.de.error(ErrorCode::WrongStructFormat));
                 }
                 _ => {}
             };
         }
 
-        let value = seed.deserialize(&mut *self.de)?;
-        Ok(Some(value))
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        let normalized = self.de.options.key_normalization() != KeyNormalization::NONE;
+        #[cfg(not(any(feature = "std", feature = "alloc")))]
+        let normalized = false;
+        let structure = self.structure;
+        let parse_key = move |de: &mut Deserializer<R, O>| match structure {
+            Some(format)
+                if de.options.deny_unknown_fields()
+                    || normalized
+                    || !de.options.key_renames().is_empty() =>
+            {
+                let known = if de.options.deny_unknown_fields() {
+                    Some(format)
+                } else {
+                    None
+                };
+                de.placed(|de| de.parse_field_key(seed, known))
+            }
+            _ => de.placed(|de| seed.deserialize(de)),
+        };
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        if let Some(order) = self.de.options.key_order() {
+            return self
+                .de
+                .ordered_key(order, &mut self.last_key, parse_key)
+                .map(Some);
+        }
+        parse_key(self.de).map(Some)
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
     where
         V: de::DeserializeSeed<'de>,
     {
-        seed.deserialize(&mut *self.de)
+        self.de.placed(|de| seed.deserialize(de))
     }
 
     fn size_hint(&self) -> Option<usize> {
@@ -1202,6 +3766,132 @@ where
     }
 }
 
+/// Wraps the visitor of an enum, its enum access and the seed of its variant identifier, so that
+/// the identifier is resolved with a `VariantResolver` before the enum sees it.
+struct ResolveVariant<T> {
+    inner: T,
+    variants: &'static [&'static str],
+    resolver: VariantResolver,
+}
+
+impl<'de, V> de::Visitor<'de> for ResolveVariant<V>
+where
+    V: de::Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.expecting(formatter)
+    }
+
+    fn visit_enum<A>(self, data: A) -> result::Result<V::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        self.inner.visit_enum(ResolveVariant {
+            inner: data,
+            variants: self.variants,
+            resolver: self.resolver,
+        })
+    }
+}
+
+impl<'de, A> de::EnumAccess<'de> for ResolveVariant<A>
+where
+    A: de::EnumAccess<'de>,
+{
+    type Error = A::Error;
+    type Variant = A::Variant;
+
+    fn variant_seed<S>(self, seed: S) -> result::Result<(S::Value, A::Variant), A::Error>
+    where
+        S: de::DeserializeSeed<'de>,
+    {
+        self.inner.variant_seed(ResolveVariant {
+            inner: seed,
+            variants: self.variants,
+            resolver: self.resolver,
+        })
+    }
+}
+
+impl<'de, S> de::DeserializeSeed<'de> for ResolveVariant<S>
+where
+    S: de::DeserializeSeed<'de>,
+{
+    type Value = S::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> result::Result<S::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(VariantIdentifier(self))
+    }
+}
+
+/// Reads a variant identifier and hands the variant name it resolves to to the seed.
+struct VariantIdentifier<S>(ResolveVariant<S>);
+
+impl<'de, S> VariantIdentifier<S>
+where
+    S: de::DeserializeSeed<'de>,
+{
+    fn resolve<E>(self, key: WireKey<'_>) -> result::Result<S::Value, E>
+    where
+        E: de::Error,
+    {
+        let ResolveVariant {
+            inner: seed,
+            variants,
+            resolver,
+        } = self.0;
+        match resolver(variants, key) {
+            Some(name) => seed.deserialize(de::value::BorrowedStrDeserializer::new(name)),
+            None => Err(match key {
+                WireKey::Text(name) => E::unknown_variant(name, variants),
+                WireKey::Integer(index) => {
+                    E::invalid_value(de::Unexpected::Unsigned(index), &"a known variant index")
+                }
+            }),
+        }
+    }
+}
+
+impl<'de, S> de::Visitor<'de> for VariantIdentifier<S>
+where
+    S: de::DeserializeSeed<'de>,
+{
+    type Value = S::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a variant identifier")
+    }
+
+    fn visit_u64<E>(self, v: u64) -> result::Result<S::Value, E>
+    where
+        E: de::Error,
+    {
+        self.resolve(WireKey::Integer(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> result::Result<S::Value, E>
+    where
+        E: de::Error,
+    {
+        self.resolve(WireKey::Text(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> result::Result<S::Value, E>
+    where
+        E: de::Error,
+    {
+        match str::from_utf8(v) {
+            Ok(v) => self.resolve(WireKey::Text(v)),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Bytes(v), &self)),
+        }
+    }
+}
+
 struct UnitVariantAccess<'a, R, O> {
     de: &'a mut Deserializer<R, O>,
 }
@@ -1380,6 +4070,13 @@ where
 #[derive(Debug)]
 pub struct StreamDeserializer<'de, R, T, O = DefaultDeserializerOptions> {
     de: Deserializer<R, O>,
+    max_items: Option<u64>,
+    max_bytes: Option<u64>,
+    /// The number of items decoded since the budget was last reset.
+    items: u64,
+    /// The offset at which the budget was last reset.
+    budget_start: u64,
+    scratch_capacity: Option<usize>,
     output: PhantomData<T>,
     lifetime: PhantomData<&'de ()>,
 }
@@ -1397,11 +4094,83 @@ where
     /// * `Deserializer::from_slice(...).into_iter()`
     /// * `Deserializer::from_reader(...).into_iter()`
     pub fn new(read: R) -> StreamDeserializer<'de, R, T> {
-        StreamDeserializer {
-            de: Deserializer::new(read),
-            output: PhantomData,
-            lifetime: PhantomData,
-        }
+        Deserializer::new(read).into_iter()
+    }
+}
+
+impl<'de, R, T, O> StreamDeserializer<'de, R, T, O>
+where
+    R: Read<'de>,
+    T: de::Deserialize<'de>,
+    O: DeserializerOptions,
+{
+    /// Stops the iterator after `n` items.
+    ///
+    /// Together with [`max_bytes`](#method.max_bytes) this bounds the work done before the
+    /// iterator returns `None`. Once the budget is used up, [`reset_budget`](#method.reset_budget)
+    /// allows as many items again.
+    ///
+    /// ```
+    /// use serde_cbor::Deserializer;
+    ///
+    /// let input = [0x01, 0x02, 0x03, 0x04, 0x05];
+    /// let mut items = Deserializer::from_slice(&input).into_iter::<u8>().max_items(2);
+    /// let first: Vec<u8> = items.by_ref().collect::<Result<_, _>>().unwrap();
+    /// assert_eq!(first, [1, 2]);
+    /// assert!(items.budget_exhausted());
+    ///
+    /// items.reset_budget();
+    /// let rest: Vec<u8> = items.by_ref().collect::<Result<_, _>>().unwrap();
+    /// assert_eq!(rest, [3, 4]);
+    /// items.reset_budget();
+    /// assert_eq!(items.next().unwrap().unwrap(), 5);
+    /// assert!(items.next().is_none());
+    /// assert!(!items.budget_exhausted());
+    /// ```
+    pub fn max_items(mut self, n: u64) -> Self {
+        self.max_items = Some(n);
+        self
+    }
+
+    /// Stops the iterator once `n` bytes have been consumed.
+    ///
+    /// An item is never split, so the item that crosses the budget is still decoded completely
+    /// and the iterator stops after it.
+    pub fn max_bytes(mut self, n: u64) -> Self {
+        self.max_bytes = Some(n);
+        self
+    }
+
+    /// Frees the memory of the scratch buffer beyond `capacity` bytes after every item, see
+    /// [`Deserializer::shrink_to`](struct.Deserializer.html#method.shrink_to).
+    ///
+    /// A stream that carries the odd huge string then only holds on to its memory until the item
+    /// containing it is decoded.
+    pub fn scratch_capacity(mut self, capacity: usize) -> Self {
+        self.scratch_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets the budget of items for the current round, like [`max_items`](#method.max_items)
+    /// followed by [`reset_budget`](#method.reset_budget).
+    pub fn take_items(&mut self, n: u64) -> &mut Self {
+        self.max_items = Some(n);
+        self.reset_budget();
+        self
+    }
+
+    /// Starts a new round of the item and byte budgets at the current position.
+    pub fn reset_budget(&mut self) {
+        self.items = 0;
+        self.budget_start = self.de.read.offset();
+    }
+
+    /// Whether the iterator stopped or would stop because a budget is used up, rather than at
+    /// the end of the input.
+    pub fn budget_exhausted(&self) -> bool {
+        let consumed = self.de.read.offset() - self.budget_start;
+        self.max_items.is_some_and(|max| self.items >= max)
+            || self.max_bytes.is_some_and(|max| consumed >= max)
     }
 }
 
@@ -1427,14 +4196,215 @@ where
     type Item = Result<T>;
 
     fn next(&mut self) -> Option<Result<T>> {
+        if self.budget_exhausted() {
+            return None;
+        }
         match self.de.peek() {
-            Ok(Some(_)) => Some(T::deserialize(&mut self.de)),
+            Ok(Some(_)) => {
+                self.items += 1;
+                let value = T::deserialize(&mut self.de);
+                if let Some(capacity) = self.scratch_capacity {
+                    self.de.shrink_to(capacity);
+                }
+                Some(value)
+            }
             Ok(None) => None,
             Err(e) => Some(Err(e)),
         }
     }
 }
 
+/// Splits a CBOR sequence into the byte slices of its top-level items.
+///
+/// Only the headers of the items are decoded: text strings are not validated, simple values are
+/// not checked against a policy and no values are built, which makes this a cheap first pass for
+/// indexing a sequence or handing the items to several threads. The input is still checked for
+/// well-formedness. After an error no further items are produced.
+///
+/// ```
+/// # use serde_cbor::de;
+/// // The CBOR sequence 1, [2, 3], "a"
+/// let input = [0x01, 0x82, 0x02, 0x03, 0x61, 0x61];
+/// let items: Vec<&[u8]> = de::split_items(&input).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(items, [&[0x01][..], &[0x82, 0x02, 0x03], &[0x61, 0x61]]);
+/// ```
+pub fn split_items(input: &[u8]) -> SplitItems<'_> {
+    // SAFETY: the items are only skipped, so no `str` is ever created from the input.
+    let options = CustomDeserializerOptions::new()
+        .set_utf8_validation(unsafe { Utf8Validation::trusted() })
+        .set_simple_values(SimpleValues::Number);
+    SplitItems {
+        de: Deserializer::new_with_options(SliceReadFixed::new(input, &mut []), options),
+        input,
+        failed: false,
+    }
+}
+
+/// Iterator over the top-level items of a CBOR sequence.
+///
+/// It is created by the [`split_items`](fn.split_items.html) function.
+#[derive(Debug)]
+pub struct SplitItems<'a> {
+    de: Deserializer<SliceReadFixed<'a, 'static>, CustomDeserializerOptions>,
+    input: &'a [u8],
+    failed: bool,
+}
+
+impl<'a> SplitItems<'a> {
+    /// Returns the offset of the next item in the input
+    #[inline]
+    pub fn byte_offset(&self) -> usize {
+        self.de.byte_offset()
+    }
+}
+
+impl<'a> Iterator for SplitItems<'a> {
+    type Item = Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Result<&'a [u8]>> {
+        let start = self.de.byte_offset();
+        if self.failed || start == self.input.len() {
+            return None;
+        }
+        match self.de.skip_value() {
+            Ok(()) => Some(Ok(&self.input[start..self.de.byte_offset()])),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Iterator that lazily deserializes the elements of a single CBOR array.
+///
+/// It is created by the `Deserializer::array_iter` method. Once the iterator returned `None`
+/// the whole array including a possible break marker has been consumed. After an error no
+/// further items are produced.
+#[derive(Debug)]
+pub struct ArrayIter<'a, 'de, R, T, O = DefaultDeserializerOptions> {
+    de: &'a mut Deserializer<R, O>,
+    len: Option<usize>,
+    done: bool,
+    output: PhantomData<T>,
+    lifetime: PhantomData<&'de ()>,
+}
+
+impl<'a, 'de, R, T, O> ArrayIter<'a, 'de, R, T, O> {
+    /// Returns the number of elements left in the array, if the array has a definite length.
+    #[inline]
+    pub fn remaining(&self) -> Option<usize> {
+        self.len
+    }
+}
+
+impl<'a, 'de, R, T, O> Iterator for ArrayIter<'a, 'de, R, T, O>
+where
+    R: Read<'de>,
+    T: de::Deserialize<'de>,
+    O: DeserializerOptions,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.done {
+            return None;
+        }
+        let item = match self
+            .de
+            .collection_has_next(&mut self.len, ErrorCode::EofWhileParsingArray)
+        {
+            Ok(true) => T::deserialize(&mut *self.de),
+            Ok(false) => {
+                self.done = true;
+                return None;
+            }
+            Err(e) => Err(e),
+        };
+        self.done = item.is_err();
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            return (0, Some(0));
+        }
+        // an error ends the iteration early
+        (0, self.len)
+    }
+}
+
+impl<'a, 'de, R, T, O> Drop for ArrayIter<'a, 'de, R, T, O> {
+    fn drop(&mut self) {
+        self.de.remaining_depth += 1;
+    }
+}
+
+/// Iterator that lazily deserializes the entries of a single CBOR map.
+///
+/// It is created by the `Deserializer::map_iter` method and behaves like
+/// [`ArrayIter`](struct.ArrayIter.html).
+#[derive(Debug)]
+pub struct MapIter<'a, 'de, R, K, V, O = DefaultDeserializerOptions> {
+    de: &'a mut Deserializer<R, O>,
+    len: Option<usize>,
+    done: bool,
+    output: PhantomData<(K, V)>,
+    lifetime: PhantomData<&'de ()>,
+}
+
+impl<'a, 'de, R, K, V, O> MapIter<'a, 'de, R, K, V, O> {
+    /// Returns the number of entries left in the map, if the map has a definite length.
+    #[inline]
+    pub fn remaining(&self) -> Option<usize> {
+        self.len
+    }
+}
+
+impl<'a, 'de, R, K, V, O> Iterator for MapIter<'a, 'de, R, K, V, O>
+where
+    R: Read<'de>,
+    K: de::Deserialize<'de>,
+    V: de::Deserialize<'de>,
+    O: DeserializerOptions,
+{
+    type Item = Result<(K, V)>;
+
+    fn next(&mut self) -> Option<Result<(K, V)>> {
+        if self.done {
+            return None;
+        }
+        let entry = match self
+            .de
+            .collection_has_next(&mut self.len, ErrorCode::EofWhileParsingMap)
+        {
+            Ok(true) => K::deserialize(&mut *self.de)
+                .and_then(|key| V::deserialize(&mut *self.de).map(|value| (key, value))),
+            Ok(false) => {
+                self.done = true;
+                return None;
+            }
+            Err(e) => Err(e),
+        };
+        self.done = entry.is_err();
+        Some(entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            return (0, Some(0));
+        }
+        // an error ends the iteration early
+        (0, self.len)
+    }
+}
+
+impl<'a, 'de, R, K, V, O> Drop for MapIter<'a, 'de, R, K, V, O> {
+    fn drop(&mut self) {
+        self.de.remaining_depth += 1;
+    }
+}
+
 struct VariantAccessMap<T> {
     map: T,
 }
@@ -1507,8 +4477,11 @@ pub(crate) trait ValidValues {
     const MAP: bool = false;
     const BOOL: bool = false;
     const NULL: bool = false;
+    /// A tag that marks the kind of collection, it is skipped like an untagged item.
+    #[cfg(feature = "tags")]
+    const TRANSPARENT_TAG: Option<u64> = None;
 }
-struct ValidAll;
+pub(crate) struct ValidAll;
 impl ValidValues for ValidAll {
     const STRING: bool = true;
     const BYTES: bool = true;
@@ -1530,12 +4503,96 @@ impl ValidValues for ValidForStringAndUInt {
     const STRING: bool = true;
     const INT_POS: bool = true;
 }
+struct ValidForIdentifier;
+impl ValidValues for ValidForIdentifier {
+    const STRING: bool = true;
+    const BYTES: bool = true;
+    const INT_POS: bool = true;
+}
 struct ValidForBytes;
 impl ValidValues for ValidForBytes {
     const ARRAY: bool = true;
     const STRING: bool = true;
     const BYTES: bool = true;
 }
+/// An element of a byte string decoded by `parse_byte_array`, which only decodes into a `u8`.
+struct ByteElement(u8);
+
+impl<'de> de::Deserializer<'de> for ByteElement {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let _ = visitor;
+        Err(de::Error::custom(
+            "a byte string only decodes into an array or tuple of u8",
+        ))
+    }
+
+    fn deserialize_u8<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_u8(self.0)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> de::IntoDeserializer<'de, Error> for ByteElement {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Passes integers within `range` on to `visitor` and hands back the ones outside of it.
+struct RangeChecked<V> {
+    visitor: V,
+    range: RangeInclusive<i128>,
+}
+
+impl<V> RangeChecked<V> {
+    fn check<'de, E>(self, value: i128) -> result::Result<result::Result<V::Value, i128>, E>
+    where
+        V: de::Visitor<'de>,
+        E: de::Error,
+    {
+        if !self.range.contains(&value) {
+            Ok(Err(value))
+        } else if value < 0 {
+            // Values below `i64::MIN` are never within the range of the checked types.
+            self.visitor.visit_i64(value as i64).map(Ok)
+        } else {
+            self.visitor.visit_u64(value as u64).map(Ok)
+        }
+    }
+}
+
+impl<'de, V> de::Visitor<'de> for RangeChecked<V>
+where
+    V: de::Visitor<'de>,
+{
+    type Value = result::Result<V::Value, i128>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.visitor.expecting(formatter)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> result::Result<Self::Value, E> {
+        self.check(value.into())
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> result::Result<Self::Value, E> {
+        self.check(value.into())
+    }
+
+    fn visit_i128<E: de::Error>(self, value: i128) -> result::Result<Self::Value, E> {
+        self.check(value)
+    }
+}
+
 struct ValidForSInt;
 impl ValidValues for ValidForSInt {
     const INT_POS: bool = true;
@@ -1554,10 +4611,14 @@ impl ValidValues for ValidForFloat {
 struct ValidForSeq;
 impl ValidValues for ValidForSeq {
     const ARRAY: bool = true;
+    #[cfg(feature = "tags")]
+    const TRANSPARENT_TAG: Option<u64> = Some(SET_TAG);
 }
 struct ValidForMap;
 impl ValidValues for ValidForMap {
     const MAP: bool = true;
+    #[cfg(feature = "tags")]
+    const TRANSPARENT_TAG: Option<u64> = Some(MAP_TAG);
 }
 struct ValidForBool;
 impl ValidValues for ValidForBool {
@@ -0,0 +1,109 @@
+//! Hash the bytes of an item while decoding it.
+//!
+//! Verifying a signature over a message needs a digest of its exact bytes. Instead of reading
+//! the message into a buffer, hashing it and decoding it from there, [`from_reader`] passes the
+//! bytes to a [`Digest`] as they are read and returns it with the decoded value. Decoding never
+//! reads past the end of the item, so the digest covers exactly its bytes and the reader can be
+//! used for the next item afterwards.
+//!
+//! ```
+//! use serde_derive::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Message {
+//!     text: String,
+//! }
+//!
+//! // a toy digest, the sum of all bytes
+//! let mut sum = 0u32;
+//! let mut stream: &[u8] = b"\xa1\x64text\x62hi\xa1\x64text\x63bye";
+//! let (message, _): (Message, _) = serde_cbor::digest::from_reader(&mut stream, |bytes: &[u8]| {
+//!     sum += bytes.iter().map(|&b| u32::from(b)).sum::<u32>()
+//! })
+//! .unwrap();
+//! assert_eq!(message.text, "hi");
+//! assert_eq!(sum, 0xa1 + 0x64 + 116 + 101 + 120 + 116 + 0x62 + 104 + 105);
+//! assert_eq!(stream.len(), 10);
+//! ```
+
+use std::io;
+
+use serde::de;
+
+use crate::de::Deserializer;
+use crate::error::Result;
+
+/// Receives the bytes of an item as they are read.
+///
+/// Implemented for closures taking a byte slice, which makes it easy to feed the bytes to any
+/// hash function.
+pub trait Digest {
+    /// Adds `bytes` to the digest.
+    fn update(&mut self, bytes: &[u8]);
+}
+
+impl<F> Digest for F
+where
+    F: FnMut(&[u8]),
+{
+    fn update(&mut self, bytes: &[u8]) {
+        self(bytes)
+    }
+}
+
+/// An `io::Read` that passes every byte read through it to a [`Digest`].
+#[derive(Debug)]
+pub struct DigestReader<R, D> {
+    reader: R,
+    digest: D,
+}
+
+impl<R, D> DigestReader<R, D>
+where
+    R: io::Read,
+    D: Digest,
+{
+    /// Wraps `reader`, passing the bytes read to `digest`.
+    pub fn new(reader: R, digest: D) -> DigestReader<R, D> {
+        DigestReader { reader, digest }
+    }
+
+    /// The digest of the bytes read so far.
+    pub fn digest(&self) -> &D {
+        &self.digest
+    }
+
+    /// Unwraps the reader and the digest.
+    pub fn into_parts(self) -> (R, D) {
+        (self.reader, self.digest)
+    }
+}
+
+impl<R, D> io::Read for DigestReader<R, D>
+where
+    R: io::Read,
+    D: Digest,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.digest.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Decodes one item from `reader` and returns it with `digest` updated with its bytes.
+///
+/// Unlike [`serde_cbor::from_reader`](../fn.from_reader.html) nothing after the item is read,
+/// pass `&mut reader` to decode the following items. A partial item that fails to decode may
+/// have been added to the digest.
+pub fn from_reader<T, R, D>(reader: R, digest: D) -> Result<(T, D)>
+where
+    T: de::DeserializeOwned,
+    R: io::Read,
+    D: Digest,
+{
+    let mut deserializer = Deserializer::from_reader(DigestReader::new(reader, digest));
+    let value = de::Deserialize::deserialize(&mut deserializer)?;
+    let (_, digest) = deserializer.into_inner().into_inner().into_parts();
+    Ok((value, digest))
+}
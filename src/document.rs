@@ -0,0 +1,542 @@
+//! Navigate into a document without decoding it.
+//!
+//! A [`Document`] points at an item in a slice. Looking up a map key or an array element skips
+//! the entries before it by their headers, nothing is decoded until `decode` is called on the
+//! item that is needed.
+//!
+//! ```
+//! use serde_cbor::document::Document;
+//!
+//! fn third_reading(input: &[u8]) -> serde_cbor::Result<u32> {
+//!     Document::new(input).get("payload")?.get(2)?.decode()
+//! }
+//!
+//! // {"id": "sensor", "payload": [20, 21, 22, 23]}
+//! let input = b"\xa2\x62id\x66sensor\x67payload\x84\x14\x15\x16\x17";
+//! assert_eq!(third_reading(input).unwrap(), 22);
+//! ```
+//!
+//! Strings have to be contiguous in the input to be decoded, indefinite length strings are
+//! skipped while navigating but fail to decode.
+//!
+//! A [`DocumentIndex`] scans a document once and remembers where every item is, for documents
+//! that are queried many times.
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::convert::TryFrom;
+use core::fmt;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use core::fmt::Write;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+use serde::de;
+
+use crate::de::{Deserializer, ValidAll, WireKey, RECURSION_LIMIT};
+use crate::error::{Error, ErrorCode, ExpectedSet, Result};
+use crate::header::{peek_header, Header, MajorType};
+
+/// An item in a slice of CBOR data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Document<'a> {
+    input: &'a [u8],
+    start: usize,
+}
+
+impl<'a> Document<'a> {
+    /// Returns the document for the first item in `input`.
+    ///
+    /// The input is not checked until it is navigated or decoded, anything after the first item
+    /// is ignored.
+    pub fn new(input: &'a [u8]) -> Document<'a> {
+        Document { input, start: 0 }
+    }
+
+    /// The offset of the item in the input passed to `new`.
+    pub fn offset(&self) -> u64 {
+        self.start as u64
+    }
+
+    /// Decodes the header of the item.
+    pub fn header(&self) -> Result<Header> {
+        header_at(self.input, self.start)
+    }
+
+    /// Returns the encoded item, including everything nested in it.
+    pub fn as_bytes(&self) -> Result<&'a [u8]> {
+        let end = item_end(self.input, self.start, RECURSION_LIMIT)?;
+        Ok(&self.input[self.start..end])
+    }
+
+    /// Looks up the value of a map key or the element of an array.
+    ///
+    /// A `&str` matches definite length text keys, a `usize` matches unsigned integer keys, as
+    /// used by the packed format, or the element at that index of an array. Tags in front of the
+    /// map or array are skipped. Fails if the key is not found.
+    pub fn get<I: Index>(&self, index: I) -> Result<Document<'a>> {
+        let key = private::Sealed::key(&index);
+        let mut pos = self.start;
+        let mut header = header_at(self.input, pos)?;
+        while header.major == MajorType::Tag {
+            pos += header.size;
+            header = header_at(self.input, pos)?;
+        }
+        let offset = pos as u64;
+        let mut entries = Entries {
+            input: self.input,
+            pos: pos + header.size,
+            remaining: header.argument,
+        };
+        match (header.major, key) {
+            (MajorType::Array, WireKey::Integer(index)) => {
+                let mut i = 0;
+                while let Some(start) = entries.next()? {
+                    if i == index {
+                        return Ok(self.at(start));
+                    }
+                    entries.pos = item_end(self.input, start, RECURSION_LIMIT)?;
+                    i += 1;
+                }
+                Err(Error::message(format_args!(
+                    "no element {} in array of length {}",
+                    index, i
+                ))
+                .or_offset(offset))
+            }
+            (MajorType::Map, key) => {
+                while let Some(start) = entries.next()? {
+                    let value = item_end(self.input, start, RECURSION_LIMIT)?;
+                    entries.pos = item_end(self.input, value, RECURSION_LIMIT)?;
+                    if self.key_matches(start, value, key)? {
+                        return Ok(self.at(value));
+                    }
+                }
+                Err(
+                    Error::message(format_args!("no key {} in map", KeyDisplay(key)))
+                        .or_offset(offset),
+                )
+            }
+            (major, key) => Err(Error::message(format_args!(
+                "cannot look up {} in {:?} item",
+                KeyDisplay(key),
+                major
+            ))
+            .or_offset(offset)),
+        }
+    }
+
+    /// Decodes the item.
+    ///
+    /// Errors report offsets in the input passed to `new`.
+    pub fn decode<T>(&self) -> Result<T>
+    where
+        T: de::Deserialize<'a>,
+    {
+        let mut deserializer =
+            Deserializer::from_slice_with_scratch(&self.input[self.start..], &mut []);
+        T::deserialize(&mut deserializer).map_err(|e| e.shift_offset(self.offset()))
+    }
+
+    fn at(&self, start: usize) -> Document<'a> {
+        Document {
+            input: self.input,
+            start,
+        }
+    }
+
+    /// Returns true if the map key from `start` to `end` equals `key`.
+    fn key_matches(&self, start: usize, end: usize, key: WireKey<'_>) -> Result<bool> {
+        let header = header_at(self.input, start)?;
+        Ok(match (header.major, header.argument, key) {
+            (MajorType::Text, Some(_), WireKey::Text(key)) => {
+                &self.input[start + header.size..end] == key.as_bytes()
+            }
+            (MajorType::Unsigned, Some(argument), WireKey::Integer(key)) => argument == key,
+            _ => false,
+        })
+    }
+}
+
+/// The byte ranges of the items of a document by their paths.
+///
+/// A path lists the map keys and array indices leading to an item, separated by `/`. Text keys
+/// appear literally and unsigned integer keys and array indices as decimal numbers. The path of
+/// the whole document is the empty string. Entries with other keys, and keys containing `/`,
+/// can't be looked up; if several keys have the same path the first one is used.
+///
+/// ```
+/// use serde_cbor::document::DocumentIndex;
+///
+/// // {"servers": [{"port": 80}, {"port": 443}], 7: true}
+/// let input = b"\xa2\x67servers\x82\xa1\x64port\x18\x50\xa1\x64port\x19\x01\xbb\x07\xf5";
+/// let index = DocumentIndex::new(input).unwrap();
+/// assert_eq!(index.range("servers/1/port"), Some(24..27));
+/// let port: u16 = index.get("servers/1/port").unwrap().decode().unwrap();
+/// assert_eq!(port, 443);
+/// assert!(index.get("7").unwrap().decode::<bool>().unwrap());
+/// assert_eq!(index.get("servers/2"), None);
+/// ```
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Clone, Debug)]
+pub struct DocumentIndex<'a> {
+    input: &'a [u8],
+    ranges: BTreeMap<String, Range<usize>>,
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a> DocumentIndex<'a> {
+    /// Indexes every item of the first item in `input`.
+    pub fn new(input: &'a [u8]) -> Result<DocumentIndex<'a>> {
+        DocumentIndex::with_max_depth(input, usize::MAX)
+    }
+
+    /// Indexes the items of the first item in `input` that are nested at most `max_depth` levels
+    /// deep, the items below them are only skipped.
+    ///
+    /// The index of a large document stays small this way, deeper items are found with
+    /// `Document::get` starting from the deepest indexed one.
+    pub fn with_max_depth(input: &'a [u8], max_depth: usize) -> Result<DocumentIndex<'a>> {
+        let mut index = DocumentIndex {
+            input,
+            ranges: BTreeMap::new(),
+        };
+        index.add(&mut String::new(), 0, max_depth, RECURSION_LIMIT)?;
+        Ok(index)
+    }
+
+    /// The byte range of the item at `path` in the input.
+    pub fn range(&self, path: &str) -> Option<Range<usize>> {
+        self.ranges.get(path).cloned()
+    }
+
+    /// The item at `path`.
+    pub fn get(&self, path: &str) -> Option<Document<'a>> {
+        self.ranges.get(path).map(|range| Document {
+            input: self.input,
+            start: range.start,
+        })
+    }
+
+    /// The number of indexed items.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Returns true if no item is indexed, which never happens for an index that was built.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Adds the item at `start` and the items nested in it under `path`, returns its end.
+    fn add(&mut self, path: &mut String, start: usize, depth: usize, limit: u8) -> Result<usize> {
+        if depth == 0 || limit == 0 {
+            let end = item_end(self.input, start, limit)?;
+            self.insert(path, start..end);
+            return Ok(end);
+        }
+        let mut pos = start;
+        let mut header = header_at(self.input, pos)?;
+        while header.major == MajorType::Tag {
+            pos += header.size;
+            header = header_at(self.input, pos)?;
+        }
+        if header.major != MajorType::Array && header.major != MajorType::Map {
+            let end = item_end(self.input, start, limit)?;
+            self.insert(path, start..end);
+            return Ok(end);
+        }
+        let mut entries = Entries {
+            input: self.input,
+            pos: pos + header.size,
+            remaining: header.argument,
+        };
+        let len = path.len();
+        let mut i = 0;
+        while let Some(entry) = entries.next()? {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            let value = if header.major == MajorType::Array {
+                write!(path, "{}", i).expect("writing to a string");
+                i += 1;
+                entry
+            } else {
+                let value = item_end(self.input, entry, limit - 1)?;
+                let key = header_at(self.input, entry)?;
+                let key_bytes = &self.input[entry + key.size..value];
+                match (key.major, key.argument, core::str::from_utf8(key_bytes)) {
+                    (MajorType::Text, Some(_), Ok(key)) if !key.contains('/') => path.push_str(key),
+                    (MajorType::Unsigned, Some(key), _) => {
+                        write!(path, "{}", key).expect("writing to a string")
+                    }
+                    _ => {
+                        path.truncate(len);
+                        entries.pos = item_end(self.input, value, limit - 1)?;
+                        continue;
+                    }
+                }
+                value
+            };
+            entries.pos = self.add(path, value, depth - 1, limit - 1)?;
+            path.truncate(len);
+        }
+        let end = entries.pos + usize::from(header.argument.is_none());
+        self.insert(path, start..end);
+        Ok(end)
+    }
+
+    fn insert(&mut self, path: &str, range: Range<usize>) {
+        if !self.ranges.contains_key(path) {
+            self.ranges.insert(path.into(), range);
+        }
+    }
+}
+
+/// Replacements for items of a document, applied without decoding the rest of it.
+///
+/// Large documents are often updated one field at a time. Applying a `Patch` finds the items
+/// to replace by their headers and copies everything around them verbatim, so the untouched
+/// parts keep their exact encoding. Paths are written like those of a [`DocumentIndex`]; a
+/// segment that is a decimal number matches an array index or an unsigned integer key, and a
+/// text key with the same digits otherwise. Items can only be replaced, not added or removed.
+///
+/// ```
+/// use serde_cbor::document::Patch;
+///
+/// // {"name": "probe", "readings": [1, 2, 3]}
+/// let input = b"\xa2\x64name\x65probe\x68readings\x83\x01\x02\x03";
+/// let mut patch = Patch::new();
+/// patch.set("readings/1", &500)?.set("name", &"sonde")?;
+/// let output = patch.apply(input)?;
+/// assert_eq!(output, b"\xa2\x64name\x65sonde\x68readings\x83\x01\x19\x01\xf4\x03");
+/// # Ok::<(), serde_cbor::Error>(())
+/// ```
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Clone, Debug, Default)]
+pub struct Patch {
+    edits: BTreeMap<String, Vec<u8>>,
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Patch {
+    /// Creates a patch without replacements.
+    pub fn new() -> Patch {
+        Patch::default()
+    }
+
+    /// Replaces the item at `path` with `value`, encoded with the default options.
+    pub fn set<T>(&mut self, path: &str, value: &T) -> Result<&mut Patch>
+    where
+        T: serde::Serialize,
+    {
+        let item = crate::ser::to_vec(value)?;
+        Ok(self.set_encoded(path, item))
+    }
+
+    /// Replaces the item at `path` with an encoded item, which is not checked.
+    pub fn set_encoded(&mut self, path: &str, item: Vec<u8>) -> &mut Patch {
+        self.edits.insert(path.into(), item);
+        self
+    }
+
+    /// The number of replaced items.
+    pub fn len(&self) -> usize {
+        self.edits.len()
+    }
+
+    /// Returns true if nothing is replaced.
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Returns `input` with the items of the patch replaced.
+    ///
+    /// Fails if a path is not found, or if one replaced item contains another.
+    pub fn apply(&self, input: &[u8]) -> Result<Vec<u8>> {
+        let mut ranges = Vec::with_capacity(self.edits.len());
+        for (path, item) in &self.edits {
+            ranges.push((find(input, path)?, path, item));
+        }
+        ranges.sort_by_key(|(range, _, _)| range.start);
+        for pair in ranges.windows(2) {
+            let ((first, first_path, _), (second, second_path, _)) = (&pair[0], &pair[1]);
+            if first.end > second.start {
+                return Err(Error::message(format_args!(
+                    "replaced items `{}` and `{}` overlap",
+                    first_path, second_path
+                ))
+                .or_offset(second.start as u64));
+            }
+        }
+        let mut output = Vec::with_capacity(input.len());
+        let mut pos = 0;
+        for (range, _, item) in ranges {
+            output.extend_from_slice(&input[pos..range.start]);
+            output.extend_from_slice(item);
+            pos = range.end;
+        }
+        output.extend_from_slice(&input[pos..]);
+        Ok(output)
+    }
+}
+
+/// The byte range of the item at `path` in the first item of `input`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn find(input: &[u8], path: &str) -> Result<Range<usize>> {
+    let mut document = Document::new(input);
+    if !path.is_empty() {
+        for segment in path.split('/') {
+            document = match segment.parse::<usize>() {
+                Ok(index) => document
+                    .get(index)
+                    .or_else(|e| document.get(segment).map_err(|_| e)),
+                Err(_) => document.get(segment),
+            }?;
+        }
+    }
+    let end = item_end(input, document.start, RECURSION_LIMIT)?;
+    Ok(document.start..end)
+}
+
+/// A key that can be looked up with `Document::get`.
+///
+/// Implemented for `&str`, which matches text keys of maps, and `usize`, which matches array
+/// indices and unsigned integer keys of maps.
+pub trait Index: private::Sealed {}
+
+impl Index for usize {}
+impl Index for str {}
+impl<I: Index + ?Sized> Index for &I {}
+
+mod private {
+    use crate::de::WireKey;
+
+    pub trait Sealed {
+        fn key(&self) -> WireKey<'_>;
+    }
+
+    impl Sealed for usize {
+        fn key(&self) -> WireKey<'_> {
+            WireKey::Integer(*self as u64)
+        }
+    }
+
+    impl Sealed for str {
+        fn key(&self) -> WireKey<'_> {
+            WireKey::Text(self)
+        }
+    }
+
+    impl<I: Sealed + ?Sized> Sealed for &I {
+        fn key(&self) -> WireKey<'_> {
+            (**self).key()
+        }
+    }
+}
+
+struct KeyDisplay<'k>(WireKey<'k>);
+
+impl fmt::Display for KeyDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            WireKey::Text(key) => write!(f, "`{}`", key),
+            WireKey::Integer(key) => write!(f, "{}", key),
+        }
+    }
+}
+
+/// The starts of the entries of an array, or of the keys of a map, read so far.
+///
+/// The caller moves `pos` past each entry before asking for the next one.
+pub(crate) struct Entries<'a> {
+    pub(crate) input: &'a [u8],
+    pub(crate) pos: usize,
+    /// The number of entries left, `None` for indefinite length.
+    pub(crate) remaining: Option<u64>,
+}
+
+impl Entries<'_> {
+    pub(crate) fn next(&mut self) -> Result<Option<usize>> {
+        match &mut self.remaining {
+            Some(0) => Ok(None),
+            Some(remaining) => {
+                *remaining -= 1;
+                Ok(Some(self.pos))
+            }
+            None => match self.input.get(self.pos) {
+                Some(0xff) => Ok(None),
+                Some(_) => Ok(Some(self.pos)),
+                None => Err(Error::syntax(
+                    ErrorCode::EofWhileParsingValue,
+                    self.pos as u64,
+                )),
+            },
+        }
+    }
+}
+
+pub(crate) fn header_at(input: &[u8], pos: usize) -> Result<Header> {
+    peek_header(&input[pos..]).map_err(|e| e.shift_offset(pos as u64))
+}
+
+/// Returns the end of the item starting at `start`, skipping everything nested in it.
+pub(crate) fn item_end(input: &[u8], start: usize, depth: u8) -> Result<usize> {
+    if depth == 0 {
+        return Err(Error::syntax(
+            ErrorCode::RecursionLimitExceeded,
+            start as u64,
+        ));
+    }
+    let header = header_at(input, start)?;
+    let mut pos = start + header.size;
+    let eof = || Error::syntax(ErrorCode::EofWhileParsingValue, input.len() as u64);
+    match (header.major, header.argument) {
+        (MajorType::Bytes, Some(len)) | (MajorType::Text, Some(len)) => {
+            pos = usize::try_from(len)
+                .ok()
+                .and_then(|len| pos.checked_add(len))
+                .filter(|&end| end <= input.len())
+                .ok_or_else(eof)?;
+        }
+        (MajorType::Bytes, None) | (MajorType::Text, None) => loop {
+            match input.get(pos) {
+                Some(0xff) => break pos += 1,
+                Some(&byte) => {
+                    let chunk = header_at(input, pos)?;
+                    if chunk.major != header.major || chunk.argument.is_none() {
+                        return Err(Error::syntax(
+                            ErrorCode::UnexpectedCode(ExpectedSet::from_valid::<ValidAll>(), byte),
+                            pos as u64,
+                        ));
+                    }
+                    pos = item_end(input, pos, depth - 1)?;
+                }
+                None => return Err(eof()),
+            }
+        },
+        (MajorType::Array, _) | (MajorType::Map, _) => {
+            let per_entry = if header.major == MajorType::Map { 2 } else { 1 };
+            let mut entries = Entries {
+                input,
+                pos,
+                remaining: header.argument.map(|len| len.saturating_mul(per_entry)),
+            };
+            while let Some(entry) = entries.next()? {
+                entries.pos = item_end(input, entry, depth - 1)?;
+            }
+            pos = entries.pos + usize::from(header.argument.is_none());
+        }
+        (MajorType::Tag, _) => pos = item_end(input, pos, depth - 1)?,
+        (MajorType::Simple, None) => {
+            return Err(Error::syntax(
+                ErrorCode::UnexpectedCode(ExpectedSet::from_valid::<ValidAll>(), 0xff),
+                start as u64,
+            ))
+        }
+        (MajorType::Unsigned, _) | (MajorType::Negative, _) | (MajorType::Simple, _) => {}
+    }
+    Ok(pos)
+}
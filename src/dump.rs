@@ -0,0 +1,259 @@
+//! Annotated hex dumps of CBOR data.
+use std::fmt::Write;
+
+use half::f16;
+
+/// Items nested deeper than this are not decoded any further.
+const MAX_DEPTH: usize = 128;
+
+/// Content bytes of strings shown per line.
+const CHUNK: usize = 16;
+
+/// Lines shown before and after the line of an error.
+const CONTEXT: usize = 4;
+
+/// Returns an annotated hex dump of `input`.
+///
+/// Every line shows the offset of its bytes, the bytes of a header or of a piece of string
+/// content indented by their nesting, and what they mean. Malformed input is annotated up to the
+/// point where it stops making sense.
+///
+/// ```
+/// // {"a": [1, -2]}
+/// let dump = serde_cbor::dump(b"\xa1\x61a\x82\x01\x21");
+/// assert_eq!(
+///     dump,
+///     "00000000  a1      # map(1)\n\
+///      00000001    61    #   text(1)\n\
+///      00000002      61  #     \"a\"\n\
+///      00000003    82    #   array(2)\n\
+///      00000004      01  #     unsigned(1)\n\
+///      00000005      21  #     negative(-2)\n"
+/// );
+/// ```
+pub fn dump(input: &[u8]) -> String {
+    render(&parse(input), None)
+}
+
+/// Returns the lines of the dump of `input` close to `offset`, marking the line containing it.
+pub(crate) fn dump_window(input: &[u8], offset: u64) -> String {
+    let lines = parse(input);
+    let target = lines
+        .iter()
+        .rposition(|line| line.offset as u64 <= offset)
+        .unwrap_or(0);
+    let start = target.saturating_sub(CONTEXT);
+    let end = lines.len().min(target + CONTEXT + 1);
+    let mut window = String::new();
+    if start > 0 {
+        window.push_str("  ...\n");
+    }
+    window.push_str(&render(&lines[start..end], Some(target - start)));
+    if end < lines.len() {
+        window.push_str("  ...\n");
+    }
+    window
+}
+
+/// The bytes of a header or of string content and their meaning.
+struct Line<'a> {
+    offset: usize,
+    depth: usize,
+    bytes: &'a [u8],
+    note: String,
+}
+
+fn render(lines: &[Line<'_>], marked: Option<usize>) -> String {
+    let width = lines
+        .iter()
+        .map(|line| line.depth * 2 + line.bytes.len() * 3)
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(marked) = marked {
+            out.push_str(if i == marked { "> " } else { "  " });
+        }
+        let indent = " ".repeat(line.depth * 2);
+        let mut hex = indent.clone();
+        for byte in line.bytes {
+            let _ = write!(hex, "{:02x} ", byte);
+        }
+        let _ = write!(out, "{:08x}  {:width$}", line.offset, hex, width = width);
+        if line.note.is_empty() {
+            out.truncate(out.trim_end().len());
+        } else {
+            let _ = write!(out, " # {}{}", indent, line.note);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn parse(input: &[u8]) -> Vec<Line<'_>> {
+    let mut parser = Parser {
+        input,
+        pos: 0,
+        lines: Vec::new(),
+    };
+    while parser.pos < input.len() {
+        if parser.item(0).is_err() {
+            break;
+        }
+    }
+    parser.lines
+}
+
+/// The argument of a header.
+enum Argument {
+    Value(u64),
+    Indefinite,
+}
+
+/// Annotates items, stopping at the first malformed one.
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    lines: Vec<Line<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    /// Adds a line for the next `len` bytes.
+    fn line(&mut self, len: usize, depth: usize, note: String) {
+        let end = self.input.len().min(self.pos + len);
+        self.lines.push(Line {
+            offset: self.pos,
+            depth,
+            bytes: &self.input[self.pos..end],
+            note,
+        });
+        self.pos = end;
+    }
+
+    /// Adds a line for the rest of the input, which could not be decoded.
+    fn fail(&mut self, depth: usize, note: &str) -> Result<(), ()> {
+        let len = (self.input.len() - self.pos).min(CHUNK);
+        self.line(len, depth, note.to_string());
+        Err(())
+    }
+
+    /// Decodes the header at the current position without consuming it.
+    fn header(&self) -> Result<(u8, Argument, usize), &'static str> {
+        let byte = *self.input.get(self.pos).ok_or("unexpected end of input")?;
+        let info = byte & 0x1f;
+        let size = match info {
+            0..=23 => return Ok((byte >> 5, Argument::Value(info.into()), 1)),
+            24..=27 => 1 << (info - 24),
+            31 => return Ok((byte >> 5, Argument::Indefinite, 1)),
+            _ => return Err("reserved additional information"),
+        };
+        let bytes = self
+            .input
+            .get(self.pos + 1..self.pos + 1 + size)
+            .ok_or("truncated header")?;
+        let value = bytes.iter().fold(0, |value, &b| value << 8 | u64::from(b));
+        Ok((byte >> 5, Argument::Value(value), 1 + size))
+    }
+
+    fn item(&mut self, depth: usize) -> Result<(), ()> {
+        if self.pos >= self.input.len() {
+            return self.fail(depth, "unexpected end of input");
+        }
+        if depth > MAX_DEPTH {
+            return self.fail(depth, "nesting too deep");
+        }
+        let (major, argument, len) = match self.header() {
+            Ok(header) => header,
+            Err(msg) => return self.fail(depth, msg),
+        };
+        match (major, argument) {
+            (0, Argument::Value(n)) => self.line(len, depth, format!("unsigned({})", n)),
+            (1, Argument::Value(n)) => {
+                self.line(len, depth, format!("negative({})", -1 - i128::from(n)))
+            }
+            (2, Argument::Value(n)) | (3, Argument::Value(n)) => {
+                let kind = if major == 2 { "bytes" } else { "text" };
+                self.line(len, depth, format!("{}({})", kind, n));
+                return self.content(major, n, depth + 1);
+            }
+            (2, Argument::Indefinite) | (3, Argument::Indefinite) => {
+                let kind = if major == 2 { "bytes" } else { "text" };
+                self.line(len, depth, format!("{}(*)", kind));
+                while !self.next_is_break() {
+                    match self.header() {
+                        Ok((chunk_major, Argument::Value(n), len)) if chunk_major == major => {
+                            self.line(len, depth + 1, format!("{}({})", kind, n));
+                            self.content(major, n, depth + 2)?;
+                        }
+                        _ => return self.fail(depth + 1, "invalid chunk"),
+                    }
+                }
+                self.line(1, depth, "break".to_string());
+            }
+            (4, argument) | (5, argument) => {
+                let kind = if major == 4 { "array" } else { "map" };
+                let per_entry = if major == 4 { 1 } else { 2 };
+                match argument {
+                    Argument::Value(n) => {
+                        self.line(len, depth, format!("{}({})", kind, n));
+                        for _ in 0..n.saturating_mul(per_entry) {
+                            self.item(depth + 1)?;
+                        }
+                    }
+                    Argument::Indefinite => {
+                        self.line(len, depth, format!("{}(*)", kind));
+                        while !self.next_is_break() {
+                            self.item(depth + 1)?;
+                        }
+                        self.line(1, depth, "break".to_string());
+                    }
+                }
+            }
+            (6, Argument::Value(n)) => {
+                self.line(len, depth, format!("tag({})", n));
+                return self.item(depth + 1);
+            }
+            (7, Argument::Value(n)) => {
+                let note = match (len, n) {
+                    (1, 20) => "false".to_string(),
+                    (1, 21) => "true".to_string(),
+                    (1, 22) => "null".to_string(),
+                    (1, 23) => "undefined".to_string(),
+                    (1, _) | (2, _) => format!("simple({})", n),
+                    (3, _) => format!("float({:?})", f16::from_bits(n as u16).to_f64()),
+                    (5, _) => format!("float({:?})", f32::from_bits(n as u32)),
+                    _ => format!("float({:?})", f64::from_bits(n)),
+                };
+                self.line(len, depth, note);
+            }
+            (7, Argument::Indefinite) => return self.fail(depth, "unexpected break"),
+            _ => return self.fail(depth, "invalid indefinite length"),
+        }
+        Ok(())
+    }
+
+    /// Adds lines for the `len` content bytes of a string.
+    fn content(&mut self, major: u8, len: u64, depth: usize) -> Result<(), ()> {
+        let available = (self.input.len() - self.pos) as u64;
+        if len > available {
+            return self.fail(depth, "truncated string");
+        }
+        let mut remaining = len as usize;
+        while remaining > 0 {
+            let n = remaining.min(CHUNK);
+            let bytes = &self.input[self.pos..self.pos + n];
+            let note = if major == 3 {
+                format!("{:?}", String::from_utf8_lossy(bytes))
+            } else {
+                String::new()
+            };
+            self.line(n, depth, note);
+            remaining -= n;
+        }
+        Ok(())
+    }
+
+    fn next_is_break(&self) -> bool {
+        self.input.get(self.pos) == Some(&0xff)
+    }
+}
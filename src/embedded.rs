@@ -0,0 +1,313 @@
+//! Input and output buffers for firmware.
+//!
+//! Devices without an operating system usually receive bytes in an interrupt handler or by DMA
+//! and send them in fixed size transfers. Instead of implementing the `Read` and `Write` traits
+//! of this crate for every such setup, this module has the buffers they need. None of them
+//! allocate, the storage is passed in, typically a `static` array.
+//!
+//! A [`RingBuffer`] is a byte queue. Received bytes are pushed into it, from a slice or straight
+//! into its free space with [`writable`](struct.RingBuffer.html#method.writable) and
+//! [`commit`](struct.RingBuffer.html#method.commit), and [`from_ring`] decodes an item once all of
+//! its bytes have arrived. [`to_ring`] encodes an item into a ring buffer that is sent from with
+//! [`readable`](struct.RingBuffer.html#method.readable) and
+//! [`consume`](struct.RingBuffer.html#method.consume).
+//!
+//! ```
+//! use serde_cbor::embedded::{self, RingBuffer};
+//!
+//! let mut storage = [0; 32];
+//! let mut ring = RingBuffer::new(&mut storage);
+//! let mut scratch = [0; 16];
+//!
+//! // [1, "ab"] arrives in two parts
+//! ring.push(b"\x82\x01");
+//! let item: Option<(u8, String)> = embedded::from_ring(&mut ring, &mut scratch).unwrap();
+//! assert!(item.is_none());
+//! ring.push(b"\x62ab");
+//! let item: Option<(u8, String)> = embedded::from_ring(&mut ring, &mut scratch).unwrap();
+//! assert_eq!(item, Some((1, "ab".to_owned())));
+//! assert!(ring.is_empty());
+//!
+//! // the answer is written into the same kind of buffer and sent from there
+//! embedded::to_ring(&mut ring, &[1, 2]).unwrap();
+//! assert_eq!(ring.readable(), b"\x82\x01\x02");
+//! ring.consume(3);
+//! ```
+//!
+//! A [`ChunkWrite`] collects the output in a buffer and passes it on in whole chunks.
+
+use serde::de;
+use serde::ser;
+
+use crate::de::Deserializer;
+use crate::error::Result;
+use crate::ser::Serializer;
+
+/// A byte queue over borrowed storage.
+///
+/// It implements `Write`, failing with `ScratchTooSmall` when the bytes do not fit.
+#[derive(Debug)]
+pub struct RingBuffer<'a> {
+    pub(crate) storage: &'a mut [u8],
+    pub(crate) head: usize,
+    pub(crate) len: usize,
+}
+
+impl<'a> RingBuffer<'a> {
+    /// Creates an empty ring buffer that stores up to `storage.len()` bytes.
+    pub fn new(storage: &'a mut [u8]) -> RingBuffer<'a> {
+        RingBuffer {
+            storage,
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// The number of bytes the buffer holds when it is full.
+    pub fn capacity(&self) -> usize {
+        self.storage.len()
+    }
+
+    /// The number of bytes in the buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the buffer holds no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all bytes.
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+
+    /// Appends as many bytes of `bytes` as fit and returns their number.
+    pub fn push(&mut self, bytes: &[u8]) -> usize {
+        let mut pushed = 0;
+        while pushed < bytes.len() {
+            let free = self.writable();
+            let n = free.len().min(bytes.len() - pushed);
+            if n == 0 {
+                break;
+            }
+            free[..n].copy_from_slice(&bytes[pushed..pushed + n]);
+            self.commit(n);
+            pushed += n;
+        }
+        pushed
+    }
+
+    /// Removes bytes from the front into `buf` until it is full or the buffer is empty, and
+    /// returns their number.
+    pub fn pop(&mut self, buf: &mut [u8]) -> usize {
+        let mut popped = 0;
+        while popped < buf.len() {
+            let available = self.readable();
+            let n = available.len().min(buf.len() - popped);
+            if n == 0 {
+                break;
+            }
+            buf[popped..popped + n].copy_from_slice(&available[..n]);
+            self.consume(n);
+            popped += n;
+        }
+        popped
+    }
+
+    /// The free space following the last byte, up to the end of the storage.
+    ///
+    /// Write received bytes into it, like the target of a DMA transfer, and append them with
+    /// [`commit`](#method.commit). After the end of the storage is reached the free space at its
+    /// start is returned.
+    pub fn writable(&mut self) -> &mut [u8] {
+        let capacity = self.capacity();
+        let tail = (self.head + self.len) % capacity.max(1);
+        let end = if tail < self.head || self.len == capacity {
+            self.head
+        } else {
+            capacity
+        };
+        &mut self.storage[tail..end]
+    }
+
+    /// Appends the first `n` bytes of the free space returned by [`writable`](#method.writable).
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are fewer than `n` free bytes.
+    pub fn commit(&mut self, n: usize) {
+        assert!(
+            n <= self.capacity() - self.len,
+            "committed more than the free space"
+        );
+        self.len += n;
+    }
+
+    /// The bytes at the front, up to the end of the storage.
+    ///
+    /// Send them, like the source of a DMA transfer, and remove them with
+    /// [`consume`](#method.consume). The remaining bytes start at the beginning of the storage.
+    pub fn readable(&self) -> &[u8] {
+        let end = (self.head + self.len).min(self.capacity());
+        &self.storage[self.head..end]
+    }
+
+    /// Removes the first `n` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer holds fewer than `n` bytes.
+    pub fn consume(&mut self, n: usize) {
+        assert!(n <= self.len, "consumed more than the buffered bytes");
+        self.len -= n;
+        self.head = if self.len == 0 {
+            0
+        } else {
+            (self.head + n) % self.capacity()
+        };
+    }
+
+    /// The byte at position `index` from the front.
+    pub fn get(&self, index: usize) -> Option<u8> {
+        if index < self.len {
+            Some(self.storage[(self.head + index) % self.capacity()])
+        } else {
+            None
+        }
+    }
+}
+
+/// CBOR input source reading from a [`RingBuffer`] without removing the bytes.
+///
+/// Strings are copied into a fixed size scratch buffer, which fails with `ScratchTooSmall` when a
+/// string does not fit. The bytes read are only removed by [`commit`](#method.commit), so an item
+/// that has not been received completely can be decoded again later.
+#[derive(Debug)]
+pub struct RingRead<'r, 'a, 's> {
+    pub(crate) ring: &'r mut RingBuffer<'a>,
+    pub(crate) scratch: &'s mut [u8],
+    pub(crate) scratch_index: usize,
+    /// The number of bytes read from the front of the ring buffer.
+    pub(crate) index: usize,
+}
+
+impl<'r, 'a, 's> RingRead<'r, 'a, 's> {
+    /// Creates a CBOR input source reading from `ring`, backed by a scratch buffer.
+    pub fn new(ring: &'r mut RingBuffer<'a>, scratch: &'s mut [u8]) -> RingRead<'r, 'a, 's> {
+        RingRead {
+            ring,
+            scratch,
+            scratch_index: 0,
+            index: 0,
+        }
+    }
+
+    /// Removes the bytes read so far from the ring buffer.
+    pub fn commit(self) {
+        self.ring.consume(self.index);
+    }
+}
+
+/// Decodes an item from the front of `ring` and removes its bytes.
+///
+/// Returns `None` and leaves the buffer as it is when the item is not complete yet. After
+/// another error the bytes are kept as well, the buffer usually has to be cleared.
+pub fn from_ring<T>(ring: &mut RingBuffer<'_>, scratch: &mut [u8]) -> Result<Option<T>>
+where
+    T: de::DeserializeOwned,
+{
+    let mut deserializer = Deserializer::new(RingRead::new(ring, scratch));
+    match de::Deserialize::deserialize(&mut deserializer) {
+        Ok(value) => {
+            deserializer.into_inner().commit();
+            Ok(Some(value))
+        }
+        Err(e) if e.is_eof() => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Encodes `value` at the end of `ring`.
+///
+/// Fails with `ScratchTooSmall` if the item does not fit, in which case none of its bytes are
+/// added.
+pub fn to_ring<T>(ring: &mut RingBuffer<'_>, value: &T) -> Result<()>
+where
+    T: ser::Serialize + ?Sized,
+{
+    let len = ring.len;
+    let result = value.serialize(&mut Serializer::new(&mut *ring));
+    if result.is_err() {
+        ring.len = len;
+    }
+    result
+}
+
+/// A `Write` that collects the output in a buffer and passes it to `sink` in chunks of the
+/// buffer's size.
+///
+/// This suits peripherals that send fixed size blocks, or DMA transfers from a static buffer.
+/// The last, partial chunk is only passed on by [`flush`](#method.flush) or
+/// [`finish`](#method.finish).
+///
+/// ```
+/// use serde_cbor::embedded::ChunkWrite;
+/// use serde_cbor::Serializer;
+///
+/// let mut chunks = Vec::new();
+/// let mut buf = [0; 4];
+/// let mut writer = ChunkWrite::new(&mut buf, |chunk: &[u8]| chunks.push(chunk.to_vec()));
+/// serde::Serialize::serialize("hello", &mut Serializer::new(&mut writer)).unwrap();
+/// let _ = writer.finish();
+/// assert_eq!(chunks, [&b"\x65hel"[..], b"lo"]);
+/// ```
+#[derive(Debug)]
+pub struct ChunkWrite<'a, F> {
+    pub(crate) buf: &'a mut [u8],
+    pub(crate) len: usize,
+    pub(crate) sink: F,
+}
+
+impl<'a, F> ChunkWrite<'a, F>
+where
+    F: FnMut(&[u8]),
+{
+    /// Collects the output in `buf`, passing it to `sink` whenever it is full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is empty.
+    pub fn new(buf: &'a mut [u8], sink: F) -> ChunkWrite<'a, F> {
+        assert!(!buf.is_empty(), "a chunk needs room for at least one byte");
+        ChunkWrite { buf, len: 0, sink }
+    }
+
+    /// Passes the collected bytes to the sink, if there are any.
+    pub fn flush(&mut self) {
+        if self.len > 0 {
+            (self.sink)(&self.buf[..self.len]);
+            self.len = 0;
+        }
+    }
+
+    /// Flushes the collected bytes and returns the sink.
+    pub fn finish(mut self) -> F {
+        self.flush();
+        self.sink
+    }
+
+    pub(crate) fn write_bytes(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let n = (self.buf.len() - self.len).min(bytes.len());
+            self.buf[self.len..self.len + n].copy_from_slice(&bytes[..n]);
+            self.len += n;
+            bytes = &bytes[n..];
+            if self.len == self.buf.len() {
+                self.flush();
+            }
+        }
+    }
+}
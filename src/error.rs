@@ -15,6 +15,36 @@ pub struct Error(ErrorImpl);
 /// Alias for a `Result` with the error type `serde_cbor::Error`.
 pub type Result<T> = result::Result<T, Error>;
 
+/// The syntax errors a custom reader can report with
+/// [`Error::syntax_at`](struct.Error.html#method.syntax_at).
+///
+/// The end of the input is reported with [`Error::eof`](struct.Error.html#method.eof) instead.
+#[cfg(feature = "unsealed_read_write")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SyntaxCode {
+    /// A length does not fit into the address space or the remaining input.
+    LengthOutOfRange,
+    /// A text string is not valid UTF-8.
+    InvalidUtf8,
+    /// There is data after the last value.
+    TrailingData,
+    /// Values are nested too deeply.
+    RecursionLimitExceeded,
+}
+
+#[cfg(feature = "unsealed_read_write")]
+impl From<SyntaxCode> for ErrorCode {
+    fn from(code: SyntaxCode) -> ErrorCode {
+        match code {
+            SyntaxCode::LengthOutOfRange => ErrorCode::LengthOutOfRange,
+            SyntaxCode::InvalidUtf8 => ErrorCode::InvalidUtf8,
+            SyntaxCode::TrailingData => ErrorCode::TrailingData,
+            SyntaxCode::RecursionLimitExceeded => ErrorCode::RecursionLimitExceeded,
+        }
+    }
+}
+
 /// Categorizes the cause of a `serde_cbor::Error`.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Category {
@@ -29,47 +59,150 @@ pub enum Category {
 }
 
 impl Error {
+    fn new(code: ErrorCode, offset: u64) -> Error {
+        Error::with_offset(code, Some(offset))
+    }
+
+    fn with_offset(code: ErrorCode, offset: Option<u64>) -> Error {
+        Error(ErrorImpl {
+            #[cfg(not(all(feature = "compact_errors", not(feature = "std"))))]
+            code,
+            #[cfg(all(feature = "compact_errors", not(feature = "std")))]
+            code: code.kind(),
+            offset,
+            #[cfg(feature = "std")]
+            dump: None,
+            #[cfg(feature = "std")]
+            path: None,
+        })
+    }
+
     /// The byte offset at which the error occurred.
+    ///
+    /// This is 0 for errors without an offset, use [`try_offset`](#method.try_offset) to tell
+    /// them apart.
     pub fn offset(&self) -> u64 {
+        self.0.offset.unwrap_or(0)
+    }
+
+    /// The byte offset in the input at which the error occurred, if it has one.
+    ///
+    /// Every error produced while decoding has an offset, including the errors raised by the
+    /// `Deserialize` implementations through `serde::de::Error::custom`, which are placed at the
+    /// start of the item they were decoding. I/O errors and errors produced while encoding have
+    /// none.
+    pub fn try_offset(&self) -> Option<u64> {
         self.0.offset
     }
 
-    pub(crate) fn syntax(code: ErrorCode, offset: u64) -> Error {
-        Error(ErrorImpl { code, offset })
+    /// The offset shown in the message.
+    ///
+    /// As before offsets were optional, an offset of 0 is only shown for syntax and EOF errors,
+    /// the other errors at the start of the input are displayed without one.
+    fn displayed_offset(&self) -> Option<u64> {
+        self.0.offset.filter(|&offset| {
+            offset != 0 || matches!(self.classify(), Category::Syntax | Category::Eof)
+        })
+    }
+
+    /// Attaches the annotated dump of `input` around the offset of this error.
+    ///
+    /// `input` has to be the data this error was produced from. The dump is shown after the
+    /// message when the error is displayed, see [`dump`](../fn.dump.html) for its format.
+    #[cfg(feature = "std")]
+    pub fn with_dump(mut self, input: &[u8]) -> Error {
+        let offset = self.0.offset.unwrap_or(0);
+        self.0.dump = Some(crate::dump::dump_window(input, offset).into());
+        self
+    }
+
+    /// The dump attached with [`with_dump`](#method.with_dump).
+    #[cfg(feature = "std")]
+    pub fn dump(&self) -> Option<&str> {
+        self.0.dump.as_deref()
+    }
+
+    /// Where in the value being serialized the error occurred, like `items[2].count`.
+    ///
+    /// Only errors of a serializer with
+    /// [`error_paths`](ser/trait.SerializerOptions.html#method.error_paths) enabled have a path.
+    #[cfg(feature = "std")]
+    pub fn path(&self) -> Option<&str> {
+        self.0
+            .path
+            .as_deref()
+            .map(|path| path.trim_start_matches('.'))
     }
 
+    /// Prepends a segment to the path of an error that occurred in a nested value.
     #[cfg(feature = "std")]
+    pub(crate) fn in_path(mut self, segment: fmt::Arguments<'_>) -> Error {
+        let path = match self.0.path.take() {
+            Some(path) => format!("{}{}", segment, path),
+            None => segment.to_string(),
+        };
+        self.0.path = Some(path.into());
+        self
+    }
+
+    pub(crate) fn syntax(code: ErrorCode, offset: u64) -> Error {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(offset, code = %code, "CBOR syntax error");
+        Error::new(code, offset)
+    }
+
+    /// Creates an error that is not tied to a position in the input.
+    pub(crate) fn unplaced(code: ErrorCode) -> Error {
+        Error::with_offset(code, None)
+    }
+
+    #[cfg(all(feature = "std", not(feature = "unsealed_read_write")))]
     pub(crate) fn io(error: io::Error) -> Error {
-        Error(ErrorImpl {
-            code: ErrorCode::Io(error),
-            offset: 0,
-        })
+        Error::unplaced(ErrorCode::Io(error))
+    }
+
+    #[cfg(all(feature = "std", feature = "unsealed_read_write"))]
+    /// Creates an error signalling that the underlying `Read` or `Write` encountered an I/O error.
+    ///
+    /// Like the I/O errors of the builtin readers it has no offset and is its
+    /// [`source`](https://doc.rust-lang.org/std/error/trait.Error.html#method.source).
+    pub fn io(error: io::Error) -> Error {
+        Error::unplaced(ErrorCode::Io(error))
+    }
+
+    #[cfg(feature = "unsealed_read_write")]
+    /// Creates an error signalling that the input is not valid CBOR at `offset`.
+    ///
+    /// ```
+    /// use serde_cbor::error::{Category, SyntaxCode};
+    /// use serde_cbor::Error;
+    ///
+    /// let err = Error::syntax_at(SyntaxCode::InvalidUtf8, 7);
+    /// assert_eq!(err.classify(), Category::Syntax);
+    /// assert_eq!(err.offset(), 7);
+    /// assert_eq!(err.to_string(), "invalid UTF-8 at offset 7");
+    /// ```
+    pub fn syntax_at(code: SyntaxCode, offset: u64) -> Error {
+        Error::syntax(code.into(), offset)
     }
 
     #[cfg(all(not(feature = "std"), feature = "unsealed_read_write"))]
     /// Creates an error signalling that the underlying `Read` encountered an I/O error.
     pub fn io() -> Error {
-        Error(ErrorImpl {
-            code: ErrorCode::Io,
-            offset: 0,
-        })
+        Error::unplaced(ErrorCode::Io)
     }
 
     #[cfg(feature = "unsealed_read_write")]
     /// Creates an error signalling that the scratch buffer was too small to fit the data.
+    ///
+    /// The sizes reported by [`scratch_needed`](#method.scratch_needed) and
+    /// [`scratch_available`](#method.scratch_available) are zero for such an error.
     pub fn scratch_too_small(offset: u64) -> Error {
-        Error(ErrorImpl {
-            code: ErrorCode::ScratchTooSmall,
-            offset,
-        })
+        Error::scratch_overflow(0, 0, offset)
     }
 
-    #[cfg(not(feature = "unsealed_read_write"))]
-    pub(crate) fn scratch_too_small(offset: u64) -> Error {
-        Error(ErrorImpl {
-            code: ErrorCode::ScratchTooSmall,
-            offset,
-        })
+    pub(crate) fn scratch_overflow(needed: usize, available: usize, offset: u64) -> Error {
+        Error::new(ErrorCode::ScratchTooSmall { needed, available }, offset)
     }
 
     #[cfg(feature = "unsealed_read_write")]
@@ -79,17 +212,11 @@ impl Error {
     pub fn message<T: fmt::Display>(_msg: T) -> Error {
         #[cfg(not(feature = "std"))]
         {
-            Error(ErrorImpl {
-                code: ErrorCode::Message,
-                offset: 0,
-            })
+            Error::unplaced(ErrorCode::Message)
         }
         #[cfg(feature = "std")]
         {
-            Error(ErrorImpl {
-                code: ErrorCode::Message(_msg.to_string().into()),
-                offset: 0,
-            })
+            Error::unplaced(ErrorCode::Message(_msg.to_string().into()))
         }
     }
 
@@ -97,17 +224,11 @@ impl Error {
     pub(crate) fn message<T: fmt::Display>(_msg: T) -> Error {
         #[cfg(not(feature = "std"))]
         {
-            Error(ErrorImpl {
-                code: ErrorCode::Message,
-                offset: 0,
-            })
+            Error::unplaced(ErrorCode::Message)
         }
         #[cfg(feature = "std")]
         {
-            Error(ErrorImpl {
-                code: ErrorCode::Message(_msg.to_string().into()),
-                offset: 0,
-            })
+            Error::unplaced(ErrorCode::Message(_msg.to_string().into()))
         }
     }
 
@@ -115,36 +236,63 @@ impl Error {
     /// Creates an error signalling that the underlying read
     /// encountered an end of input.
     pub fn eof(offset: u64) -> Error {
-        Error(ErrorImpl {
-            code: ErrorCode::EofWhileParsingValue,
-            offset,
-        })
+        Error::new(ErrorCode::EofWhileParsingValue, offset)
+    }
+
+    /// Moves the offset of an error produced while decoding a sub-slice of the input.
+    pub(crate) fn shift_offset(mut self, base: u64) -> Error {
+        if let Some(offset) = &mut self.0.offset {
+            *offset += base;
+        }
+        self
+    }
+
+    /// Places an error that does not carry an offset yet at `offset`.
+    pub(crate) fn or_offset(mut self, offset: u64) -> Error {
+        if self.0.offset.is_none() && !self.is_io() {
+            self.0.offset = Some(offset);
+        }
+        self
+    }
+
+    #[cfg(not(all(feature = "compact_errors", not(feature = "std"))))]
+    fn kind(&self) -> ErrorKind {
+        self.0.code.kind()
+    }
+
+    #[cfg(all(feature = "compact_errors", not(feature = "std")))]
+    fn kind(&self) -> ErrorKind {
+        self.0.code
     }
 
     /// Categorizes the cause of this error.
     pub fn classify(&self) -> Category {
-        match self.0.code {
-            #[cfg(feature = "std")]
-            ErrorCode::Message(_) => Category::Data,
-            #[cfg(not(feature = "std"))]
-            ErrorCode::Message => Category::Data,
-            #[cfg(feature = "std")]
-            ErrorCode::Io(_) => Category::Io,
-            #[cfg(not(feature = "std"))]
-            ErrorCode::Io => Category::Io,
-            ErrorCode::ScratchTooSmall => Category::Io,
-            ErrorCode::EofWhileParsingValue
-            | ErrorCode::EofWhileParsingArray
-            | ErrorCode::EofWhileParsingMap => Category::Eof,
-            ErrorCode::LengthOutOfRange
-            | ErrorCode::InvalidUtf8
-            | ErrorCode::UnexpectedCode { .. }
-            | ErrorCode::TrailingData
-            | ErrorCode::ArrayTooShort
-            | ErrorCode::ArrayTooLong
-            | ErrorCode::RecursionLimitExceeded
-            | ErrorCode::WrongEnumFormat
-            | ErrorCode::WrongStructFormat => Category::Syntax,
+        match self.kind() {
+            ErrorKind::Message => Category::Data,
+            ErrorKind::Io => Category::Io,
+            ErrorKind::ScratchTooSmall => Category::Io,
+            ErrorKind::NumberOutOfRange
+            | ErrorKind::NotSingleChar
+            | ErrorKind::ByteArrayLength
+            | ErrorKind::InvalidLength
+            | ErrorKind::InvalidEncodedText
+            | ErrorKind::DuplicateKey
+            | ErrorKind::KeyOrderUnavailable => Category::Data,
+            ErrorKind::EofWhileParsingValue
+            | ErrorKind::EofWhileParsingArray
+            | ErrorKind::EofWhileParsingMap => Category::Eof,
+            ErrorKind::LengthOutOfRange
+            | ErrorKind::InvalidUtf8
+            | ErrorKind::UnexpectedCode
+            | ErrorKind::TrailingData
+            | ErrorKind::UnreadElements
+            | ErrorKind::TooManyChunks
+            | ErrorKind::UnorderedKey
+            | ErrorKind::ArrayTooShort
+            | ErrorKind::ArrayTooLong
+            | ErrorKind::RecursionLimitExceeded
+            | ErrorKind::WrongEnumFormat
+            | ErrorKind::WrongStructFormat => Category::Syntax,
         }
     }
 
@@ -184,11 +332,112 @@ impl Error {
     ///
     /// Note this being `true` implies that `is_io()` is also `true`.
     pub fn is_scratch_too_small(&self) -> bool {
+        self.kind() == ErrorKind::ScratchTooSmall
+    }
+
+    /// The size the scratch buffer would have needed, if this error was caused by it being too
+    /// small.
+    ///
+    /// When decoding, this is the size needed by the string that did not fit, later strings may
+    /// need more. [`de::scratch_size`](../de/fn.scratch_size.html) tells the size needed by the
+    /// whole input.
+    ///
+    /// Always `None` with `compact_errors`.
+    #[cfg(not(all(feature = "compact_errors", not(feature = "std"))))]
+    pub fn scratch_needed(&self) -> Option<usize> {
         match self.0.code {
-            ErrorCode::ScratchTooSmall => true,
-            _ => false,
+            ErrorCode::ScratchTooSmall { needed, .. } => Some(needed),
+            _ => None,
+        }
+    }
+
+    /// The size of the scratch buffer, if this error was caused by it being too small.
+    ///
+    /// Always `None` with `compact_errors`.
+    #[cfg(not(all(feature = "compact_errors", not(feature = "std"))))]
+    pub fn scratch_available(&self) -> Option<usize> {
+        match self.0.code {
+            ErrorCode::ScratchTooSmall { available, .. } => Some(available),
+            _ => None,
         }
     }
+
+    /// The length a `Deserialize` implementation rejected through
+    /// `serde::de::Error::invalid_length`.
+    ///
+    /// Types with a fixed capacity, like `heapless::String` and `heapless::Vec`, report their
+    /// overflow this way: a string reports its length, a sequence the number of elements read
+    /// when its capacity ran out. The error is placed at the start of the rejected item.
+    ///
+    /// Always `None` with `compact_errors`.
+    ///
+    /// ```
+    /// let err = serde_cbor::from_slice::<heapless::String<4>>(b"\x65hello").unwrap_err();
+    /// assert_eq!(err.invalid_length(), Some(5));
+    /// assert_eq!(err.offset(), 0);
+    /// ```
+    #[cfg(not(all(feature = "compact_errors", not(feature = "std"))))]
+    pub fn invalid_length(&self) -> Option<usize> {
+        match self.0.code {
+            ErrorCode::InvalidLength { len, .. } => Some(len),
+            _ => None,
+        }
+    }
+
+    #[allow(missing_docs)]
+    #[cfg(all(feature = "compact_errors", not(feature = "std")))]
+    pub fn invalid_length(&self) -> Option<usize> {
+        None
+    }
+
+    /// The index of the first element of an array or map that was not read, if this error was
+    /// caused by a collection not being read to the end with
+    /// [`strict_lengths`](../de/trait.DeserializerOptions.html#method.strict_lengths).
+    ///
+    /// Map entries are counted in pairs. Always `None` with `compact_errors`.
+    #[cfg(not(all(feature = "compact_errors", not(feature = "std"))))]
+    pub fn unread_element(&self) -> Option<usize> {
+        match self.0.code {
+            ErrorCode::UnreadElements { index, .. } => Some(index),
+            _ => None,
+        }
+    }
+
+    /// The number of elements the array or map declared, if this error was caused by a
+    /// collection not being read to the end.
+    ///
+    /// Always `None` with `compact_errors`.
+    #[cfg(not(all(feature = "compact_errors", not(feature = "std"))))]
+    pub fn declared_elements(&self) -> Option<usize> {
+        match self.0.code {
+            ErrorCode::UnreadElements { declared, .. } => Some(declared),
+            _ => None,
+        }
+    }
+
+    #[allow(missing_docs)]
+    #[cfg(all(feature = "compact_errors", not(feature = "std")))]
+    pub fn unread_element(&self) -> Option<usize> {
+        None
+    }
+
+    #[allow(missing_docs)]
+    #[cfg(all(feature = "compact_errors", not(feature = "std")))]
+    pub fn declared_elements(&self) -> Option<usize> {
+        None
+    }
+
+    #[allow(missing_docs)]
+    #[cfg(all(feature = "compact_errors", not(feature = "std")))]
+    pub fn scratch_needed(&self) -> Option<usize> {
+        None
+    }
+
+    #[allow(missing_docs)]
+    #[cfg(all(feature = "compact_errors", not(feature = "std")))]
+    pub fn scratch_available(&self) -> Option<usize> {
+        None
+    }
 }
 
 #[cfg(feature = "std")]
@@ -201,16 +450,61 @@ impl error::Error for Error {
     }
 }
 
+#[cfg(all(not(feature = "std"), not(no_core_error)))]
+impl core::error::Error for Error {}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.0.offset == 0 {
-            fmt::Display::fmt(&self.0.code, f)
-        } else {
-            write!(f, "{} at offset {}", self.0.code, self.0.offset)
+        match self.displayed_offset() {
+            Some(offset) => write!(f, "{} at offset {}", self.0.code, offset)?,
+            None => fmt::Display::fmt(&self.0.code, f)?,
+        }
+        #[cfg(feature = "std")]
+        {
+            if let Some(path) = self.path() {
+                write!(f, " at `{}`", path)?;
+            }
+            if let Some(dump) = &self.0.dump {
+                write!(f, "\n{}", dump.trim_end())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Error {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self.displayed_offset() {
+            Some(offset) => defmt::write!(f, "{} at offset {=u64}", self.0.code, offset),
+            None => defmt::write!(f, "{}", self.0.code),
+        }
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uDisplay for Error {
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> core::result::Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        match self.displayed_offset() {
+            Some(offset) => ufmt::uwrite!(f, "{} at offset {}", self.0.code, offset),
+            None => ufmt::uwrite!(f, "{}", self.0.code),
         }
     }
 }
 
+#[cfg(feature = "ufmt")]
+impl ufmt::uDebug for Error {
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> core::result::Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        ufmt::uDisplay::fmt(self, f)
+    }
+}
+
 impl fmt::Debug for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         fmt::Debug::fmt(&self.0, fmt)
@@ -219,6 +513,8 @@ impl fmt::Debug for Error {
 
 impl de::Error for Error {
     fn custom<T: fmt::Display>(msg: T) -> Error {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(error = %msg, "CBOR data error");
         Error::message(msg)
     }
 
@@ -229,6 +525,21 @@ impl de::Error for Error {
             Error::custom(format_args!("invalid type: {}, expected {}", unexp, exp))
         }
     }
+
+    fn invalid_length(len: usize, exp: &dyn de::Expected) -> Error {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            error = %format_args!("invalid length {}, expected {}", len, exp),
+            "CBOR data error"
+        );
+        #[cfg(not(feature = "std"))]
+        let _ = exp;
+        Error::unplaced(ErrorCode::InvalidLength {
+            len,
+            #[cfg(feature = "std")]
+            expected: exp.to_string().into(),
+        })
+    }
 }
 
 impl ser::Error for Error {
@@ -247,17 +558,23 @@ impl From<io::Error> for Error {
 #[cfg(not(feature = "std"))]
 impl From<core::fmt::Error> for Error {
     fn from(_: core::fmt::Error) -> Error {
-        Error(ErrorImpl {
-            code: ErrorCode::Message,
-            offset: 0,
-        })
+        Error::unplaced(ErrorCode::Message)
     }
 }
 
 #[derive(Debug)]
 struct ErrorImpl {
+    #[cfg(not(all(feature = "compact_errors", not(feature = "std"))))]
     code: ErrorCode,
-    offset: u64,
+    #[cfg(all(feature = "compact_errors", not(feature = "std")))]
+    code: ErrorKind,
+    offset: Option<u64>,
+    /// An annotated dump of the input around `offset`.
+    #[cfg(feature = "std")]
+    dump: Option<Box<str>>,
+    /// The path to the value being serialized, see `Error::path`.
+    #[cfg(feature = "std")]
+    path: Option<Box<str>>,
 }
 
 #[derive(Debug)]
@@ -271,7 +588,10 @@ pub(crate) enum ErrorCode {
     #[allow(unused)]
     #[cfg(not(feature = "std"))]
     Io,
-    ScratchTooSmall,
+    ScratchTooSmall {
+        needed: usize,
+        available: usize,
+    },
     EofWhileParsingValue,
     EofWhileParsingArray,
     EofWhileParsingMap,
@@ -279,11 +599,179 @@ pub(crate) enum ErrorCode {
     InvalidUtf8,
     UnexpectedCode(ExpectedSet, u8),
     TrailingData,
+    UnreadElements {
+        map: bool,
+        declared: usize,
+        index: usize,
+    },
+    TooManyChunks {
+        max: usize,
+    },
+    #[allow(unused)]
+    UnorderedKey,
+    #[allow(unused)]
+    KeyOrderUnavailable,
     ArrayTooShort,
     ArrayTooLong,
     RecursionLimitExceeded,
     WrongEnumFormat,
     WrongStructFormat,
+    NumberOutOfRange {
+        value: i128,
+        target: &'static str,
+    },
+    NotSingleChar {
+        chars: usize,
+    },
+    ByteArrayLength {
+        expected: usize,
+        found: usize,
+    },
+    InvalidLength {
+        len: usize,
+        #[cfg(feature = "std")]
+        expected: Box<str>,
+    },
+    #[allow(unused)]
+    InvalidEncodedText,
+    #[allow(unused)]
+    DuplicateKey,
+}
+
+impl ErrorCode {
+    /// The kind of the error, without the data carried by some codes.
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            #[cfg(feature = "std")]
+            ErrorCode::Message(_) => ErrorKind::Message,
+            #[cfg(not(feature = "std"))]
+            ErrorCode::Message => ErrorKind::Message,
+            #[cfg(feature = "std")]
+            ErrorCode::Io(_) => ErrorKind::Io,
+            #[cfg(not(feature = "std"))]
+            ErrorCode::Io => ErrorKind::Io,
+            ErrorCode::ScratchTooSmall { .. } => ErrorKind::ScratchTooSmall,
+            ErrorCode::EofWhileParsingValue => ErrorKind::EofWhileParsingValue,
+            ErrorCode::EofWhileParsingArray => ErrorKind::EofWhileParsingArray,
+            ErrorCode::EofWhileParsingMap => ErrorKind::EofWhileParsingMap,
+            ErrorCode::LengthOutOfRange => ErrorKind::LengthOutOfRange,
+            ErrorCode::InvalidUtf8 => ErrorKind::InvalidUtf8,
+            ErrorCode::UnexpectedCode(..) => ErrorKind::UnexpectedCode,
+            ErrorCode::TrailingData => ErrorKind::TrailingData,
+            ErrorCode::UnreadElements { .. } => ErrorKind::UnreadElements,
+            ErrorCode::TooManyChunks { .. } => ErrorKind::TooManyChunks,
+            ErrorCode::UnorderedKey => ErrorKind::UnorderedKey,
+            ErrorCode::KeyOrderUnavailable => ErrorKind::KeyOrderUnavailable,
+            ErrorCode::ArrayTooShort => ErrorKind::ArrayTooShort,
+            ErrorCode::ArrayTooLong => ErrorKind::ArrayTooLong,
+            ErrorCode::RecursionLimitExceeded => ErrorKind::RecursionLimitExceeded,
+            ErrorCode::WrongEnumFormat => ErrorKind::WrongEnumFormat,
+            ErrorCode::WrongStructFormat => ErrorKind::WrongStructFormat,
+            ErrorCode::NumberOutOfRange { .. } => ErrorKind::NumberOutOfRange,
+            ErrorCode::NotSingleChar { .. } => ErrorKind::NotSingleChar,
+            ErrorCode::ByteArrayLength { .. } => ErrorKind::ByteArrayLength,
+            ErrorCode::InvalidLength { .. } => ErrorKind::InvalidLength,
+            ErrorCode::InvalidEncodedText => ErrorKind::InvalidEncodedText,
+            ErrorCode::DuplicateKey => ErrorKind::DuplicateKey,
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        self.kind().description()
+    }
+}
+
+/// An `ErrorCode` without its data, which is all an `Error` keeps with `compact_errors`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ErrorKind {
+    Message,
+    Io,
+    ScratchTooSmall,
+    EofWhileParsingValue,
+    EofWhileParsingArray,
+    EofWhileParsingMap,
+    LengthOutOfRange,
+    InvalidUtf8,
+    UnexpectedCode,
+    TrailingData,
+    UnreadElements,
+    TooManyChunks,
+    UnorderedKey,
+    KeyOrderUnavailable,
+    ArrayTooShort,
+    ArrayTooLong,
+    RecursionLimitExceeded,
+    WrongEnumFormat,
+    WrongStructFormat,
+    NumberOutOfRange,
+    NotSingleChar,
+    ByteArrayLength,
+    InvalidLength,
+    InvalidEncodedText,
+    DuplicateKey,
+}
+
+impl ErrorKind {
+    /// A fixed description of the error.
+    fn description(self) -> &'static str {
+        match self {
+            ErrorKind::Message => "Unknown error",
+            ErrorKind::Io => "Unknown I/O error",
+            ErrorKind::ScratchTooSmall => "Scratch buffer too small",
+            ErrorKind::EofWhileParsingValue => "EOF while parsing a value",
+            ErrorKind::EofWhileParsingArray => "EOF while parsing an array",
+            ErrorKind::EofWhileParsingMap => "EOF while parsing a map",
+            ErrorKind::LengthOutOfRange => "length out of range",
+            ErrorKind::InvalidUtf8 => "invalid UTF-8",
+            ErrorKind::UnexpectedCode => "unexpected code",
+            ErrorKind::TrailingData => "trailing data",
+            ErrorKind::UnreadElements => "collection not read to the end",
+            ErrorKind::TooManyChunks => "too many chunks in an indefinite length item",
+            ErrorKind::UnorderedKey => "map key out of order",
+            ErrorKind::KeyOrderUnavailable => "map key order cannot be checked with this reader",
+            ErrorKind::ArrayTooShort => "array too short",
+            ErrorKind::ArrayTooLong => "array too long",
+            ErrorKind::RecursionLimitExceeded => "recursion limit exceeded",
+            ErrorKind::WrongEnumFormat => "wrong enum format",
+            ErrorKind::WrongStructFormat => "wrong struct format",
+            ErrorKind::NumberOutOfRange => "number out of range",
+            ErrorKind::NotSingleChar => "expected a single character",
+            ErrorKind::ByteArrayLength => "wrong byte string length",
+            ErrorKind::InvalidLength => "invalid length",
+            ErrorKind::InvalidEncodedText => "text does not match its expected encoding",
+            ErrorKind::DuplicateKey => "duplicate map key",
+        }
+    }
+}
+
+#[cfg(all(feature = "compact_errors", not(feature = "std")))]
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+#[cfg(all(
+    all(feature = "compact_errors", not(feature = "std")),
+    feature = "defmt"
+))]
+impl defmt::Format for ErrorKind {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "{=str}", self.description())
+    }
+}
+
+#[cfg(all(
+    all(feature = "compact_errors", not(feature = "std")),
+    feature = "ufmt"
+))]
+impl ufmt::uDisplay for ErrorKind {
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> core::result::Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        f.write_str(self.description())
+    }
 }
 
 impl fmt::Display for ErrorCode {
@@ -291,31 +779,151 @@ impl fmt::Display for ErrorCode {
         match *self {
             #[cfg(feature = "std")]
             ErrorCode::Message(ref msg) => f.write_str(msg),
-            #[cfg(not(feature = "std"))]
-            ErrorCode::Message => f.write_str("Unknown error"),
             #[cfg(feature = "std")]
             ErrorCode::Io(ref err) => fmt::Display::fmt(err, f),
-            #[cfg(not(feature = "std"))]
-            ErrorCode::Io => f.write_str("Unknown I/O error"),
-            ErrorCode::ScratchTooSmall => f.write_str("Scratch buffer too small"),
-            ErrorCode::EofWhileParsingValue => f.write_str("EOF while parsing a value"),
-            ErrorCode::EofWhileParsingArray => f.write_str("EOF while parsing an array"),
-            ErrorCode::EofWhileParsingMap => f.write_str("EOF while parsing a map"),
-            ErrorCode::LengthOutOfRange => f.write_str("length out of range"),
-            ErrorCode::InvalidUtf8 => f.write_str("invalid UTF-8"),
+            ErrorCode::ScratchTooSmall { needed, available } if needed != 0 => {
+                write!(
+                    f,
+                    "scratch buffer too small, needed {needed} bytes but {available} are available"
+                )
+            }
             ErrorCode::UnexpectedCode(expected, byte) => {
                 write!(f, "expected {expected:?} found byte {byte:#02X}")
             }
-            ErrorCode::TrailingData => f.write_str("trailing data"),
-            ErrorCode::ArrayTooShort => f.write_str("array too short"),
-            ErrorCode::ArrayTooLong => f.write_str("array too long"),
-            ErrorCode::RecursionLimitExceeded => f.write_str("recursion limit exceeded"),
-            ErrorCode::WrongEnumFormat => f.write_str("wrong enum format"),
-            ErrorCode::WrongStructFormat => f.write_str("wrong struct format"),
+            ErrorCode::NumberOutOfRange { value, target } => {
+                write!(f, "integer {value} out of range for {target}")
+            }
+            ErrorCode::NotSingleChar { chars } => {
+                write!(f, "expected a single character, found {chars} characters")
+            }
+            ErrorCode::ByteArrayLength { expected, found } => {
+                write!(f, "expected {expected} bytes, got {found}")
+            }
+            #[cfg(feature = "std")]
+            ErrorCode::InvalidLength { len, ref expected } => {
+                write!(f, "invalid length {len}, expected {expected}")
+            }
+            #[cfg(not(feature = "std"))]
+            ErrorCode::InvalidLength { len } => write!(f, "invalid length {len}"),
+            ErrorCode::UnreadElements {
+                map,
+                declared,
+                index,
+            } => {
+                let (collection, elements, element) = if map {
+                    ("map", "entries", "entry")
+                } else {
+                    ("array", "elements", "element")
+                };
+                write!(
+                    f,
+                    "{collection} of {declared} {elements} has {element} {index} left unread"
+                )
+            }
+            ErrorCode::TooManyChunks { max } => {
+                write!(f, "indefinite length item has more than {max} chunks")
+            }
+            _ => f.write_str(self.description()),
         }
     }
 }
 
+#[cfg(feature = "defmt")]
+impl defmt::Format for ErrorCode {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match *self {
+            #[cfg(feature = "std")]
+            ErrorCode::Message(ref msg) => defmt::write!(f, "{=str}", msg.as_str()),
+            #[cfg(feature = "std")]
+            ErrorCode::Io(ref err) => defmt::write!(f, "{}", defmt::Display2Format(err)),
+            ErrorCode::ScratchTooSmall { needed, available } if needed != 0 => {
+                defmt::write!(
+                    f,
+                    "scratch buffer too small, needed {=usize} bytes but {=usize} are available",
+                    needed,
+                    available
+                )
+            }
+            ErrorCode::UnexpectedCode(expected, byte) => {
+                defmt::write!(f, "expected {} found byte {=u8:#04X}", expected, byte)
+            }
+            ErrorCode::NumberOutOfRange { value, target } => {
+                defmt::write!(f, "integer {=i128} out of range for {=str}", value, target)
+            }
+            ErrorCode::NotSingleChar { chars } => {
+                defmt::write!(
+                    f,
+                    "expected a single character, found {=usize} characters",
+                    chars
+                )
+            }
+            ErrorCode::ByteArrayLength { expected, found } => {
+                defmt::write!(f, "expected {=usize} bytes, got {=usize}", expected, found)
+            }
+            ErrorCode::InvalidLength { len, .. } => {
+                defmt::write!(f, "invalid length {=usize}", len)
+            }
+            ErrorCode::UnreadElements {
+                declared, index, ..
+            } => {
+                defmt::write!(
+                    f,
+                    "collection of {=usize} elements has element {=usize} left unread",
+                    declared,
+                    index
+                )
+            }
+            ErrorCode::TooManyChunks { max } => {
+                defmt::write!(
+                    f,
+                    "indefinite length item has more than {=usize} chunks",
+                    max
+                )
+            }
+            _ => defmt::write!(f, "{=str}", self.description()),
+        }
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uDisplay for ErrorCode {
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> core::result::Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        match *self {
+            #[cfg(feature = "std")]
+            ErrorCode::Message(ref msg) => f.write_str(msg),
+            ErrorCode::UnexpectedCode(expected, byte) => {
+                ufmt::uwrite!(f, "expected {:?} found byte 0x{:?}", expected, Hex(byte))
+            }
+            #[cfg(feature = "std")]
+            ErrorCode::InvalidLength { len, ref expected } => {
+                ufmt::uwrite!(f, "invalid length {}, expected {}", len, &expected[..])
+            }
+            #[cfg(not(feature = "std"))]
+            ErrorCode::InvalidLength { len } => ufmt::uwrite!(f, "invalid length {}", len),
+            _ => f.write_str(self.description()),
+        }
+    }
+}
+
+/// Formats a byte as two upper case hexadecimal digits.
+#[cfg(feature = "ufmt")]
+struct Hex(u8);
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uDebug for Hex {
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> core::result::Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+        f.write_char(DIGITS[usize::from(self.0 >> 4)] as char)?;
+        f.write_char(DIGITS[usize::from(self.0 & 0xf)] as char)
+    }
+}
+
 #[allow(missing_docs)]
 #[derive(Copy, Clone)]
 pub(crate) struct ExpectedSet(u16);
@@ -330,6 +938,8 @@ impl ExpectedSet {
     pub(crate) const MAP: ExpectedSet = ExpectedSet(64);
     pub(crate) const BOOL: ExpectedSet = ExpectedSet(128);
     pub(crate) const NULL: ExpectedSet = ExpectedSet(256);
+    #[cfg(feature = "msgpack")]
+    pub(crate) const ANY: ExpectedSet = ExpectedSet(511);
 
     pub(crate) const fn from_valid<V: crate::de::ValidValues>() -> Self {
         let mut v = 0u16;
@@ -364,35 +974,59 @@ impl ExpectedSet {
     }
 }
 
+impl ExpectedSet {
+    const NAMES: [(ExpectedSet, &'static str); 9] = [
+        (Self::STRING, "String"),
+        (Self::BYTES, "Bytes"),
+        (Self::INT_POS, "IntPos"),
+        (Self::INT_NEG, "IntNeg"),
+        (Self::FLOAT, "Float"),
+        (Self::ARRAY, "Array"),
+        (Self::MAP, "Map"),
+        (Self::BOOL, "Bool"),
+        (Self::NULL, "Null"),
+    ];
+
+    fn names(self) -> impl Iterator<Item = &'static str> {
+        Self::NAMES
+            .iter()
+            .filter(move |(set, _)| self.0 & set.0 != 0)
+            .map(|(_, name)| *name)
+    }
+}
+
 impl core::fmt::Debug for ExpectedSet {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.write_str("{")?;
-        if self.0 & Self::STRING.0 != 0 {
-            f.write_str("String,")?;
-        }
-        if self.0 & Self::BYTES.0 != 0 {
-            f.write_str("Bytes,")?;
-        }
-        if self.0 & Self::INT_POS.0 != 0 {
-            f.write_str("IntPos,")?;
-        }
-        if self.0 & Self::INT_NEG.0 != 0 {
-            f.write_str("IntNeg,")?;
-        }
-        if self.0 & Self::FLOAT.0 != 0 {
-            f.write_str("Float,")?;
-        }
-        if self.0 & Self::ARRAY.0 != 0 {
-            f.write_str("Array,")?;
-        }
-        if self.0 & Self::MAP.0 != 0 {
-            f.write_str("Map,")?;
+        for name in self.names() {
+            f.write_str(name)?;
+            f.write_str(",")?;
         }
-        if self.0 & Self::BOOL.0 != 0 {
-            f.write_str("Bool,")?;
+        f.write_str("}")
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for ExpectedSet {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "{{");
+        for name in self.names() {
+            defmt::write!(f, "{=str},", name);
         }
-        if self.0 & Self::NULL.0 != 0 {
-            f.write_str("Null,")?;
+        defmt::write!(f, "}}");
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uDebug for ExpectedSet {
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> core::result::Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        f.write_str("{")?;
+        for name in self.names() {
+            f.write_str(name)?;
+            f.write_str(",")?;
         }
         f.write_str("}")
     }
@@ -0,0 +1,84 @@
+//! Half precision floats as `half::f16`.
+//!
+//! Use this module with `#[serde(with = "serde_cbor::float16")]` on an `f16` field. It is written
+//! as a half precision float instead of being promoted to a wider one, and decoding accepts any
+//! float that a half holds exactly. Other serializers see a newtype struct holding an `f64`.
+//!
+//! The `f16` type is re-exported from the `half` version this crate uses, so it always matches
+//! the one in these signatures.
+//!
+//! ```
+//! use serde_cbor::float16::f16;
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, PartialEq, Deserialize, Serialize)]
+//! struct Reading {
+//!     #[serde(with = "serde_cbor::float16")]
+//!     celsius: f16,
+//! }
+//!
+//! let reading = Reading { celsius: f16::from_f32(21.5) };
+//! let bytes = serde_cbor::to_vec(&reading).unwrap();
+//! assert_eq!(bytes, b"\xa1\x67celsius\xf9\x4d\x60");
+//! assert_eq!(serde_cbor::from_slice::<Reading>(&bytes).unwrap(), reading);
+//!
+//! // 0.1 has no exact half precision representation
+//! let bytes = b"\xa1\x67celsius\xfb\x3f\xb9\x99\x99\x99\x99\x99\x9a";
+//! assert!(serde_cbor::from_slice::<Reading>(bytes).is_err());
+//! ```
+
+use core::fmt;
+
+pub use half::f16;
+use serde::de;
+use serde::ser;
+
+use crate::ser::SIZED_FLOAT_NAMES;
+
+/// Writes `value` as a half precision float.
+pub fn serialize<S>(value: &f16, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: ser::Serializer,
+{
+    serializer.serialize_newtype_struct(SIZED_FLOAT_NAMES[0].0, &value.to_f64())
+}
+
+/// Reads a float of any width that a half holds exactly.
+///
+/// `NaN`s are accepted with any payload and decoded as a `NaN` of the same sign.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<f16, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct HalfVisitor;
+
+    impl<'de> de::Visitor<'de> for HalfVisitor {
+        type Value = f16;
+
+        fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt.write_str("a half precision float")
+        }
+
+        fn visit_f32<E: de::Error>(self, v: f32) -> Result<f16, E> {
+            self.visit_f64(v.into())
+        }
+
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<f16, E> {
+            let half = f16::from_f64(v);
+            if v.is_nan() || half.to_f64() == v {
+                Ok(half)
+            } else {
+                Err(E::invalid_value(de::Unexpected::Float(v), &self))
+            }
+        }
+
+        fn visit_newtype_struct<D>(self, deserializer: D) -> Result<f16, D::Error>
+        where
+            D: de::Deserializer<'de>,
+        {
+            deserializer.deserialize_f32(self)
+        }
+    }
+
+    deserializer.deserialize_f32(HalfVisitor)
+}
@@ -0,0 +1,138 @@
+//! Decode the header of an item without its contents.
+//!
+//! Every CBOR item starts with a header of one to nine bytes holding the major type and an
+//! argument, which is the value of an integer, the length of a string or collection, or the
+//! number of a tag. Routers often only need this to decide where to forward a message.
+//!
+//! ```
+//! use serde_cbor::header::{peek_header, MajorType};
+//!
+//! // [1, 2, 3] tagged with 1000
+//! let input = b"\xd9\x03\xe8\x83\x01\x02\x03";
+//! let tag = peek_header(input).unwrap();
+//! assert_eq!((tag.major, tag.tag(), tag.size), (MajorType::Tag, Some(1000), 3));
+//! let array = peek_header(&input[tag.size..]).unwrap();
+//! assert_eq!((array.major, array.length()), (MajorType::Array, Some(3)));
+//! ```
+
+use crate::de::ValidAll;
+use crate::error::{Error, ErrorCode, ExpectedSet, Result};
+
+/// The major type of an item, the upper three bits of its initial byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MajorType {
+    /// An unsigned integer.
+    Unsigned,
+    /// A negative integer.
+    Negative,
+    /// A byte string.
+    Bytes,
+    /// A text string.
+    Text,
+    /// An array.
+    Array,
+    /// A map.
+    Map,
+    /// A tag.
+    Tag,
+    /// A float, a simple value like `true` or `null`, or the break code.
+    Simple,
+}
+
+impl MajorType {
+    fn from_byte(byte: u8) -> MajorType {
+        match byte >> 5 {
+            0 => MajorType::Unsigned,
+            1 => MajorType::Negative,
+            2 => MajorType::Bytes,
+            3 => MajorType::Text,
+            4 => MajorType::Array,
+            5 => MajorType::Map,
+            6 => MajorType::Tag,
+            _ => MajorType::Simple,
+        }
+    }
+}
+
+/// The header of an item, see [`peek_header`](fn.peek_header.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// The major type.
+    pub major: MajorType,
+    /// The additional information, the lower five bits of the initial byte.
+    pub info: u8,
+    /// The argument, `None` for indefinite length items and the break code.
+    ///
+    /// For floats this holds the bits of the float.
+    pub argument: Option<u64>,
+    /// The number of bytes of the header, the contents of the item start after them.
+    pub size: usize,
+}
+
+impl Header {
+    /// The declared length of a string, array or map, the number of entries for maps.
+    ///
+    /// Returns `None` for other items and indefinite length items.
+    pub fn length(&self) -> Option<u64> {
+        match self.major {
+            MajorType::Bytes | MajorType::Text | MajorType::Array | MajorType::Map => self.argument,
+            _ => None,
+        }
+    }
+
+    /// The number of a tag, `None` for other items.
+    pub fn tag(&self) -> Option<u64> {
+        match self.major {
+            MajorType::Tag => self.argument,
+            _ => None,
+        }
+    }
+
+    /// Returns true for indefinite length strings, arrays and maps.
+    pub fn is_indefinite(&self) -> bool {
+        self.info == 31 && self.major != MajorType::Simple
+    }
+}
+
+/// Decodes the header at the start of `input`, ignoring the contents of the item and anything
+/// after it.
+///
+/// Fails if `input` ends within the header or the additional information is one of the reserved
+/// values 28 to 30, or 31 for an integer or a tag.
+pub fn peek_header(input: &[u8]) -> Result<Header> {
+    let byte = *input
+        .first()
+        .ok_or_else(|| Error::syntax(ErrorCode::EofWhileParsingValue, 0))?;
+    let major = MajorType::from_byte(byte);
+    let info = byte & 0x1f;
+    let (argument, size) = match info {
+        0..=23 => (Some(u64::from(info)), 1),
+        24..=27 => {
+            let len = 1 << (info - 24);
+            let bytes = input.get(1..1 + len).ok_or_else(|| {
+                Error::syntax(ErrorCode::EofWhileParsingValue, input.len() as u64)
+            })?;
+            let value = bytes.iter().fold(0, |value, &b| value << 8 | u64::from(b));
+            (Some(value), 1 + len)
+        }
+        31 if !matches!(
+            major,
+            MajorType::Unsigned | MajorType::Negative | MajorType::Tag
+        ) =>
+        {
+            (None, 1)
+        }
+        _ => {
+            return Err(Error::syntax(
+                ErrorCode::UnexpectedCode(ExpectedSet::from_valid::<ValidAll>(), byte),
+                0,
+            ))
+        }
+    };
+    Ok(Header {
+        major,
+        info,
+        argument,
+        size,
+    })
+}
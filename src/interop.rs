@@ -0,0 +1,67 @@
+//! Options matching the conventions of CBOR implementations in other languages.
+//!
+//! Every [`Ecosystem`](enum.Ecosystem.html) bundles the serializer and deserializer options that
+//! make data written by the other implementation decode, and data written by this crate look the
+//! way the other implementation expects.
+//!
+//! ```
+//! use serde::Deserialize;
+//! use serde_cbor::de::{Deserializer, SliceRead};
+//! use serde_cbor::interop::Ecosystem;
+//!
+//! // cbor-x writes integers above 32 bits as floats: 1.6e12
+//! let input = b"\xfb\x42\x77\x48\x76\xe8\x00\x00\x00";
+//! let options = Ecosystem::CborX.deserializer_options();
+//! let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+//! let millis = u64::deserialize(&mut deserializer).unwrap();
+//! assert_eq!(millis, 1_600_000_000_000);
+//! ```
+
+use crate::de::CustomDeserializerOptions;
+use crate::ser::{CustomSerializerOptions, KeyOrder};
+
+/// A CBOR implementation whose conventions can be matched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Ecosystem {
+    /// The Python library `cbor2`.
+    ///
+    /// Values with a text representation, like IP addresses, are written as text. `cbor2` tags
+    /// datetimes with 0, decimals with 4 and sets with 258. Fields with
+    /// [`tags::datetime`](../tags/datetime/index.html),
+    /// [`tags::decimal`](../tags/decimal/index.html) and [`tags::set`](../tags/set/index.html)
+    /// are written and read the same way when the `tags` feature is enabled.
+    Cbor2,
+    /// The Go library `github.com/fxamacker/cbor`.
+    ///
+    /// Values with a text representation are written as text. Map keys are sorted bytewise like
+    /// its deterministic encoding modes, so the same data encodes to the same bytes on both
+    /// sides even though Go randomizes map iteration.
+    Fxamacker,
+    /// The JavaScript library `cbor-x`.
+    ///
+    /// Values with a text representation are written as text. JavaScript has a single number
+    /// type and `cbor-x` writes integers that do not fit into 32 bits as floats, these floats
+    /// are accepted for integer types.
+    CborX,
+}
+
+impl Ecosystem {
+    /// Options for serializing data that is read by this implementation.
+    pub fn serializer_options(self) -> CustomSerializerOptions {
+        let options = CustomSerializerOptions::new().set_human_readable(true);
+        match self {
+            Ecosystem::Cbor2 | Ecosystem::CborX => options,
+            Ecosystem::Fxamacker => options.set_key_order(Some(KeyOrder::Bytewise)),
+        }
+    }
+
+    /// Options for deserializing data that was written by this implementation.
+    pub fn deserializer_options(self) -> CustomDeserializerOptions {
+        let options = CustomDeserializerOptions::new().set_human_readable(true);
+        match self {
+            Ecosystem::Cbor2 | Ecosystem::Fxamacker => options,
+            Ecosystem::CborX => options.set_accept_integral_floats(true),
+        }
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! # Usage
 //!
-//! Serde CBOR supports Rust 1.40 and up. Add this to your `Cargo.toml`:
+//! Serde CBOR supports Rust 1.70 and up. Add this to your `Cargo.toml`:
 //! ```toml
 //! [dependencies]
 //! serde_cbor = "0.10"
@@ -206,6 +206,16 @@
 //!
 //! [alloc-lib]: https://doc.rust-lang.org/alloc/
 //!
+//! Errors carry the values they report, like the integer that was out of range. The
+//! `compact_errors` feature drops these in `no_std` builds and keeps just the kind of the error
+//! and its offset, which makes `Error` and the results returning it smaller.
+//!
+//! With `std` the `Error` type implements `std::error::Error`. In `no_std` builds it implements
+//! `core::error::Error` instead when the compiler is Rust 1.81 or later.
+//!
+//! `Value` requires the `std` feature. To inspect documents of unknown shape without an
+//! allocator decode them into a fixed number of nodes with [value_slice].
+//!
 //! *Note*: to use derive macros in serde you will need to declare `serde`
 //! dependency like so:
 //! ``` toml
@@ -292,6 +302,68 @@
 //! # }
 //! ```
 //!
+//! Owned strings and sequences of bounded size can be decoded into the fixed capacity containers
+//! of the [`heapless`](https://docs.rs/heapless) crate, with its `serde` feature enabled. Input
+//! that does not fit fails with an error whose
+//! [`invalid_length`](struct.Error.html#method.invalid_length) tells the rejected length, placed
+//! at the start of the item.
+//! ``` rust
+//! # #[macro_use] extern crate serde_derive;
+//! use serde_cbor::de::from_slice_with_scratch;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Config {
+//!     name: heapless::String<8>,
+//!     thresholds: heapless::Vec<u16, 4>,
+//! }
+//!
+//! // {"name": "pump", "thresholds": [10, 200]}
+//! let input = b"\xa2\x64name\x64pump\x6athresholds\x82\x0a\x18\xc8";
+//! let config: Config = from_slice_with_scratch(input, &mut []).unwrap();
+//! assert_eq!(config.name, "pump");
+//! assert_eq!(config.thresholds, [10, 200]);
+//!
+//! // {"name": "pump", "thresholds": [1, 2, 3, 4, 5]}
+//! let input = b"\xa2\x64name\x64pump\x6athresholds\x85\x01\x02\x03\x04\x05";
+//! let err = from_slice_with_scratch::<Config>(input, &mut []).unwrap_err();
+//! assert_eq!(err.invalid_length(), Some(5));
+//! assert_eq!(err.offset(), 22);
+//! ```
+//!
+//! # Borrowing
+//!
+//! Strings and byte strings are handed to the visitor as borrowed data whenever the input
+//! allows it. Combined with `#[serde(borrow)]` this makes `Cow<'de, str>` and `Cow<'de, [u8]>`
+//! fields zero-copy:
+//!
+//! * `from_slice` borrows definite length strings and copies indefinite length (chunked) ones.
+//! * `from_mut_slice` borrows all strings, chunked strings are joined in place.
+//! * `from_slice_with_scratch` borrows definite length strings and copies chunked ones into the
+//!   scratch buffer.
+//! * `from_reader` never borrows, so every `Cow` ends up `Owned`.
+//!
+//! ``` rust
+//! # #[macro_use] extern crate serde_derive;
+//! # fn main() -> Result<(), serde_cbor::Error> {
+//! use std::borrow::Cow;
+//!
+//! #[derive(Deserialize)]
+//! struct Message<'a> {
+//!     #[serde(borrow)]
+//!     text: Cow<'a, str>,
+//!     #[serde(borrow)]
+//!     data: Cow<'a, [u8]>,
+//! }
+//!
+//! // {"text": "hi", "data": h'0102'}
+//! let bytes = b"\xa2\x64text\x62hi\x64data\x42\x01\x02";
+//! let message: Message = serde_cbor::from_slice(bytes)?;
+//! assert!(matches!(message.text, Cow::Borrowed("hi")));
+//! assert!(matches!(message.data, Cow::Borrowed(&[1, 2])));
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! # Limitations
 //!
 //! While Serde CBOR strives to support all features of Serde and CBOR
@@ -325,15 +397,57 @@ extern crate std;
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+#[cfg(feature = "std")]
+pub mod arena;
+#[cfg(feature = "bumpalo")]
+pub mod bump;
+#[cfg(feature = "bytes")]
+pub mod bytes;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod canonical;
+#[cfg(feature = "std")]
+pub mod codegen;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+pub mod constant;
 pub mod de;
+#[cfg(feature = "std")]
+pub mod digest;
+pub mod document;
+#[cfg(feature = "std")]
+mod dump;
+#[cfg(feature = "embedded")]
+pub mod embedded;
 pub mod error;
+#[cfg(feature = "f16")]
+pub mod float16;
+pub mod header;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod interop;
+#[cfg(feature = "std")]
+pub mod log;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod profile;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod projection;
 mod read;
 pub mod ser;
+#[cfg(feature = "futures")]
+pub mod stream;
 pub mod tags;
 mod write;
 
 #[cfg(feature = "std")]
 pub mod value;
+pub mod value_slice;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "zstd")]
+pub mod zstd;
 
 // Re-export the [items recommended by serde](https://serde.rs/conventions.html).
 #[doc(inline)]
@@ -345,6 +459,10 @@ pub use crate::error::{Error, Result};
 #[doc(inline)]
 pub use crate::ser::Serializer;
 
+#[cfg(feature = "std")]
+#[doc(inline)]
+pub use crate::dump::dump;
+
 // Convenience functions for serialization and deserialization.
 // These functions are only available in `std` mode.
 #[cfg(feature = "std")]
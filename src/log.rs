@@ -0,0 +1,338 @@
+//! Append-only logs of CBOR records.
+//!
+//! A log is a CBOR sequence (RFC 8742): every record is a single data item written after the
+//! previous one. With checksums enabled every item is followed by the CRC-32 of its encoding,
+//! written as a 32 bit unsigned integer, so a log with checksums is still a valid CBOR sequence.
+//!
+//! A crash while appending can leave the last record incomplete. [`LogReader`] stops at such a
+//! torn tail instead of failing and reports where it starts, so the log can be truncated there
+//! before appending again.
+//!
+//! ```
+//! use serde_cbor::log::{LogReader, LogWriter};
+//!
+//! let mut writer = LogWriter::new(Vec::new()).checksums(true);
+//! writer.append(&"first").unwrap();
+//! writer.append(&"second").unwrap();
+//! let mut log = writer.into_inner();
+//! // a crash in the middle of the third record
+//! log.extend_from_slice(b"\x65thi");
+//!
+//! let mut reader = LogReader::<_, String>::new(&log[..]).checksums(true);
+//! let records = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+//! assert_eq!(records[1].offset, 11);
+//! assert_eq!(records[1].value, "second");
+//! assert_eq!(reader.torn_tail(), Some(23));
+//! ```
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+use serde::de;
+use serde::ser;
+
+use crate::de::Deserializer;
+use crate::error::{Error, Result};
+
+const DEFAULT_CHUNK_SIZE: usize = 8 * 1024;
+
+/// The header of the 32 bit unsigned integer holding a checksum.
+const CHECKSUM_HEADER: u8 = 0x1a;
+
+/// Length of an encoded checksum.
+const CHECKSUM_LEN: usize = 5;
+
+/// CRC-32 with the polynomial of IEEE 802.3, as used by zlib and PNG.
+fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 == 1 {
+                    0xedb8_8320 ^ (crc >> 1)
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+    !data.iter().fold(!0, |crc, &byte| {
+        TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Appends records to a log.
+#[derive(Debug)]
+pub struct LogWriter<W> {
+    writer: W,
+    offset: u64,
+    checksums: bool,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> LogWriter<W> {
+    /// Creates a writer appending to `writer`, which starts at offset 0.
+    pub fn new(writer: W) -> LogWriter<W> {
+        LogWriter::at_offset(writer, 0)
+    }
+
+    /// Creates a writer appending to `writer`, which is positioned `offset` bytes into the log.
+    ///
+    /// This is the length of the existing log when reopening it, the offsets returned by
+    /// [`append`](#method.append) are counted from there.
+    pub fn at_offset(writer: W, offset: u64) -> LogWriter<W> {
+        LogWriter {
+            writer,
+            offset,
+            checksums: false,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Sets whether every record is followed by its checksum. Defaults to `false`.
+    pub fn checksums(mut self, checksums: bool) -> Self {
+        self.checksums = checksums;
+        self
+    }
+
+    /// Appends `value` and returns the offset of its record.
+    ///
+    /// The record is encoded completely before it is handed to the writer with a single
+    /// `write_all`.
+    pub fn append<T: ser::Serialize>(&mut self, value: &T) -> Result<u64> {
+        self.buffer.clear();
+        crate::to_writer(&mut self.buffer, value)?;
+        if self.checksums {
+            let crc = crc32(&self.buffer);
+            self.buffer.push(CHECKSUM_HEADER);
+            self.buffer.extend_from_slice(&crc.to_be_bytes());
+        }
+        self.writer.write_all(&self.buffer).map_err(Error::io)?;
+        let offset = self.offset;
+        self.offset += self.buffer.len() as u64;
+        Ok(offset)
+    }
+
+    /// The offset at which the next record will be written.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush().map_err(Error::io)
+    }
+
+    /// Unwraps the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// A record read from a log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record<T> {
+    /// The offset of the first byte of the record.
+    pub offset: u64,
+    /// The decoded record.
+    pub value: T,
+}
+
+/// The outcome of decoding the buffered bytes.
+enum Step<T> {
+    Record(Record<T>),
+    NeedInput,
+    Torn,
+    Error(Error),
+}
+
+/// Iterates over the records of a log.
+///
+/// The iterator ends at the end of the input, or at a torn tail: a last record that is
+/// incomplete, or whose checksum does not match while nothing follows it. Checksum mismatches and
+/// malformed records elsewhere are errors, after an error no further records are produced.
+#[derive(Debug)]
+pub struct LogReader<R, T> {
+    reader: R,
+    buffer: Vec<u8>,
+    start: usize,
+    offset: u64,
+    chunk_size: usize,
+    checksums: bool,
+    eof: bool,
+    done: bool,
+    torn_tail: Option<u64>,
+    output: PhantomData<fn() -> T>,
+}
+
+impl<R, T> LogReader<R, T>
+where
+    R: Read,
+    T: de::DeserializeOwned,
+{
+    /// Creates a reader for the log in `reader`.
+    pub fn new(reader: R) -> LogReader<R, T> {
+        LogReader {
+            reader,
+            buffer: Vec::new(),
+            start: 0,
+            offset: 0,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            checksums: false,
+            eof: false,
+            done: false,
+            torn_tail: None,
+            output: PhantomData,
+        }
+    }
+
+    /// Sets whether every record is followed by its checksum. Defaults to `false`.
+    ///
+    /// This has to match the setting the log was written with.
+    pub fn checksums(mut self, checksums: bool) -> Self {
+        self.checksums = checksums;
+        self
+    }
+
+    /// Sets the number of bytes requested from the reader at once. Defaults to 8 KiB.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// The offset of the first byte after the last record returned.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The offset of the torn last record, once the iterator stopped at one.
+    ///
+    /// Truncating the log to this length removes the incomplete record.
+    pub fn torn_tail(&self) -> Option<u64> {
+        self.torn_tail
+    }
+
+    /// Unwraps the reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Tries to decode a record from the buffered bytes.
+    fn decode_buffered(&mut self) -> Step<T> {
+        let input = &self.buffer[self.start..];
+        let mut deserializer = Deserializer::from_slice(input);
+        let value = match T::deserialize(&mut deserializer) {
+            Ok(value) => value,
+            Err(ref e) if e.is_eof() => return self.incomplete(),
+            Err(e) => return Step::Error(e.shift_offset(self.offset)),
+        };
+        let mut len = deserializer.byte_offset();
+        if self.checksums {
+            if input.get(len).is_some_and(|&byte| byte != CHECKSUM_HEADER) {
+                return Step::Error(Error::message(format_args!(
+                    "log record at offset {} is not followed by a checksum",
+                    self.offset
+                )));
+            }
+            let checksum = match input.get(len + 1..len + CHECKSUM_LEN) {
+                Some(checksum) => checksum,
+                None => return self.incomplete(),
+            };
+            let expected = u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]);
+            if crc32(&input[..len]) != expected {
+                // a corrupted last record is torn, so wait for the end of the input to tell
+                if len + CHECKSUM_LEN == input.len() {
+                    return self.incomplete();
+                }
+                return Step::Error(Error::message(format_args!(
+                    "checksum mismatch in log record at offset {}",
+                    self.offset
+                )));
+            }
+            len += CHECKSUM_LEN;
+        }
+        let record = Record {
+            offset: self.offset,
+            value,
+        };
+        self.start += len;
+        self.offset += len as u64;
+        if self.start * 2 > self.buffer.len() {
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+        Step::Record(record)
+    }
+
+    /// Handles a record that ends after the buffered bytes.
+    fn incomplete(&self) -> Step<T> {
+        if self.eof {
+            Step::Torn
+        } else {
+            Step::NeedInput
+        }
+    }
+
+    fn fill_buffer(&mut self) -> Result<()> {
+        let filled = self.buffer.len();
+        self.buffer.resize(filled + self.chunk_size, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buffer[filled..]) {
+                Ok(n) => break n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => {
+                    self.buffer.truncate(filled);
+                    return Err(Error::io(e));
+                }
+            }
+        };
+        self.buffer.truncate(filled + read);
+        if read == 0 {
+            self.eof = true;
+        }
+        Ok(())
+    }
+}
+
+impl<R, T> Iterator for LogReader<R, T>
+where
+    R: Read,
+    T: de::DeserializeOwned,
+{
+    type Item = Result<Record<T>>;
+
+    fn next(&mut self) -> Option<Result<Record<T>>> {
+        loop {
+            if self.done {
+                return None;
+            }
+            if self.start < self.buffer.len() {
+                match self.decode_buffered() {
+                    Step::Record(record) => return Some(Ok(record)),
+                    Step::NeedInput => (),
+                    Step::Torn => {
+                        self.done = true;
+                        self.torn_tail = Some(self.offset);
+                        return None;
+                    }
+                    Step::Error(e) => {
+                        self.done = true;
+                        return Some(Err(e));
+                    }
+                }
+            } else if self.eof {
+                return None;
+            }
+            if let Err(e) = self.fill_buffer() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+    }
+}
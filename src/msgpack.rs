@@ -0,0 +1,448 @@
+//! Streaming transcoders between CBOR and MessagePack.
+//!
+//! [`cbor_to_msgpack`](fn.cbor_to_msgpack.html) and [`msgpack_to_cbor`](fn.msgpack_to_cbor.html)
+//! convert a sequence of data items header by header, without decoding them into values. Integers
+//! keep the width they were written with as far as the other format has an equivalent, byte
+//! strings stay binary and maps keep the order of their entries.
+//!
+//! ```
+//! let mut msgpack = Vec::new();
+//! // {"a": [1, h'ff']}
+//! serde_cbor::msgpack::cbor_to_msgpack(&b"\xa1\x61a\x82\x01\x41\xff"[..], &mut msgpack).unwrap();
+//! assert_eq!(msgpack, b"\x81\xa1a\x92\x01\xc4\x01\xff");
+//!
+//! let mut cbor = Vec::new();
+//! serde_cbor::msgpack::msgpack_to_cbor(&msgpack[..], &mut cbor).unwrap();
+//! assert_eq!(cbor, b"\xa1\x61a\x82\x01\x41\xff");
+//! ```
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+use half::f16;
+use rmp::{encode, Marker};
+
+use crate::error::{Error, ErrorCode, ExpectedSet, Result};
+
+/// Items nested deeper than this are rejected.
+const RECURSION_LIMIT: usize = 128;
+
+/// Transcodes every CBOR data item of `reader` to MessagePack.
+///
+/// Integers written with 1, 2, 4 or 8 bytes become the unsigned or signed MessagePack integer of
+/// the same width, negative integers that do not fit into it use the next wider one. Indefinite
+/// length items are collected and written with their length. MessagePack has no tags, they are
+/// dropped and only their content is kept. Half precision floats are widened to single precision,
+/// `undefined` becomes `nil`. Other simple values and negative integers below `i64::MIN` cannot be
+/// represented and fail the conversion.
+pub fn cbor_to_msgpack<R: Read, W: Write>(reader: R, mut writer: W) -> Result<()> {
+    let mut transcoder = Transcoder { reader, offset: 0 };
+    while let Some(byte) = transcoder.next_byte()? {
+        transcoder.cbor_item(byte, &mut writer, 0)?;
+    }
+    Ok(())
+}
+
+/// Transcodes every MessagePack object of `reader` to CBOR.
+///
+/// Integers are written with the same width they had, a MessagePack `int8` of `-1` becomes the
+/// CBOR negative integer with a one byte argument. Strings have to be valid UTF-8 to become text
+/// strings. Extension types have no CBOR equivalent and fail the conversion.
+pub fn msgpack_to_cbor<R: Read, W: Write>(reader: R, mut writer: W) -> Result<()> {
+    let mut transcoder = Transcoder { reader, offset: 0 };
+    while let Some(byte) = transcoder.next_byte()? {
+        transcoder.msgpack_item(byte, &mut writer, 0)?;
+    }
+    Ok(())
+}
+
+fn unexpected(byte: u8, offset: u64) -> Error {
+    Error::syntax(ErrorCode::UnexpectedCode(ExpectedSet::ANY, byte), offset)
+}
+
+fn io_result<T, E: Into<io::Error>>(result: std::result::Result<T, E>) -> Result<T> {
+    result.map_err(|e| Error::io(e.into()))
+}
+
+struct Transcoder<R> {
+    reader: R,
+    offset: u64,
+}
+
+impl<R: Read> Transcoder<R> {
+    /// Reads the next byte, or `None` at the end of the input.
+    fn next_byte(&mut self) -> Result<Option<u8>> {
+        let mut byte = [0];
+        loop {
+            return match self.reader.read(&mut byte) {
+                Ok(0) => Ok(None),
+                Ok(_) => {
+                    self.offset += 1;
+                    Ok(Some(byte[0]))
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => Err(Error::io(e)),
+            };
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        self.next_byte()?
+            .ok_or_else(|| Error::syntax(ErrorCode::EofWhileParsingValue, self.offset))
+    }
+
+    fn exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        match self.reader.read_exact(buf) {
+            Ok(()) => {
+                self.offset += buf.len() as u64;
+                Ok(())
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                Err(Error::syntax(ErrorCode::EofWhileParsingValue, self.offset))
+            }
+            Err(e) => Err(Error::io(e)),
+        }
+    }
+
+    /// Reads a big endian integer of `size` bytes.
+    fn uint(&mut self, size: usize) -> Result<u64> {
+        let mut buf = [0; 8];
+        self.exact(&mut buf[8 - size..])?;
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    fn bytes(&mut self, len: u64) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        let read = (&mut self.reader)
+            .take(len)
+            .read_to_end(&mut buf)
+            .map_err(Error::io)?;
+        self.offset += read as u64;
+        if (read as u64) < len {
+            return Err(Error::syntax(ErrorCode::EofWhileParsingValue, self.offset));
+        }
+        Ok(buf)
+    }
+
+    fn check_depth(&self, depth: usize) -> Result<()> {
+        if depth >= RECURSION_LIMIT {
+            return Err(Error::syntax(
+                ErrorCode::RecursionLimitExceeded,
+                self.offset,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reads the argument of a CBOR header, returning its value and its size in bytes, `None`
+    /// for indefinite lengths.
+    fn cbor_argument(&mut self, byte: u8) -> Result<Option<(u64, usize)>> {
+        match byte & 0x1f {
+            info @ 0..=23 => Ok(Some((info.into(), 0))),
+            info @ 24..=27 => {
+                let size = 1 << (info - 24);
+                Ok(Some((self.uint(size)?, size)))
+            }
+            31 => Ok(None),
+            _ => Err(Error::syntax(ErrorCode::LengthOutOfRange, self.offset - 1)),
+        }
+    }
+
+    fn cbor_length(&mut self, byte: u8) -> Result<Option<u32>> {
+        let offset = self.offset - 1;
+        match self.cbor_argument(byte)? {
+            Some((len, _)) => u32::try_from(len)
+                .map(Some)
+                .map_err(|_| Error::syntax(ErrorCode::LengthOutOfRange, offset)),
+            None => Ok(None),
+        }
+    }
+
+    /// Transcodes the CBOR item starting with `byte`.
+    fn cbor_item<W: Write>(&mut self, byte: u8, out: &mut W, depth: usize) -> Result<()> {
+        self.check_depth(depth)?;
+        let offset = self.offset - 1;
+        match byte >> 5 {
+            0 => {
+                let (n, size) = self
+                    .cbor_argument(byte)?
+                    .ok_or_else(|| unexpected(byte, offset))?;
+                match size {
+                    0 => io_result(encode::write_pfix(out, n as u8)),
+                    1 => io_result(encode::write_u8(out, n as u8)),
+                    2 => io_result(encode::write_u16(out, n as u16)),
+                    4 => io_result(encode::write_u32(out, n as u32)),
+                    _ => io_result(encode::write_u64(out, n)),
+                }
+            }
+            1 => {
+                let (n, size) = self
+                    .cbor_argument(byte)?
+                    .ok_or_else(|| unexpected(byte, offset))?;
+                let value = -1 - i128::from(n);
+                match (size, value) {
+                    (0, v) => io_result(encode::write_nfix(out, v as i8)),
+                    (1, v) if v >= i128::from(i8::MIN) => io_result(encode::write_i8(out, v as i8)),
+                    (1, v) | (2, v) if v >= i128::from(i16::MIN) => {
+                        io_result(encode::write_i16(out, v as i16))
+                    }
+                    (1, v) | (2, v) | (4, v) if v >= i128::from(i32::MIN) => {
+                        io_result(encode::write_i32(out, v as i32))
+                    }
+                    (_, v) if v >= i128::from(i64::MIN) => {
+                        io_result(encode::write_i64(out, v as i64))
+                    }
+                    (_, value) => Err(Error::syntax(
+                        ErrorCode::NumberOutOfRange {
+                            value,
+                            target: "i64",
+                        },
+                        offset,
+                    )),
+                }
+            }
+            major @ 2..=3 => {
+                let content = match self.cbor_length(byte)? {
+                    Some(len) => self.bytes(len.into())?,
+                    None => {
+                        let mut content = Vec::new();
+                        loop {
+                            let chunk = self.byte()?;
+                            if chunk == 0xff {
+                                break;
+                            }
+                            if chunk >> 5 != major {
+                                return Err(unexpected(chunk, self.offset - 1));
+                            }
+                            let len = self
+                                .cbor_length(chunk)?
+                                .ok_or_else(|| unexpected(chunk, self.offset - 1))?;
+                            content.extend(self.bytes(len.into())?);
+                        }
+                        content
+                    }
+                };
+                let len = u32::try_from(content.len())
+                    .map_err(|_| Error::syntax(ErrorCode::LengthOutOfRange, offset))?;
+                if major == 2 {
+                    io_result(encode::write_bin_len(out, len))?;
+                } else {
+                    if std::str::from_utf8(&content).is_err() {
+                        return Err(Error::syntax(ErrorCode::InvalidUtf8, offset));
+                    }
+                    io_result(encode::write_str_len(out, len))?;
+                }
+                io_result(out.write_all(&content))
+            }
+            major @ 4..=5 => {
+                let per_entry = if major == 4 { 1 } else { 2 };
+                match self.cbor_length(byte)? {
+                    Some(len) => {
+                        if major == 4 {
+                            io_result(encode::write_array_len(out, len))?;
+                        } else {
+                            io_result(encode::write_map_len(out, len))?;
+                        }
+                        for _ in 0..u64::from(len) * per_entry {
+                            let byte = self.byte()?;
+                            self.cbor_item(byte, out, depth + 1)?;
+                        }
+                        Ok(())
+                    }
+                    None => {
+                        let mut items = Vec::new();
+                        let mut count = 0u64;
+                        loop {
+                            let byte = self.byte()?;
+                            if byte == 0xff {
+                                break;
+                            }
+                            self.cbor_item(byte, &mut items, depth + 1)?;
+                            if major == 5 {
+                                let byte = self.byte()?;
+                                self.cbor_item(byte, &mut items, depth + 1)?;
+                            }
+                            count += 1;
+                        }
+                        let len = u32::try_from(count)
+                            .map_err(|_| Error::syntax(ErrorCode::LengthOutOfRange, offset))?;
+                        if major == 4 {
+                            io_result(encode::write_array_len(out, len))?;
+                        } else {
+                            io_result(encode::write_map_len(out, len))?;
+                        }
+                        io_result(out.write_all(&items))
+                    }
+                }
+            }
+            6 => {
+                self.cbor_argument(byte)?
+                    .ok_or_else(|| unexpected(byte, offset))?;
+                let byte = self.byte()?;
+                self.cbor_item(byte, out, depth + 1)
+            }
+            _ => match byte {
+                0xf4 => io_result(encode::write_bool(out, false)),
+                0xf5 => io_result(encode::write_bool(out, true)),
+                0xf6 | 0xf7 => io_result(encode::write_nil(out)),
+                0xf9 => {
+                    let half = f16::from_bits(self.uint(2)? as u16);
+                    io_result(encode::write_f32(out, half.to_f32()))
+                }
+                0xfa => io_result(encode::write_f32(out, f32::from_bits(self.uint(4)? as u32))),
+                0xfb => io_result(encode::write_f64(out, f64::from_bits(self.uint(8)?))),
+                _ => Err(unexpected(byte, offset)),
+            },
+        }
+    }
+
+    /// Transcodes the MessagePack object starting with `byte`.
+    fn msgpack_item<W: Write>(&mut self, byte: u8, out: &mut W, depth: usize) -> Result<()> {
+        self.check_depth(depth)?;
+        let offset = self.offset - 1;
+        match Marker::from_u8(byte) {
+            Marker::FixPos(n) => write_cbor_header(out, 0, n.into(), 0),
+            Marker::FixNeg(n) => write_cbor_header(out, 1, (-1 - i64::from(n)) as u64, 0),
+            Marker::U8 => {
+                let n = self.uint(1)?;
+                write_cbor_header(out, 0, n, 1)
+            }
+            Marker::U16 => {
+                let n = self.uint(2)?;
+                write_cbor_header(out, 0, n, 2)
+            }
+            Marker::U32 => {
+                let n = self.uint(4)?;
+                write_cbor_header(out, 0, n, 4)
+            }
+            Marker::U64 => {
+                let n = self.uint(8)?;
+                write_cbor_header(out, 0, n, 8)
+            }
+            Marker::I8 => {
+                let n = self.uint(1)? as u8 as i8;
+                write_cbor_int(out, n.into(), 1)
+            }
+            Marker::I16 => {
+                let n = self.uint(2)? as u16 as i16;
+                write_cbor_int(out, n.into(), 2)
+            }
+            Marker::I32 => {
+                let n = self.uint(4)? as u32 as i32;
+                write_cbor_int(out, n.into(), 4)
+            }
+            Marker::I64 => {
+                let n = self.uint(8)? as i64;
+                write_cbor_int(out, n, 8)
+            }
+            Marker::Null => io_result(out.write_all(&[0xf6])),
+            Marker::False => io_result(out.write_all(&[0xf4])),
+            Marker::True => io_result(out.write_all(&[0xf5])),
+            Marker::F32 => {
+                let bits = self.uint(4)? as u32;
+                io_result(out.write_all(&[0xfa]))?;
+                io_result(out.write_all(&bits.to_be_bytes()))
+            }
+            Marker::F64 => {
+                let bits = self.uint(8)?;
+                io_result(out.write_all(&[0xfb]))?;
+                io_result(out.write_all(&bits.to_be_bytes()))
+            }
+            Marker::Bin8 | Marker::Bin16 | Marker::Bin32 => {
+                let size = 1 << (byte - 0xc4);
+                let len = self.uint(size)?;
+                let content = self.bytes(len)?;
+                write_cbor_header(out, 2, len, minimal_size(len))?;
+                io_result(out.write_all(&content))
+            }
+            marker @ Marker::FixStr(_)
+            | marker @ Marker::Str8
+            | marker @ Marker::Str16
+            | marker @ Marker::Str32 => {
+                let len = match marker {
+                    Marker::FixStr(len) => len.into(),
+                    _ => self.uint(1 << (byte - 0xd9))?,
+                };
+                let content = self.bytes(len)?;
+                if std::str::from_utf8(&content).is_err() {
+                    return Err(Error::syntax(ErrorCode::InvalidUtf8, offset));
+                }
+                write_cbor_header(out, 3, len, minimal_size(len))?;
+                io_result(out.write_all(&content))
+            }
+            marker @ Marker::FixArray(_)
+            | marker @ Marker::Array16
+            | marker @ Marker::Array32
+            | marker @ Marker::FixMap(_)
+            | marker @ Marker::Map16
+            | marker @ Marker::Map32 => {
+                let (major, len) = match marker {
+                    Marker::FixArray(len) => (4, len.into()),
+                    Marker::Array16 => (4, self.uint(2)?),
+                    Marker::Array32 => (4, self.uint(4)?),
+                    Marker::FixMap(len) => (5, len.into()),
+                    Marker::Map16 => (5, self.uint(2)?),
+                    _ => (5, self.uint(4)?),
+                };
+                write_cbor_header(out, major, len, minimal_size(len))?;
+                let per_entry = if major == 4 { 1 } else { 2 };
+                for _ in 0..len * per_entry {
+                    let byte = self.byte()?;
+                    self.msgpack_item(byte, out, depth + 1)?;
+                }
+                Ok(())
+            }
+            Marker::FixExt1
+            | Marker::FixExt2
+            | Marker::FixExt4
+            | Marker::FixExt8
+            | Marker::FixExt16
+            | Marker::Ext8
+            | Marker::Ext16
+            | Marker::Ext32 => Err(Error::message(format_args!(
+                "MessagePack extension type at offset {} has no CBOR equivalent",
+                offset
+            ))),
+            Marker::Reserved => Err(unexpected(byte, offset)),
+        }
+    }
+}
+
+/// The smallest argument size that holds `n`.
+fn minimal_size(n: u64) -> usize {
+    match n {
+        0..=23 => 0,
+        24..=0xff => 1,
+        0x100..=0xffff => 2,
+        0x1_0000..=0xffff_ffff => 4,
+        _ => 8,
+    }
+}
+
+/// Writes the CBOR integer `n` with an argument of `size` bytes.
+fn write_cbor_int<W: Write>(out: &mut W, n: i64, size: usize) -> Result<()> {
+    if n >= 0 {
+        write_cbor_header(out, 0, n as u64, size)
+    } else {
+        write_cbor_header(out, 1, (-1 - n) as u64, size)
+    }
+}
+
+/// Writes a CBOR header with an argument of `size` bytes, or the smallest size holding `n`.
+fn write_cbor_header<W: Write>(out: &mut W, major: u8, n: u64, size: usize) -> Result<()> {
+    let size = size.max(minimal_size(n));
+    let major = major << 5;
+    let bytes = n.to_be_bytes();
+    io_result(match size {
+        0 => out.write_all(&[major | n as u8]),
+        1 => out.write_all(&[major | 24, n as u8]),
+        2 => out
+            .write_all(&[major | 25])
+            .and_then(|()| out.write_all(&bytes[6..])),
+        4 => out
+            .write_all(&[major | 26])
+            .and_then(|()| out.write_all(&bytes[4..])),
+        _ => out
+            .write_all(&[major | 27])
+            .and_then(|()| out.write_all(&bytes)),
+    })
+}
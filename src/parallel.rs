@@ -0,0 +1,65 @@
+//! Decode many independent items concurrently.
+//!
+//! The items are decoded on the global `rayon` thread pool. Every item is decoded on its own, a
+//! malformed item only fails its own entry of the result.
+//!
+//! ```
+//! use serde_cbor::parallel::decode_sequence_parallel;
+//!
+//! // The CBOR sequence 1, "two", 3
+//! let input = b"\x01\x63two\x03";
+//! let values: Vec<serde_cbor::Result<serde_cbor::Value>> =
+//!     decode_sequence_parallel(input).unwrap();
+//! assert_eq!(values.len(), 3);
+//! assert!(values[1].is_ok());
+//! ```
+
+use rayon::prelude::*;
+use serde::de;
+
+use crate::de::split_items;
+use crate::error::Result;
+
+/// Decodes every segment as a single item.
+///
+/// The results are in the same order as the segments. Error offsets are relative to the start of
+/// the segment.
+pub fn decode_parallel<'a, T>(segments: &[&'a [u8]]) -> Vec<Result<T>>
+where
+    T: de::Deserialize<'a> + Send,
+{
+    segments
+        .par_iter()
+        .map(|segment| crate::de::from_slice(segment))
+        .collect()
+}
+
+/// Splits a CBOR sequence into its top-level items and decodes them concurrently.
+///
+/// The item boundaries are found first with [`split_items`](../de/fn.split_items.html). The
+/// outer result fails if the sequence cannot be split because it is not well-formed, the inner
+/// results fail for items that cannot be deserialized into `T`. Error offsets are relative to the
+/// start of the sequence.
+pub fn decode_sequence_parallel<'a, T>(input: &'a [u8]) -> Result<Vec<Result<T>>>
+where
+    T: de::Deserialize<'a> + Send,
+{
+    let items = split(input)?;
+    Ok(items
+        .par_iter()
+        .map(|&(start, item)| crate::de::from_slice(item).map_err(|e| e.shift_offset(start)))
+        .collect())
+}
+
+/// Finds the top-level items of a CBOR sequence and their offsets.
+fn split(input: &[u8]) -> Result<Vec<(u64, &[u8])>> {
+    let mut items = split_items(input);
+    let mut result = Vec::new();
+    loop {
+        let start = items.byte_offset() as u64;
+        match items.next() {
+            Some(item) => result.push((start, item?)),
+            None => return Ok(result),
+        }
+    }
+}
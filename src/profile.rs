@@ -0,0 +1,200 @@
+//! Measure how many encoded bytes each field of a document takes.
+//!
+//! A [`Profile`] walks documents by their item headers and adds up the encoded size of every
+//! item per path, showing which fields dominate the payload before deciding on the packed format
+//! or compression. Paths are `/` separated like in `DocumentIndex`, but the elements of an array
+//! are counted together under a `*` segment, so the fields of a list of records add up.
+//!
+//! ```
+//! use serde_cbor::profile::Profile;
+//!
+//! // {"id": 7, "samples": [{"t": 1, "note": "warm"}, {"t": 2, "note": "hot"}]}
+//! let input = b"\xa2\x62id\x07\x67samples\x82\
+//!               \xa2\x61t\x01\x64note\x64warm\xa2\x61t\x02\x64note\x63hot";
+//! let mut profile = Profile::new();
+//! profile.add(input).unwrap();
+//!
+//! let note = profile.get("samples/*/note").unwrap();
+//! assert_eq!((note.count, note.key_bytes, note.value_bytes), (2, 10, 9));
+//! assert_eq!(profile.get("").unwrap().value_bytes, input.len() as u64);
+//!
+//! // the report lists the fields by their size, largest first
+//! let report = profile.to_string();
+//! assert_eq!(report.lines().nth(2).unwrap(), "        36  87.8%       1  samples");
+//! ```
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::fmt::{self, Write};
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+use crate::de::RECURSION_LIMIT;
+use crate::document::{header_at, item_end, Entries};
+use crate::error::Result;
+use crate::header::{Header, MajorType};
+
+/// The encoded size of the items found at one path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FieldSize {
+    /// How many items were found at the path.
+    pub count: u64,
+    /// The bytes taken by the map keys of these items, zero for array elements.
+    pub key_bytes: u64,
+    /// The bytes taken by the items, including everything nested in them.
+    pub value_bytes: u64,
+}
+
+impl FieldSize {
+    /// The bytes taken by the keys and the items together.
+    pub fn total(&self) -> u64 {
+        self.key_bytes + self.value_bytes
+    }
+}
+
+/// The encoded sizes of the fields of one or more documents.
+///
+/// The root of a document has the empty path. Map entries whose key is neither a text without
+/// `/` nor an unsigned integer are counted under a `?` segment.
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    fields: BTreeMap<String, FieldSize>,
+}
+
+impl Profile {
+    /// Creates an empty profile.
+    pub fn new() -> Profile {
+        Profile::default()
+    }
+
+    /// Adds the fields of the item in `input` to the profile.
+    ///
+    /// Only the first item is profiled. Adding many documents of the same kind gives the sizes
+    /// over all of them. Fails if the item is not well-formed, leaving the profile with the
+    /// fields read up to the error.
+    pub fn add(&mut self, input: &[u8]) -> Result<()> {
+        self.item(input, &mut String::new(), 0, 0, RECURSION_LIMIT)?;
+        Ok(())
+    }
+
+    /// The sizes of the items at `path`.
+    pub fn get(&self, path: &str) -> Option<&FieldSize> {
+        self.fields.get(path)
+    }
+
+    /// Iterates over the paths and their sizes, ordered by path.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &FieldSize)> {
+        self.fields.iter().map(|(path, size)| (path.as_str(), size))
+    }
+
+    /// The number of distinct paths.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns true if no document was added yet.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Records the item at `start` and its contents under `path`, returns its end.
+    fn item(
+        &mut self,
+        input: &[u8],
+        path: &mut String,
+        start: usize,
+        key_bytes: usize,
+        limit: u8,
+    ) -> Result<usize> {
+        if limit == 0 {
+            // fails with the recursion limit error
+            return item_end(input, start, limit);
+        }
+        let mut pos = start;
+        let mut header = header_at(input, pos)?;
+        while header.major == MajorType::Tag {
+            pos += header.size;
+            header = header_at(input, pos)?;
+        }
+        let end = if header.major == MajorType::Array || header.major == MajorType::Map {
+            self.entries(input, path, pos, header, limit)?
+        } else {
+            item_end(input, start, limit)?
+        };
+        let size = self.fields.entry(path.clone()).or_default();
+        size.count += 1;
+        size.key_bytes += key_bytes as u64;
+        size.value_bytes += (end - start) as u64;
+        Ok(end)
+    }
+
+    /// Records the entries of the array or map at `start`, returns its end.
+    fn entries(
+        &mut self,
+        input: &[u8],
+        path: &mut String,
+        start: usize,
+        header: Header,
+        limit: u8,
+    ) -> Result<usize> {
+        let mut entries = Entries {
+            input,
+            pos: start + header.size,
+            remaining: header.argument,
+        };
+        let path_len = path.len();
+        while let Some(entry) = entries.next()? {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            let (value, key_bytes) = if header.major == MajorType::Array {
+                path.push('*');
+                (entry, 0)
+            } else {
+                let value = item_end(input, entry, limit - 1)?;
+                let key = header_at(input, entry)?;
+                let key_contents = &input[entry + key.size..value];
+                match (key.major, key.argument, core::str::from_utf8(key_contents)) {
+                    (MajorType::Text, Some(_), Ok(key)) if !key.contains('/') => path.push_str(key),
+                    (MajorType::Unsigned, Some(key), _) => {
+                        write!(path, "{}", key).expect("writing to a string")
+                    }
+                    _ => path.push('?'),
+                }
+                (value, value - entry)
+            };
+            entries.pos = self.item(input, path, value, key_bytes, limit - 1)?;
+            path.truncate(path_len);
+        }
+        Ok(entries.pos + usize::from(header.argument.is_none()))
+    }
+}
+
+/// Writes a table of the paths ordered by their total size, largest first, with their share of
+/// the documents, the number of items and the path. The root is shown as `.`.
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let documents = self.fields.get("").map_or(0, FieldSize::total);
+        let mut fields: Vec<_> = self.fields.iter().collect();
+        fields
+            .sort_by(|(a, a_size), (b, b_size)| b_size.total().cmp(&a_size.total()).then(a.cmp(b)));
+        writeln!(f, "{:>10} {:>6} {:>7}  path", "bytes", "share", "count")?;
+        for (path, size) in fields {
+            let share = if documents == 0 {
+                0.0
+            } else {
+                size.total() as f64 * 100.0 / documents as f64
+            };
+            let path = if path.is_empty() { "." } else { path };
+            writeln!(
+                f,
+                "{:>10} {:>5.1}% {:>7}  {}",
+                size.total(),
+                share,
+                size.count,
+                path
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,496 @@
+//! Decode only selected parts of a document.
+//!
+//! A projection is described by a list of paths. Each path is a list of map keys separated by
+//! `/`. Text keys are matched literally, a path segment that is a decimal number also matches the
+//! equal unsigned integer key used by the packed format. Map entries that are not on any of the
+//! paths are skipped without being decoded, everything below the end of a path is decoded
+//! completely.
+//!
+//! The target type has to tolerate missing fields, for example by using `Option` or
+//! `#[serde(default)]` for fields that are not selected.
+//!
+//! ```
+//! use serde_derive::Deserialize;
+//! use serde_cbor::projection::from_slice_paths;
+//!
+//! #[derive(Debug, PartialEq, Deserialize)]
+//! struct Payload {
+//!     timestamp: u64,
+//! }
+//!
+//! #[derive(Debug, PartialEq, Deserialize)]
+//! struct Message {
+//!     header: String,
+//!     payload: Payload,
+//! }
+//!
+//! // {"header": "h", "payload": {"timestamp": 1, "body": "..."}, "trailer": "..."}
+//! let data = b"\xa3\x66header\x61h\x67payload\xa2\x69timestamp\x01\x64body\x63...\
+//!              \x67trailer\x63...";
+//! let message: Message = from_slice_paths(data, &["header", "payload/timestamp"]).unwrap();
+//! assert_eq!(message.payload.timestamp, 1);
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+use serde::de::{self, IntoDeserializer};
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::de::Deserializer;
+use crate::error::Result;
+
+/// Decodes the selected paths of a value from CBOR data in a slice.
+///
+/// See the [module documentation](index.html) for the path syntax.
+pub fn from_slice_paths<'a, T>(slice: &'a [u8], paths: &[&str]) -> Result<T>
+where
+    T: de::Deserialize<'a>,
+{
+    let selection = Selection::new(paths);
+    let mut deserializer = Deserializer::from_slice(slice);
+    let value = T::deserialize(Projected::new(&mut deserializer, &selection))?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Decodes the selected paths of a value from CBOR data in a reader.
+///
+/// See the [module documentation](index.html) for the path syntax.
+#[cfg(feature = "std")]
+pub fn from_reader_paths<T, R>(reader: R, paths: &[&str]) -> Result<T>
+where
+    T: de::DeserializeOwned,
+    R: io::Read,
+{
+    let selection = Selection::new(paths);
+    let mut deserializer = Deserializer::from_reader(reader);
+    let value = T::deserialize(Projected::new(&mut deserializer, &selection))?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// A parsed set of paths.
+///
+/// Parsing the paths once and reusing the selection is cheaper when many documents are decoded
+/// with the same projection.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Selection<'p> {
+    all: bool,
+    children: Vec<(&'p str, Selection<'p>)>,
+}
+
+impl<'p> Selection<'p> {
+    /// Builds a selection from a list of `/` separated paths.
+    pub fn new(paths: &[&'p str]) -> Selection<'p> {
+        let mut selection = Selection::default();
+        for path in paths {
+            selection.insert(path.split('/'));
+        }
+        selection
+    }
+
+    /// A selection that contains everything.
+    pub fn all() -> Selection<'p> {
+        Selection {
+            all: true,
+            children: Vec::new(),
+        }
+    }
+
+    fn insert<I>(&mut self, mut segments: I)
+    where
+        I: Iterator<Item = &'p str>,
+    {
+        if self.all {
+            return;
+        }
+        match segments.next() {
+            None => {
+                self.all = true;
+                self.children.clear();
+            }
+            Some(segment) => {
+                let index = match self.children.iter().position(|(s, _)| *s == segment) {
+                    Some(index) => index,
+                    None => {
+                        self.children.push((segment, Selection::default()));
+                        self.children.len() - 1
+                    }
+                };
+                self.children[index].1.insert(segments);
+            }
+        }
+    }
+
+    fn child(&self, key: &Key<'_>) -> Option<&Selection<'p>> {
+        self.children
+            .iter()
+            .find(|(segment, _)| key.matches(segment))
+            .map(|(_, child)| child)
+    }
+}
+
+/// A deserializer that only decodes the parts of the input selected by a `Selection`.
+///
+/// This wraps any other deserializer, typically a `&mut serde_cbor::Deserializer`.
+pub struct Projected<'s, 'p, D> {
+    de: D,
+    selection: &'s Selection<'p>,
+}
+
+impl<'s, 'p, D> Projected<'s, 'p, D> {
+    /// Restricts the given deserializer to the selection.
+    pub fn new(de: D, selection: &'s Selection<'p>) -> Self {
+        Projected { de, selection }
+    }
+}
+
+macro_rules! forward_projected {
+    ($($method:ident ( $($arg:ident : $ty:ty),* ))*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> core::result::Result<V::Value, D::Error>
+            where
+                V: de::Visitor<'de>,
+            {
+                if self.selection.all {
+                    self.de.$method($($arg,)* visitor)
+                } else {
+                    self.de.$method($($arg,)* ProjectedVisitor {
+                        visitor,
+                        selection: self.selection,
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl<'de, 's, 'p, D> de::Deserializer<'de> for Projected<'s, 'p, D>
+where
+    D: de::Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    forward_projected! {
+        deserialize_any()
+        deserialize_bool()
+        deserialize_i8()
+        deserialize_i16()
+        deserialize_i32()
+        deserialize_i64()
+        deserialize_i128()
+        deserialize_u8()
+        deserialize_u16()
+        deserialize_u32()
+        deserialize_u64()
+        deserialize_u128()
+        deserialize_f32()
+        deserialize_f64()
+        deserialize_char()
+        deserialize_str()
+        deserialize_string()
+        deserialize_bytes()
+        deserialize_byte_buf()
+        deserialize_option()
+        deserialize_unit()
+        deserialize_unit_struct(name: &'static str)
+        deserialize_newtype_struct(name: &'static str)
+        deserialize_seq()
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_map()
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        deserialize_identifier()
+        deserialize_ignored_any()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.de.is_human_readable()
+    }
+}
+
+struct ProjectedSeed<'s, 'p, S> {
+    seed: S,
+    selection: &'s Selection<'p>,
+}
+
+impl<'de, 's, 'p, S> de::DeserializeSeed<'de> for ProjectedSeed<'s, 'p, S>
+where
+    S: de::DeserializeSeed<'de>,
+{
+    type Value = S::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> core::result::Result<S::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.seed
+            .deserialize(Projected::new(deserializer, self.selection))
+    }
+}
+
+struct ProjectedVisitor<'s, 'p, V> {
+    visitor: V,
+    selection: &'s Selection<'p>,
+}
+
+macro_rules! forward_visit {
+    ($($method:ident ( $ty:ty ))*) => {
+        $(
+            fn $method<E>(self, v: $ty) -> core::result::Result<V::Value, E>
+            where
+                E: de::Error,
+            {
+                self.visitor.$method(v)
+            }
+        )*
+    };
+}
+
+impl<'de, 's, 'p, V> de::Visitor<'de> for ProjectedVisitor<'s, 'p, V>
+where
+    V: de::Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.visitor.expecting(fmt)
+    }
+
+    forward_visit! {
+        visit_bool(bool)
+        visit_i8(i8)
+        visit_i16(i16)
+        visit_i32(i32)
+        visit_i64(i64)
+        visit_i128(i128)
+        visit_u8(u8)
+        visit_u16(u16)
+        visit_u32(u32)
+        visit_u64(u64)
+        visit_u128(u128)
+        visit_f32(f32)
+        visit_f64(f64)
+        visit_char(char)
+        visit_str(&str)
+        visit_borrowed_str(&'de str)
+        visit_string(String)
+        visit_bytes(&[u8])
+        visit_borrowed_bytes(&'de [u8])
+        visit_byte_buf(Vec<u8>)
+    }
+
+    fn visit_none<E>(self) -> core::result::Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visitor.visit_none()
+    }
+
+    fn visit_unit<E>(self) -> core::result::Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visitor.visit_unit()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> core::result::Result<V::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.visitor
+            .visit_some(Projected::new(deserializer, self.selection))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> core::result::Result<V::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.visitor
+            .visit_newtype_struct(Projected::new(deserializer, self.selection))
+    }
+
+    fn visit_seq<A>(self, seq: A) -> core::result::Result<V::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        self.visitor.visit_seq(seq)
+    }
+
+    fn visit_map<A>(self, map: A) -> core::result::Result<V::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        self.visitor.visit_map(ProjectedMap {
+            map,
+            selection: self.selection,
+            pending: None,
+        })
+    }
+
+    fn visit_enum<A>(self, data: A) -> core::result::Result<V::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        self.visitor.visit_enum(data)
+    }
+}
+
+/// Yields only the map entries whose keys are part of the selection.
+struct ProjectedMap<'s, 'p, A> {
+    map: A,
+    selection: &'s Selection<'p>,
+    pending: Option<&'s Selection<'p>>,
+}
+
+impl<'de, 's, 'p, A> de::MapAccess<'de> for ProjectedMap<'s, 'p, A>
+where
+    A: de::MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> core::result::Result<Option<K::Value>, A::Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        while let Some(key) = self.map.next_key::<Key<'de>>()? {
+            if let Some(child) = self.selection.child(&key) {
+                self.pending = Some(child);
+                return match key {
+                    Key::Borrowed(s) => {
+                        seed.deserialize(de::value::BorrowedStrDeserializer::new(s))
+                    }
+                    Key::Owned(s) => seed.deserialize(s.into_deserializer()),
+                    Key::Unsigned(n) => seed.deserialize(n.into_deserializer()),
+                    Key::Other => unreachable!("only text and integer keys can be selected"),
+                }
+                .map(Some);
+            }
+            self.map.next_value::<de::IgnoredAny>()?;
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> core::result::Result<V::Value, A::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        // Panic because this indicates a bug in the program rather than an
+        // expected failure.
+        let selection = self
+            .pending
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        self.map.next_value_seed(ProjectedSeed { seed, selection })
+    }
+}
+
+/// A map key as far as it is relevant for matching paths.
+enum Key<'de> {
+    Borrowed(&'de str),
+    Owned(String),
+    Unsigned(u64),
+    Other,
+}
+
+impl<'de> Key<'de> {
+    fn matches(&self, segment: &str) -> bool {
+        match self {
+            Key::Borrowed(s) => *s == segment,
+            Key::Owned(s) => s == segment,
+            Key::Unsigned(n) => segment.parse() == Ok(*n),
+            Key::Other => false,
+        }
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Key<'de> {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Key<'de>, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct KeyVisitor;
+
+        impl<'de> de::Visitor<'de> for KeyVisitor {
+            type Value = Key<'de>;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt.write_str("a map key")
+            }
+
+            fn visit_borrowed_str<E>(self, v: &'de str) -> core::result::Result<Key<'de>, E> {
+                Ok(Key::Borrowed(v))
+            }
+
+            fn visit_str<E>(self, v: &str) -> core::result::Result<Key<'de>, E> {
+                Ok(Key::Owned(v.into()))
+            }
+
+            fn visit_string<E>(self, v: String) -> core::result::Result<Key<'de>, E> {
+                Ok(Key::Owned(v))
+            }
+
+            fn visit_u64<E>(self, v: u64) -> core::result::Result<Key<'de>, E> {
+                Ok(Key::Unsigned(v))
+            }
+
+            fn visit_i64<E>(self, _: i64) -> core::result::Result<Key<'de>, E> {
+                Ok(Key::Other)
+            }
+
+            fn visit_i128<E>(self, _: i128) -> core::result::Result<Key<'de>, E> {
+                Ok(Key::Other)
+            }
+
+            fn visit_f64<E>(self, _: f64) -> core::result::Result<Key<'de>, E> {
+                Ok(Key::Other)
+            }
+
+            fn visit_bool<E>(self, _: bool) -> core::result::Result<Key<'de>, E> {
+                Ok(Key::Other)
+            }
+
+            fn visit_unit<E>(self) -> core::result::Result<Key<'de>, E> {
+                Ok(Key::Other)
+            }
+
+            fn visit_bytes<E>(self, _: &[u8]) -> core::result::Result<Key<'de>, E> {
+                Ok(Key::Other)
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> core::result::Result<Key<'de>, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                while seq.next_element::<de::IgnoredAny>()?.is_some() {}
+                Ok(Key::Other)
+            }
+
+            fn visit_map<A>(self, mut map: A) -> core::result::Result<Key<'de>, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                while map
+                    .next_entry::<de::IgnoredAny, de::IgnoredAny>()?
+                    .is_some()
+                {}
+                Ok(Key::Other)
+            }
+
+            fn visit_newtype_struct<D>(
+                self,
+                deserializer: D,
+            ) -> core::result::Result<Key<'de>, D::Error>
+            where
+                D: de::Deserializer<'de>,
+            {
+                de::Deserialize::deserialize(deserializer)
+            }
+        }
+
+        deserializer.deserialize_any(KeyVisitor)
+    }
+}
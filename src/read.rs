@@ -7,6 +7,8 @@ use core::mem;
 #[cfg(feature = "std")]
 use std::io::{self, Read as StdRead};
 
+#[cfg(feature = "std")]
+use crate::arena::Arena;
 use crate::error::{Error, ErrorCode, Result};
 
 #[cfg(not(feature = "unsealed_read_write"))]
@@ -56,11 +58,57 @@ pub trait Read<'de>: private::Sealed {
     #[doc(hidden)]
     fn read_into(&mut self, buf: &mut [u8]) -> Result<()>;
 
+    #[doc(hidden)]
+    /// Reads n bytes from the input into a new vector.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn read_owned(&mut self, n: usize) -> Result<Vec<u8>> {
+        Ok(match self.read(n)? {
+            EitherLifetime::Short(buf) => buf.to_vec(),
+            EitherLifetime::Long(buf) => buf.to_vec(),
+        })
+    }
+
     #[doc(hidden)]
     fn discard(&mut self);
 
+    #[doc(hidden)]
+    /// Clears the scratch buffer and frees its memory beyond `capacity` bytes.
+    fn shrink_buffer(&mut self, capacity: usize) {
+        let _ = capacity;
+    }
+
     #[doc(hidden)]
     fn offset(&self) -> u64;
+
+    #[doc(hidden)]
+    /// Returns the byte `n` bytes after the next one if it is available without reading.
+    fn peek_at(&self, n: usize) -> Option<u8> {
+        let _ = n;
+        None
+    }
+
+    #[doc(hidden)]
+    /// Returns the input already read between two offsets, if the reader keeps it.
+    fn input_range(&self, start: u64, end: u64) -> Option<&[u8]> {
+        let _ = (start, end);
+        None
+    }
+
+    #[doc(hidden)]
+    /// Starts copying the bytes that are consumed, if the reader can, and returns the length of
+    /// the copy so far. Calls can be nested, each one is ended by `end_copy`.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn start_copy(&mut self) -> Option<usize> {
+        None
+    }
+
+    #[doc(hidden)]
+    /// Returns the bytes consumed since `start_copy` returned `start`.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn end_copy(&mut self, start: usize) -> Vec<u8> {
+        let _ = start;
+        Vec::new()
+    }
 }
 
 #[cfg(feature = "unsealed_read_write")]
@@ -108,11 +156,68 @@ pub trait Read<'de> {
     /// Read from the input until `buf` is full or end of input is encountered.
     fn read_into(&mut self, buf: &mut [u8]) -> Result<()>;
 
+    /// Read n bytes from the input into a new vector, for strings decoded into owned types.
+    ///
+    /// The default implementation copies the result of `read`. Readers that have to copy the
+    /// bytes anyway can read them into the vector directly instead.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn read_owned(&mut self, n: usize) -> Result<Vec<u8>> {
+        Ok(match self.read(n)? {
+            EitherLifetime::Short(buf) => buf.to_vec(),
+            EitherLifetime::Long(buf) => buf.to_vec(),
+        })
+    }
+
     /// Discard any data read by `peek`.
     fn discard(&mut self);
 
+    /// Clears the scratch buffer and frees its memory beyond `capacity` bytes. The default
+    /// implementation does nothing, which suits readers without a growable buffer.
+    fn shrink_buffer(&mut self, capacity: usize) {
+        let _ = capacity;
+    }
+
     /// Returns the offset from the start of the reader.
     fn offset(&self) -> u64;
+
+    /// Returns the byte `n` bytes after the next one if it is available without reading, as it is
+    /// for readers of slices.
+    ///
+    /// Readers whose `read` returns slices with the long lifetime implement this, so indefinite
+    /// length strings that consist of a single chunk are borrowed as well. It also lets
+    /// `DeserializerOptions::tagged_null_as_none` look past tags. The default implementation
+    /// returns `None`.
+    fn peek_at(&self, n: usize) -> Option<u8> {
+        let _ = n;
+        None
+    }
+
+    /// Returns the input already read between two offsets, as readers of slices can.
+    ///
+    /// This lets `DeserializerOptions::key_order` compare the encoded keys of a map. The default
+    /// implementation returns `None`.
+    fn input_range(&self, start: u64, end: u64) -> Option<&[u8]> {
+        let _ = (start, end);
+        None
+    }
+
+    /// Starts copying the bytes that are consumed and returns the length of the copy so far.
+    ///
+    /// Readers that don't keep their input for `input_range`, like readers of streams, implement
+    /// this so `DeserializerOptions::key_order` can compare the encoded keys of a map. Calls can
+    /// be nested, each one is ended by `end_copy`. The default implementation returns `None`.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn start_copy(&mut self) -> Option<usize> {
+        None
+    }
+
+    /// Returns the bytes consumed since `start_copy` returned `start`, and stops copying when
+    /// the outermost call has ended.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn end_copy(&mut self, start: usize) -> Vec<u8> {
+        let _ = start;
+        Vec::new()
+    }
 }
 
 /// Represents a reader that can return its current position
@@ -134,6 +239,12 @@ mod private {
 }
 
 /// CBOR input source that reads from a std::io input stream.
+///
+/// Bytes are read from the stream one at a time or in exactly the amounts needed, decoding a
+/// complete item never reads past its last byte. Only `Deserializer::end` and
+/// `StreamDeserializer` look at the following byte, to check for trailing data or the end of the
+/// stream. This makes it possible to decode an item and continue reading the stream afterwards
+/// through `into_inner`.
 #[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct IoRead<R>
@@ -143,6 +254,8 @@ where
     reader: OffsetReader<R>,
     scratch: Vec<u8>,
     ch: Option<u8>,
+    /// The bytes consumed since the outermost `start_copy`, and the number of open copies.
+    copy: Option<(Vec<u8>, usize)>,
 }
 
 #[cfg(feature = "std")]
@@ -156,9 +269,18 @@ where
             reader: OffsetReader { reader, offset: 0 },
             scratch: vec![],
             ch: None,
+            copy: None,
         }
     }
 
+    /// Unwraps the underlying reader.
+    ///
+    /// A byte that has been peeked at but not consumed is lost. This is never the case after a
+    /// complete item has been decoded.
+    pub fn into_inner(self) -> R {
+        self.reader.reader
+    }
+
     #[inline]
     fn next_inner(&mut self) -> Result<Option<u8>> {
         let mut buf = [0; 1];
@@ -171,59 +293,40 @@ where
             }
         }
     }
-}
 
-#[cfg(all(feature = "std", not(feature = "unsealed_read_write")))]
-impl<R> private::Sealed for IoRead<R> where R: io::Read {}
-
-#[cfg(feature = "std")]
-impl<'de, R> Read<'de> for IoRead<R>
-where
-    R: io::Read,
-{
     #[inline]
-    fn next(&mut self) -> Result<Option<u8>> {
-        match self.ch.take() {
-            Some(ch) => Ok(Some(ch)),
-            None => self.next_inner(),
+    fn copy_consumed(&mut self, bytes: &[u8]) {
+        if let Some((copy, _)) = &mut self.copy {
+            copy.extend_from_slice(bytes);
         }
     }
 
-    #[inline]
-    fn peek(&mut self) -> Result<Option<u8>> {
-        match self.ch {
-            Some(ch) => Ok(Some(ch)),
-            None => {
-                self.ch = self.next_inner()?;
-                Ok(self.ch)
-            }
-        }
+    /// Appends the next `n` bytes to `buf`.
+    fn append_to(&mut self, buf: &mut Vec<u8>, n: usize) -> Result<()> {
+        let start = buf.len();
+        let result = self.append_inner(buf, n);
+        self.copy_consumed(&buf[start..]);
+        result
     }
 
-    fn read_to_buffer(&mut self, mut n: usize) -> Result<()> {
+    fn append_inner(&mut self, buf: &mut Vec<u8>, mut n: usize) -> Result<()> {
         // defend against malicious input pretending to be huge strings by limiting growth
-        self.scratch.reserve(cmp::min(n, 16 * 1024));
+        buf.reserve(cmp::min(n, 16 * 1024));
 
         if n == 0 {
             return Ok(());
         }
 
         if let Some(ch) = self.ch.take() {
-            self.scratch.push(ch);
+            buf.push(ch);
             n -= 1;
         }
 
         // n == 0 is OK here and needs no further special treatment
 
-        let transfer_result = {
-            // Prepare for take() (which consumes its reader) by creating a reference adaptor
-            // that'll only live in this block
-            let reference = self.reader.by_ref();
-            // Append the first n bytes of the reader to the scratch vector (or up to
-            // an error or EOF indicated by a shorter read)
-            let mut taken = reference.take(n as u64);
-            taken.read_to_end(&mut self.scratch)
-        };
+        // Append the first n bytes of the reader to the vector (or up to an error or EOF
+        // indicated by a shorter read)
+        let transfer_result = self.reader.by_ref().take(n as u64).read_to_end(buf);
 
         match transfer_result {
             Ok(r) if r == n => Ok(()),
@@ -234,33 +337,139 @@ where
             Err(e) => Err(Error::io(e)),
         }
     }
+}
+
+#[cfg(feature = "std")]
+impl<R> IoRead<R>
+where
+    R: io::Read + io::Seek,
+{
+    /// The offset of the next byte that has not been decoded yet.
+    pub(crate) fn position(&self) -> u64 {
+        self.reader.offset - self.ch.is_some() as u64
+    }
+
+    /// Moves the reader to `position`, which has been returned by `position` before.
+    pub(crate) fn seek_to(&mut self, position: u64) -> Result<()> {
+        let delta = position as i64 - self.reader.offset as i64;
+        self.reader
+            .reader
+            .seek(io::SeekFrom::Current(delta))
+            .map_err(Error::io)?;
+        self.reader.offset = position;
+        self.ch = None;
+        self.scratch.clear();
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "std", not(feature = "unsealed_read_write")))]
+impl<R> private::Sealed for IoRead<R> where R: io::Read {}
+
+#[cfg(feature = "std")]
+impl<'de, R> Read<'de> for IoRead<R>
+where
+    R: io::Read,
+{
+    #[inline]
+    fn next(&mut self) -> Result<Option<u8>> {
+        let next = match self.ch.take() {
+            Some(ch) => Some(ch),
+            None => self.next_inner()?,
+        };
+        if let Some(byte) = next {
+            self.copy_consumed(&[byte]);
+        }
+        Ok(next)
+    }
+
+    #[inline]
+    fn peek(&mut self) -> Result<Option<u8>> {
+        match self.ch {
+            Some(ch) => Ok(Some(ch)),
+            None => {
+                self.ch = self.next_inner()?;
+                Ok(self.ch)
+            }
+        }
+    }
+
+    fn read_to_buffer(&mut self, n: usize) -> Result<()> {
+        let mut scratch = mem::take(&mut self.scratch);
+        let result = self.append_to(&mut scratch, n);
+        self.scratch = scratch;
+        result
+    }
 
     fn clear_buffer(&mut self) {
         self.scratch.clear();
     }
 
+    fn shrink_buffer(&mut self, capacity: usize) {
+        self.scratch.clear();
+        self.scratch.shrink_to(capacity);
+    }
+
     fn take_buffer<'a>(&'a mut self) -> EitherLifetime<'a, 'de> {
         EitherLifetime::Short(&self.scratch)
     }
 
+    fn read_owned(&mut self, n: usize) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.append_to(&mut buf, n)?;
+        Ok(buf)
+    }
+
     fn read_into(&mut self, buf: &mut [u8]) -> Result<()> {
-        self.reader.read_exact(buf).map_err(|e| {
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let mut rest = &mut buf[..];
+        if let Some(ch) = self.ch.take() {
+            rest[0] = ch;
+            rest = &mut rest[1..];
+        }
+        self.reader.read_exact(rest).map_err(|e| {
             if e.kind() == io::ErrorKind::UnexpectedEof {
                 Error::syntax(ErrorCode::EofWhileParsingValue, self.offset())
             } else {
                 Error::io(e)
             }
-        })
+        })?;
+        self.copy_consumed(buf);
+        Ok(())
     }
 
     #[inline]
     fn discard(&mut self) {
-        self.ch = None;
+        if let Some(ch) = self.ch.take() {
+            self.copy_consumed(&[ch]);
+        }
     }
 
     fn offset(&self) -> u64 {
         self.reader.offset
     }
+
+    fn start_copy(&mut self) -> Option<usize> {
+        let (copy, open) = self.copy.get_or_insert_with(|| (Vec::new(), 0));
+        *open += 1;
+        Some(copy.len())
+    }
+
+    fn end_copy(&mut self, start: usize) -> Vec<u8> {
+        match &mut self.copy {
+            Some((copy, open)) => {
+                let bytes = copy[start..].to_vec();
+                *open -= 1;
+                if *open == 0 {
+                    self.copy = None;
+                }
+                bytes
+            }
+            None => Vec::new(),
+        }
+    }
 }
 
 #[cfg(feature = "std")]
@@ -273,6 +482,100 @@ where
     }
 }
 
+/// CBOR input source that reads from a std::io input stream and copies the strings into an
+/// `Arena`, so they can be borrowed for the lifetime of the arena.
+///
+/// Every string read is copied, including the ones of skipped items. Definite length strings
+/// decoded into owned types like `String` are read into their own allocation instead.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ArenaRead<'a, R>
+where
+    R: io::Read,
+{
+    reader: IoRead<R>,
+    arena: &'a Arena,
+}
+
+#[cfg(feature = "std")]
+impl<'a, R> ArenaRead<'a, R>
+where
+    R: io::Read,
+{
+    /// Creates a new CBOR input source to read from a std::io input stream into `arena`.
+    pub fn new(reader: R, arena: &'a Arena) -> ArenaRead<'a, R> {
+        ArenaRead {
+            reader: IoRead::new(reader),
+            arena,
+        }
+    }
+
+    /// Unwraps the underlying reader, see `IoRead::into_inner`.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+#[cfg(all(feature = "std", not(feature = "unsealed_read_write")))]
+impl<'a, R> private::Sealed for ArenaRead<'a, R> where R: io::Read {}
+
+#[cfg(feature = "std")]
+impl<'a, R> Read<'a> for ArenaRead<'a, R>
+where
+    R: io::Read,
+{
+    #[inline]
+    fn next(&mut self) -> Result<Option<u8>> {
+        Read::next(&mut self.reader)
+    }
+
+    #[inline]
+    fn peek(&mut self) -> Result<Option<u8>> {
+        self.reader.peek()
+    }
+
+    fn read_to_buffer(&mut self, n: usize) -> Result<()> {
+        self.reader.read_to_buffer(n)
+    }
+
+    fn clear_buffer(&mut self) {
+        self.reader.clear_buffer()
+    }
+
+    fn shrink_buffer(&mut self, capacity: usize) {
+        self.reader.shrink_buffer(capacity)
+    }
+
+    fn take_buffer<'b>(&'b mut self) -> EitherLifetime<'b, 'a> {
+        EitherLifetime::Long(self.arena.alloc(&self.reader.scratch))
+    }
+
+    fn read_owned(&mut self, n: usize) -> Result<Vec<u8>> {
+        self.reader.read_owned(n)
+    }
+
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.reader.read_into(buf)
+    }
+
+    #[inline]
+    fn discard(&mut self) {
+        self.reader.discard()
+    }
+
+    fn offset(&self) -> u64 {
+        Read::offset(&self.reader)
+    }
+
+    fn start_copy(&mut self) -> Option<usize> {
+        self.reader.start_copy()
+    }
+
+    fn end_copy(&mut self, start: usize) -> Vec<u8> {
+        self.reader.end_copy(start)
+    }
+}
+
 #[cfg(feature = "std")]
 #[derive(Debug)]
 struct OffsetReader<R> {
@@ -315,6 +618,11 @@ impl<'a> SliceRead<'a> {
         }
     }
 
+    /// The bytes that have not been read yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.slice[self.index..]
+    }
+
     fn end(&self, n: usize) -> Result<usize> {
         match self.index.checked_add(n) {
             Some(end) if end <= self.slice.len() => Ok(end),
@@ -366,6 +674,11 @@ impl<'a> Read<'a> for SliceRead<'a> {
         self.scratch.clear();
     }
 
+    fn shrink_buffer(&mut self, capacity: usize) {
+        self.scratch.clear();
+        self.scratch.shrink_to(capacity);
+    }
+
     fn read_to_buffer(&mut self, n: usize) -> Result<()> {
         let end = self.end(n)?;
         let slice = &self.slice[self.index..end];
@@ -403,6 +716,100 @@ impl<'a> Read<'a> for SliceRead<'a> {
     fn offset(&self) -> u64 {
         self.index as u64
     }
+
+    fn peek_at(&self, n: usize) -> Option<u8> {
+        self.slice.get(self.index.checked_add(n)?).copied()
+    }
+
+    fn input_range(&self, start: u64, end: u64) -> Option<&[u8]> {
+        self.slice[..self.index].get(start as usize..end as usize)
+    }
+}
+
+#[cfg(feature = "embedded")]
+impl<'r, 'a, 's> crate::embedded::RingRead<'r, 'a, 's> {
+    fn end(&self, n: usize) -> Result<usize> {
+        match self.index.checked_add(n) {
+            Some(end) if end <= self.ring.len() => Ok(end),
+            _ => Err(Error::syntax(
+                ErrorCode::EofWhileParsingValue,
+                self.ring.len() as u64,
+            )),
+        }
+    }
+
+    fn copy_to(&mut self, buf: &mut [u8]) -> Result<()> {
+        let end = self.end(buf.len())?;
+        for (i, byte) in (self.index..end).zip(buf.iter_mut()) {
+            *byte = self.ring.get(i).unwrap_or_default();
+        }
+        self.index = end;
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "embedded", not(feature = "unsealed_read_write")))]
+impl<'r, 'a, 's> private::Sealed for crate::embedded::RingRead<'r, 'a, 's> {}
+
+#[cfg(feature = "embedded")]
+impl<'de, 'r, 'a, 's> Read<'de> for crate::embedded::RingRead<'r, 'a, 's> {
+    #[inline]
+    fn next(&mut self) -> Result<Option<u8>> {
+        let ch = self.ring.get(self.index);
+        if ch.is_some() {
+            self.index += 1;
+        }
+        Ok(ch)
+    }
+
+    #[inline]
+    fn peek(&mut self) -> Result<Option<u8>> {
+        Ok(self.ring.get(self.index))
+    }
+
+    fn clear_buffer(&mut self) {
+        self.scratch_index = 0;
+    }
+
+    fn read_to_buffer(&mut self, n: usize) -> Result<()> {
+        self.end(n)?;
+        let scratch_end = match self.scratch_index.checked_add(n) {
+            Some(end) if end <= self.scratch.len() => end,
+            _ => {
+                return Err(Error::scratch_overflow(
+                    self.scratch_index.saturating_add(n),
+                    self.scratch.len(),
+                    self.index as u64,
+                ))
+            }
+        };
+        let scratch = mem::take(&mut self.scratch);
+        let result = self.copy_to(&mut scratch[self.scratch_index..scratch_end]);
+        self.scratch = scratch;
+        self.scratch_index = scratch_end;
+        result
+    }
+
+    fn take_buffer<'b>(&'b mut self) -> EitherLifetime<'b, 'de> {
+        EitherLifetime::Short(&self.scratch[..self.scratch_index])
+    }
+
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.copy_to(buf)
+    }
+
+    #[inline]
+    fn discard(&mut self) {
+        self.index += 1;
+    }
+
+    fn offset(&self) -> u64 {
+        self.index as u64
+    }
+
+    fn peek_at(&self, n: usize) -> Option<u8> {
+        self.ring.get(self.index.checked_add(n)?)
+    }
 }
 
 /// A CBOR input source that reads from a slice of bytes using a fixed size scratch buffer.
@@ -428,6 +835,11 @@ impl<'a, 'b> SliceReadFixed<'a, 'b> {
         }
     }
 
+    /// The bytes that have not been read yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.slice[self.index..]
+    }
+
     fn end(&self, n: usize) -> Result<usize> {
         match self.index.checked_add(n) {
             Some(end) if end <= self.slice.len() => Ok(end),
@@ -441,7 +853,11 @@ impl<'a, 'b> SliceReadFixed<'a, 'b> {
     fn scratch_end(&self, n: usize) -> Result<usize> {
         match self.scratch_index.checked_add(n) {
             Some(end) if end <= self.scratch.len() => Ok(end),
-            _ => Err(Error::scratch_too_small(self.index as u64)),
+            _ => Err(Error::scratch_overflow(
+                self.scratch_index.saturating_add(n),
+                self.scratch.len(),
+                self.index as u64,
+            )),
         }
     }
 }
@@ -512,9 +928,16 @@ impl<'a, 'b> Read<'a> for SliceReadFixed<'a, 'b> {
     fn offset(&self) -> u64 {
         self.index as u64
     }
+
+    fn peek_at(&self, n: usize) -> Option<u8> {
+        self.slice.get(self.index.checked_add(n)?).copied()
+    }
+
+    fn input_range(&self, start: u64, end: u64) -> Option<&[u8]> {
+        self.slice[..self.index].get(start as usize..end as usize)
+    }
 }
 
-#[cfg(any(feature = "std", feature = "alloc"))]
 impl<'a, 'b> Offset for SliceReadFixed<'a, 'b> {
     #[inline]
     fn byte_offset(&self) -> usize {
@@ -548,6 +971,14 @@ impl<'a> MutSliceRead<'a> {
         }
     }
 
+    /// The bytes that have not been read yet.
+    ///
+    /// Unlike the other slice readers this borrows from the reader, as the bytes are still to be
+    /// rearranged when indefinite strings follow.
+    pub fn remaining(&self) -> &[u8] {
+        &self.slice[self.index..]
+    }
+
     fn end(&self, n: usize) -> Result<usize> {
         match self.index.checked_add(n) {
             Some(end) if end <= self.slice.len() => Ok(end),
@@ -634,4 +1065,15 @@ impl<'a> Read<'a> for MutSliceRead<'a> {
     fn offset(&self) -> u64 {
         (self.before + self.index) as u64
     }
+
+    fn peek_at(&self, n: usize) -> Option<u8> {
+        self.slice.get(self.index.checked_add(n)?).copied()
+    }
+}
+
+impl<'a> Offset for MutSliceRead<'a> {
+    #[inline]
+    fn byte_offset(&self) -> usize {
+        self.before + self.index
+    }
 }
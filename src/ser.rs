@@ -4,10 +4,15 @@
 use alloc::vec::Vec;
 
 #[cfg(feature = "std")]
-pub use crate::write::IoWrite;
+pub use crate::write::{BufferedIoWrite, IoWrite};
 pub use crate::write::{SliceWrite, Write};
 
-use crate::error::{Error, Result};
+use crate::de::RECURSION_LIMIT;
+use crate::document::item_end;
+use crate::error::{Error, ErrorCode, Result};
+use crate::header::{peek_header, MajorType};
+#[cfg(any(feature = "std", feature = "alloc"))]
+use core::cmp::Ordering;
 use half::f16;
 use serde::ser::{self, Serialize};
 #[cfg(feature = "std")]
@@ -15,6 +20,26 @@ use std::io;
 
 use crate::tags::{get_tag, CBOR_NEWTYPE_NAME};
 
+/// The names of the newtype structs a float is wrapped in to be written with 2, 4 or 8 bytes.
+pub(crate) const SIZED_FLOAT_NAMES: [(&str, u8); 3] =
+    [("\0cbor_f16", 2), ("\0cbor_f32", 4), ("\0cbor_f64", 8)];
+
+/// The names of the newtype structs an integer is wrapped in to be written with an argument of
+/// 0, 1, 2, 4 or 8 bytes.
+pub(crate) const SIZED_INTEGER_NAMES: [(&str, u8); 5] = [
+    ("\0cbor_int0", 0),
+    ("\0cbor_int1", 1),
+    ("\0cbor_int2", 2),
+    ("\0cbor_int4", 4),
+    ("\0cbor_int8", 8),
+];
+
+/// The name of the newtype struct the bytes of a `PreEncoded` are wrapped in.
+pub(crate) const PRE_ENCODED_NAME: &str = "\0cbor_pre_encoded";
+
+/// The name of the newtype struct the number of a `Value::Simple` is wrapped in.
+pub(crate) const SIMPLE_VALUE_NAME: &str = "\0cbor_simple";
+
 /// Serializes a value to a vector.
 #[cfg(any(feature = "std", feature = "alloc"))]
 pub fn to_vec<T>(value: &T) -> Result<Vec<u8>>
@@ -27,21 +52,56 @@ where
 }
 
 /// Serializes a value to a vector in packed format.
-#[cfg(feature = "std")]
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub fn to_vec_packed<T>(value: &T) -> Result<Vec<u8>>
 where
     T: ser::Serialize,
 {
-    let options = CustomSerializerOptions {
-        packed: true,
-        enum_as_map: true,
-    };
+    let options = CustomSerializerOptions::new().set_packed(true);
     let mut vec = Vec::new();
     let mut serializer = Serializer::new_with_options(&mut vec, options);
     value.serialize(&mut serializer)?;
     Ok(vec)
 }
 
+/// Serializes a value to a vector in canonical form.
+///
+/// The entries of maps and structs are sorted by their encoded keys, shorter keys first as in
+/// the canonical encoding of RFC 7049, and a key that occurs twice is an error. Numbers and
+/// lengths are always written in their shortest form. Collections of unknown length, like the
+/// structs with `#[serde(flatten)]` fields, are buffered and written with definite length.
+/// [`canonicalize`](../canonical/fn.canonicalize.html) brings encoded items into this form.
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let mut map = HashMap::new();
+/// map.insert("bb", 1);
+/// map.insert("a", 2);
+/// map.insert("c", 3);
+/// let vec = serde_cbor::ser::to_vec_canonical(&map).unwrap();
+/// assert_eq!(vec, b"\xa3\x61a\x02\x61c\x03\x62bb\x01");
+/// ```
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn to_vec_canonical<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ser::Serialize,
+{
+    let mut vec = Vec::new();
+    let mut serializer = Serializer::new_with_options(&mut vec, canonical_options());
+    value.serialize(&mut serializer)?;
+    Ok(vec)
+}
+
+/// The options of `to_vec_canonical` and `to_writer_canonical`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn canonical_options() -> CustomSerializerOptions {
+    CustomSerializerOptions::new()
+        .set_key_order(Some(KeyOrder::LengthFirst))
+        .set_deny_duplicate_keys(true)
+        .set_definite_lengths(true)
+}
+
 /// Serializes a value to a writer.
 #[cfg(feature = "std")]
 pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
@@ -52,6 +112,54 @@ where
     value.serialize(&mut Serializer::new(&mut IoWrite::new(writer)))
 }
 
+/// Serializes a value to a writer in packed format.
+#[cfg(feature = "std")]
+pub fn to_writer_packed<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: io::Write,
+    T: ser::Serialize,
+{
+    let options = CustomSerializerOptions::new().set_packed(true);
+    value.serialize(&mut Serializer::new_with_options(
+        &mut IoWrite::new(writer),
+        options,
+    ))
+}
+
+/// Serializes a value to a writer in canonical form, see [`to_vec_canonical`].
+#[cfg(feature = "std")]
+pub fn to_writer_canonical<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: io::Write,
+    T: ser::Serialize,
+{
+    value.serialize(&mut Serializer::new_with_options(
+        &mut IoWrite::new(writer),
+        canonical_options(),
+    ))
+}
+
+/// Serializes a value to a writer, passing it the complete item at once.
+///
+/// Unlike [`to_writer`] nothing reaches the writer when serializing fails, and the item is
+/// handed over with a single `write_all`, see
+/// [`SerializerOptions::fail_safe`](trait.SerializerOptions.html#method.fail_safe).
+///
+/// ```
+/// let mut log = Vec::new();
+/// serde_cbor::ser::to_writer_buffered(&mut log, &[1i128, 2]).unwrap();
+/// assert!(serde_cbor::ser::to_writer_buffered(&mut log, &[3i128, 1 << 100]).is_err());
+/// assert_eq!(log, b"\x82\x01\x02");
+/// ```
+#[cfg(feature = "std")]
+pub fn to_writer_buffered<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: io::Write,
+    T: ser::Serialize,
+{
+    value.serialize(&mut Serializer::new(&mut IoWrite::new(writer)).fail_safe())
+}
+
 /// Serializer options
 pub trait SerializerOptions {
     /// Choose concise/packed format for serializer.
@@ -103,25 +211,269 @@ pub trait SerializerOptions {
         true
     }
 
+    /// Use the variant index as map key of newtype and tuple variants in the packed format.
+    ///
+    /// With `enum_as_map` the packed format only replaces the variant name of unit and struct
+    /// variants by its index. Enabling this option does the same for the remaining variants,
+    /// so that the packed format does not contain any variant names.
+    #[inline]
+    fn packed_variant_keys(&self) -> bool {
+        false
+    }
+
+    /// Names of structs and enums that are always encoded in the packed format.
+    ///
+    /// This allows encoding a size-critical inner struct packed while the rest of the document
+    /// uses the named format. The name is the one given to `#[derive(Serialize)]`, including a
+    /// `#[serde(rename)]`.
+    #[inline]
+    fn packed_structs(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Names of structs and enums that are always encoded in the named format.
+    ///
+    /// This is the opposite of `packed_structs` for documents that are packed otherwise.
+    #[inline]
+    fn named_structs(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// The key of the first field of a struct in the packed format.
+    ///
+    /// The fields are numbered in declaration order starting at this index, including skipped
+    /// fields. Use it for schemas whose integer keys start at 1. When decoding, the keys are
+    /// mapped back with `DeserializerOptions::key_renames`.
+    #[inline]
+    fn packed_index_base(&self) -> u32 {
+        0
+    }
+
+    /// The key of the first field of particular structs in the packed format, overriding
+    /// `packed_index_base`.
+    ///
+    /// The name is the one given to `#[derive(Serialize)]`, the enum name for struct variants.
+    #[inline]
+    fn packed_index_offsets(&self) -> &'static [(&'static str, u32)] {
+        &[]
+    }
+
+    /// Sort the entries of maps and structs by their encoded keys.
+    ///
+    /// Numbers and floats are encoded as usual, only the order of the entries is changed. This
+    /// gives a stable encoding for diffing or caching without requiring the full deterministic
+    /// encoding. The entries of every map are buffered until the map is complete.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    fn key_order(&self) -> Option<KeyOrder> {
+        None
+    }
+
+    /// Fail when a map or struct contains the same encoded key more than once.
+    ///
+    /// A `HashMap` cannot contain duplicate keys, but distinct keys may have the same encoding,
+    /// and a hand-written `Serialize` implementation may emit a key twice. Strict receivers reject
+    /// such documents. Like `key_order` this buffers the entries of every map.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    fn deny_duplicate_keys(&self) -> bool {
+        false
+    }
+
+    /// Write arrays and maps of unknown length with a definite length.
+    ///
+    /// Serde passes no length for sequences built from iterators and for structs with
+    /// `#[serde(flatten)]` fields, and these are written with indefinite length by default. With
+    /// this option their items are buffered and the header is written with the number of items
+    /// once the collection is complete, as the deterministic encodings require.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    fn definite_lengths(&self) -> bool {
+        false
+    }
+
+    /// Hold back every top-level item until it is complete.
+    ///
+    /// A value that fails to serialize midway leaves the bytes written up to the error in the
+    /// output, which corrupts a stream of items. With this option the items are assembled in a
+    /// buffer and passed to the writer once they are complete, so a failed item writes nothing
+    /// and the serializer can be used for the next one. A `Serialize` implementation that
+    /// catches the errors of its fields and continues defeats this. The cost is a copy of every
+    /// item.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    fn fail_safe(&self) -> bool {
+        false
+    }
+
+    /// Report the output as human readable to the types being serialized.
+    ///
+    /// Some types like `std::net::IpAddr` choose between a text and a binary representation
+    /// based on this flag. Enable it to match a wire format that uses the text representations.
+    #[inline]
+    fn human_readable(&self) -> bool {
+        false
+    }
+
+    /// Record where in the value an error occurred, see [`Error::path`](../struct.Error.html#method.path).
+    ///
+    /// The path is built from struct field names, element indices and map keys while the error
+    /// is passed up. On success the only cost is keeping a copy of the current key of every map.
+    #[cfg(feature = "std")]
+    #[inline]
+    fn error_paths(&self) -> bool {
+        false
+    }
+
     #[allow(missing_docs)]
     #[inline]
     fn to_custom(&self) -> CustomSerializerOptions {
         CustomSerializerOptions {
+            human_readable: self.human_readable(),
             enum_as_map: self.enum_as_map(),
             packed: self.packed(),
+            packed_variant_keys: self.packed_variant_keys(),
+            packed_structs: self.packed_structs(),
+            named_structs: self.named_structs(),
+            packed_index_base: self.packed_index_base(),
+            packed_index_offsets: self.packed_index_offsets(),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            key_order: self.key_order(),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            deny_duplicate_keys: self.deny_duplicate_keys(),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            definite_lengths: self.definite_lengths(),
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            fail_safe: self.fail_safe(),
+            #[cfg(feature = "std")]
+            error_paths: self.error_paths(),
+        }
+    }
+}
+
+/// The order of the entries of maps and structs, see `SerializerOptions::key_order`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// Bytewise lexicographic order of the encoded keys, as in the deterministic encoding of
+    /// RFC 8949.
+    Bytewise,
+    /// Shorter encoded keys first and keys of the same length in bytewise order, as in the
+    /// canonical encoding of RFC 7049.
+    LengthFirst,
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl KeyOrder {
+    /// Compares two encoded keys.
+    pub(crate) fn compare(self, a: &[u8], b: &[u8]) -> Ordering {
+        match self {
+            KeyOrder::Bytewise => a.cmp(b),
+            KeyOrder::LengthFirst => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+        }
+    }
+}
+
+/// Buffers the entries of the maps being written while their keys are sorted or checked.
+#[derive(Debug, Default)]
+struct BufferedMaps {
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    levels: Vec<BufferedMap>,
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Debug, Default)]
+pub(crate) struct BufferedMap {
+    pub(crate) buf: Vec<u8>,
+    /// The start of every entry in `buf` and the end of its key.
+    pub(crate) entries: Vec<(usize, usize)>,
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl BufferedMap {
+    /// Returns the encoded keys and entries in the given order, or as written without an order.
+    pub(crate) fn entries(&self, order: Option<KeyOrder>) -> Vec<(&[u8], &[u8])> {
+        let mut entries: Vec<(&[u8], &[u8])> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, &(start, key_end))| {
+                let end = self.entries.get(i + 1).map_or(self.buf.len(), |e| e.0);
+                (&self.buf[start..key_end], &self.buf[start..end])
+            })
+            .collect();
+        if let Some(order) = order {
+            entries.sort_by(|a, b| order.compare(a.0, b.0));
         }
+        entries
     }
 }
 
+/// The top-level item being written, see `SerializerOptions::fail_safe`.
+#[derive(Debug, Default)]
+struct Rollback {
+    /// How deeply the current item is nested in arrays, maps and tags.
+    depth: usize,
+    /// The part of the item written so far in fail-safe mode.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    buf: Vec<u8>,
+}
+
+/// Whether the fields and variants of the struct or enum `name` are encoded packed.
+#[inline]
+fn is_packed<O>(options: &O, name: &str) -> bool
+where
+    O: SerializerOptions,
+{
+    if options.packed() {
+        !options.named_structs().contains(&name)
+    } else {
+        options.packed_structs().contains(&name)
+    }
+}
+
+/// The key of the first field of the struct or enum `name` in the packed format.
+#[inline]
+fn packed_index_start<O>(options: &O, name: &str) -> u32
+where
+    O: SerializerOptions,
+{
+    options
+        .packed_index_offsets()
+        .iter()
+        .find(|(struct_name, _)| *struct_name == name)
+        .map_or_else(|| options.packed_index_base(), |&(_, start)| start)
+}
+
 /// Default serializer options
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct DefaultSerializerOptions;
 
 impl SerializerOptions for DefaultSerializerOptions {}
 
 /// Custom serializer options
+///
+/// The options are plain data, they can be copied and shared between threads freely.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct CustomSerializerOptions {
     packed: bool,
     enum_as_map: bool,
+    packed_variant_keys: bool,
+    packed_structs: &'static [&'static str],
+    named_structs: &'static [&'static str],
+    packed_index_base: u32,
+    packed_index_offsets: &'static [(&'static str, u32)],
+    human_readable: bool,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    key_order: Option<KeyOrder>,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    deny_duplicate_keys: bool,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    definite_lengths: bool,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fail_safe: bool,
+    #[cfg(feature = "std")]
+    error_paths: bool,
 }
 
 #[allow(missing_docs)]
@@ -142,6 +494,77 @@ impl CustomSerializerOptions {
         self.enum_as_map = new;
         self
     }
+
+    #[inline]
+    pub fn set_packed_variant_keys(mut self, new: bool) -> Self {
+        self.packed_variant_keys = new;
+        self
+    }
+
+    #[inline]
+    pub fn set_packed_structs(mut self, names: &'static [&'static str]) -> Self {
+        self.packed_structs = names;
+        self
+    }
+
+    #[inline]
+    pub fn set_named_structs(mut self, names: &'static [&'static str]) -> Self {
+        self.named_structs = names;
+        self
+    }
+
+    #[inline]
+    pub fn set_packed_index_base(mut self, base: u32) -> Self {
+        self.packed_index_base = base;
+        self
+    }
+
+    #[inline]
+    pub fn set_packed_index_offsets(mut self, offsets: &'static [(&'static str, u32)]) -> Self {
+        self.packed_index_offsets = offsets;
+        self
+    }
+
+    #[inline]
+    pub fn set_human_readable(mut self, new: bool) -> Self {
+        self.human_readable = new;
+        self
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub fn set_key_order(mut self, order: Option<KeyOrder>) -> Self {
+        self.key_order = order;
+        self
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub fn set_deny_duplicate_keys(mut self, new: bool) -> Self {
+        self.deny_duplicate_keys = new;
+        self
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub fn set_definite_lengths(mut self, new: bool) -> Self {
+        self.definite_lengths = new;
+        self
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub fn set_fail_safe(mut self, new: bool) -> Self {
+        self.fail_safe = new;
+        self
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn set_error_paths(mut self, new: bool) -> Self {
+        self.error_paths = new;
+        self
+    }
 }
 
 impl SerializerOptions for CustomSerializerOptions {
@@ -153,6 +576,55 @@ impl SerializerOptions for CustomSerializerOptions {
     fn enum_as_map(&self) -> bool {
         self.enum_as_map
     }
+    #[inline]
+    fn packed_variant_keys(&self) -> bool {
+        self.packed_variant_keys
+    }
+    #[inline]
+    fn packed_structs(&self) -> &'static [&'static str] {
+        self.packed_structs
+    }
+    #[inline]
+    fn named_structs(&self) -> &'static [&'static str] {
+        self.named_structs
+    }
+    #[inline]
+    fn packed_index_base(&self) -> u32 {
+        self.packed_index_base
+    }
+    #[inline]
+    fn packed_index_offsets(&self) -> &'static [(&'static str, u32)] {
+        self.packed_index_offsets
+    }
+    #[inline]
+    fn human_readable(&self) -> bool {
+        self.human_readable
+    }
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    fn key_order(&self) -> Option<KeyOrder> {
+        self.key_order
+    }
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    fn deny_duplicate_keys(&self) -> bool {
+        self.deny_duplicate_keys
+    }
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    fn definite_lengths(&self) -> bool {
+        self.definite_lengths
+    }
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    fn fail_safe(&self) -> bool {
+        self.fail_safe
+    }
+    #[cfg(feature = "std")]
+    #[inline]
+    fn error_paths(&self) -> bool {
+        self.error_paths
+    }
 }
 
 impl Default for CustomSerializerOptions {
@@ -161,11 +633,208 @@ impl Default for CustomSerializerOptions {
     }
 }
 
+/// An item about to be written by a `Serializer`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Item<'a> {
+    /// `null`, written for `None` and unit values.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// An unsigned or negative integer.
+    Integer(i128),
+    /// A floating point number, before it is shortened to the smallest lossless width.
+    Float(f64),
+    /// A text string, including map keys and enum variant names.
+    Text(&'a str),
+    /// A byte string.
+    Bytes(&'a [u8]),
+    /// The start of an array of the given length, `None` for indefinite arrays.
+    Array(Option<usize>),
+    /// The start of a map of the given length, `None` for indefinite maps.
+    Map(Option<usize>),
+    /// A tag set via `serde_cbor::tags::Tagged`.
+    Tag(u64),
+    /// An item written as it is from a `PreEncoded`.
+    PreEncoded(&'a [u8]),
+    /// A simple value from a `Value::Simple`.
+    Simple(u8),
+}
+
+/// An item that is already encoded, written as it is by the `Serializer`.
+///
+/// This embeds byte-exact sub-items, like a signed payload, inside a larger message. Other
+/// serializers see a newtype struct holding a byte string, `to_value` decodes the item with the
+/// widths of its numbers preserved.
+///
+/// ```
+/// use serde_cbor::ser::PreEncoded;
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Envelope<'a> {
+///     signature: &'a str,
+///     payload: PreEncoded<'a>,
+/// }
+///
+/// // {"a": 1}, written with a longer integer than necessary
+/// let payload = PreEncoded::new_checked(b"\xa1\x61a\x18\x01").unwrap();
+/// let envelope = Envelope { signature: "sig", payload };
+/// let bytes = serde_cbor::to_vec(&envelope).unwrap();
+/// assert!(bytes.ends_with(b"\x67payload\xa1\x61a\x18\x01"));
+///
+/// assert!(PreEncoded::new_checked(b"\x82\x01").is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PreEncoded<'a>(&'a [u8]);
+
+impl<'a> PreEncoded<'a> {
+    /// Wraps `bytes`, which have to be exactly one well-formed item.
+    ///
+    /// The bytes are not checked, invalid ones corrupt the output.
+    pub fn new(bytes: &'a [u8]) -> PreEncoded<'a> {
+        PreEncoded(bytes)
+    }
+
+    /// Wraps `bytes` after checking that they are exactly one well-formed item.
+    pub fn new_checked(bytes: &'a [u8]) -> Result<PreEncoded<'a>> {
+        let end = item_end(bytes, 0, RECURSION_LIMIT)?;
+        if end != bytes.len() {
+            return Err(Error::syntax(ErrorCode::TrailingData, end as u64));
+        }
+        Ok(PreEncoded(bytes))
+    }
+
+    /// The encoded item.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+}
+
+impl ser::Serialize for PreEncoded<'_> {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        struct Bytes<'a>(&'a [u8]);
+
+        impl ser::Serialize for Bytes<'_> {
+            fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+            where
+                S: ser::Serializer,
+            {
+                serializer.serialize_bytes(self.0)
+            }
+        }
+
+        serializer.serialize_newtype_struct(PRE_ENCODED_NAME, &Bytes(self.0))
+    }
+}
+
+/// The text of a pre-encoded map key, which may be tagged, if it is a definite text string.
+fn pre_encoded_text(bytes: &[u8]) -> Option<&str> {
+    let mut rest = bytes;
+    let mut header = peek_header(rest).ok()?;
+    while header.major == MajorType::Tag {
+        rest = &rest[header.size..];
+        header = peek_header(rest).ok()?;
+    }
+    let text = &rest[header.size..];
+    if header.major != MajorType::Text || header.argument != Some(text.len() as u64) {
+        return None;
+    }
+    core::str::from_utf8(text).ok()
+}
+
+/// Observes or rewrites the items written by a `Serializer`.
+///
+/// All methods default to doing nothing, so a hook only implements what it needs. Install a hook
+/// with `Serializer::with_hook`.
+///
+/// ```
+/// use serde_cbor::ser::{Item, Serializer, SerializerHook};
+/// use serde::Serialize;
+///
+/// // Tag every byte string as "expected base64" and hide passwords.
+/// struct Policy;
+///
+/// impl SerializerHook for Policy {
+///     fn item(&mut self, item: Item<'_>) -> Option<u64> {
+///         match item {
+///             Item::Bytes(_) => Some(22),
+///             _ => None,
+///         }
+///     }
+///
+///     fn redact(&mut self, key: &str) -> bool {
+///         key == "password"
+///     }
+/// }
+///
+/// let mut map = std::collections::BTreeMap::new();
+/// map.insert("password", "hunter2");
+/// let mut vec = Vec::new();
+/// map.serialize(&mut Serializer::new(&mut vec).with_hook(Policy)).unwrap();
+/// assert_eq!(vec, b"\xa1\x68password\xf6");
+/// ```
+pub trait SerializerHook {
+    /// Called before an item is written.
+    ///
+    /// Returning a tag number wraps the item in that tag.
+    #[inline]
+    fn item(&mut self, _item: Item<'_>) -> Option<u64> {
+        None
+    }
+
+    /// Called for every struct field name and text map key.
+    ///
+    /// Tagged keys are passed without their tags. A `PreEncoded` key is passed if it holds a
+    /// definite text string, other pre-encoded keys are not. Returning `true` replaces the value
+    /// of the entry with `null`.
+    #[inline]
+    fn redact(&mut self, _key: &str) -> bool {
+        false
+    }
+}
+
+/// The hook used by default, it does nothing.
+#[derive(Debug)]
+pub struct NoHook;
+
+impl SerializerHook for NoHook {}
+
+impl<H> SerializerHook for &mut H
+where
+    H: SerializerHook + ?Sized,
+{
+    #[inline]
+    fn item(&mut self, item: Item<'_>) -> Option<u64> {
+        (**self).item(item)
+    }
+
+    #[inline]
+    fn redact(&mut self, key: &str) -> bool {
+        (**self).redact(key)
+    }
+}
+
 /// A structure for serializing Rust values to CBOR.
 #[derive(Debug)]
-pub struct Serializer<W, O = DefaultSerializerOptions> {
+pub struct Serializer<W, O = DefaultSerializerOptions, H = NoHook> {
     writer: W,
     options: O,
+    hook: H,
+    in_key: bool,
+    redact_value: bool,
+    /// The width in bytes the next number is written with, set by `Value::SizedFloat` and
+    /// `Value::SizedInteger`.
+    number_width: Option<u8>,
+    /// Set while the bytes of a `PreEncoded` are serialized, they are written as they are.
+    pre_encoded: bool,
+    /// Set while the number of a `Value::Simple` is serialized, it is written as a simple value.
+    simple_value: bool,
+    maps: BufferedMaps,
+    rollback: Rollback,
 }
 
 impl<W> Serializer<W>
@@ -191,21 +860,133 @@ where
     /// `to_vec` and `to_writer` should normally be used instead of this method.
     #[inline]
     pub fn new_with_options(writer: W, options: O) -> Self {
-        Serializer { writer, options }
+        Serializer {
+            writer,
+            options,
+            hook: NoHook,
+            in_key: false,
+            redact_value: false,
+            number_width: None,
+            pre_encoded: false,
+            simple_value: false,
+            maps: BufferedMaps::default(),
+            rollback: Rollback::default(),
+        }
     }
+}
 
-    /// Choose concise/packed format for serializer.
+impl<W, O, H> Serializer<W, O, H>
+where
+    W: Write,
+    O: SerializerOptions,
+    H: SerializerHook,
+{
+    /// Installs a hook that observes or rewrites the items written by this serializer.
     ///
-    /// In the packed format enum variant names and field names
-    /// are replaced with numeric indizes to conserve space.
+    /// Pass `&mut hook` to inspect the hook after serializing.
+    #[inline]
+    pub fn with_hook<H2>(self, hook: H2) -> Serializer<W, O, H2>
+    where
+        H2: SerializerHook,
+    {
+        Serializer {
+            writer: self.writer,
+            options: self.options,
+            hook,
+            in_key: false,
+            redact_value: false,
+            number_width: None,
+            pre_encoded: false,
+            simple_value: false,
+            maps: BufferedMaps::default(),
+            rollback: Rollback::default(),
+        }
+    }
+
+    /// Moves the writer and hook into a serializer with other options, the state is reset.
     #[inline]
-    pub fn packed_format(self) -> Serializer<W, CustomSerializerOptions> {
+    fn with_options<O2>(self, options: O2) -> Serializer<W, O2, H> {
         Serializer {
             writer: self.writer,
-            options: self.options.to_custom().set_packed(true),
+            options,
+            hook: self.hook,
+            in_key: false,
+            redact_value: false,
+            number_width: None,
+            pre_encoded: false,
+            simple_value: false,
+            maps: BufferedMaps::default(),
+            rollback: Rollback::default(),
         }
     }
 
+    /// Returns a mutable reference to the installed hook.
+    #[inline]
+    pub fn hook_mut(&mut self) -> &mut H {
+        &mut self.hook
+    }
+
+    /// Choose concise/packed format for serializer.
+    ///
+    /// In the packed format enum variant names and field names
+    /// are replaced with numeric indizes to conserve space.
+    #[inline]
+    pub fn packed_format(self) -> Serializer<W, CustomSerializerOptions, H> {
+        let options = self.options.to_custom().set_packed(true);
+        self.with_options(options)
+    }
+
+    /// Use the variant index as map key of all enum variants in the packed format.
+    ///
+    /// See [`SerializerOptions::packed_variant_keys`](trait.SerializerOptions.html).
+    #[inline]
+    pub fn packed_variant_keys(self) -> Serializer<W, CustomSerializerOptions, H> {
+        let options = self.options.to_custom().set_packed_variant_keys(true);
+        self.with_options(options)
+    }
+
+    /// Report the output as human readable to the types being serialized.
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_cbor::Serializer;
+    /// use std::net::Ipv4Addr;
+    ///
+    /// let mut vec = Vec::new();
+    /// let ip = Ipv4Addr::new(127, 0, 0, 1);
+    /// ip.serialize(&mut Serializer::new(&mut vec).human_readable()).unwrap();
+    /// assert_eq!(vec, b"\x69127.0.0.1");
+    /// ```
+    #[inline]
+    pub fn human_readable(self) -> Serializer<W, CustomSerializerOptions, H> {
+        let options = self.options.to_custom().set_human_readable(true);
+        self.with_options(options)
+    }
+
+    /// Sort the entries of maps and structs by their encoded keys.
+    ///
+    /// See [`SerializerOptions::key_order`](trait.SerializerOptions.html#method.key_order).
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_cbor::ser::{KeyOrder, Serializer};
+    ///
+    /// let mut map = std::collections::HashMap::new();
+    /// map.insert("bb", 1);
+    /// map.insert("a", 2);
+    /// map.insert("c", 3);
+    /// let mut vec = Vec::new();
+    /// map.serialize(&mut Serializer::new(&mut vec).sort_keys(KeyOrder::Bytewise))
+    ///     .unwrap();
+    /// assert_eq!(vec, b"\xa3\x61a\x02\x61c\x03\x62bb\x01");
+    /// ```
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub fn sort_keys(self, order: KeyOrder) -> Serializer<W, CustomSerializerOptions, H> {
+        let options = self.options.to_custom().set_key_order(Some(order));
+        self.with_options(options)
+    }
+
     /// Enable old enum format used by `serde_cbor` versions <= v0.9.
     ///
     /// The `legacy_enums` option determines how enums are encoded.
@@ -242,11 +1023,56 @@ where
     /// * `Enum::Tuple("x", true)` encodes as `["Tuple", "x", true]`
     /// * `Enum::Struct{ x: 5, y: -5 }` encodes as `["Struct", {"x": 5, "y": -5}]`
     #[inline]
-    pub fn legacy_enums(self) -> Serializer<W, CustomSerializerOptions> {
-        Serializer {
-            writer: self.writer,
-            options: self.options.to_custom().set_enum_as_map(false),
-        }
+    pub fn legacy_enums(self) -> Serializer<W, CustomSerializerOptions, H> {
+        let options = self.options.to_custom().set_enum_as_map(false);
+        self.with_options(options)
+    }
+
+    /// Hold back every top-level item until it is complete.
+    ///
+    /// See [`SerializerOptions::fail_safe`](trait.SerializerOptions.html#method.fail_safe).
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_cbor::Serializer;
+    ///
+    /// let mut vec = Vec::new();
+    /// let mut serializer = Serializer::new(&mut vec).fail_safe();
+    /// [1i128, 2].serialize(&mut serializer).unwrap();
+    /// [3i128, 1 << 100].serialize(&mut serializer).unwrap_err();
+    /// [5i128, 6].serialize(&mut serializer).unwrap();
+    /// assert_eq!(vec, b"\x82\x01\x02\x82\x05\x06");
+    /// ```
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub fn fail_safe(self) -> Serializer<W, CustomSerializerOptions, H> {
+        let options = self.options.to_custom().set_fail_safe(true);
+        self.with_options(options)
+    }
+
+    /// Record where in the value an error occurred.
+    ///
+    /// See [`SerializerOptions::error_paths`](trait.SerializerOptions.html#method.error_paths).
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_cbor::Serializer;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Order {
+    ///     counts: Vec<i128>,
+    /// }
+    ///
+    /// let order = Order { counts: vec![1, 1 << 100] };
+    /// let mut vec = Vec::new();
+    /// let err = order.serialize(&mut Serializer::new(&mut vec).error_paths()).unwrap_err();
+    /// assert_eq!(err.path(), Some("counts[1]"));
+    /// ```
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn error_paths(self) -> Serializer<W, CustomSerializerOptions, H> {
+        let options = self.options.to_custom().set_error_paths(true);
+        self.with_options(options)
     }
 
     /// Writes a CBOR self-describe tag to the stream.
@@ -257,7 +1083,7 @@ where
     pub fn self_describe(&mut self) -> Result<()> {
         let mut buf = [6 << 5 | 25, 0, 0];
         (&mut buf[1..]).copy_from_slice(&55799u16.to_be_bytes());
-        self.writer.write_all(&buf).map_err(|e| e.into())
+        self.write_all(&buf)
     }
 
     /// Unwrap the `Writer` from the `Serializer`.
@@ -266,8 +1092,59 @@ where
         self.writer
     }
 
+    /// Returns a reference to the `Writer`.
+    #[inline]
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the `Writer`.
+    #[inline]
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
     #[inline]
     fn write_u64(&mut self, major: u8, value: u64) -> Result<()> {
+        self.write_header(major, value, &[])
+    }
+
+    /// Writes the simple value `value`, which has to be below 24 or at least 32.
+    #[cold]
+    fn write_simple(&mut self, value: u64) -> Result<()> {
+        if (24..32).contains(&value) || value > 0xff {
+            return Err(Error::message("invalid simple value"));
+        }
+        self.begin_item(Item::Simple(value as u8))?;
+        self.write_u64(7, value)
+    }
+
+    /// Writes a header with the argument width requested by `Value::SizedInteger`, or the
+    /// shortest one if the value does not fit into it.
+    fn write_sized_u64(&mut self, major: u8, value: u64) -> Result<()> {
+        let major = major << 5;
+        let bytes = value.to_be_bytes();
+        match self.number_width.take() {
+            Some(0) if value <= 0x17 => self.write_all(&[major | value as u8]),
+            Some(1) if value <= u64::from(u8::MAX) => self.write_all(&[major | 24, value as u8]),
+            Some(2) if value <= u64::from(u16::MAX) => {
+                self.write_all(&[major | 25, bytes[6], bytes[7]])
+            }
+            Some(4) if value <= u64::from(u32::MAX) => {
+                self.write_all(&[major | 26, bytes[4], bytes[5], bytes[6], bytes[7]])
+            }
+            Some(8) => {
+                let mut buf = [major | 27, 0, 0, 0, 0, 0, 0, 0, 0];
+                buf[1..].copy_from_slice(&bytes);
+                self.write_all(&buf)
+            }
+            _ => self.write_u64(major >> 5, value),
+        }
+    }
+
+    /// Writes a header followed by the payload of a string.
+    #[inline]
+    fn write_header(&mut self, major: u8, value: u64, payload: &[u8]) -> Result<()> {
         let mut buf = [major << 5, 0, 0, 0, 0, 0, 0, 0, 0];
         let buf_view = if value <= 0x17 {
             buf[0] |= value as u8;
@@ -290,8 +1167,285 @@ where
                 (&mut buf[1..9]).copy_from_slice(&value.to_be_bytes());
                 &buf[..9]
             }
-        };
-        self.writer.write_all(buf_view).map_err(|e| e.into())
+        };
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if let Some(map) = self.maps.levels.last_mut() {
+                map.buf.extend_from_slice(buf_view);
+                map.buf.extend_from_slice(payload);
+                return Ok(());
+            }
+            if self.options.fail_safe() {
+                self.rollback.buf.extend_from_slice(buf_view);
+                self.rollback.buf.extend_from_slice(payload);
+                return self.commit();
+            }
+        }
+        if payload.is_empty() {
+            self.writer.write_all(buf_view)
+        } else {
+            self.writer.reserve(buf_view.len() + payload.len());
+            self.writer.write_vectored(&[buf_view, payload])
+        }
+        .map_err(|e| e.into())
+    }
+
+    /// Writes raw bytes, into the buffer of the innermost buffered map if there is one.
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if let Some(map) = self.maps.levels.last_mut() {
+                map.buf.extend_from_slice(buf);
+                return Ok(());
+            }
+            if self.options.fail_safe() {
+                self.rollback.buf.extend_from_slice(buf);
+                return self.commit();
+            }
+        }
+        self.writer.write_all(buf).map_err(|e| e.into())
+    }
+
+    /// Passes the held back item to the writer in fail-safe mode once it is complete.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    fn commit(&mut self) -> Result<()> {
+        if self.rollback.depth > 0 || self.rollback.buf.is_empty() {
+            return Ok(());
+        }
+        let result = self.writer.write_all(&self.rollback.buf);
+        self.rollback.buf.clear();
+        result.map_err(|e| e.into())
+    }
+
+    /// Marks the start of an array, map or tag, returns the depth to go back to at its end.
+    #[inline]
+    fn open(&mut self) -> usize {
+        self.rollback.depth += 1;
+        self.rollback.depth - 1
+    }
+
+    /// Marks the end of the arrays, maps and tags opened at `depth`.
+    #[inline]
+    fn close(&mut self, depth: usize, result: Result<()>) -> Result<()> {
+        self.guard(result)?;
+        self.rollback.depth = depth;
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if self.options.fail_safe() {
+                let result = self.commit();
+                return self.guard(result);
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops the buffered maps if `result` is an error, and the held back item in fail-safe mode.
+    ///
+    /// Later values are written to the writer again, not into the buffer of a map that will never
+    /// be finished.
+    #[inline]
+    fn guard<T>(&mut self, result: Result<T>) -> Result<T> {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if result.is_err() {
+                if self.options.fail_safe() {
+                    self.rollback.depth = 0;
+                    self.rollback.buf.clear();
+                }
+                self.maps = BufferedMaps::default();
+                self.in_key = false;
+                self.redact_value = false;
+                self.number_width = None;
+            }
+        }
+        result
+    }
+
+    /// Starts buffering the entries of a map if its keys are sorted or checked for duplicates.
+    ///
+    /// Returns whether the entries are buffered.
+    #[inline]
+    fn begin_map_entries(&mut self) -> bool {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if self.options.key_order().is_some() || self.options.deny_duplicate_keys() {
+                self.maps.levels.push(BufferedMap::default());
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Starts buffering the items of an array or map of unknown length to write it with definite
+    /// length, returns whether they are buffered.
+    #[inline]
+    fn begin_deferred(&mut self, len: Option<usize>) -> bool {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if len.is_none() && self.options.definite_lengths() {
+                self.maps.levels.push(BufferedMap::default());
+                return true;
+            }
+        }
+        #[cfg(not(any(feature = "std", feature = "alloc")))]
+        let _ = len;
+        false
+    }
+
+    /// Writes the buffered items of an array of unknown length after its header.
+    #[inline]
+    fn end_deferred_array(&mut self, count: usize) -> Result<()> {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            let array = self.maps.levels.pop().expect("array is buffered");
+            self.write_u64(4, count as u64)?;
+            self.write_all(&array.buf)
+        }
+        #[cfg(not(any(feature = "std", feature = "alloc")))]
+        {
+            let _ = count;
+            unreachable!("arrays are only buffered with an allocator")
+        }
+    }
+
+    /// Marks the start of a key in the innermost buffered map.
+    #[inline]
+    fn begin_key(&mut self, buffered: bool) {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if buffered {
+                let map = self.maps.levels.last_mut().expect("map is buffered");
+                map.entries.push((map.buf.len(), map.buf.len()));
+            }
+        }
+        #[cfg(not(any(feature = "std", feature = "alloc")))]
+        let _ = buffered;
+    }
+
+    /// Marks the end of a key in the innermost buffered map.
+    #[inline]
+    fn end_key(&mut self, buffered: bool) {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if buffered {
+                let map = self.maps.levels.last_mut().expect("map is buffered");
+                let len = map.buf.len();
+                map.entries.last_mut().expect("key has started").1 = len;
+            }
+        }
+        #[cfg(not(any(feature = "std", feature = "alloc")))]
+        let _ = buffered;
+    }
+
+    /// Writes the buffered entries of the innermost buffered map in order.
+    ///
+    /// The header of a map of unknown length is written here too if it was `deferred`.
+    #[inline]
+    fn end_map_entries(&mut self, buffered: bool, deferred: bool) -> Result<()> {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if buffered {
+                let map = self.maps.levels.pop().expect("map is buffered");
+                let entries = map.entries(self.options.key_order());
+                if self.options.deny_duplicate_keys() {
+                    let mut keys: Vec<&[u8]> = entries.iter().map(|entry| entry.0).collect();
+                    keys.sort_unstable();
+                    if keys.windows(2).any(|pair| pair[0] == pair[1]) {
+                        return Err(Error::unplaced(ErrorCode::DuplicateKey));
+                    }
+                }
+                if deferred {
+                    self.write_u64(5, entries.len() as u64)?;
+                }
+                for (_, entry) in entries {
+                    self.write_all(entry)?;
+                }
+            }
+        }
+        #[cfg(not(any(feature = "std", feature = "alloc")))]
+        let _ = (buffered, deferred);
+        Ok(())
+    }
+
+    #[inline]
+    fn begin_item(&mut self, item: Item<'_>) -> Result<()> {
+        if self.in_key {
+            // the key is the item after its tags
+            match item {
+                Item::Tag(_) => {}
+                Item::Text(key) => {
+                    self.in_key = false;
+                    self.redact_value = self.hook.redact(key);
+                }
+                Item::PreEncoded(bytes) => {
+                    self.in_key = false;
+                    if let Some(key) = pre_encoded_text(bytes) {
+                        self.redact_value = self.hook.redact(key);
+                    }
+                }
+                _ => self.in_key = false,
+            }
+        }
+        if let Some(tag) = self.hook.item(item) {
+            // the tag is held back together with the item in fail-safe mode
+            self.rollback.depth += 1;
+            let result = self.write_u64(6, tag);
+            self.rollback.depth -= 1;
+            result?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn write_f32(&mut self, value: f32) -> Result<()> {
+        if value.is_infinite() {
+            if value.is_sign_positive() {
+                self.write_all(&[0xf9, 0x7c, 0x00])
+            } else {
+                self.write_all(&[0xf9, 0xfc, 0x00])
+            }
+        } else if value.is_nan() {
+            self.write_all(&[0xf9, 0x7e, 0x00])
+        } else if f32::from(f16::from_f32(value)) == value {
+            let mut buf = [0xf9, 0, 0];
+            (&mut buf[1..]).copy_from_slice(&f16::from_f32(value).to_bits().to_be_bytes());
+            self.write_all(&buf)
+        } else {
+            let mut buf = [0xfa, 0, 0, 0, 0];
+            (&mut buf[1..]).copy_from_slice(&value.to_bits().to_be_bytes());
+            self.write_all(&buf)
+        }
+    }
+
+    /// Adds `segment` to the path of an error when `error_paths` is enabled.
+    #[inline]
+    fn locate(&self, result: Result<()>, segment: core::fmt::Arguments<'_>) -> Result<()> {
+        #[cfg(feature = "std")]
+        {
+            if self.options.error_paths() {
+                return result.map_err(|e| e.in_path(segment));
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = segment;
+        result
+    }
+
+    /// Writes the key of a newtype or tuple variant in the map enum format.
+    #[inline]
+    fn serialize_variant_key(
+        &mut self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        if self.options.packed_variant_keys() {
+            ser::Serializer::serialize_unit_variant(self, name, variant_index, variant)
+        } else {
+            variant.serialize(self)
+        }
     }
 
     #[inline]
@@ -299,47 +1453,215 @@ where
         &'a mut self,
         major: u8,
         len: Option<usize>,
-    ) -> Result<CollectionSerializer<'a, W, O>> {
+    ) -> Result<CollectionSerializer<'a, W, O, H>> {
+        let outer = self.open();
+        let deferred = self.begin_deferred(len);
+        let result = self.begin_collection(major, len, deferred);
+        let needs_eof = self.guard(result)?;
+        let buffered = major == 5 && (deferred || self.begin_map_entries());
+        Ok(CollectionSerializer::new(
+            self, needs_eof, buffered, deferred, outer,
+        ))
+    }
+
+    /// Writes the header of an array or map, returns whether it has indefinite length.
+    ///
+    /// Nothing is written for a `deferred` collection, its header follows once its items are
+    /// counted.
+    #[inline]
+    fn begin_collection(&mut self, major: u8, len: Option<usize>, deferred: bool) -> Result<bool> {
+        if major == 4 {
+            self.begin_item(Item::Array(len))?;
+        } else {
+            self.begin_item(Item::Map(len))?;
+        }
         let needs_eof = match len {
             Some(len) => {
                 self.write_u64(major, len as u64)?;
                 false
             }
+            None if deferred => false,
             None => {
-                self.writer
-                    .write_all(&[major << 5 | 31])
-                    .map_err(|e| e.into())?;
+                self.write_all(&[major << 5 | 31])?;
                 true
             }
         };
 
-        Ok(CollectionSerializer {
+        Ok(needs_eof)
+    }
+
+    /// Writes the tags of a `tags::Tagged` value.
+    #[inline]
+    fn write_tags(&mut self) -> Result<()> {
+        for tag in get_tag().into_iter() {
+            self.begin_item(Item::Tag(tag))?;
+            self.write_u64(6, tag)?;
+        }
+        Ok(())
+    }
+
+    /// Writes what comes before the value of a newtype variant.
+    #[inline]
+    fn begin_newtype_variant(
+        &mut self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        if self.options.enum_as_map() {
+            self.begin_item(Item::Map(Some(1)))?;
+            self.write_u64(5, 1u64)?;
+            self.serialize_variant_key(name, variant_index, variant)
+        } else {
+            self.begin_item(Item::Array(Some(2)))?;
+            self.write_all(&[4 << 5 | 2])?;
+            ser::Serializer::serialize_unit_variant(self, name, variant_index, variant)
+        }
+    }
+
+    #[inline]
+    fn begin_tuple(&mut self, len: usize) -> Result<()> {
+        self.begin_item(Item::Array(Some(len)))?;
+        self.write_u64(4, len as u64)
+    }
+
+    /// Writes what comes before the fields of a tuple variant.
+    #[inline]
+    fn begin_tuple_variant(
+        &mut self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<()> {
+        if self.options.enum_as_map() {
+            self.begin_item(Item::Map(Some(1)))?;
+            self.write_u64(5, 1u64)?;
+            self.serialize_variant_key(name, variant_index, variant)?;
+            self.begin_tuple(len)
+        } else {
+            self.begin_item(Item::Array(Some(len + 1)))?;
+            self.write_u64(4, (len + 1) as u64)?;
+            ser::Serializer::serialize_unit_variant(self, name, variant_index, variant)
+        }
+    }
+
+    /// Writes what comes before the fields of a struct variant.
+    #[inline]
+    fn begin_struct_variant(
+        &mut self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<()> {
+        if self.options.enum_as_map() {
+            self.begin_item(Item::Map(Some(1)))?;
+            self.write_u64(5, 1u64)?;
+        } else {
+            self.begin_item(Item::Array(Some(2)))?;
+            self.write_all(&[4 << 5 | 2])?;
+        }
+        ser::Serializer::serialize_unit_variant(&mut *self, name, variant_index, variant)?;
+        self.begin_struct(len)
+    }
+
+    #[inline]
+    fn begin_struct(&mut self, len: usize) -> Result<()> {
+        self.begin_item(Item::Map(Some(len)))?;
+        self.write_u64(5, len as u64)
+    }
+
+    #[inline]
+    fn struct_serializer(
+        &mut self,
+        name: &'static str,
+        outer: usize,
+    ) -> StructSerializer<'_, W, O, H> {
+        let packed = is_packed(&self.options, name);
+        let idx = if packed {
+            packed_index_start(&self.options, name).into()
+        } else {
+            0
+        };
+        let buffered = self.begin_map_entries();
+        StructSerializer {
             ser: self,
-            needs_eof,
-        })
+            idx,
+            packed,
+            buffered,
+            outer,
+        }
+    }
+}
+
+impl<'a, O, H> Serializer<SliceWrite<'a>, O, H>
+where
+    O: SerializerOptions,
+    H: SerializerHook,
+{
+    /// Prepares the serializer for encoding the next value into the same slice.
+    ///
+    /// The options and the hook are kept, the slice is written from the beginning again. This
+    /// allows an encode loop to reuse one buffer without constructing a new serializer for every
+    /// value.
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_cbor::ser::{Serializer, SliceWrite};
+    ///
+    /// let mut buf = [0u8; 16];
+    /// let mut serializer = Serializer::new(SliceWrite::new(&mut buf)).packed_format();
+    /// for i in 0..3u8 {
+    ///     serializer.reset();
+    ///     [i, i].serialize(&mut serializer).unwrap();
+    ///     assert_eq!(serializer.written(), [0x82, i, i]);
+    /// }
+    /// ```
+    #[inline]
+    pub fn reset(&mut self) {
+        self.writer.reset();
+        self.in_key = false;
+        self.redact_value = false;
+        self.maps = BufferedMaps::default();
+        self.rollback = Rollback::default();
+    }
+
+    /// Returns the number of bytes written since the last reset.
+    #[inline]
+    pub fn bytes_written(&self) -> usize {
+        self.writer.bytes_written()
+    }
+
+    /// Returns the bytes written since the last reset.
+    #[inline]
+    pub fn written(&self) -> &[u8] {
+        self.writer.written()
     }
 }
 
-impl<'a, W, O> ser::Serializer for &'a mut Serializer<W, O>
+impl<'a, W, O, H> ser::Serializer for &'a mut Serializer<W, O, H>
 where
     W: Write,
     O: SerializerOptions,
+    H: SerializerHook,
 {
     type Ok = ();
     type Error = Error;
 
-    type SerializeSeq = CollectionSerializer<'a, W, O>;
-    type SerializeTuple = &'a mut Serializer<W, O>;
-    type SerializeTupleStruct = &'a mut Serializer<W, O>;
-    type SerializeTupleVariant = &'a mut Serializer<W, O>;
-    type SerializeMap = CollectionSerializer<'a, W, O>;
-    type SerializeStruct = StructSerializer<'a, W, O>;
-    type SerializeStructVariant = StructSerializer<'a, W, O>;
+    type SerializeSeq = CollectionSerializer<'a, W, O, H>;
+    type SerializeTuple = CollectionSerializer<'a, W, O, H>;
+    type SerializeTupleStruct = CollectionSerializer<'a, W, O, H>;
+    type SerializeTupleVariant = CollectionSerializer<'a, W, O, H>;
+    type SerializeMap = CollectionSerializer<'a, W, O, H>;
+    type SerializeStruct = StructSerializer<'a, W, O, H>;
+    type SerializeStructVariant = StructSerializer<'a, W, O, H>;
 
     #[inline]
     fn serialize_bool(self, value: bool) -> Result<()> {
+        self.begin_item(Item::Bool(value))?;
         let value = if value { 0xf5 } else { 0xf4 };
-        self.writer.write_all(&[value]).map_err(|e| e.into())
+        self.write_all(&[value])
     }
 
     #[inline]
@@ -359,6 +1681,7 @@ where
 
     #[inline]
     fn serialize_i64(self, value: i64) -> Result<()> {
+        self.begin_item(Item::Integer(value.into()))?;
         let (major, u64_value) = if value < 0 {
             (1, -(value + 1) as u64)
         } else {
@@ -373,32 +1696,39 @@ where
             if -(value + 1) > i128::from(u64::max_value()) {
                 return Err(Error::message("The number can't be stored in CBOR"));
             }
-            self.write_u64(1, -(value + 1) as u64)
+            self.begin_item(Item::Integer(value))?;
+            self.write_sized_u64(1, -(value + 1) as u64)
         } else {
             if value > i128::from(u64::max_value()) {
                 return Err(Error::message("The number can't be stored in CBOR"));
             }
-            self.write_u64(0, value as u64)
+            self.begin_item(Item::Integer(value))?;
+            self.write_sized_u64(0, value as u64)
         }
     }
 
     #[inline]
     fn serialize_u8(self, value: u8) -> Result<()> {
-        self.write_u64(0, value as u64)
+        self.serialize_u64(value.into())
     }
 
     #[inline]
     fn serialize_u16(self, value: u16) -> Result<()> {
-        self.write_u64(0, value as u64)
+        self.serialize_u64(value.into())
     }
 
     #[inline]
     fn serialize_u32(self, value: u32) -> Result<()> {
-        self.write_u64(0, value as u64)
+        self.serialize_u64(value.into())
     }
 
     #[inline]
     fn serialize_u64(self, value: u64) -> Result<()> {
+        if self.simple_value {
+            self.simple_value = false;
+            return self.write_simple(value);
+        }
+        self.begin_item(Item::Integer(value.into()))?;
         self.write_u64(0, value)
     }
 
@@ -407,41 +1737,45 @@ where
         if value > u128::from(u64::max_value()) {
             return Err(Error::message("The number can't be stored in CBOR"));
         }
+        self.begin_item(Item::Integer(value as i128))?;
         self.write_u64(0, value as u64)
     }
 
     #[inline]
     #[allow(clippy::float_cmp)]
     fn serialize_f32(self, value: f32) -> Result<()> {
-        if value.is_infinite() {
-            if value.is_sign_positive() {
-                self.writer.write_all(&[0xf9, 0x7c, 0x00])
-            } else {
-                self.writer.write_all(&[0xf9, 0xfc, 0x00])
-            }
-        } else if value.is_nan() {
-            self.writer.write_all(&[0xf9, 0x7e, 0x00])
-        } else if f32::from(f16::from_f32(value)) == value {
-            let mut buf = [0xf9, 0, 0];
-            (&mut buf[1..]).copy_from_slice(&f16::from_f32(value).to_bits().to_be_bytes());
-            self.writer.write_all(&buf)
-        } else {
-            let mut buf = [0xfa, 0, 0, 0, 0];
-            (&mut buf[1..]).copy_from_slice(&value.to_bits().to_be_bytes());
-            self.writer.write_all(&buf)
-        }
-        .map_err(|e| e.into())
+        self.begin_item(Item::Float(value.into()))?;
+        self.write_f32(value)
     }
 
     #[inline]
     #[allow(clippy::float_cmp)]
     fn serialize_f64(self, value: f64) -> Result<()> {
+        self.begin_item(Item::Float(value))?;
+        match self.number_width.take() {
+            Some(2) if value.is_nan() || f16::from_f64(value).to_f64() == value => {
+                let mut buf = [0xf9, 0, 0];
+                buf[1..].copy_from_slice(&f16::from_f64(value).to_bits().to_be_bytes());
+                return self.write_all(&buf);
+            }
+            Some(4) if value.is_nan() || f64::from(value as f32) == value => {
+                let mut buf = [0xfa, 0, 0, 0, 0];
+                buf[1..].copy_from_slice(&(value as f32).to_bits().to_be_bytes());
+                return self.write_all(&buf);
+            }
+            Some(8) => {
+                let mut buf = [0xfb, 0, 0, 0, 0, 0, 0, 0, 0];
+                buf[1..].copy_from_slice(&value.to_bits().to_be_bytes());
+                return self.write_all(&buf);
+            }
+            _ => (),
+        }
         if !value.is_finite() || f64::from(value as f32) == value {
-            self.serialize_f32(value as f32)
+            self.write_f32(value as f32)
         } else {
             let mut buf = [0xfb, 0, 0, 0, 0, 0, 0, 0, 0];
             (&mut buf[1..]).copy_from_slice(&value.to_bits().to_be_bytes());
-            self.writer.write_all(&buf).map_err(|e| e.into())
+            self.write_all(&buf)
         }
     }
 
@@ -454,16 +1788,19 @@ where
 
     #[inline]
     fn serialize_str(self, value: &str) -> Result<()> {
-        self.write_u64(3, value.len() as u64)?;
-        self.writer
-            .write_all(value.as_bytes())
-            .map_err(|e| e.into())
+        self.begin_item(Item::Text(value))?;
+        self.write_header(3, value.len() as u64, value.as_bytes())
     }
 
     #[inline]
     fn serialize_bytes(self, value: &[u8]) -> Result<()> {
-        self.write_u64(2, value.len() as u64)?;
-        self.writer.write_all(value).map_err(|e| e.into())
+        if self.pre_encoded {
+            self.pre_encoded = false;
+            self.begin_item(Item::PreEncoded(value))?;
+            return self.write_all(value);
+        }
+        self.begin_item(Item::Bytes(value))?;
+        self.write_header(2, value.len() as u64, value)
     }
 
     #[inline]
@@ -481,7 +1818,8 @@ where
 
     #[inline]
     fn serialize_none(self) -> Result<()> {
-        self.writer.write_all(&[0xf6]).map_err(|e| e.into())
+        self.begin_item(Item::Null)?;
+        self.write_all(&[0xf6])
     }
 
     #[inline]
@@ -492,11 +1830,11 @@ where
     #[inline]
     fn serialize_unit_variant(
         self,
-        _name: &'static str,
+        name: &'static str,
         variant_index: u32,
         variant: &'static str,
     ) -> Result<()> {
-        if self.options.packed() {
+        if is_packed(&self.options, name) {
             self.serialize_u32(variant_index)
         } else {
             self.serialize_str(variant)
@@ -509,9 +1847,28 @@ where
         T: ?Sized + ser::Serialize,
     {
         if name == CBOR_NEWTYPE_NAME {
-            for tag in get_tag().into_iter() {
-                self.write_u64(6, tag)?;
-            }
+            let outer = self.open();
+            let result = self.write_tags().and_then(|()| value.serialize(&mut *self));
+            return self.close(outer, result);
+        } else if let Some(&(_, width)) = SIZED_FLOAT_NAMES
+            .iter()
+            .chain(&SIZED_INTEGER_NAMES)
+            .find(|&&(n, _)| n == name)
+        {
+            self.number_width = Some(width);
+            let r = value.serialize(&mut *self);
+            self.number_width = None;
+            return r;
+        } else if name == PRE_ENCODED_NAME {
+            self.pre_encoded = true;
+            let r = value.serialize(&mut *self);
+            self.pre_encoded = false;
+            return r;
+        } else if name == SIMPLE_VALUE_NAME {
+            self.simple_value = true;
+            let r = value.serialize(&mut *self);
+            self.simple_value = false;
+            return r;
         }
         value.serialize(self)
     }
@@ -527,25 +1884,24 @@ where
     where
         T: ?Sized + ser::Serialize,
     {
-        if self.options.enum_as_map() {
-            self.write_u64(5, 1u64)?;
-            variant.serialize(&mut *self)?;
-        } else {
-            self.writer.write_all(&[4 << 5 | 2]).map_err(|e| e.into())?;
-            self.serialize_unit_variant(name, variant_index, variant)?;
-        }
-        value.serialize(self)
+        let outer = self.open();
+        let result = self
+            .begin_newtype_variant(name, variant_index, variant)
+            .and_then(|()| value.serialize(&mut *self));
+        self.close(outer, result)
     }
 
     #[inline]
-    fn serialize_seq(self, len: Option<usize>) -> Result<CollectionSerializer<'a, W, O>> {
+    fn serialize_seq(self, len: Option<usize>) -> Result<CollectionSerializer<'a, W, O, H>> {
         self.serialize_collection(4, len)
     }
 
     #[inline]
-    fn serialize_tuple(self, len: usize) -> Result<&'a mut Serializer<W, O>> {
-        self.write_u64(4, len as u64)?;
-        Ok(self)
+    fn serialize_tuple(self, len: usize) -> Result<CollectionSerializer<'a, W, O, H>> {
+        let outer = self.open();
+        let result = self.begin_tuple(len);
+        self.guard(result)?;
+        Ok(CollectionSerializer::new(self, false, false, false, outer))
     }
 
     #[inline]
@@ -553,7 +1909,7 @@ where
         self,
         _name: &'static str,
         len: usize,
-    ) -> Result<&'a mut Serializer<W, O>> {
+    ) -> Result<CollectionSerializer<'a, W, O, H>> {
         self.serialize_tuple(len)
     }
 
@@ -564,20 +1920,15 @@ where
         variant_index: u32,
         variant: &'static str,
         len: usize,
-    ) -> Result<&'a mut Serializer<W, O>> {
-        if self.options.enum_as_map() {
-            self.write_u64(5, 1u64)?;
-            variant.serialize(&mut *self)?;
-            self.serialize_tuple(len)
-        } else {
-            self.write_u64(4, (len + 1) as u64)?;
-            self.serialize_unit_variant(name, variant_index, variant)?;
-            Ok(self)
-        }
+    ) -> Result<CollectionSerializer<'a, W, O, H>> {
+        let outer = self.open();
+        let result = self.begin_tuple_variant(name, variant_index, variant, len);
+        self.guard(result)?;
+        Ok(CollectionSerializer::new(self, false, false, false, outer))
     }
 
     #[inline]
-    fn serialize_map(self, len: Option<usize>) -> Result<CollectionSerializer<'a, W, O>> {
+    fn serialize_map(self, len: Option<usize>) -> Result<CollectionSerializer<'a, W, O, H>> {
         self.serialize_collection(5, len)
     }
 
@@ -597,11 +1948,13 @@ where
     #[inline]
     fn serialize_struct(
         self,
-        _name: &'static str,
+        name: &'static str,
         len: usize,
-    ) -> Result<StructSerializer<'a, W, O>> {
-        self.write_u64(5, len as u64)?;
-        Ok(StructSerializer { ser: self, idx: 0 })
+    ) -> Result<StructSerializer<'a, W, O, H>> {
+        let outer = self.open();
+        let result = self.begin_struct(len);
+        self.guard(result)?;
+        Ok(self.struct_serializer(name, outer))
     }
 
     #[inline]
@@ -611,145 +1964,221 @@ where
         variant_index: u32,
         variant: &'static str,
         len: usize,
-    ) -> Result<StructSerializer<'a, W, O>> {
-        if self.options.enum_as_map() {
-            self.write_u64(5, 1u64)?;
-        } else {
-            self.writer.write_all(&[4 << 5 | 2]).map_err(|e| e.into())?;
-        }
-        self.serialize_unit_variant(name, variant_index, variant)?;
-        self.serialize_struct(name, len)
+    ) -> Result<StructSerializer<'a, W, O, H>> {
+        let outer = self.open();
+        let result = self.begin_struct_variant(name, variant_index, variant, len);
+        self.guard(result)?;
+        Ok(self.struct_serializer(name, outer))
     }
 
     #[inline]
     fn is_human_readable(&self) -> bool {
-        false
+        self.options.human_readable()
     }
 }
 
-impl<'a, W, O> ser::SerializeTuple for &'a mut Serializer<W, O>
+#[doc(hidden)]
+pub struct StructSerializer<'a, W, O, H> {
+    ser: &'a mut Serializer<W, O, H>,
+    /// The packed key of the next field, wide enough not to overflow for any starting index.
+    idx: u64,
+    packed: bool,
+    buffered: bool,
+    /// The depth to go back to at the end, see `Serializer::open`.
+    outer: usize,
+}
+
+impl<'a, W, O, H> StructSerializer<'a, W, O, H>
 where
     W: Write,
     O: SerializerOptions,
+    H: SerializerHook,
 {
-    type Ok = ();
-    type Error = Error;
+    #[inline]
+    fn serialize_field_inner<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        let result = self.write_field(key, value);
+        self.ser.guard(result)
+    }
 
     #[inline]
-    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    fn write_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + ser::Serialize,
     {
-        value.serialize(&mut **self)
+        self.ser.begin_key(self.buffered);
+        if self.packed {
+            self.idx.serialize(&mut *self.ser)?;
+        } else {
+            key.serialize(&mut *self.ser)?;
+        }
+        self.ser.end_key(self.buffered);
+        if self.ser.hook.redact(key) {
+            ser::Serializer::serialize_none(&mut *self.ser)?;
+        } else {
+            let result = value.serialize(&mut *self.ser);
+            self.ser.locate(result, format_args!(".{}", key))?;
+        }
+        self.idx += 1;
+        Ok(())
     }
 
     #[inline]
-    fn end(self) -> Result<()> {
+    fn skip_field_inner(&mut self, _: &'static str) -> Result<()> {
+        self.idx += 1;
         Ok(())
     }
+
+    #[inline]
+    fn end_inner(self) -> Result<()> {
+        let result = self.ser.end_map_entries(self.buffered, false);
+        self.ser.close(self.outer, result)
+    }
 }
 
-impl<'a, W, O> ser::SerializeTupleStruct for &'a mut Serializer<W, O>
+impl<'a, W, O, H> ser::SerializeStruct for StructSerializer<'a, W, O, H>
 where
     W: Write,
     O: SerializerOptions,
+    H: SerializerHook,
 {
     type Ok = ();
     type Error = Error;
 
     #[inline]
-    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + ser::Serialize,
     {
-        value.serialize(&mut **self)
+        self.serialize_field_inner(key, value)
+    }
+
+    #[inline]
+    fn skip_field(&mut self, key: &'static str) -> Result<()> {
+        self.skip_field_inner(key)
     }
 
     #[inline]
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_inner()
     }
 }
 
-impl<'a, W, O> ser::SerializeTupleVariant for &'a mut Serializer<W, O>
+impl<'a, W, O, H> ser::SerializeStructVariant for StructSerializer<'a, W, O, H>
 where
     W: Write,
     O: SerializerOptions,
+    H: SerializerHook,
 {
     type Ok = ();
     type Error = Error;
 
     #[inline]
-    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + ser::Serialize,
     {
-        value.serialize(&mut **self)
+        self.serialize_field_inner(key, value)
+    }
+
+    #[inline]
+    fn skip_field(&mut self, key: &'static str) -> Result<()> {
+        self.skip_field_inner(key)
     }
 
     #[inline]
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_inner()
     }
 }
 
 #[doc(hidden)]
-pub struct StructSerializer<'a, W, O> {
-    ser: &'a mut Serializer<W, O>,
-    idx: u32,
+pub struct CollectionSerializer<'a, W, O, H> {
+    ser: &'a mut Serializer<W, O, H>,
+    needs_eof: bool,
+    buffered: bool,
+    /// Whether the header is written at the end, see `SerializerOptions::definite_lengths`.
+    deferred: bool,
+    /// The depth to go back to at the end, see `Serializer::open`.
+    outer: usize,
+    index: usize,
+    /// The path segment of the current map key, only kept with `error_paths`.
+    #[cfg(feature = "std")]
+    key: Option<String>,
 }
 
-impl<'a, W, O> StructSerializer<'a, W, O>
+impl<'a, W, O, H> CollectionSerializer<'a, W, O, H>
 where
     W: Write,
     O: SerializerOptions,
+    H: SerializerHook,
 {
     #[inline]
-    fn serialize_field_inner<T>(&mut self, key: &'static str, value: &T) -> Result<()>
-    where
-        T: ?Sized + ser::Serialize,
-    {
-        if self.ser.options.packed() {
-            self.idx.serialize(&mut *self.ser)?;
-        } else {
-            key.serialize(&mut *self.ser)?;
+    fn new(
+        ser: &'a mut Serializer<W, O, H>,
+        needs_eof: bool,
+        buffered: bool,
+        deferred: bool,
+        outer: usize,
+    ) -> CollectionSerializer<'a, W, O, H> {
+        CollectionSerializer {
+            ser,
+            needs_eof,
+            buffered,
+            deferred,
+            outer,
+            index: 0,
+            #[cfg(feature = "std")]
+            key: None,
         }
-        value.serialize(&mut *self.ser)?;
-        self.idx += 1;
-        Ok(())
     }
 
     #[inline]
-    fn skip_field_inner(&mut self, _: &'static str) -> Result<()> {
-        self.idx += 1;
-        Ok(())
+    fn serialize_element_inner<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        let result = value.serialize(&mut *self.ser);
+        let result = self.ser.locate(result, format_args!("[{}]", self.index));
+        self.index += 1;
+        self.ser.guard(result)
     }
 
     #[inline]
     fn end_inner(self) -> Result<()> {
-        Ok(())
+        let result = if self.deferred && !self.buffered {
+            self.ser.end_deferred_array(self.index)
+        } else {
+            self.ser.end_map_entries(self.buffered, self.deferred)
+        };
+        let result = result.and_then(|()| {
+            if self.needs_eof {
+                self.ser.write_all(&[0xff])
+            } else {
+                Ok(())
+            }
+        });
+        self.ser.close(self.outer, result)
     }
 }
 
-impl<'a, W, O> ser::SerializeStruct for StructSerializer<'a, W, O>
+impl<'a, W, O, H> ser::SerializeSeq for CollectionSerializer<'a, W, O, H>
 where
     W: Write,
     O: SerializerOptions,
+    H: SerializerHook,
 {
     type Ok = ();
     type Error = Error;
 
     #[inline]
-    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + ser::Serialize,
     {
-        self.serialize_field_inner(key, value)
-    }
-
-    #[inline]
-    fn skip_field(&mut self, key: &'static str) -> Result<()> {
-        self.skip_field_inner(key)
+        self.serialize_element_inner(value)
     }
 
     #[inline]
@@ -758,25 +2187,21 @@ where
     }
 }
 
-impl<'a, W, O> ser::SerializeStructVariant for StructSerializer<'a, W, O>
+impl<'a, W, O, H> ser::SerializeTuple for CollectionSerializer<'a, W, O, H>
 where
     W: Write,
     O: SerializerOptions,
+    H: SerializerHook,
 {
     type Ok = ();
     type Error = Error;
 
     #[inline]
-    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + ser::Serialize,
     {
-        self.serialize_field_inner(key, value)
-    }
-
-    #[inline]
-    fn skip_field(&mut self, key: &'static str) -> Result<()> {
-        self.skip_field_inner(key)
+        self.serialize_element_inner(value)
     }
 
     #[inline]
@@ -785,41 +2210,44 @@ where
     }
 }
 
-#[doc(hidden)]
-pub struct CollectionSerializer<'a, W, O> {
-    ser: &'a mut Serializer<W, O>,
-    needs_eof: bool,
-}
-
-impl<'a, W, O> CollectionSerializer<'a, W, O>
+impl<'a, W, O, H> ser::SerializeTupleStruct for CollectionSerializer<'a, W, O, H>
 where
     W: Write,
     O: SerializerOptions,
+    H: SerializerHook,
 {
+    type Ok = ();
+    type Error = Error;
+
     #[inline]
-    fn end_inner(self) -> Result<()> {
-        if self.needs_eof {
-            self.ser.writer.write_all(&[0xff]).map_err(|e| e.into())
-        } else {
-            Ok(())
-        }
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        self.serialize_element_inner(value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<()> {
+        self.end_inner()
     }
 }
 
-impl<'a, W, O> ser::SerializeSeq for CollectionSerializer<'a, W, O>
+impl<'a, W, O, H> ser::SerializeTupleVariant for CollectionSerializer<'a, W, O, H>
 where
     W: Write,
     O: SerializerOptions,
+    H: SerializerHook,
 {
     type Ok = ();
     type Error = Error;
 
     #[inline]
-    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + ser::Serialize,
     {
-        value.serialize(&mut *self.ser)
+        self.serialize_element_inner(value)
     }
 
     #[inline]
@@ -828,10 +2256,11 @@ where
     }
 }
 
-impl<'a, W, O> ser::SerializeMap for CollectionSerializer<'a, W, O>
+impl<'a, W, O, H> ser::SerializeMap for CollectionSerializer<'a, W, O, H>
 where
     W: Write,
     O: SerializerOptions,
+    H: SerializerHook,
 {
     type Ok = ();
     type Error = Error;
@@ -841,7 +2270,25 @@ where
     where
         T: ?Sized + ser::Serialize,
     {
-        key.serialize(&mut *self.ser)
+        self.ser.begin_key(self.buffered);
+        self.ser.in_key = true;
+        let result = key.serialize(&mut *self.ser);
+        self.ser.in_key = false;
+        self.ser.end_key(self.buffered);
+        let result = self.ser.guard(result);
+        #[cfg(feature = "std")]
+        {
+            self.key = if self.ser.options.error_paths() {
+                Some(match crate::value::to_value(key) {
+                    Ok(crate::Value::Text(text)) => format!(".{}", text),
+                    Ok(crate::Value::Integer(i)) => format!("[{}]", i),
+                    _ => "[?]".to_string(),
+                })
+            } else {
+                None
+            };
+        }
+        result
     }
 
     #[inline]
@@ -849,7 +2296,18 @@ where
     where
         T: ?Sized + ser::Serialize,
     {
-        value.serialize(&mut *self.ser)
+        if self.ser.redact_value {
+            self.ser.redact_value = false;
+            ser::Serializer::serialize_none(&mut *self.ser)
+        } else {
+            let result = value.serialize(&mut *self.ser);
+            #[cfg(feature = "std")]
+            let result = match self.key.take() {
+                Some(key) => self.ser.locate(result, format_args!("{}", key)),
+                None => result,
+            };
+            self.ser.guard(result)
+        }
     }
 
     #[inline]
@@ -0,0 +1,277 @@
+//! Decode a sequence of CBOR values from an asynchronous reader.
+//!
+//! ```
+//! use futures::executor::block_on;
+//! use futures::StreamExt;
+//!
+//! let connection: &[u8] = &[0x01, 0x02, 0x03];
+//! let mut stream = serde_cbor::stream::into_stream::<u8, _>(connection);
+//! block_on(async {
+//!     let mut sum = 0;
+//!     while let Some(value) = stream.next().await {
+//!         sum += value.unwrap();
+//!     }
+//!     assert_eq!(sum, 6);
+//! });
+//! ```
+
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use futures_io::AsyncRead;
+use serde::de;
+
+use crate::de::Deserializer;
+use crate::error::{Error, ErrorCode, Result};
+use crate::header::{peek_header, MajorType};
+
+const DEFAULT_CHUNK_SIZE: usize = 8 * 1024;
+const DEFAULT_MAX_ITEM_LEN: usize = 16 * 1024 * 1024;
+
+/// Decodes values of type `T` from an asynchronous reader, see [`AsyncStreamDeserializer`].
+pub fn into_stream<T, R>(reader: R) -> AsyncStreamDeserializer<R, T>
+where
+    T: de::DeserializeOwned,
+    R: AsyncRead + Unpin,
+{
+    AsyncStreamDeserializer::new(reader)
+}
+
+/// A stream of values decoded from an asynchronous reader.
+///
+/// The reader is only polled when the next value is requested and the buffered bytes do not
+/// contain a complete value, so a slow consumer slows down reading. The stream ends when the
+/// reader reaches the end of its input between two values. After an error no further values are
+/// produced.
+#[derive(Debug)]
+pub struct AsyncStreamDeserializer<R, T> {
+    reader: R,
+    buffer: Vec<u8>,
+    start: usize,
+    offset: u64,
+    chunk_size: usize,
+    max_item_len: usize,
+    scan: Scan,
+    eof: bool,
+    failed: bool,
+    output: PhantomData<fn() -> T>,
+}
+
+impl<R, T> AsyncStreamDeserializer<R, T>
+where
+    T: de::DeserializeOwned,
+    R: AsyncRead + Unpin,
+{
+    /// Creates a stream reading from `reader`.
+    pub fn new(reader: R) -> AsyncStreamDeserializer<R, T> {
+        AsyncStreamDeserializer {
+            reader,
+            buffer: Vec::new(),
+            start: 0,
+            offset: 0,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            max_item_len: DEFAULT_MAX_ITEM_LEN,
+            scan: Scan::default(),
+            eof: false,
+            failed: false,
+            output: PhantomData,
+        }
+    }
+
+    /// Sets the number of bytes requested from the reader at once. Defaults to 8 KiB.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Fails with a `LengthOutOfRange` error when a single value does not fit into `len` bytes.
+    /// Defaults to 16 MiB.
+    ///
+    /// The limit keeps a peer from making the stream buffer arbitrary amounts of data.
+    pub fn max_item_len(mut self, len: usize) -> Self {
+        self.max_item_len = len;
+        self
+    }
+
+    /// The offset of the first byte after the last value returned by the stream.
+    pub fn byte_offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Unwraps the reader and the bytes that have been read but not decoded yet.
+    pub fn into_inner(mut self) -> (R, Vec<u8>) {
+        self.buffer.drain(..self.start);
+        (self.reader, self.buffer)
+    }
+
+    /// Tries to decode a value from the buffered bytes. `None` means more input is needed.
+    fn decode_buffered(&mut self) -> Option<Result<T>> {
+        let input = &self.buffer[self.start..];
+        // only decode once the value is complete, the scan continues where the last one stopped
+        if !self.eof && !self.scan.complete(input) {
+            if input.len() > self.max_item_len {
+                let offset = self.offset + input.len() as u64;
+                return Some(Err(Error::syntax(ErrorCode::LengthOutOfRange, offset)));
+            }
+            return None;
+        }
+        let mut deserializer = Deserializer::from_slice(input);
+        let result = T::deserialize(&mut deserializer);
+        let len = deserializer.byte_offset();
+        self.scan = Scan::default();
+        match result {
+            Ok(value) => {
+                self.start += len;
+                self.offset += len as u64;
+                if self.start * 2 > self.buffer.len() {
+                    self.buffer.drain(..self.start);
+                    self.start = 0;
+                }
+                Some(Ok(value))
+            }
+            Err(e) => Some(Err(e.shift_offset(self.offset))),
+        }
+    }
+}
+
+/// How far the headers of the next value have been looked at, so every read only scans the new
+/// bytes instead of decoding the whole value again.
+///
+/// The scan only finds the end of the value, errors are left to the deserializer.
+#[derive(Debug, Default)]
+struct Scan {
+    /// The offset of the next header from the start of the value.
+    pos: usize,
+    /// The items left in every enclosing array, map or tag, `None` for indefinite lengths.
+    open: Vec<Option<u64>>,
+}
+
+impl Scan {
+    /// Returns whether `input` holds the complete value.
+    fn complete(&mut self, input: &[u8]) -> bool {
+        loop {
+            let header = match peek_header(&input[self.pos..]) {
+                Ok(header) => header,
+                Err(e) if e.is_eof() => return false,
+                Err(_) => return true,
+            };
+            let contents = self.pos + header.size;
+            match (header.major, header.argument) {
+                (MajorType::Bytes, Some(len)) | (MajorType::Text, Some(len)) => {
+                    match usize::try_from(len)
+                        .ok()
+                        .and_then(|len| contents.checked_add(len))
+                    {
+                        Some(end) if end <= input.len() => self.pos = end,
+                        Some(_) => return false,
+                        None => return true,
+                    }
+                }
+                (MajorType::Array, Some(len)) | (MajorType::Map, Some(len)) if len > 0 => {
+                    self.pos = contents;
+                    let per_entry = if header.major == MajorType::Map { 2 } else { 1 };
+                    self.open.push(Some(len.saturating_mul(per_entry)));
+                    continue;
+                }
+                (MajorType::Tag, _) => {
+                    self.pos = contents;
+                    self.open.push(Some(1));
+                    continue;
+                }
+                (MajorType::Simple, None) => {
+                    // the break code ends the innermost indefinite length item
+                    self.pos = contents;
+                    if self.open.pop().is_none() {
+                        return true;
+                    }
+                }
+                (_, None) => {
+                    self.pos = contents;
+                    self.open.push(None);
+                    continue;
+                }
+                _ => self.pos = contents,
+            }
+            if self.finish_item() {
+                return true;
+            }
+        }
+    }
+
+    /// Counts a complete item in the enclosing items, returns whether it was the whole value.
+    fn finish_item(&mut self) -> bool {
+        loop {
+            match self.open.last_mut() {
+                None => return true,
+                Some(Some(left)) if *left > 1 => {
+                    *left -= 1;
+                    return false;
+                }
+                Some(Some(_)) => {
+                    self.open.pop();
+                }
+                Some(None) => return false,
+            }
+        }
+    }
+}
+
+impl<R, T> Stream for AsyncStreamDeserializer<R, T>
+where
+    T: de::DeserializeOwned,
+    R: AsyncRead + Unpin,
+{
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<T>>> {
+        let this = self.get_mut();
+        loop {
+            if this.failed {
+                return Poll::Ready(None);
+            }
+            if this.start < this.buffer.len() {
+                match this.decode_buffered() {
+                    Some(Ok(value)) => return Poll::Ready(Some(Ok(value))),
+                    Some(Err(e)) => {
+                        this.failed = true;
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                    None => (),
+                }
+            } else if this.eof {
+                return Poll::Ready(None);
+            }
+
+            let filled = this.buffer.len();
+            this.buffer.resize(filled + this.chunk_size, 0);
+            let read = Pin::new(&mut this.reader).poll_read(cx, &mut this.buffer[filled..]);
+            let read = match read {
+                Poll::Ready(Ok(n)) => n,
+                Poll::Ready(Err(e)) => {
+                    this.buffer.truncate(filled);
+                    this.failed = true;
+                    return Poll::Ready(Some(Err(Error::io(e))));
+                }
+                Poll::Pending => {
+                    this.buffer.truncate(filled);
+                    return Poll::Pending;
+                }
+            };
+            this.buffer.truncate(filled + read);
+            if read == 0 {
+                this.eof = true;
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.failed || (self.eof && self.start == self.buffer.len()) {
+            (0, Some(0))
+        } else {
+            (0, None)
+        }
+    }
+}
@@ -1,4 +1,6 @@
 //! Support for cbor tags
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::{string::String, vec::Vec};
 use core::fmt;
 use core::marker::PhantomData;
 use serde::de::{
@@ -36,6 +38,146 @@ impl<T: Serialize> Serialize for Tagged<T> {
     }
 }
 
+/// The encoding a byte string is expected to be converted to, as hinted by the tags 21 to 23.
+///
+/// These tags are used when CBOR data is converted to JSON, which has no byte strings. They can
+/// be attached to a value with [`tagged`](#method.tagged), the tag of a decoded value is available
+/// through `Value::Tag` and with [`DeserializerOptions::decode_expected_encodings`] the converted
+/// text can be decoded back into bytes.
+///
+/// [`DeserializerOptions::decode_expected_encodings`]: ../de/trait.DeserializerOptions.html#method.decode_expected_encodings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExpectedEncoding {
+    /// base64url without padding, tag 21.
+    Base64Url,
+    /// base64 with padding, tag 22.
+    Base64,
+    /// base16 with lower case letters, tag 23.
+    Base16,
+}
+
+impl ExpectedEncoding {
+    /// Returns the encoding hinted by `tag`, if it is one of the tags 21 to 23.
+    pub fn from_tag(tag: u64) -> Option<ExpectedEncoding> {
+        match tag {
+            21 => Some(ExpectedEncoding::Base64Url),
+            22 => Some(ExpectedEncoding::Base64),
+            23 => Some(ExpectedEncoding::Base16),
+            _ => None,
+        }
+    }
+
+    /// The tag hinting this encoding.
+    pub fn tag(self) -> u64 {
+        match self {
+            ExpectedEncoding::Base64Url => 21,
+            ExpectedEncoding::Base64 => 22,
+            ExpectedEncoding::Base16 => 23,
+        }
+    }
+
+    /// Wraps `value` so it is serialized with the tag hinting this encoding.
+    ///
+    /// The tag is only written if the `tags` feature is enabled.
+    pub fn tagged<T>(self, value: T) -> Tagged<T> {
+        Tagged::new(Some(self.tag()), value)
+    }
+
+    /// Converts `bytes` to text in this encoding.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn encode(self, bytes: &[u8]) -> String {
+        let mut text = String::new();
+        match self {
+            ExpectedEncoding::Base16 => {
+                for byte in bytes {
+                    text.push(HEX_DIGITS[usize::from(byte >> 4)] as char);
+                    text.push(HEX_DIGITS[usize::from(byte & 0xf)] as char);
+                }
+            }
+            ExpectedEncoding::Base64Url | ExpectedEncoding::Base64 => {
+                let alphabet = self.base64_alphabet();
+                for chunk in bytes.chunks(3) {
+                    let mut buf = [0; 3];
+                    buf[..chunk.len()].copy_from_slice(chunk);
+                    let group =
+                        u32::from(buf[0]) << 16 | u32::from(buf[1]) << 8 | u32::from(buf[2]);
+                    for i in 0..=chunk.len() {
+                        text.push(alphabet[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+                    }
+                    if self == ExpectedEncoding::Base64 {
+                        for _ in chunk.len()..3 {
+                            text.push('=');
+                        }
+                    }
+                }
+            }
+        }
+        text
+    }
+
+    /// Converts `text` in this encoding back to bytes.
+    ///
+    /// Padding is optional for both base64 variants and base16 accepts upper case letters.
+    /// Returns `None` if the text is not valid in this encoding.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn decode(self, text: &str) -> Option<Vec<u8>> {
+        let text = text.as_bytes();
+        match self {
+            ExpectedEncoding::Base16 => {
+                let pairs = text.chunks_exact(2);
+                if !pairs.remainder().is_empty() {
+                    return None;
+                }
+                pairs
+                    .map(|pair| {
+                        let high = (pair[0] as char).to_digit(16)?;
+                        let low = (pair[1] as char).to_digit(16)?;
+                        Some((high << 4 | low) as u8)
+                    })
+                    .collect()
+            }
+            ExpectedEncoding::Base64Url | ExpectedEncoding::Base64 => {
+                let alphabet = self.base64_alphabet();
+                let unpadded = match text.iter().position(|&c| c == b'=') {
+                    Some(end)
+                        if text.chunks_exact(4).remainder().is_empty()
+                            && text[end..].iter().all(|&c| c == b'=') =>
+                    {
+                        &text[..end]
+                    }
+                    Some(_) => return None,
+                    None => text,
+                };
+                if unpadded.len() % 4 == 1 {
+                    return None;
+                }
+                let mut bytes = Vec::with_capacity(unpadded.len() * 3 / 4);
+                for chunk in unpadded.chunks(4) {
+                    let mut group = 0u32;
+                    for (i, &c) in chunk.iter().enumerate() {
+                        let digit = alphabet.iter().position(|&a| a == c)? as u32;
+                        group |= digit << (18 - 6 * i);
+                    }
+                    bytes.extend_from_slice(&group.to_be_bytes()[1..chunk.len()]);
+                }
+                Some(bytes)
+            }
+        }
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn base64_alphabet(self) -> &'static [u8; 64] {
+        if self == ExpectedEncoding::Base64Url {
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_"
+        } else {
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/"
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
 fn untagged<T>(value: T) -> Tagged<T> {
     Tagged::new(None, value)
 }
@@ -187,6 +329,749 @@ impl<'de, T: serde::de::Deserialize<'de>> serde::de::Deserialize<'de> for Tagged
     }
 }
 
+macro_rules! tagged_text {
+    ($(#[$attr:meta])* $name:ident, $tag:expr, $kind:expr, $validate:path) => {
+        $(#[$attr])*
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(String);
+
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        impl $name {
+            #[doc = concat!("The tag written before ", $kind, ".")]
+            pub const TAG: u64 = $tag;
+
+            #[doc = concat!("Checks that `text` is ", $kind, " and wraps it.")]
+            pub fn new<S: Into<String>>(text: S) -> crate::Result<$name> {
+                let text = text.into();
+                if $validate(&text) {
+                    Ok($name(text))
+                } else {
+                    Err(crate::Error::message(format_args!(
+                        concat!("invalid ", $kind, ": {:?}"),
+                        text
+                    )))
+                }
+            }
+
+            /// The wrapped text.
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            /// Returns the wrapped text.
+            pub fn into_string(self) -> String {
+                self.0
+            }
+        }
+
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                Tagged::new(Some($tag), self.0.as_str()).serialize(s)
+            }
+        }
+
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let tagged = Tagged::<String>::deserialize(deserializer)?;
+                match tagged.tag {
+                    None | Some($tag) => {}
+                    Some(tag) => {
+                        return Err(serde::de::Error::custom(format_args!(
+                            concat!("expected tag {} for ", $kind, ", found tag {}"),
+                            $tag, tag
+                        )))
+                    }
+                }
+                $name::new(tagged.value).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+tagged_text!(
+    /// A URI, tag 32.
+    ///
+    /// The text has to start with a scheme and may not contain whitespace or control characters.
+    /// The tag is only written and checked if the `tags` feature is enabled, untagged text is
+    /// accepted as well.
+    ///
+    /// ```
+    /// use serde_cbor::tags::CborUri;
+    ///
+    /// let uri = CborUri::new("https://example.com/").unwrap();
+    /// let data = serde_cbor::to_vec(&uri).unwrap();
+    /// let decoded: CborUri = serde_cbor::from_slice(&data).unwrap();
+    /// assert_eq!(decoded, uri);
+    ///
+    /// assert!(CborUri::new("no scheme").is_err());
+    /// ```
+    CborUri,
+    32,
+    "a URI",
+    is_uri
+);
+
+tagged_text!(
+    /// A regular expression, tag 35.
+    ///
+    /// Only the structure of the expression is checked: parentheses and brackets have to be
+    /// balanced and it may not end in an unfinished escape. The tag is only written and checked
+    /// if the `tags` feature is enabled, untagged text is accepted as well.
+    CborRegex,
+    35,
+    "a regular expression",
+    is_regex
+);
+
+tagged_text!(
+    /// A MIME message, tag 36.
+    ///
+    /// The message has to start with a `Content-Type` header or with a media type like
+    /// `text/plain`. The tag is only written and checked if the `tags` feature is enabled,
+    /// untagged text is accepted as well.
+    CborMime,
+    36,
+    "a MIME message",
+    is_mime
+);
+
+#[cfg(feature = "url")]
+impl From<url::Url> for CborUri {
+    fn from(url: url::Url) -> CborUri {
+        CborUri(url.into())
+    }
+}
+
+#[cfg(feature = "url")]
+impl CborUri {
+    /// Parses the URI with the `url` crate.
+    pub fn to_url(&self) -> Result<url::Url, url::ParseError> {
+        url::Url::parse(&self.0)
+    }
+}
+
+/// The tag of a date and time in the text format of RFC 3339.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub(crate) const DATETIME_TAG: u64 = 0;
+
+/// The tag of a decimal fraction, an array of an exponent of 10 and a mantissa.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub(crate) const DECIMAL_TAG: u64 = 4;
+
+/// The tag of a finite set, an array without duplicate elements.
+#[cfg(any(feature = "std", feature = "alloc", feature = "tags"))]
+pub(crate) const SET_TAG: u64 = 258;
+
+/// The tag of a map whose keys are not all strings.
+#[cfg(any(feature = "std", feature = "alloc", feature = "tags"))]
+pub(crate) const MAP_TAG: u64 = 259;
+
+/// The tag of an IP or MAC address.
+#[cfg(feature = "std")]
+const NETWORK_ADDRESS_TAG: u64 = 260;
+
+/// The tag of an IP network, a map from the prefix length to the address.
+#[cfg(feature = "std")]
+const NETWORK_PREFIX_TAG: u64 = 261;
+
+/// Checks that an optional tag is the one `expected` for the `kind` of value.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn check_tag<E: serde::de::Error>(expected: u64, kind: &str, tag: Option<u64>) -> Result<(), E> {
+    match tag {
+        None => Ok(()),
+        Some(tag) if tag == expected => Ok(()),
+        Some(tag) => Err(E::custom(format_args!(
+            "expected tag {} for {}, found tag {}",
+            expected, kind, tag
+        ))),
+    }
+}
+
+/// Encodes a set as an array with the set tag 258.
+///
+/// Use it with `#[serde(with = "serde_cbor::tags::set")]` on `HashSet` or `BTreeSet` fields. The
+/// tag is only written and checked if the `tags` feature is enabled. When decoding, set types
+/// accept tagged arrays even without this module, and like them [`deserialize`](fn.deserialize.html)
+/// drops duplicate elements. [`deserialize_unique`](fn.deserialize_unique.html) rejects them
+/// instead.
+///
+/// ```
+/// use std::collections::BTreeSet;
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// struct Roles {
+///     #[serde(with = "serde_cbor::tags::set")]
+///     names: BTreeSet<String>,
+/// }
+///
+/// let roles = Roles { names: vec!["admin".to_string()].into_iter().collect() };
+/// let data = serde_cbor::to_vec(&roles).unwrap();
+/// let decoded: Roles = serde_cbor::from_slice(&data).unwrap();
+/// assert_eq!(decoded, roles);
+/// ```
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod set {
+    #[cfg(all(feature = "alloc", not(feature = "std")))]
+    use alloc::vec::Vec;
+    use core::iter::FromIterator;
+    use serde::de::{Deserialize, Deserializer, Error};
+    use serde::ser::{Serialize, Serializer};
+
+    use super::{check_tag, Tagged, SET_TAG};
+
+    /// Serializes `set` as an array tagged with 258.
+    pub fn serialize<T, S>(set: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        Tagged::new(Some(SET_TAG), set).serialize(serializer)
+    }
+
+    /// Deserializes an array that is either untagged or tagged with 258, dropping duplicates.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let tagged = Tagged::<T>::deserialize(deserializer)?;
+        check_tag(SET_TAG, "a set", tagged.tag)?;
+        Ok(tagged.value)
+    }
+
+    /// Deserializes an array that is either untagged or tagged with 258, rejecting duplicates.
+    pub fn deserialize_unique<'de, T, E, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromIterator<E>,
+        for<'a> &'a T: IntoIterator,
+        E: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let tagged = Tagged::<Vec<E>>::deserialize(deserializer)?;
+        check_tag(SET_TAG, "a set", tagged.tag)?;
+        let len = tagged.value.len();
+        let set = T::from_iter(tagged.value);
+        if (&set).into_iter().count() != len {
+            return Err(D::Error::custom("duplicate element in set"));
+        }
+        Ok(set)
+    }
+}
+
+/// Encodes a map with the tag 259, which marks maps with keys that are not strings.
+///
+/// Use it with `#[serde(with = "serde_cbor::tags::map")]`. The tag is only written and checked
+/// if the `tags` feature is enabled. When decoding, map types accept tagged maps even without
+/// this module.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod map {
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::{Serialize, Serializer};
+
+    use super::{check_tag, Tagged, MAP_TAG};
+
+    /// Serializes `map` tagged with 259.
+    pub fn serialize<T, S>(map: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        Tagged::new(Some(MAP_TAG), map).serialize(serializer)
+    }
+
+    /// Deserializes a map that is either untagged or tagged with 259.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let tagged = Tagged::<T>::deserialize(deserializer)?;
+        check_tag(MAP_TAG, "a map", tagged.tag)?;
+        Ok(tagged.value)
+    }
+}
+
+/// Encodes a date and time as a text string with the tag 0.
+///
+/// Use it with `#[serde(with = "serde_cbor::tags::datetime")]` on fields whose type serializes
+/// as an RFC 3339 string, like the `DateTime` of `chrono` or the `OffsetDateTime` of `time` with
+/// its `rfc3339` module. The text is passed through as it is. The tag is only written and
+/// checked if the `tags` feature is enabled.
+///
+/// ```
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// struct Login {
+///     #[serde(with = "serde_cbor::tags::datetime")]
+///     at: String,
+/// }
+///
+/// let login = Login { at: "2013-03-21T20:04:00Z".to_string() };
+/// let data = serde_cbor::to_vec(&login).unwrap();
+/// let decoded: Login = serde_cbor::from_slice(&data).unwrap();
+/// assert_eq!(decoded, login);
+/// ```
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod datetime {
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::{Serialize, Serializer};
+
+    use super::{check_tag, Tagged, DATETIME_TAG};
+
+    /// Serializes `value` tagged with 0.
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        Tagged::new(Some(DATETIME_TAG), value).serialize(serializer)
+    }
+
+    /// Deserializes a value that is either untagged or tagged with 0.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let tagged = Tagged::<T>::deserialize(deserializer)?;
+        check_tag(DATETIME_TAG, "a date and time", tagged.tag)?;
+        Ok(tagged.value)
+    }
+}
+
+/// Encodes a decimal number as a decimal fraction with the tag 4.
+///
+/// Use it with `#[serde(with = "serde_cbor::tags::decimal")]` on fields of a type that formats
+/// as and parses from decimal text, like the `Decimal` of `rust_decimal` or `BigDecimal`. The
+/// text `-1.50` is written as `4([-2, -150])`, the exponent of 10 and the mantissa, which has to
+/// fit into an `i128`. The tag is only written and checked if the `tags` feature is enabled.
+///
+/// Decoding accepts a decimal fraction, untagged or tagged with 4, and decimal text. Exponents
+/// beyond ±4096 are rejected, the value is passed to `FromStr` without exponent.
+///
+/// ```
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// struct Price {
+///     #[serde(with = "serde_cbor::tags::decimal")]
+///     amount: f64,
+/// }
+///
+/// let price = Price { amount: 12.5 };
+/// let data = serde_cbor::to_vec(&price).unwrap();
+/// assert!(data.ends_with(b"\x82\x20\x18\x7d"));
+/// let decoded: Price = serde_cbor::from_slice(&data).unwrap();
+/// assert_eq!(decoded, price);
+/// ```
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod decimal {
+    #[cfg(all(feature = "alloc", not(feature = "std")))]
+    use alloc::string::{String, ToString};
+    use core::fmt::{self, Display};
+    use core::str::FromStr;
+    use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+    use serde::ser::{self, Serialize, Serializer};
+
+    use super::{check_tag, Tagged, DECIMAL_TAG};
+
+    const MAX_EXPONENT: i64 = 4096;
+
+    /// Serializes `value` as a decimal fraction tagged with 4.
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+        S: Serializer,
+    {
+        let text = value.to_string();
+        match parse(&text) {
+            Some(fraction) => Tagged::new(Some(DECIMAL_TAG), fraction).serialize(serializer),
+            None => Err(ser::Error::custom(format_args!(
+                "{:?} is not a decimal number with a mantissa that fits into an i128",
+                text
+            ))),
+        }
+    }
+
+    /// Deserializes a decimal fraction that is either untagged or tagged with 4, or decimal text.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        let tagged = Tagged::<Repr>::deserialize(deserializer)?;
+        let text = match tagged.value {
+            Repr::Fraction(exponent, mantissa) => {
+                check_tag(DECIMAL_TAG, "a decimal fraction", tagged.tag)?;
+                format(exponent, mantissa)?
+            }
+            Repr::Text(text) if tagged.tag.is_none() => text,
+            Repr::Text(_) => return Err(de::Error::custom("expected a decimal fraction")),
+        };
+        text.parse().map_err(de::Error::custom)
+    }
+
+    /// Splits decimal text like `-1.5e3` into its exponent and mantissa.
+    fn parse(text: &str) -> Option<(i64, i128)> {
+        let (number, exponent) = match text.find(['e', 'E']) {
+            Some(i) => (&text[..i], text[i + 1..].parse::<i64>().ok()?),
+            None => (text, 0),
+        };
+        let (sign, digits) = match number.as_bytes().first()? {
+            b'-' => (-1, &number[1..]),
+            b'+' => (1, &number[1..]),
+            _ => (1, number),
+        };
+        let (integer, fraction) = match digits.find('.') {
+            Some(i) => (&digits[..i], &digits[i + 1..]),
+            None => (digits, ""),
+        };
+        if integer.is_empty() && fraction.is_empty() {
+            return None;
+        }
+        let mut mantissa: i128 = 0;
+        for c in integer.bytes().chain(fraction.bytes()) {
+            if !c.is_ascii_digit() {
+                return None;
+            }
+            mantissa = mantissa
+                .checked_mul(10)?
+                .checked_add(i128::from(c - b'0'))?;
+        }
+        let exponent = exponent.checked_sub(fraction.len() as i64)?;
+        Some((exponent, sign * mantissa))
+    }
+
+    /// Formats a decimal fraction as text without exponent.
+    fn format<E: de::Error>(exponent: i64, mantissa: i128) -> Result<String, E> {
+        if !(-MAX_EXPONENT..=MAX_EXPONENT).contains(&exponent) {
+            return Err(E::custom(format_args!(
+                "decimal exponent {} is out of range",
+                exponent
+            )));
+        }
+        let digits = mantissa.unsigned_abs().to_string();
+        let mut text = String::new();
+        if mantissa < 0 {
+            text.push('-');
+        }
+        if exponent >= 0 {
+            text.push_str(&digits);
+            text.extend((0..exponent).map(|_| '0'));
+        } else {
+            let scale = exponent.unsigned_abs() as usize;
+            if digits.len() > scale {
+                let (integer, fraction) = digits.split_at(digits.len() - scale);
+                text.push_str(integer);
+                text.push('.');
+                text.push_str(fraction);
+            } else {
+                text.push_str("0.");
+                text.extend((digits.len()..scale).map(|_| '0'));
+                text.push_str(&digits);
+            }
+        }
+        Ok(text)
+    }
+
+    enum Repr {
+        Fraction(i64, i128),
+        Text(String),
+    }
+
+    impl<'de> Deserialize<'de> for Repr {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Repr, D::Error> {
+            deserializer.deserialize_any(ReprVisitor)
+        }
+    }
+
+    struct ReprVisitor;
+
+    impl<'de> Visitor<'de> for ReprVisitor {
+        type Value = Repr;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a decimal fraction or decimal text")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Repr, E> {
+            Ok(Repr::Text(v.into()))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Repr, A::Error> {
+            let exponent = match seq.next_element()? {
+                Some(exponent) => exponent,
+                None => return Err(de::Error::invalid_length(0, &self)),
+            };
+            let mantissa = match seq.next_element()? {
+                Some(mantissa) => mantissa,
+                None => return Err(de::Error::invalid_length(1, &self)),
+            };
+            if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                return Err(de::Error::invalid_length(3, &self));
+            }
+            Ok(Repr::Fraction(exponent, mantissa))
+        }
+    }
+}
+
+/// Encodes an `IpAddr` as a byte string with the network address tag 260.
+///
+/// Use it with `#[serde(with = "serde_cbor::tags::network_address")]`. IPv4 addresses take 4
+/// bytes and IPv6 addresses 16. The tag is only written and checked if the `tags` feature is
+/// enabled.
+///
+/// ```
+/// use std::net::{IpAddr, Ipv4Addr};
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// struct Peer {
+///     #[serde(with = "serde_cbor::tags::network_address")]
+///     addr: IpAddr,
+/// }
+///
+/// let peer = Peer { addr: IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)) };
+/// let data = serde_cbor::to_vec(&peer).unwrap();
+/// let decoded: Peer = serde_cbor::from_slice(&data).unwrap();
+/// assert_eq!(decoded, peer);
+/// ```
+#[cfg(feature = "std")]
+pub mod network_address {
+    use std::net::IpAddr;
+
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::{Serialize, Serializer};
+
+    use super::{check_tag, AddressBytes, Tagged, NETWORK_ADDRESS_TAG};
+
+    /// Serializes `addr` as its bytes tagged with 260.
+    pub fn serialize<S: Serializer>(addr: &IpAddr, serializer: S) -> Result<S::Ok, S::Error> {
+        Tagged::new(Some(NETWORK_ADDRESS_TAG), AddressBytes(*addr)).serialize(serializer)
+    }
+
+    /// Deserializes the bytes of an address that are either untagged or tagged with 260.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<IpAddr, D::Error> {
+        let tagged = Tagged::<AddressBytes>::deserialize(deserializer)?;
+        check_tag(NETWORK_ADDRESS_TAG, "a network address", tagged.tag)?;
+        Ok(tagged.value.0)
+    }
+}
+
+/// Encodes an IP network `(IpAddr, u8)` as a map with the network prefix tag 261.
+///
+/// Use it with `#[serde(with = "serde_cbor::tags::network_prefix")]`. The map has a single entry
+/// from the prefix length to the bytes of the address. The tag is only written and checked if
+/// the `tags` feature is enabled.
+///
+/// ```
+/// use std::net::{IpAddr, Ipv4Addr};
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// struct Route {
+///     #[serde(with = "serde_cbor::tags::network_prefix")]
+///     network: (IpAddr, u8),
+/// }
+///
+/// let route = Route { network: (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)), 8) };
+/// let data = serde_cbor::to_vec(&route).unwrap();
+/// let decoded: Route = serde_cbor::from_slice(&data).unwrap();
+/// assert_eq!(decoded, route);
+/// ```
+#[cfg(feature = "std")]
+pub mod network_prefix {
+    use std::fmt;
+    use std::net::IpAddr;
+
+    use serde::de::{Deserialize, Deserializer, Error, MapAccess, Visitor};
+    use serde::ser::{Serialize, SerializeMap, Serializer};
+
+    use super::{check_tag, AddressBytes, Tagged, NETWORK_PREFIX_TAG};
+
+    struct Prefix(IpAddr, u8);
+
+    impl Serialize for Prefix {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut map = serializer.serialize_map(Some(1))?;
+            map.serialize_entry(&self.1, &AddressBytes(self.0))?;
+            map.end()
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Prefix {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Prefix, D::Error> {
+            deserializer.deserialize_map(PrefixVisitor)
+        }
+    }
+
+    struct PrefixVisitor;
+
+    impl<'de> Visitor<'de> for PrefixVisitor {
+        type Value = Prefix;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a map from a prefix length to an address")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Prefix, A::Error> {
+            let (len, addr) = match map.next_entry::<u8, AddressBytes>()? {
+                Some(entry) => entry,
+                None => return Err(A::Error::invalid_length(0, &self)),
+            };
+            if map.next_key::<serde::de::IgnoredAny>()?.is_some() {
+                return Err(A::Error::custom("network prefix has more than one entry"));
+            }
+            let max = if addr.0.is_ipv4() { 32 } else { 128 };
+            if len > max {
+                return Err(A::Error::custom(format_args!(
+                    "prefix length {} is longer than the address",
+                    len
+                )));
+            }
+            Ok(Prefix(addr.0, len))
+        }
+    }
+
+    /// Serializes the network as a map tagged with 261.
+    pub fn serialize<S: Serializer>(
+        network: &(IpAddr, u8),
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Tagged::new(Some(NETWORK_PREFIX_TAG), Prefix(network.0, network.1)).serialize(serializer)
+    }
+
+    /// Deserializes a network map that is either untagged or tagged with 261.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<(IpAddr, u8), D::Error> {
+        let tagged = Tagged::<Prefix>::deserialize(deserializer)?;
+        check_tag(NETWORK_PREFIX_TAG, "a network prefix", tagged.tag)?;
+        Ok((tagged.value.0, tagged.value.1))
+    }
+}
+
+/// The bytes of an IP address.
+#[cfg(feature = "std")]
+struct AddressBytes(std::net::IpAddr);
+
+#[cfg(feature = "std")]
+impl Serialize for AddressBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            std::net::IpAddr::V4(addr) => serializer.serialize_bytes(&addr.octets()),
+            std::net::IpAddr::V6(addr) => serializer.serialize_bytes(&addr.octets()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for AddressBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<AddressBytes, D::Error> {
+        deserializer.deserialize_bytes(AddressBytesVisitor)
+    }
+}
+
+#[cfg(feature = "std")]
+struct AddressBytesVisitor;
+
+#[cfg(feature = "std")]
+impl<'de> Visitor<'de> for AddressBytesVisitor {
+    type Value = AddressBytes;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("4 or 16 bytes of an IP address")
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, value: &[u8]) -> Result<AddressBytes, E> {
+        use std::convert::TryFrom;
+        if let Ok(octets) = <[u8; 4]>::try_from(value) {
+            Ok(AddressBytes(octets.into()))
+        } else if let Ok(octets) = <[u8; 16]>::try_from(value) {
+            Ok(AddressBytes(octets.into()))
+        } else {
+            Err(E::invalid_length(value.len(), &self))
+        }
+    }
+}
+
+/// Checks for a scheme followed by characters allowed in URIs.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn is_uri(text: &str) -> bool {
+    let scheme = match text.find(':') {
+        Some(end) => &text[..end],
+        None => return false,
+    };
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+        && !text.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+/// Checks that groups and classes are balanced and escapes are complete.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn is_regex(text: &str) -> bool {
+    let mut groups = 0usize;
+    let mut in_class = false;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.next().is_none() => return false,
+            '\\' => {}
+            ']' if in_class => in_class = false,
+            _ if in_class => {}
+            '[' => in_class = true,
+            '(' => groups += 1,
+            ')' if groups == 0 => return false,
+            ')' => groups -= 1,
+            _ => {}
+        }
+    }
+    groups == 0 && !in_class
+}
+
+/// Checks for a `Content-Type` header or a media type at the start of the message.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn is_mime(text: &str) -> bool {
+    let first_line = text.lines().next().unwrap_or("");
+    let media_type = if first_line.contains(':') {
+        let header = text
+            .lines()
+            .take_while(|line| !line.is_empty())
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-type"));
+        match header {
+            Some((_, value)) => value,
+            None => return false,
+        }
+    } else {
+        first_line
+    };
+    let media_type = media_type.split(';').next().unwrap_or("").trim();
+    let is_token = |s: &str| {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$&^_.+-".contains(c))
+    };
+    match media_type.split_once('/') {
+        Some((kind, subtype)) => is_token(kind) && is_token(subtype),
+        None => false,
+    }
+}
+
 /// function to get the current cbor tag
 ///
 /// The only place where it makes sense to call this function is within visit_newtype_struct of a serde visitor.
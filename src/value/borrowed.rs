@@ -0,0 +1,237 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de;
+use serde::ser::{self, SerializeMap};
+
+use crate::tags::Tagged;
+use crate::value::de::take_simple;
+use crate::value::Value;
+
+/// A `Value` whose byte and text strings borrow from the input they were decoded from.
+///
+/// Decoding a `Value` copies every string. A proxy that decodes a message, changes a few fields
+/// and encodes it again can use a `ValueRef` instead, which only copies the strings that are not
+/// contiguous in the input, like indefinite length strings of several chunks. Maps keep their
+/// entries in the order they were read. Numbers are written with the shortest width, like those of
+/// a `Value`.
+///
+/// ```
+/// use std::borrow::Cow;
+/// use serde_cbor::value::ValueRef;
+///
+/// // {"id": 7, "body": h'00010203'}
+/// let input = b"\xa2\x62id\x07\x64body\x44\x00\x01\x02\x03";
+/// let mut value: ValueRef = serde_cbor::from_slice(input).unwrap();
+/// if let ValueRef::Map(entries) = &mut value {
+///     assert!(matches!(entries[1].1, ValueRef::Bytes(Cow::Borrowed(_))));
+///     entries[0].1 = ValueRef::Integer(8);
+/// }
+/// let output = serde_cbor::to_vec(&value).unwrap();
+/// assert_eq!(output, b"\xa2\x62id\x08\x64body\x44\x00\x01\x02\x03");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueRef<'a> {
+    /// `null` or `undefined`.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// An integer.
+    Integer(i128),
+    /// A floating point number.
+    Float(f64),
+    /// A byte string.
+    Bytes(Cow<'a, [u8]>),
+    /// A text string.
+    Text(Cow<'a, str>),
+    /// An array.
+    Array(Vec<ValueRef<'a>>),
+    /// A map, with its entries in the order they were read.
+    Map(Vec<(ValueRef<'a>, ValueRef<'a>)>),
+    /// A tagged value.
+    Tag(u64, Box<ValueRef<'a>>),
+    /// A simple value other than `false`, `true`, `null` and `undefined`.
+    Simple(u8),
+}
+
+impl ValueRef<'_> {
+    /// Converts to a `Value`, copying the borrowed strings.
+    ///
+    /// A key that occurs more than once in a map keeps its last value.
+    pub fn into_owned(self) -> Value {
+        match self {
+            ValueRef::Null => Value::Null,
+            ValueRef::Bool(v) => Value::Bool(v),
+            ValueRef::Integer(v) => Value::Integer(v),
+            ValueRef::Float(v) => Value::Float(v),
+            ValueRef::Bytes(v) => Value::Bytes(v.into_owned()),
+            ValueRef::Text(v) => Value::Text(v.into_owned()),
+            ValueRef::Array(v) => Value::Array(v.into_iter().map(ValueRef::into_owned).collect()),
+            ValueRef::Map(v) => Value::Map(
+                v.into_iter()
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect::<BTreeMap<_, _>>(),
+            ),
+            ValueRef::Tag(tag, v) => Value::Tag(tag, Box::new(v.into_owned())),
+            ValueRef::Simple(v) => Value::Simple(v),
+        }
+    }
+}
+
+/// Borrows the strings of a `Value`. Sized numbers lose their width.
+impl<'a> From<&'a Value> for ValueRef<'a> {
+    fn from(value: &'a Value) -> ValueRef<'a> {
+        match *value {
+            Value::Null => ValueRef::Null,
+            Value::Bool(v) => ValueRef::Bool(v),
+            Value::Integer(v) | Value::SizedInteger(v, _) => ValueRef::Integer(v),
+            Value::Float(v) | Value::SizedFloat(v, _) => ValueRef::Float(v),
+            Value::Bytes(ref v) => ValueRef::Bytes(Cow::Borrowed(v)),
+            Value::Text(ref v) => ValueRef::Text(Cow::Borrowed(v)),
+            Value::Array(ref v) => ValueRef::Array(v.iter().map(ValueRef::from).collect()),
+            Value::Map(ref v) => ValueRef::Map(
+                v.iter()
+                    .map(|(k, v)| (ValueRef::from(k), ValueRef::from(v)))
+                    .collect(),
+            ),
+            Value::Tag(tag, ref v) => ValueRef::Tag(tag, Box::new(ValueRef::from(&**v))),
+            Value::Simple(v) => ValueRef::Simple(v),
+            Value::__Hidden => unreachable!(),
+        }
+    }
+}
+
+impl ser::Serialize for ValueRef<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        match *self {
+            ValueRef::Null => serializer.serialize_unit(),
+            ValueRef::Bool(v) => serializer.serialize_bool(v),
+            ValueRef::Integer(v) => serializer.serialize_i128(v),
+            ValueRef::Float(v) => serializer.serialize_f64(v),
+            ValueRef::Bytes(ref v) => serializer.serialize_bytes(v),
+            ValueRef::Text(ref v) => serializer.serialize_str(v),
+            ValueRef::Array(ref v) => v.serialize(serializer),
+            ValueRef::Map(ref v) => {
+                let mut map = serializer.serialize_map(Some(v.len()))?;
+                for (key, value) in v {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+            ValueRef::Tag(tag, ref v) => Tagged::new(Some(tag), v).serialize(serializer),
+            ValueRef::Simple(v) => Value::Simple(v).serialize(serializer),
+        }
+    }
+}
+
+impl<'de: 'a, 'a> de::Deserialize<'de> for ValueRef<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<ValueRef<'a>, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct ValueRefVisitor;
+
+        impl<'de> de::Visitor<'de> for ValueRefVisitor {
+            type Value = ValueRef<'de>;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt.write_str("any valid CBOR value")
+            }
+
+            fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+                Ok(ValueRef::Text(Cow::Borrowed(v)))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(ValueRef::Text(Cow::Owned(v.to_owned())))
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+                Ok(ValueRef::Text(Cow::Owned(v)))
+            }
+
+            fn visit_borrowed_bytes<E: de::Error>(self, v: &'de [u8]) -> Result<Self::Value, E> {
+                Ok(ValueRef::Bytes(Cow::Borrowed(v)))
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(ValueRef::Bytes(Cow::Owned(v.to_owned())))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(ValueRef::Bytes(Cow::Owned(v)))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                if let Some(v) = take_simple() {
+                    return Ok(ValueRef::Simple(v));
+                }
+                Ok(ValueRef::Integer(v.into()))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(ValueRef::Integer(v.into()))
+            }
+
+            fn visit_i128<E: de::Error>(self, v: i128) -> Result<Self::Value, E> {
+                Ok(ValueRef::Integer(v))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+                Ok(ValueRef::Float(v))
+            }
+
+            fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+                Ok(ValueRef::Bool(v))
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(ValueRef::Null)
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(ValueRef::Null)
+            }
+
+            fn visit_seq<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
+            where
+                V: de::SeqAccess<'de>,
+            {
+                let mut vec = Vec::new();
+                while let Some(elem) = visitor.next_element()? {
+                    vec.push(elem);
+                }
+                Ok(ValueRef::Array(vec))
+            }
+
+            fn visit_map<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
+            where
+                V: de::MapAccess<'de>,
+            {
+                let mut entries = Vec::new();
+                while let Some(entry) = visitor.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(ValueRef::Map(entries))
+            }
+
+            fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+            where
+                D: de::Deserializer<'de>,
+            {
+                let tag = crate::tags::get_tag();
+                let inner = deserializer.deserialize_any(self);
+                match tag {
+                    Some(tag) => inner.map(|v| ValueRef::Tag(tag, Box::new(v))),
+                    None => inner,
+                }
+            }
+        }
+
+        deserializer.deserialize_any(ValueRefVisitor)
+    }
+}
@@ -1,8 +1,11 @@
 use std::collections::BTreeMap;
 use std::fmt;
 
-use crate::value::Value;
+use crate::de::{CustomDeserializerOptions, Deserializer, DeserializerOptions, SliceRead};
+use crate::value::{FloatWidth, IntegerWidth, Value};
 use serde::de;
+#[cfg(feature = "std")]
+use std::cell::Cell;
 
 impl<'de> de::Deserialize<'de> for Value {
     #[inline]
@@ -55,7 +58,11 @@ impl<'de> de::Deserialize<'de> for Value {
             where
                 E: de::Error,
             {
-                Ok(Value::Integer(v.into()))
+                #[cfg(feature = "std")]
+                if let Some(v) = take_simple() {
+                    return Ok(Value::Simple(v));
+                }
+                Ok(integer(v.into()))
             }
 
             #[inline]
@@ -63,7 +70,7 @@ impl<'de> de::Deserialize<'de> for Value {
             where
                 E: de::Error,
             {
-                Ok(Value::Integer(v.into()))
+                Ok(integer(v.into()))
             }
 
             #[inline]
@@ -71,7 +78,7 @@ impl<'de> de::Deserialize<'de> for Value {
             where
                 E: de::Error,
             {
-                Ok(Value::Integer(v))
+                Ok(integer(v))
             }
 
             #[inline]
@@ -131,6 +138,10 @@ impl<'de> de::Deserialize<'de> for Value {
             where
                 E: de::Error,
             {
+                #[cfg(feature = "std")]
+                if let Some(width) = FLOAT_WIDTH.with(Cell::take) {
+                    return Ok(Value::SizedFloat(v, width));
+                }
                 Ok(Value::Float(v))
             }
 
@@ -151,16 +162,120 @@ impl<'de> de::Deserialize<'de> for Value {
     }
 }
 
+#[cfg(feature = "std")]
+thread_local!(static FLOAT_WIDTH: Cell<Option<FloatWidth>> = const { Cell::new(None) });
+
+#[cfg(feature = "std")]
+thread_local!(static INTEGER_WIDTH: Cell<Option<IntegerWidth>> = const { Cell::new(None) });
+
+#[cfg(feature = "std")]
+thread_local!(static SIMPLE_VALUE: Cell<Option<u8>> = const { Cell::new(None) });
+
+/// Returns the simple value being visited, if the `u64` being visited is one.
+#[cfg(feature = "std")]
+pub(crate) fn take_simple() -> Option<u8> {
+    SIMPLE_VALUE.with(Cell::take)
+}
+
+/// Visits the simple value `value` as its number, which `Value` decodes as `Value::Simple`.
+#[cfg(feature = "std")]
+pub(crate) fn visit_simple<'de, V, E>(visitor: V, value: u8) -> Result<V::Value, E>
+where
+    V: de::Visitor<'de>,
+    E: de::Error,
+{
+    SIMPLE_VALUE.with(|v| v.set(Some(value)));
+    let r = visitor.visit_u64(value.into());
+    SIMPLE_VALUE.with(|v| v.set(None));
+    r
+}
+
+fn integer(value: i128) -> Value {
+    #[cfg(feature = "std")]
+    if let Some(width) = INTEGER_WIDTH.with(Cell::take) {
+        return Value::SizedInteger(value, width);
+    }
+    Value::Integer(value)
+}
+
+/// Visits an integer that was written with `width`, which `Value` keeps if it is not the
+/// shortest.
+#[cfg(feature = "std")]
+pub(crate) fn visit_sized_integer<'de, V, E>(
+    visitor: V,
+    value: i128,
+    width: IntegerWidth,
+) -> Result<V::Value, E>
+where
+    V: de::Visitor<'de>,
+    E: de::Error,
+{
+    let visit = |visitor: V| {
+        if value >= 0 {
+            visitor.visit_u64(value as u64)
+        } else if value >= i128::from(i64::MIN) {
+            visitor.visit_i64(value as i64)
+        } else {
+            visitor.visit_i128(value)
+        }
+    };
+    if width == IntegerWidth::shortest(value) {
+        return visit(visitor);
+    }
+    INTEGER_WIDTH.with(|w| w.set(Some(width)));
+    let r = visit(visitor);
+    INTEGER_WIDTH.with(|w| w.set(None));
+    r
+}
+
+/// Visits a float that was written with `width`, which `Value` keeps if it is not the shortest.
+#[cfg(feature = "std")]
+pub(crate) fn visit_sized_float<'de, V, E>(
+    visitor: V,
+    value: f64,
+    width: FloatWidth,
+) -> Result<V::Value, E>
+where
+    V: de::Visitor<'de>,
+    E: de::Error,
+{
+    if width == FloatWidth::shortest(value) {
+        return visitor.visit_f64(value);
+    }
+    FLOAT_WIDTH.with(|w| w.set(Some(width)));
+    let r = visitor.visit_f64(value);
+    FLOAT_WIDTH.with(|w| w.set(None));
+    r
+}
+
 /// Convert a `serde_cbor::Value` into a type `T`
+///
+/// Enums are accepted in the standard format as well as in the legacy format used by
+/// `serde_cbor` versions <= v0.9, so values from archives written by either version can be
+/// decoded uniformly. Use `from_value_with_options` to restrict the accepted formats.
 #[allow(clippy::needless_pass_by_value)]
 pub fn from_value<T>(value: Value) -> Result<T, crate::error::Error>
 where
     T: de::DeserializeOwned,
+{
+    let options = CustomDeserializerOptions::new().set_accept_legacy_enums(true);
+    from_value_with_options(value, options)
+}
+
+/// Convert a `serde_cbor::Value` into a type `T` using the given deserializer options.
+#[allow(clippy::needless_pass_by_value)]
+pub fn from_value_with_options<T, O>(value: Value, options: O) -> Result<T, crate::error::Error>
+where
+    T: de::DeserializeOwned,
+    O: DeserializerOptions,
 {
     // TODO implement in a way that doesn't require
     // roundtrip through buffer (i.e. by implementing
     // `serde::de::Deserializer` for `Value` and then doing
     // `T::deserialize(value)`).
     let buf = crate::to_vec(&value)?;
-    crate::from_slice(buf.as_slice())
+    let mut deserializer = Deserializer::new_with_options(SliceRead::new(&buf), options);
+    let value = de::Deserialize::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
 }
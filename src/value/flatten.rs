@@ -0,0 +1,83 @@
+use serde::de::{self, DeserializeOwned};
+use serde::ser::{self, SerializeMap};
+
+use crate::value::unknown::{count_entries, write_entries};
+use crate::value::{from_value, Value, WithUnknown};
+
+/// Two structs sharing the entries of one map, like a field marked `#[serde(flatten)]`.
+///
+/// Serde buffers the entries of a map with flattened fields in its own content type, which does
+/// not know about byte strings, integer keys or tags. `Flatten` keeps the entries that `outer`
+/// does not use as CBOR values and decodes `inner` from them, so these distinctions survive.
+/// When serializing, the entries of `inner` are written after those of `outer`, both in their
+/// field order. As with `WithUnknown`, the fields are written with text keys even in the packed
+/// format, the values are encoded with the options of the serializer.
+///
+/// Both types have to be encoded as maps.
+///
+/// ```
+/// use serde_derive::{Deserialize, Serialize};
+/// use serde_cbor::value::{Flatten, Value};
+///
+/// #[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// struct Header {
+///     id: u32,
+/// }
+///
+/// #[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// struct Body {
+///     payload: Value,
+/// }
+///
+/// let message = Flatten {
+///     outer: Header { id: 1 },
+///     inner: Body { payload: Value::Bytes(vec![1, 2]) },
+/// };
+/// let data = serde_cbor::to_vec(&message).unwrap();
+/// // {"id": 1, "payload": h'0102'}
+/// assert_eq!(data, b"\xa2\x62id\x01\x67payload\x42\x01\x02");
+/// let decoded: Flatten<Header, Body> = serde_cbor::from_slice(&data).unwrap();
+/// assert_eq!(decoded, message);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Flatten<A, B> {
+    /// The struct that is offered the entries first.
+    pub outer: A,
+    /// The struct decoded from the entries that `outer` did not use.
+    pub inner: B,
+}
+
+impl<A, B> ser::Serialize for Flatten<A, B>
+where
+    A: ser::Serialize,
+    B: ser::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let len = count_entries(&self.outer, "Flatten")? + count_entries(&self.inner, "Flatten")?;
+        let mut map = serializer.serialize_map(Some(len))?;
+        write_entries(&self.outer, &mut map, "Flatten")?;
+        write_entries(&self.inner, &mut map, "Flatten")?;
+        map.end()
+    }
+}
+
+impl<'de, A, B> de::Deserialize<'de> for Flatten<A, B>
+where
+    A: de::Deserialize<'de>,
+    B: DeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Flatten<A, B>, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let outer = WithUnknown::<A>::deserialize(deserializer)?;
+        let inner = from_value(Value::Map(outer.unknown)).map_err(de::Error::custom)?;
+        Ok(Flatten {
+            outer: outer.value,
+            inner,
+        })
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::btree_map;
+use std::iter::{Enumerate, FromIterator, FusedIterator};
+use std::vec;
+
+use crate::value::Value;
+
+/// An owning iterator over the entries of a `Value`, created by `Value::into_iter`.
+///
+/// Map entries are yielded as `(key, value)` pairs and array elements as `(index, element)`
+/// pairs with an `Integer` index, so both containers are walked the same way. All other values
+/// yield nothing.
+///
+/// ```
+/// use serde_cbor::Value;
+///
+/// let value: Value = serde_cbor::from_slice(b"\xa2\x61a\x01\x61b\x02").unwrap();
+/// let doubled: Value = value
+///     .into_iter()
+///     .map(|(key, value)| match value {
+///         Value::Integer(n) => (key, Value::Integer(n * 2)),
+///         value => (key, value),
+///     })
+///     .collect();
+/// assert_eq!(serde_cbor::to_vec(&doubled).unwrap(), b"\xa2\x61a\x02\x61b\x04");
+///
+/// let array = Value::Array(vec![Value::Null, Value::Bool(true)]);
+/// let elements: Value = array.into_iter().map(|(_, element)| element).rev().collect();
+/// assert_eq!(elements, Value::Array(vec![Value::Bool(true), Value::Null]));
+/// ```
+#[derive(Debug)]
+pub struct IntoIter(Entries);
+
+#[derive(Debug)]
+enum Entries {
+    Array(Enumerate<vec::IntoIter<Value>>),
+    Map(btree_map::IntoIter<Value, Value>),
+    Empty,
+}
+
+impl Iterator for IntoIter {
+    type Item = (Value, Value);
+
+    fn next(&mut self) -> Option<(Value, Value)> {
+        match &mut self.0 {
+            Entries::Array(elements) => elements
+                .next()
+                .map(|(index, element)| (Value::Integer(index as i128), element)),
+            Entries::Map(entries) => entries.next(),
+            Entries::Empty => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            Entries::Array(elements) => elements.size_hint(),
+            Entries::Map(entries) => entries.size_hint(),
+            Entries::Empty => (0, Some(0)),
+        }
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    fn next_back(&mut self) -> Option<(Value, Value)> {
+        match &mut self.0 {
+            Entries::Array(elements) => elements
+                .next_back()
+                .map(|(index, element)| (Value::Integer(index as i128), element)),
+            Entries::Map(entries) => entries.next_back(),
+            Entries::Empty => None,
+        }
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl FusedIterator for IntoIter {}
+
+impl IntoIterator for Value {
+    type Item = (Value, Value);
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter(match self {
+            Value::Array(elements) => Entries::Array(elements.into_iter().enumerate()),
+            Value::Map(entries) => Entries::Map(entries.into_iter()),
+            _ => Entries::Empty,
+        })
+    }
+}
+
+/// Collects the values into a `Value::Array`.
+impl FromIterator<Value> for Value {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Value {
+        Value::Array(iter.into_iter().collect())
+    }
+}
+
+/// Collects the entries into a `Value::Map`, later entries replace earlier ones with the same key.
+impl FromIterator<(Value, Value)> for Value {
+    fn from_iter<I: IntoIterator<Item = (Value, Value)>>(iter: I) -> Value {
+        Value::Map(iter.into_iter().collect())
+    }
+}
+
+/// Appends the values to a `Value::Array`, a `Value::Null` becomes an empty array first.
+///
+/// # Panics
+///
+/// Panics if the value is neither an array nor `Null`.
+impl Extend<Value> for Value {
+    fn extend<I: IntoIterator<Item = Value>>(&mut self, iter: I) {
+        if let Value::Null = self {
+            *self = Value::Array(Vec::new());
+        }
+        match self {
+            Value::Array(elements) => elements.extend(iter),
+            _ => panic!(
+                "only an array can be extended with elements, not {:?}",
+                self
+            ),
+        }
+    }
+}
+
+/// Inserts the entries into a `Value::Map`, a `Value::Null` becomes an empty map first.
+///
+/// # Panics
+///
+/// Panics if the value is neither a map nor `Null`.
+impl Extend<(Value, Value)> for Value {
+    fn extend<I: IntoIterator<Item = (Value, Value)>>(&mut self, iter: I) {
+        if let Value::Null = self {
+            *self = Value::Map(Default::default());
+        }
+        match self {
+            Value::Map(entries) => entries.extend(iter),
+            _ => panic!("only a map can be extended with entries, not {:?}", self),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use serde_json::{Map, Number};
+
+use crate::error::{Error, Result};
+use crate::tags::ExpectedEncoding;
+use crate::value::Value;
+
+/// How byte strings are converted to JSON.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum JsonBytes {
+    /// A string in base64url without padding, as recommended by RFC 8949.
+    Base64Url,
+    /// An array of numbers.
+    Array,
+}
+
+/// How map keys that are not text strings are converted to JSON.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum JsonKeys {
+    /// The key is converted to JSON and its text is used, the integer 1 becomes the key `"1"`.
+    ///
+    /// Keys that become equal, like 1 and `"1"`, fail the conversion.
+    Stringify,
+    /// The conversion fails.
+    Error,
+}
+
+/// How integers that do not fit into an `i64` or `u64` are converted to JSON.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum JsonIntegers {
+    /// A string with the decimal digits of the integer.
+    String,
+    /// The nearest float, which loses precision.
+    LossyFloat,
+}
+
+/// Options for converting a `Value` to a `serde_json::Value`.
+///
+/// JSON has no byte strings, no tags and only text keys, and most implementations only read
+/// 64 bit integers. These options pick how such items are converted. Tags are dropped and their
+/// content is converted, `NaN` and infinite floats become `null` like in `serde_json`, and so do
+/// simple values.
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use serde_cbor::value::{JsonBytes, JsonOptions, Value};
+///
+/// let mut map = BTreeMap::new();
+/// map.insert(Value::Integer(1), Value::Bytes(vec![1, 2]));
+/// let value = Value::Map(map);
+///
+/// let json = value.to_json_with(JsonOptions::new()).unwrap();
+/// assert_eq!(json, serde_json::json!({"1": "AQI"}));
+///
+/// let options = JsonOptions::new().set_bytes(JsonBytes::Array);
+/// let json = value.to_json_with(options).unwrap();
+/// assert_eq!(json, serde_json::json!({"1": [1, 2]}));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct JsonOptions {
+    bytes: JsonBytes,
+    keys: JsonKeys,
+    integers: JsonIntegers,
+}
+
+impl Default for JsonOptions {
+    fn default() -> Self {
+        JsonOptions {
+            bytes: JsonBytes::Base64Url,
+            keys: JsonKeys::Stringify,
+            integers: JsonIntegers::String,
+        }
+    }
+}
+
+impl JsonOptions {
+    /// Base64url byte strings, stringified keys and large integers as strings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how byte strings are converted.
+    pub fn set_bytes(mut self, bytes: JsonBytes) -> Self {
+        self.bytes = bytes;
+        self
+    }
+
+    /// Sets how map keys that are not text strings are converted.
+    pub fn set_keys(mut self, keys: JsonKeys) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Sets how integers outside of the 64 bit range are converted.
+    pub fn set_integers(mut self, integers: JsonIntegers) -> Self {
+        self.integers = integers;
+        self
+    }
+}
+
+impl Value {
+    /// Converts the value to JSON as described by `options`.
+    pub fn to_json_with(&self, options: JsonOptions) -> Result<serde_json::Value> {
+        Ok(match self {
+            Value::Null | Value::Simple(_) => serde_json::Value::Null,
+            Value::Bool(b) => serde_json::Value::Bool(*b),
+            Value::Integer(i) | Value::SizedInteger(i, _) => {
+                if let Ok(i) = i64::try_from(*i) {
+                    i.into()
+                } else if let Ok(i) = u64::try_from(*i) {
+                    i.into()
+                } else {
+                    match options.integers {
+                        JsonIntegers::String => serde_json::Value::String(i.to_string()),
+                        JsonIntegers::LossyFloat => float(*i as f64),
+                    }
+                }
+            }
+            Value::Float(f) | Value::SizedFloat(f, _) => float(*f),
+            Value::Bytes(bytes) => match options.bytes {
+                JsonBytes::Base64Url => {
+                    serde_json::Value::String(ExpectedEncoding::Base64Url.encode(bytes))
+                }
+                JsonBytes::Array => bytes.iter().map(|&b| serde_json::Value::from(b)).collect(),
+            },
+            Value::Text(text) => serde_json::Value::String(text.clone()),
+            Value::Array(items) => serde_json::Value::Array(
+                items
+                    .iter()
+                    .map(|item| item.to_json_with(options))
+                    .collect::<Result<_>>()?,
+            ),
+            Value::Map(entries) => {
+                let mut map = Map::new();
+                for (key, value) in entries {
+                    let key = json_key(key, options)?;
+                    if map.contains_key(&key) {
+                        return Err(Error::message(format_args!(
+                            "map keys convert to the same JSON key {:?}",
+                            key
+                        )));
+                    }
+                    map.insert(key, value.to_json_with(options)?);
+                }
+                serde_json::Value::Object(map)
+            }
+            Value::Tag(_, value) => value.to_json_with(options)?,
+            Value::__Hidden => unreachable!(),
+        })
+    }
+}
+
+fn float(f: f64) -> serde_json::Value {
+    Number::from_f64(f).map_or(serde_json::Value::Null, serde_json::Value::Number)
+}
+
+fn json_key(key: &Value, options: JsonOptions) -> Result<String> {
+    match (key, options.keys) {
+        (Value::Text(text), _) => Ok(text.clone()),
+        (Value::Tag(_, key), _) => json_key(key, options),
+        (_, JsonKeys::Error) => Err(Error::message(format_args!(
+            "map key {:?} is not a string",
+            key
+        ))),
+        (_, JsonKeys::Stringify) => match key.to_json_with(options)? {
+            serde_json::Value::String(text) => Ok(text),
+            key => Ok(key.to_string()),
+        },
+    }
+}
+
+/// Converts a CBOR value with the default [`JsonOptions`](struct.JsonOptions.html).
+impl TryFrom<Value> for serde_json::Value {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<serde_json::Value> {
+        value.to_json_with(JsonOptions::default())
+    }
+}
+
+/// Converts JSON to CBOR, which can represent every JSON value.
+///
+/// Numbers become integers if they are integral and fit into 64 bits, floats otherwise. Only a
+/// number that is not even a float, which `serde_json` keeps with its `arbitrary_precision`
+/// feature, fails the conversion.
+impl TryFrom<serde_json::Value> for Value {
+    type Error = Error;
+
+    fn try_from(value: serde_json::Value) -> Result<Value> {
+        Ok(match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(b),
+            serde_json::Value::Number(n) => {
+                if let Some(i) = n.as_u64() {
+                    Value::Integer(i.into())
+                } else if let Some(i) = n.as_i64() {
+                    Value::Integer(i.into())
+                } else if let Some(f) = n.as_f64() {
+                    Value::Float(f)
+                } else {
+                    return Err(Error::message(format_args!(
+                        "JSON number {} is out of range",
+                        n
+                    )));
+                }
+            }
+            serde_json::Value::String(text) => Value::Text(text),
+            serde_json::Value::Array(items) => Value::Array(
+                items
+                    .into_iter()
+                    .map(Value::try_from)
+                    .collect::<Result<_>>()?,
+            ),
+            serde_json::Value::Object(entries) => Value::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| Ok((Value::Text(key), Value::try_from(value)?)))
+                    .collect::<Result<BTreeMap<_, _>>>()?,
+            ),
+        })
+    }
+}
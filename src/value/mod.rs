@@ -1,15 +1,39 @@
 //! CBOR values, keys and serialization routines.
 
+mod borrowed;
 mod de;
+mod flatten;
+mod iter;
+#[cfg(feature = "json")]
+mod json;
+mod number;
+mod redact;
 mod ser;
+mod unknown;
+mod untagged;
 
 use std::cmp::{Ord, Ordering, PartialOrd};
 use std::collections::BTreeMap;
 
+use half::f16;
+
+use crate::tags::ExpectedEncoding;
+
+pub use self::borrowed::ValueRef;
 #[doc(inline)]
-pub use self::de::from_value;
+pub use self::de::{from_value, from_value_with_options};
+#[cfg(feature = "std")]
+pub(crate) use self::de::{visit_simple, visit_sized_float, visit_sized_integer};
+pub use self::flatten::Flatten;
+pub use self::iter::IntoIter;
+#[cfg(feature = "json")]
+pub use self::json::{JsonBytes, JsonIntegers, JsonKeys, JsonOptions};
+pub use self::number::Number;
+pub use self::redact::{redact_slice, Redact, REDACTED};
 #[doc(inline)]
 pub use self::ser::to_value;
+pub use self::unknown::WithUnknown;
+pub use self::untagged::Untagged;
 
 /// The `Value` enum, a loosely typed way of representing any valid CBOR value.
 ///
@@ -32,8 +56,47 @@ pub enum Value {
     /// Values outside this range can't be serialized
     /// and will cause an error.
     Integer(i128),
+    /// An integer written with the given argument width instead of the shortest one.
+    ///
+    /// It is only decoded with the `preserve_integer_widths` deserializer option, so decoding and
+    /// encoding a `Value` gives back the original bytes. The value is written as an `Integer` if
+    /// it does not fit into the width.
+    ///
+    /// ```
+    /// use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead};
+    /// use serde_cbor::value::{IntegerWidth, Value};
+    ///
+    /// // -1 with an 8 byte argument
+    /// let input = b"\x3b\x00\x00\x00\x00\x00\x00\x00\x00";
+    /// let options = CustomDeserializerOptions::new().set_preserve_integer_widths(true);
+    /// let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    /// let value: Value = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(value, Value::SizedInteger(-1, IntegerWidth::Eight));
+    /// assert_eq!(serde_cbor::to_vec(&value).unwrap(), input);
+    /// ```
+    SizedInteger(i128, IntegerWidth),
     /// Represents a floating point value.
     Float(f64),
+    /// A floating point value written with the given width instead of the shortest one that
+    /// holds it exactly.
+    ///
+    /// It is only decoded with the `preserve_float_widths` deserializer option, so decoding and
+    /// encoding a `Value` gives back the original bytes. The payload of a `NaN` is not preserved.
+    /// The value is written as a `Float` if it does not fit into the width.
+    ///
+    /// ```
+    /// use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead};
+    /// use serde_cbor::value::{FloatWidth, Value};
+    ///
+    /// // 1.5 as a double
+    /// let input = b"\xfb\x3f\xf8\x00\x00\x00\x00\x00\x00";
+    /// let options = CustomDeserializerOptions::new().set_preserve_float_widths(true);
+    /// let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    /// let value: Value = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(value, Value::SizedFloat(1.5, FloatWidth::Double));
+    /// assert_eq!(serde_cbor::to_vec(&value).unwrap(), input);
+    /// ```
+    SizedFloat(f64, FloatWidth),
     /// Represents a byte string.
     Bytes(Vec<u8>),
     /// Represents an UTF-8 encoded string.
@@ -53,12 +116,105 @@ pub enum Value {
     Map(BTreeMap<Value, Value>),
     /// Represents a tagged value
     Tag(u64, Box<Value>),
+    /// A simple value other than `false`, `true`, `null` and `undefined`.
+    ///
+    /// It is only decoded with `SimpleValues::Number` or `SimpleValues::Map`, see
+    /// `DeserializerOptions::simple_values`. The values 20 to 23 are written as `false`, `true`,
+    /// `null` and `undefined`, 24 to 31 can't be written.
+    ///
+    /// ```
+    /// use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SimpleValues, SliceRead};
+    /// use serde_cbor::Value;
+    ///
+    /// // simple(99)
+    /// let input = b"\xf8\x63";
+    /// let options = CustomDeserializerOptions::new().set_simple_values(SimpleValues::Number);
+    /// let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    /// let value: Value = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(value, Value::Simple(99));
+    /// assert_eq!(serde_cbor::to_vec(&value).unwrap(), input);
+    /// ```
+    Simple(u8),
     // The hidden variant allows the enum to be extended
     // with variants for tags and simple values.
     #[doc(hidden)]
     __Hidden,
 }
 
+/// The width a float is encoded with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FloatWidth {
+    /// Half precision, 2 bytes.
+    Half,
+    /// Single precision, 4 bytes.
+    Single,
+    /// Double precision, 8 bytes.
+    Double,
+}
+
+impl FloatWidth {
+    /// The width of the float with the initial byte `byte`, which is 0xf9, 0xfa or 0xfb.
+    pub(crate) fn from_header(byte: u8) -> FloatWidth {
+        match byte {
+            0xf9 => FloatWidth::Half,
+            0xfa => FloatWidth::Single,
+            _ => FloatWidth::Double,
+        }
+    }
+
+    /// The width `value` is written with as a `Value::Float`.
+    #[allow(clippy::float_cmp)]
+    pub(crate) fn shortest(value: f64) -> FloatWidth {
+        if !value.is_finite() || f16::from_f64(value).to_f64() == value {
+            FloatWidth::Half
+        } else if f64::from(value as f32) == value {
+            FloatWidth::Single
+        } else {
+            FloatWidth::Double
+        }
+    }
+}
+
+/// The width of the argument an integer is encoded with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IntegerWidth {
+    /// The value is stored in the initial byte, which holds values up to 23.
+    Inline,
+    /// A 1 byte argument.
+    One,
+    /// A 2 byte argument.
+    Two,
+    /// A 4 byte argument.
+    Four,
+    /// An 8 byte argument.
+    Eight,
+}
+
+impl IntegerWidth {
+    /// The width of the integer with the initial byte `byte`.
+    pub(crate) fn from_header(byte: u8) -> IntegerWidth {
+        match byte & 0x1f {
+            0..=23 => IntegerWidth::Inline,
+            24 => IntegerWidth::One,
+            25 => IntegerWidth::Two,
+            26 => IntegerWidth::Four,
+            _ => IntegerWidth::Eight,
+        }
+    }
+
+    /// The width `value` is written with as a `Value::Integer`.
+    pub(crate) fn shortest(value: i128) -> IntegerWidth {
+        let argument = if value < 0 { -1 - value } else { value };
+        match argument {
+            0..=23 => IntegerWidth::Inline,
+            24..=0xff => IntegerWidth::One,
+            0x100..=0xffff => IntegerWidth::Two,
+            0x1_0000..=0xffff_ffff => IntegerWidth::Four,
+            _ => IntegerWidth::Eight,
+        }
+    }
+}
+
 impl PartialEq for Value {
     fn eq(&self, other: &Value) -> bool {
         self.cmp(other) == Ordering::Equal
@@ -93,11 +249,8 @@ impl Ord for Value {
             (Map(a), Map(b)) if a.len() != b.len() => a.len().cmp(&b.len()),
             (Bytes(a), Bytes(b)) => a.cmp(b),
             (Text(a), Text(b)) => a.cmp(b),
-            (a, b) => {
-                let a = crate::to_vec(a).expect("self is serializable");
-                let b = crate::to_vec(b).expect("other is serializable");
-                a.cmp(&b)
-            }
+            (Simple(a), Simple(b)) => a.cmp(b),
+            (a, b) => a.encoded().cmp(&b.encoded()),
         }
     }
 }
@@ -132,12 +285,39 @@ impl_from!(Value::Array, Vec<Value>);
 impl_from!(Value::Map, BTreeMap<Value, Value>);
 
 impl Value {
+    /// Returns the encoding hinted by a tag 21 to 23 and the tagged value.
+    ///
+    /// ```
+    /// use serde_cbor::tags::ExpectedEncoding;
+    /// use serde_cbor::Value;
+    ///
+    /// let value = Value::Tag(22, Box::new(Value::Bytes(vec![0xff])));
+    /// let (encoding, bytes) = value.expected_encoding().unwrap();
+    /// assert_eq!(encoding, ExpectedEncoding::Base64);
+    /// assert_eq!(*bytes, Value::Bytes(vec![0xff]));
+    /// ```
+    pub fn expected_encoding(&self) -> Option<(ExpectedEncoding, &Value)> {
+        match self {
+            Value::Tag(tag, value) => Some((ExpectedEncoding::from_tag(*tag)?, value)),
+            _ => None,
+        }
+    }
+
+    /// The encoding of the value, which is compared when nothing else tells values apart.
+    fn encoded(&self) -> Vec<u8> {
+        match *self {
+            // can't be written, but still sort where they would be
+            Value::Simple(v @ 24..=31) => vec![0xf8, v],
+            _ => crate::to_vec(self).expect("value is serializable"),
+        }
+    }
+
     fn major_type(&self) -> u8 {
         use self::Value::*;
         match self {
             Null => 7,
             Bool(_) => 7,
-            Integer(v) => {
+            Integer(v) | SizedInteger(v, _) => {
                 if *v >= 0 {
                     0
                 } else {
@@ -145,7 +325,7 @@ impl Value {
                 }
             }
             Tag(_, _) => 6,
-            Float(_) => 7,
+            Float(_) | SizedFloat(_, _) | Simple(_) => 7,
             Bytes(_) => 2,
             Text(_) => 3,
             Array(_) => 4,
@@ -0,0 +1,116 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::value::Value;
+
+/// An integer or floating point number held by a `Value`, see `Value::as_number`.
+///
+/// Integers keep the full CBOR range of -2^64 to 2^64 - 1, the conversions check that the number
+/// fits into the target type instead of truncating it.
+///
+/// ```
+/// use serde_cbor::Value;
+///
+/// let value: Value = serde_cbor::from_slice(b"\x3b\xff\xff\xff\xff\xff\xff\xff\xff").unwrap();
+/// let number = value.as_number().unwrap();
+/// assert_eq!(number.as_i128(), Some(-(1 << 64)));
+/// assert_eq!(number.as_i64_checked(), None);
+/// assert_eq!(number.as_f64_lossy(), -18446744073709551616.0);
+///
+/// let number = Value::Integer(7).as_number().unwrap();
+/// assert_eq!((number.as_i64_checked(), number.as_u64_checked()), (Some(7), Some(7)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Number(N);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum N {
+    Integer(i128),
+    Float(f64),
+}
+
+impl Number {
+    /// Returns a number holding the float `value`.
+    pub fn from_f64(value: f64) -> Number {
+        Number(N::Float(value))
+    }
+
+    /// Returns true if the number is an integer.
+    pub fn is_integer(&self) -> bool {
+        matches!(self.0, N::Integer(_))
+    }
+
+    /// Returns true if the number is a float, even one without a fractional part.
+    pub fn is_float(&self) -> bool {
+        matches!(self.0, N::Float(_))
+    }
+
+    /// Returns the integer if the number is one.
+    pub fn as_i128(&self) -> Option<i128> {
+        match self.0 {
+            N::Integer(v) => Some(v),
+            N::Float(_) => None,
+        }
+    }
+
+    /// Returns the integer if the number is one that fits into an `i64`.
+    pub fn as_i64_checked(&self) -> Option<i64> {
+        self.as_i128().and_then(|v| i64::try_from(v).ok())
+    }
+
+    /// Returns the integer if the number is one that fits into a `u64`.
+    pub fn as_u64_checked(&self) -> Option<u64> {
+        self.as_i128().and_then(|v| u64::try_from(v).ok())
+    }
+
+    /// Returns the number as a float, rounding integers beyond 2^53 to the nearest float.
+    pub fn as_f64_lossy(&self) -> f64 {
+        match self.0 {
+            N::Integer(v) => v as f64,
+            N::Float(v) => v,
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            N::Integer(v) => fmt::Display::fmt(&v, f),
+            N::Float(v) => fmt::Display::fmt(&v, f),
+        }
+    }
+}
+
+macro_rules! impl_from_integer {
+    ($($for_type:ty),*) => {
+        $(
+            impl From<$for_type> for Number {
+                fn from(v: $for_type) -> Number {
+                    Number(N::Integer(v.into()))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_integer!(i8, i16, i32, i64, u8, u16, u32, u64);
+
+impl From<Number> for Value {
+    fn from(number: Number) -> Value {
+        match number.0 {
+            N::Integer(v) => Value::Integer(v),
+            N::Float(v) => Value::Float(v),
+        }
+    }
+}
+
+impl Value {
+    /// Returns the number if the value is an integer or a float, of any width.
+    pub fn as_number(&self) -> Option<Number> {
+        match *self {
+            Value::Integer(v) | Value::SizedInteger(v, _) => Some(Number(N::Integer(v))),
+            Value::Float(v) | Value::SizedFloat(v, _) => Some(Number(N::Float(v))),
+            _ => None,
+        }
+    }
+}
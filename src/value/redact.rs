@@ -0,0 +1,265 @@
+use std::fmt::{Display, Write as _};
+use std::io;
+
+use crate::de::RECURSION_LIMIT;
+use crate::document::{header_at, item_end, Entries};
+use crate::error::{Error, ErrorCode, Result};
+use crate::header::MajorType;
+use crate::value::Value;
+
+/// The text string that replaces redacted items.
+pub const REDACTED: &str = "[redacted]";
+
+/// `REDACTED` encoded as a text string.
+const REDACTED_ITEM: &[u8] = b"\x6a[redacted]";
+
+/// Selects the items replaced by `Value::redact` and `redact_slice`.
+///
+/// Items are addressed by `/` separated paths like in `DocumentIndex`: a path segment is the text
+/// key of a map entry, its unsigned integer key in decimal, or the index of an array element. The
+/// path of the whole value is the empty string, and tags are not part of a path. Entries of maps
+/// with other keys, or with text keys containing `/`, can't be addressed and are kept as they
+/// are.
+///
+/// A list of paths redacts the items at these paths, where a `*` segment matches any key or
+/// index. A closure is asked about every addressable item and redacts it by returning true, the
+/// items inside a redacted item are not visited.
+pub trait Redact {
+    /// Returns true if the item at `path` is replaced.
+    fn redacts(&mut self, path: &str) -> bool;
+}
+
+impl<F: FnMut(&str) -> bool> Redact for F {
+    fn redacts(&mut self, path: &str) -> bool {
+        self(path)
+    }
+}
+
+impl Redact for &[&str] {
+    fn redacts(&mut self, path: &str) -> bool {
+        self.iter().any(|pattern| path_matches(pattern, path))
+    }
+}
+
+impl<const N: usize> Redact for [&str; N] {
+    fn redacts(&mut self, path: &str) -> bool {
+        self.iter().any(|pattern| path_matches(pattern, path))
+    }
+}
+
+fn path_matches(pattern: &str, path: &str) -> bool {
+    if path.is_empty() || pattern.is_empty() {
+        return path == pattern;
+    }
+    let mut segments = path.split('/');
+    for expected in pattern.split('/') {
+        match segments.next() {
+            Some(segment) if expected == "*" || expected == segment => {}
+            _ => return false,
+        }
+    }
+    segments.next().is_none()
+}
+
+/// Appends the segment of an entry to `path`.
+fn push_segment(path: &mut String, segment: impl Display) {
+    if !path.is_empty() {
+        path.push('/');
+    }
+    write!(path, "{}", segment).expect("writing to a string");
+}
+
+impl Value {
+    /// Returns a copy of the value with the items selected by `redact` replaced by the text
+    /// `REDACTED`, for example to log a payload holding secrets.
+    ///
+    /// See [`Redact`](trait.Redact.html) for how items are selected.
+    ///
+    /// ```
+    /// use serde_cbor::Value;
+    ///
+    /// // {"user": "ann", "token": "secret", "keys": [{"id": 1, "private": h'00'}]}
+    /// let input = b"\xa3\x64user\x63ann\x65token\x66secret\
+    ///               \x64keys\x81\xa2\x62id\x01\x67private\x41\x00";
+    /// let value: Value = serde_cbor::from_slice(input).unwrap();
+    /// let redacted = value.redact(["token", "keys/*/private"]);
+    ///
+    /// let expected: Value = serde_cbor::from_slice(b"\xa3\x64user\x63ann\x65token\x6a[redacted]\
+    ///     \x64keys\x81\xa2\x62id\x01\x67private\x6a[redacted]").unwrap();
+    /// assert_eq!(redacted, expected);
+    ///
+    /// // a closure can decide on anything it finds in the path
+    /// let redacted = value.redact(|path: &str| path == "token" || path.ends_with("/private"));
+    /// assert_eq!(redacted, expected);
+    /// ```
+    pub fn redact<R: Redact>(&self, mut redact: R) -> Value {
+        redact_value(self, &mut String::new(), &mut redact)
+    }
+}
+
+fn redact_value<R: Redact>(value: &Value, path: &mut String, redact: &mut R) -> Value {
+    if redact.redacts(path) {
+        return Value::Text(REDACTED.into());
+    }
+    copy_value(value, path, redact)
+}
+
+fn copy_value<R: Redact>(value: &Value, path: &mut String, redact: &mut R) -> Value {
+    let len = path.len();
+    match value {
+        Value::Tag(tag, value) => Value::Tag(*tag, Box::new(copy_value(value, path, redact))),
+        Value::Array(elements) => {
+            let mut redacted = Vec::with_capacity(elements.len());
+            for (i, element) in elements.iter().enumerate() {
+                push_segment(path, i);
+                redacted.push(redact_value(element, path, redact));
+                path.truncate(len);
+            }
+            Value::Array(redacted)
+        }
+        Value::Map(entries) => {
+            let mut redacted = entries.clone();
+            for (key, entry) in redacted.iter_mut() {
+                match key {
+                    Value::Text(key) if !key.contains('/') => push_segment(path, key),
+                    Value::Integer(key) | Value::SizedInteger(key, _) if *key >= 0 => {
+                        push_segment(path, key)
+                    }
+                    _ => continue,
+                }
+                *entry = redact_value(entry, path, redact);
+                path.truncate(len);
+            }
+            Value::Map(redacted)
+        }
+        value => value.clone(),
+    }
+}
+
+/// Copies the CBOR item in `input` to `writer`, replacing the items selected by `redact` with
+/// the text `REDACTED`.
+///
+/// This is the same as decoding the input into a `Value`, calling `Value::redact` and encoding
+/// the result, but no values are built: everything that is kept is copied byte by byte, so the
+/// encoding of the input is preserved, and redacted items are only skipped over. The input has
+/// to hold exactly one item.
+///
+/// ```
+/// use serde_cbor::value::redact_slice;
+///
+/// // {1: "alice", 2: [h'cafe', h'f00d']}
+/// let input = b"\xa2\x01\x65alice\x02\x82\x42\xca\xfe\x42\xf0\x0d";
+/// let mut log = Vec::new();
+/// redact_slice(input, &mut log, ["2/*"]).unwrap();
+/// assert_eq!(log, b"\xa2\x01\x65alice\x02\x82\x6a[redacted]\x6a[redacted]");
+/// ```
+pub fn redact_slice<W, R>(input: &[u8], writer: W, redact: R) -> Result<()>
+where
+    W: io::Write,
+    R: Redact,
+{
+    let mut redactor = Redactor {
+        input,
+        writer,
+        redact,
+        path: String::new(),
+    };
+    let end = redactor.item(0, RECURSION_LIMIT)?;
+    if end < input.len() {
+        return Err(Error::syntax(ErrorCode::TrailingData, end as u64));
+    }
+    Ok(())
+}
+
+struct Redactor<'a, W, R> {
+    input: &'a [u8],
+    writer: W,
+    redact: R,
+    path: String,
+}
+
+impl<W: io::Write, R: Redact> Redactor<'_, W, R> {
+    /// Writes the item at `start` or its replacement, returns its end.
+    fn item(&mut self, start: usize, limit: u8) -> Result<usize> {
+        if self.redact.redacts(&self.path) {
+            let end = item_end(self.input, start, limit)?;
+            self.write(REDACTED_ITEM)?;
+            return Ok(end);
+        }
+        self.copy(start, limit)
+    }
+
+    fn copy(&mut self, start: usize, limit: u8) -> Result<usize> {
+        if limit == 0 {
+            return Err(Error::syntax(
+                ErrorCode::RecursionLimitExceeded,
+                start as u64,
+            ));
+        }
+        let header = header_at(self.input, start)?;
+        let contents = start + header.size;
+        match header.major {
+            MajorType::Tag => {
+                self.write_range(start, contents)?;
+                self.copy(contents, limit - 1)
+            }
+            MajorType::Array | MajorType::Map => {
+                self.write_range(start, contents)?;
+                let mut entries = Entries {
+                    input: self.input,
+                    pos: contents,
+                    remaining: header.argument,
+                };
+                let len = self.path.len();
+                let mut i = 0;
+                while let Some(entry) = entries.next()? {
+                    let value = if header.major == MajorType::Array {
+                        push_segment(&mut self.path, i);
+                        i += 1;
+                        entry
+                    } else {
+                        let value = item_end(self.input, entry, limit - 1)?;
+                        self.write_range(entry, value)?;
+                        let key = header_at(self.input, entry)?;
+                        let key_bytes = &self.input[entry + key.size..value];
+                        match (key.major, key.argument, std::str::from_utf8(key_bytes)) {
+                            (MajorType::Text, Some(_), Ok(key)) if !key.contains('/') => {
+                                push_segment(&mut self.path, key)
+                            }
+                            (MajorType::Unsigned, Some(key), _) => {
+                                push_segment(&mut self.path, key)
+                            }
+                            _ => {
+                                entries.pos = item_end(self.input, value, limit - 1)?;
+                                self.write_range(value, entries.pos)?;
+                                continue;
+                            }
+                        }
+                        value
+                    };
+                    entries.pos = self.item(value, limit - 1)?;
+                    self.path.truncate(len);
+                }
+                if header.argument.is_none() {
+                    self.write(&[0xff])?;
+                    return Ok(entries.pos + 1);
+                }
+                Ok(entries.pos)
+            }
+            _ => {
+                let end = item_end(self.input, start, limit)?;
+                self.write_range(start, end)?;
+                Ok(end)
+            }
+        }
+    }
+
+    fn write_range(&mut self, start: usize, end: usize) -> Result<()> {
+        let bytes = &self.input[start..end];
+        self.write(bytes)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.writer.write_all(bytes).map_err(Error::io)
+    }
+}
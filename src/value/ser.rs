@@ -7,12 +7,15 @@
 // except according to those terms.
 
 use std::collections::BTreeMap;
+use std::convert::TryFrom;
 
+use crate::de::{CustomDeserializerOptions, Deserializer, SliceRead};
 use crate::error::Error;
-use serde::{self, Serialize};
+use serde::{self, Deserialize, Serialize};
 
+use crate::ser::{PRE_ENCODED_NAME, SIMPLE_VALUE_NAME, SIZED_FLOAT_NAMES, SIZED_INTEGER_NAMES};
 use crate::tags::Tagged;
-use crate::value::Value;
+use crate::value::{FloatWidth, IntegerWidth, Value};
 
 impl serde::Serialize for Value {
     #[inline]
@@ -22,14 +25,21 @@ impl serde::Serialize for Value {
     {
         match *self {
             Value::Integer(v) => serializer.serialize_i128(v),
+            Value::SizedInteger(v, width) => {
+                serializer.serialize_newtype_struct(SIZED_INTEGER_NAMES[width as usize].0, &v)
+            }
             Value::Bytes(ref v) => serializer.serialize_bytes(&v),
             Value::Text(ref v) => serializer.serialize_str(&v),
             Value::Array(ref v) => v.serialize(serializer),
             Value::Map(ref v) => v.serialize(serializer),
             Value::Tag(tag, ref v) => Tagged::new(Some(tag), v).serialize(serializer),
             Value::Float(v) => serializer.serialize_f64(v),
+            Value::SizedFloat(v, width) => {
+                serializer.serialize_newtype_struct(SIZED_FLOAT_NAMES[width as usize].0, &v)
+            }
             Value::Bool(v) => serializer.serialize_bool(v),
             Value::Null => serializer.serialize_unit(),
+            Value::Simple(v) => serializer.serialize_newtype_struct(SIMPLE_VALUE_NAME, &v),
             Value::__Hidden => unreachable!(),
         }
     }
@@ -147,13 +157,43 @@ impl serde::Serializer for Serializer {
     #[inline]
     fn serialize_newtype_struct<T: ?Sized>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<Value, Error>
     where
         T: Serialize,
     {
-        value.serialize(self)
+        let value = value.serialize(self)?;
+        if name == PRE_ENCODED_NAME {
+            if let Value::Bytes(bytes) = value {
+                // keeps the widths of numbers, so the item is encoded the same way again
+                let options = CustomDeserializerOptions::new()
+                    .set_preserve_float_widths(true)
+                    .set_preserve_integer_widths(true);
+                let mut deserializer =
+                    Deserializer::new_with_options(SliceRead::new(&bytes), options);
+                let value = Deserialize::deserialize(&mut deserializer)?;
+                deserializer.end()?;
+                return Ok(value);
+            }
+        }
+        let float_width = SIZED_FLOAT_NAMES.iter().position(|&(n, _)| n == name);
+        let integer_width = SIZED_INTEGER_NAMES.iter().position(|&(n, _)| n == name);
+        Ok(match (value, float_width, integer_width) {
+            (Value::Float(v), Some(0), _) => Value::SizedFloat(v, FloatWidth::Half),
+            (Value::Float(v), Some(1), _) => Value::SizedFloat(v, FloatWidth::Single),
+            (Value::Float(v), Some(2), _) => Value::SizedFloat(v, FloatWidth::Double),
+            (Value::Integer(v), _, Some(0)) => Value::SizedInteger(v, IntegerWidth::Inline),
+            (Value::Integer(v), _, Some(1)) => Value::SizedInteger(v, IntegerWidth::One),
+            (Value::Integer(v), _, Some(2)) => Value::SizedInteger(v, IntegerWidth::Two),
+            (Value::Integer(v), _, Some(3)) => Value::SizedInteger(v, IntegerWidth::Four),
+            (Value::Integer(v), _, Some(4)) => Value::SizedInteger(v, IntegerWidth::Eight),
+            (Value::Integer(v), _, _) if name == SIMPLE_VALUE_NAME => match u8::try_from(v) {
+                Ok(v) => Value::Simple(v),
+                Err(_) => return Err(Error::message("invalid simple value")),
+            },
+            (value, _, _) => value,
+        })
     }
 
     fn serialize_newtype_variant<T: ?Sized>(
@@ -0,0 +1,474 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, IntoDeserializer};
+use serde::ser::{self, SerializeMap};
+
+use crate::value::Value;
+
+/// A struct together with the map entries it did not understand.
+///
+/// When deserializing, every map entry that the inner type ignores is captured into `unknown`
+/// instead of being dropped. When serializing, the fields of the inner type are written in their
+/// order, followed by the captured entries with their original keys. This allows proxies to pass
+/// through fields added by newer versions of a format.
+///
+/// The fields of the inner type are always written with text keys, also by a serializer using the
+/// packed format, as they are passed to it as map entries. Nested structs are packed as usual.
+///
+/// The inner type has to be deserialized from a map, this is the case for structs with named
+/// fields and for packed structs. Entries whose keys are not text, byte strings or unsigned
+/// integers are always captured.
+///
+/// ```
+/// use serde_derive::{Deserialize, Serialize};
+/// use serde_cbor::value::{Value, WithUnknown};
+///
+/// #[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// struct Version1 {
+///     id: u32,
+/// }
+///
+/// // {"id": 1, "name": "x"}
+/// let data = b"\xa2\x62id\x01\x64name\x61x";
+/// let value: WithUnknown<Version1> = serde_cbor::from_slice(data).unwrap();
+/// assert_eq!(value.value, Version1 { id: 1 });
+/// assert_eq!(
+///     value.unknown.get(&Value::Text("name".to_owned())),
+///     Some(&Value::Text("x".to_owned()))
+/// );
+/// assert_eq!(serde_cbor::to_vec(&value).unwrap(), &data[..]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WithUnknown<T> {
+    /// The decoded value.
+    pub value: T,
+    /// The map entries that were not consumed by `value`.
+    pub unknown: BTreeMap<Value, Value>,
+}
+
+impl<T> WithUnknown<T> {
+    /// Wraps a value without any unknown entries.
+    pub fn new(value: T) -> WithUnknown<T> {
+        WithUnknown {
+            value,
+            unknown: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> ser::Serialize for WithUnknown<T>
+where
+    T: ser::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        // the fields are counted first, so the map is written with a definite length
+        let len = count_entries(&self.value, "WithUnknown")?;
+        let mut map = serializer.serialize_map(Some(len + self.unknown.len()))?;
+        write_entries(&self.value, &mut map, "WithUnknown")?;
+        for (key, value) in &self.unknown {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+/// Counts the entries of a struct or map without serializing them.
+pub(super) fn count_entries<T, E>(value: &T, wrapper: &'static str) -> Result<usize, E>
+where
+    T: ?Sized + ser::Serialize,
+    E: ser::Error,
+{
+    let mut count = Count(0, PhantomData);
+    value.serialize(Entries {
+        sink: &mut count,
+        wrapper,
+    })?;
+    Ok(count.0)
+}
+
+/// Writes the entries of a struct or map into a map that is being serialized, in their order.
+pub(super) fn write_entries<T, M>(
+    value: &T,
+    map: &mut M,
+    wrapper: &'static str,
+) -> Result<(), M::Error>
+where
+    T: ?Sized + ser::Serialize,
+    M: SerializeMap,
+{
+    value.serialize(Entries { sink: map, wrapper })
+}
+
+/// Receives the entries of the wrapped type of a `WithUnknown` or `Flatten`.
+trait EntrySink {
+    type Error: ser::Error;
+
+    fn key<K: ?Sized + ser::Serialize>(&mut self, key: &K) -> Result<(), Self::Error>;
+
+    fn value<V: ?Sized + ser::Serialize>(&mut self, value: &V) -> Result<(), Self::Error>;
+}
+
+impl<M: SerializeMap> EntrySink for M {
+    type Error = M::Error;
+
+    fn key<K: ?Sized + ser::Serialize>(&mut self, key: &K) -> Result<(), M::Error> {
+        self.serialize_key(key)
+    }
+
+    fn value<V: ?Sized + ser::Serialize>(&mut self, value: &V) -> Result<(), M::Error> {
+        self.serialize_value(value)
+    }
+}
+
+/// Counts the entries without serializing them.
+struct Count<E>(usize, PhantomData<E>);
+
+impl<E: ser::Error> EntrySink for Count<E> {
+    type Error = E;
+
+    fn key<K: ?Sized + ser::Serialize>(&mut self, _key: &K) -> Result<(), E> {
+        self.0 += 1;
+        Ok(())
+    }
+
+    fn value<V: ?Sized + ser::Serialize>(&mut self, _value: &V) -> Result<(), E> {
+        Ok(())
+    }
+}
+
+/// Serializes a struct or map by passing its entries to a sink, other types are rejected.
+struct Entries<'s, S> {
+    sink: &'s mut S,
+    wrapper: &'static str,
+}
+
+impl<'s, S: EntrySink> Entries<'s, S> {
+    fn not_a_map(&self) -> S::Error {
+        ser::Error::custom(format_args!("{} can only wrap maps", self.wrapper))
+    }
+}
+
+macro_rules! reject_entries {
+    ($($method:ident ( $($arg:ident : $ty:ty),* ) -> $ret:ty;)*) => {
+        $(
+            fn $method(self, $($arg: $ty),*) -> Result<$ret, S::Error> {
+                let _ = ($($arg,)*);
+                Err(self.not_a_map())
+            }
+        )*
+    };
+}
+
+impl<'s, S: EntrySink> ser::Serializer for Entries<'s, S> {
+    type Ok = ();
+    type Error = S::Error;
+    type SerializeSeq = ser::Impossible<(), S::Error>;
+    type SerializeTuple = ser::Impossible<(), S::Error>;
+    type SerializeTupleStruct = ser::Impossible<(), S::Error>;
+    type SerializeTupleVariant = ser::Impossible<(), S::Error>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = ser::Impossible<(), S::Error>;
+
+    reject_entries! {
+        serialize_bool(v: bool) -> ();
+        serialize_i8(v: i8) -> ();
+        serialize_i16(v: i16) -> ();
+        serialize_i32(v: i32) -> ();
+        serialize_i64(v: i64) -> ();
+        serialize_u8(v: u8) -> ();
+        serialize_u16(v: u16) -> ();
+        serialize_u32(v: u32) -> ();
+        serialize_u64(v: u64) -> ();
+        serialize_f32(v: f32) -> ();
+        serialize_f64(v: f64) -> ();
+        serialize_char(v: char) -> ();
+        serialize_str(v: &str) -> ();
+        serialize_bytes(v: &[u8]) -> ();
+        serialize_none() -> ();
+        serialize_unit() -> ();
+        serialize_unit_struct(name: &'static str) -> ();
+        serialize_unit_variant(name: &'static str, index: u32, variant: &'static str) -> ();
+        serialize_seq(len: Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(len: usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(name: &'static str, len: usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(
+            name: &'static str,
+            index: u32,
+            variant: &'static str,
+            len: usize
+        ) -> Self::SerializeTupleVariant;
+        serialize_struct_variant(
+            name: &'static str,
+            index: u32,
+            variant: &'static str,
+            len: usize
+        ) -> Self::SerializeStructVariant;
+    }
+
+    fn serialize_some<T: ?Sized + ser::Serialize>(self, value: &T) -> Result<(), S::Error> {
+        let _ = value;
+        Err(self.not_a_map())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + ser::Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), S::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + ser::Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), S::Error> {
+        let _ = value;
+        Err(self.not_a_map())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self, S::Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, S::Error> {
+        Ok(self)
+    }
+}
+
+impl<'s, S: EntrySink> ser::SerializeMap for Entries<'s, S> {
+    type Ok = ();
+    type Error = S::Error;
+
+    fn serialize_key<T: ?Sized + ser::Serialize>(&mut self, key: &T) -> Result<(), S::Error> {
+        self.sink.key(key)
+    }
+
+    fn serialize_value<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<(), S::Error> {
+        self.sink.value(value)
+    }
+
+    fn end(self) -> Result<(), S::Error> {
+        Ok(())
+    }
+}
+
+impl<'s, S: EntrySink> ser::SerializeStruct for Entries<'s, S> {
+    type Ok = ();
+    type Error = S::Error;
+
+    fn serialize_field<T: ?Sized + ser::Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), S::Error> {
+        self.sink.key(key)?;
+        self.sink.value(value)
+    }
+
+    fn end(self) -> Result<(), S::Error> {
+        Ok(())
+    }
+}
+
+impl<'de, T> de::Deserialize<'de> for WithUnknown<T>
+where
+    T: de::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<WithUnknown<T>, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct WithUnknownVisitor<T>(PhantomData<T>);
+
+        impl<'de, T> de::Visitor<'de> for WithUnknownVisitor<T>
+        where
+            T: de::Deserialize<'de>,
+        {
+            type Value = WithUnknown<T>;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt.write_str("a map")
+            }
+
+            fn visit_map<A>(self, access: A) -> Result<WithUnknown<T>, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                let mut unknown = BTreeMap::new();
+                let value = T::deserialize(de::value::MapAccessDeserializer::new(CaptureMap {
+                    access,
+                    unknown: &mut unknown,
+                    key: None,
+                }))?;
+                Ok(WithUnknown { value, unknown })
+            }
+        }
+
+        deserializer.deserialize_map(WithUnknownVisitor(PhantomData))
+    }
+}
+
+/// Offers the entries of a map to the inner type and captures those it ignores.
+struct CaptureMap<'u, A> {
+    access: A,
+    unknown: &'u mut BTreeMap<Value, Value>,
+    key: Option<Value>,
+}
+
+impl<'de, 'u, A> de::MapAccess<'de> for CaptureMap<'u, A>
+where
+    A: de::MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        loop {
+            let key = match self.access.next_key::<Value>()? {
+                Some(key) => key,
+                None => return Ok(None),
+            };
+            // Derived types only match text, byte string and unsigned integer keys, all other entries
+            // are captured right away.
+            let offered = match key {
+                Value::Text(ref s) => seed.deserialize(s.as_str().into_deserializer()),
+                Value::Bytes(ref b) => {
+                    seed.deserialize(de::value::BytesDeserializer::new(b.as_slice()))
+                }
+                Value::Integer(i) if i >= 0 && i <= u64::MAX as i128 => {
+                    seed.deserialize((i as u64).into_deserializer())
+                }
+                _ => {
+                    let value = self.access.next_value()?;
+                    self.unknown.insert(key, value);
+                    continue;
+                }
+            };
+            self.key = Some(key);
+            return offered.map(Some);
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, A::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let mut ignored = None;
+        let value = self.access.next_value_seed(CaptureSeed {
+            seed,
+            ignored: &mut ignored,
+        })?;
+        if let (Some(key), Some(ignored)) = (self.key.take(), ignored) {
+            self.unknown.insert(key, ignored);
+        }
+        Ok(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.access.size_hint()
+    }
+}
+
+struct CaptureSeed<'i, S> {
+    seed: S,
+    ignored: &'i mut Option<Value>,
+}
+
+impl<'de, 'i, S> de::DeserializeSeed<'de> for CaptureSeed<'i, S>
+where
+    S: de::DeserializeSeed<'de>,
+{
+    type Value = S::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<S::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.seed.deserialize(Capture {
+            de: deserializer,
+            ignored: self.ignored,
+        })
+    }
+}
+
+/// Forwards to the wrapped deserializer, but keeps values that are deserialized as ignored.
+struct Capture<'i, D> {
+    de: D,
+    ignored: &'i mut Option<Value>,
+}
+
+macro_rules! forward_capture {
+    ($($method:ident ( $($arg:ident : $ty:ty),* ))*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, D::Error>
+            where
+                V: de::Visitor<'de>,
+            {
+                self.de.$method($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, 'i, D> de::Deserializer<'de> for Capture<'i, D>
+where
+    D: de::Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    forward_capture! {
+        deserialize_any()
+        deserialize_bool()
+        deserialize_i8()
+        deserialize_i16()
+        deserialize_i32()
+        deserialize_i64()
+        deserialize_i128()
+        deserialize_u8()
+        deserialize_u16()
+        deserialize_u32()
+        deserialize_u64()
+        deserialize_u128()
+        deserialize_f32()
+        deserialize_f64()
+        deserialize_char()
+        deserialize_str()
+        deserialize_string()
+        deserialize_bytes()
+        deserialize_byte_buf()
+        deserialize_option()
+        deserialize_unit()
+        deserialize_unit_struct(name: &'static str)
+        deserialize_newtype_struct(name: &'static str)
+        deserialize_seq()
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_map()
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        deserialize_identifier()
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, D::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.ignored = Some(de::Deserialize::deserialize(self.de)?);
+        visitor.visit_unit()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.de.is_human_readable()
+    }
+}
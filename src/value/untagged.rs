@@ -0,0 +1,118 @@
+use std::fmt::Write;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::{Error, Result};
+use crate::value::{from_value, Value};
+
+/// Tries to decode a captured value as each arm of an untagged enum in turn.
+///
+/// `#[serde(untagged)]` reports a single "did not match any variant" message when every arm
+/// fails. Implementing `Deserialize` with this helper instead keeps the error of every arm. The
+/// item is captured into a `Value` first, so it can be decoded several times even when it was
+/// read from a reader.
+///
+/// ```
+/// use serde::de::{self, Deserialize, Deserializer};
+/// use serde_derive::Deserialize;
+/// use serde_cbor::value::{Untagged, Value};
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// struct Circle {
+///     radius: u32,
+/// }
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// struct Square {
+///     side: u32,
+/// }
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Shape {
+///     Circle(Circle),
+///     Square(Square),
+/// }
+///
+/// impl<'de> Deserialize<'de> for Shape {
+///     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+///         let value = Value::deserialize(deserializer)?;
+///         Untagged::new("Shape", value)
+///             .arm("Circle", Shape::Circle)
+///             .arm("Square", Shape::Square)
+///             .finish()
+///             .map_err(de::Error::custom)
+///     }
+/// }
+///
+/// // {"side": 2}
+/// let shape: Shape = serde_cbor::from_reader(&b"\xa1\x64side\x02"[..]).unwrap();
+/// assert_eq!(shape, Shape::Square(Square { side: 2 }));
+///
+/// // {"width": 2}
+/// let err = serde_cbor::from_slice::<Shape>(b"\xa1\x65width\x02").unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "data did not match any variant of untagged enum Shape \
+///      (Circle: missing field `radius`; Square: missing field `side`)"
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Untagged<T> {
+    name: &'static str,
+    value: Value,
+    result: Option<T>,
+    errors: Vec<(&'static str, Error)>,
+}
+
+impl<T> Untagged<T> {
+    /// Starts matching `value` against the arms of the enum `name`.
+    pub fn new(name: &'static str, value: Value) -> Untagged<T> {
+        Untagged {
+            name,
+            value,
+            result: None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Tries to decode the value as `A` and converts it with `f` on success.
+    ///
+    /// Arms are tried in order, once an arm matched the remaining ones are skipped.
+    pub fn arm<A, F>(mut self, arm: &'static str, f: F) -> Untagged<T>
+    where
+        A: DeserializeOwned,
+        F: FnOnce(A) -> T,
+    {
+        if self.result.is_none() {
+            match from_value(self.value.clone()) {
+                Ok(value) => self.result = Some(f(value)),
+                Err(e) => self.errors.push((arm, e)),
+            }
+        }
+        self
+    }
+
+    /// The arms that have been tried without success together with their errors.
+    pub fn errors(&self) -> &[(&'static str, Error)] {
+        &self.errors
+    }
+
+    /// Returns the value of the first matching arm, or an error listing why every arm failed.
+    pub fn finish(self) -> Result<T> {
+        if let Some(result) = self.result {
+            return Ok(result);
+        }
+        let mut msg = format!(
+            "data did not match any variant of untagged enum {}",
+            self.name
+        );
+        for (i, (arm, error)) in self.errors.iter().enumerate() {
+            let separator = if i == 0 { " (" } else { "; " };
+            let _ = write!(msg, "{}{}: {}", separator, arm, error);
+        }
+        if !self.errors.is_empty() {
+            msg.push(')');
+        }
+        Err(Error::message(msg))
+    }
+}
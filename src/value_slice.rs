@@ -0,0 +1,452 @@
+//! Dynamically inspect CBOR values without allocating.
+//!
+//! `Value` needs an allocator to represent arbitrary documents. This module provides an
+//! alternative for `no_std` targets without `alloc`: a value is decoded into caller-provided
+//! storage as a flat list of [`Node`]s in pre-order, where every array, map and tag is followed by
+//! its contents. Strings and byte strings borrow from the input.
+//!
+//! ```
+//! use serde_cbor::value_slice::{Node, ValueBuf};
+//!
+//! // {"id": 7, "tags": ["a", "b"]}
+//! let input = b"\xa2\x62id\x07\x64tags\x82\x61a\x61b";
+//! let buf: ValueBuf<'_, 8> = ValueBuf::from_slice(input).unwrap();
+//! let value = buf.value();
+//! assert_eq!(value.get_key("id").unwrap().node(), Node::Integer(7));
+//! let tags = value.get_key("tags").unwrap();
+//! assert_eq!(tags.get(1).unwrap().as_text(), Some("b"));
+//! ```
+
+use core::fmt;
+
+use serde::de;
+
+use crate::de::{Deserializer, Read};
+use crate::error::{Error, Result};
+use crate::read::Offset;
+
+/// A single decoded item.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Node<'a> {
+    /// `null` or `undefined`.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// An integer.
+    Integer(i128),
+    /// A floating point number.
+    Float(f64),
+    /// A byte string.
+    Bytes(&'a [u8]),
+    /// A text string.
+    Text(&'a str),
+    /// The start of an array, its elements follow.
+    Array {
+        /// The number of elements.
+        len: usize,
+        /// The number of nodes making up the elements.
+        descendants: usize,
+    },
+    /// The start of a map, its keys and values follow alternately.
+    Map {
+        /// The number of entries.
+        len: usize,
+        /// The number of nodes making up the keys and values.
+        descendants: usize,
+    },
+    /// A tag, the tagged value follows.
+    Tag(u64),
+}
+
+/// Decodes a value from a slice into `storage`.
+///
+/// Strings have to be contiguous in the input to be borrowed, indefinite length strings are
+/// rejected. Use `from_mut_slice` to decode them. Fails with an error for which
+/// `is_scratch_too_small` returns true if the value has more nodes than `storage` can hold, its
+/// `scratch_needed` is the number of nodes of the whole value.
+pub fn from_slice<'s, 'a>(
+    input: &'a [u8],
+    storage: &'s mut [Node<'a>],
+) -> Result<ValueSlice<'s, 'a>> {
+    let mut deserializer = Deserializer::from_slice_with_scratch(input, &mut []);
+    decode(&mut deserializer, storage)
+}
+
+/// Decodes a value from a mutable slice into `storage`.
+///
+/// Indefinite length strings are joined in place, like `serde_cbor::de::from_mut_slice` does.
+pub fn from_mut_slice<'s, 'a>(
+    input: &'a mut [u8],
+    storage: &'s mut [Node<'a>],
+) -> Result<ValueSlice<'s, 'a>> {
+    let mut deserializer = Deserializer::from_mut_slice(input);
+    decode(&mut deserializer, storage)
+}
+
+fn decode<'s, 'a, R>(
+    deserializer: &mut Deserializer<R>,
+    storage: &'s mut [Node<'a>],
+) -> Result<ValueSlice<'s, 'a>>
+where
+    R: Read<'a> + Offset,
+{
+    let mut builder = Builder {
+        nodes: storage,
+        len: 0,
+    };
+    de::DeserializeSeed::deserialize(NodeSeed(&mut builder), &mut *deserializer)?;
+    if builder.len > builder.nodes.len() {
+        return Err(Error::scratch_overflow(
+            builder.len,
+            builder.nodes.len(),
+            deserializer.byte_offset() as u64,
+        ));
+    }
+    deserializer.end()?;
+    let len = builder.len;
+    Ok(ValueSlice {
+        nodes: &builder.nodes[..len],
+    })
+}
+
+/// A value decoded into fixed-capacity storage of `N` nodes.
+#[derive(Clone, Debug)]
+pub struct ValueBuf<'a, const N: usize> {
+    nodes: [Node<'a>; N],
+    len: usize,
+}
+
+impl<'a, const N: usize> ValueBuf<'a, N> {
+    /// Decodes a value from a slice, see [`from_slice`].
+    pub fn from_slice(input: &'a [u8]) -> Result<ValueBuf<'a, N>> {
+        let mut nodes = [Node::Null; N];
+        let len = from_slice(input, &mut nodes)?.nodes.len();
+        Ok(ValueBuf { nodes, len })
+    }
+
+    /// Decodes a value from a mutable slice, see [`from_mut_slice`].
+    pub fn from_mut_slice(input: &'a mut [u8]) -> Result<ValueBuf<'a, N>> {
+        let mut nodes = [Node::Null; N];
+        let len = from_mut_slice(input, &mut nodes)?.nodes.len();
+        Ok(ValueBuf { nodes, len })
+    }
+
+    /// The decoded value.
+    pub fn value(&self) -> ValueSlice<'_, 'a> {
+        ValueSlice {
+            nodes: &self.nodes[..self.len],
+        }
+    }
+}
+
+/// A decoded value, backed by a list of nodes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ValueSlice<'s, 'a> {
+    nodes: &'s [Node<'a>],
+}
+
+impl<'s, 'a> ValueSlice<'s, 'a> {
+    /// The node at the root of this value.
+    pub fn node(&self) -> Node<'a> {
+        self.nodes[0]
+    }
+
+    /// All nodes of this value in pre-order.
+    pub fn nodes(&self) -> &'s [Node<'a>] {
+        self.nodes
+    }
+
+    /// Returns true if the value is `null` or `undefined`.
+    pub fn is_null(&self) -> bool {
+        self.node() == Node::Null
+    }
+
+    /// Returns the boolean if the value is one.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.node() {
+            Node::Bool(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer if the value is one.
+    pub fn as_integer(&self) -> Option<i128> {
+        match self.node() {
+            Node::Integer(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the floating point number if the value is one.
+    pub fn as_float(&self) -> Option<f64> {
+        match self.node() {
+            Node::Float(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the byte string if the value is one.
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        match self.node() {
+            Node::Bytes(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the text string if the value is one.
+    pub fn as_text(&self) -> Option<&'a str> {
+        match self.node() {
+            Node::Text(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the tag and the tagged value if the value is tagged.
+    pub fn as_tagged(&self) -> Option<(u64, ValueSlice<'s, 'a>)> {
+        match self.node() {
+            Node::Tag(tag) => Some((
+                tag,
+                ValueSlice {
+                    nodes: &self.nodes[1..],
+                },
+            )),
+            _ => None,
+        }
+    }
+
+    /// The number of elements of an array or entries of a map.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> Option<usize> {
+        match self.node() {
+            Node::Array { len, .. } | Node::Map { len, .. } => Some(len),
+            _ => None,
+        }
+    }
+
+    /// Returns the element at `index` if the value is an array.
+    pub fn get(&self, index: usize) -> Option<ValueSlice<'s, 'a>> {
+        self.elements()?.nth(index)
+    }
+
+    /// Returns the value for the text key `key` if the value is a map.
+    pub fn get_key(&self, key: &str) -> Option<ValueSlice<'s, 'a>> {
+        self.entries()?
+            .find(|(k, _)| k.as_text() == Some(key))
+            .map(|(_, v)| v)
+    }
+
+    /// Iterates over the elements of an array.
+    pub fn elements(&self) -> Option<Elements<'s, 'a>> {
+        match self.node() {
+            Node::Array { len, .. } => Some(Elements {
+                nodes: &self.nodes[1..],
+                remaining: len,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Iterates over the entries of a map.
+    pub fn entries(&self) -> Option<Entries<'s, 'a>> {
+        match self.node() {
+            Node::Map { len, .. } => Some(Entries {
+                elements: Elements {
+                    nodes: &self.nodes[1..],
+                    remaining: len * 2,
+                },
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The number of nodes making up the value starting at `nodes[0]`.
+fn value_len(nodes: &[Node<'_>]) -> usize {
+    let mut len = 0;
+    loop {
+        match nodes[len] {
+            Node::Array { descendants, .. } | Node::Map { descendants, .. } => {
+                return len + 1 + descendants
+            }
+            Node::Tag(_) => len += 1,
+            _ => return len + 1,
+        }
+    }
+}
+
+/// An iterator over the elements of an array.
+#[derive(Clone, Debug)]
+pub struct Elements<'s, 'a> {
+    nodes: &'s [Node<'a>],
+    remaining: usize,
+}
+
+impl<'s, 'a> Iterator for Elements<'s, 'a> {
+    type Item = ValueSlice<'s, 'a>;
+
+    fn next(&mut self) -> Option<ValueSlice<'s, 'a>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let (value, rest) = self.nodes.split_at(value_len(self.nodes));
+        self.nodes = rest;
+        Some(ValueSlice { nodes: value })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// An iterator over the entries of a map.
+#[derive(Clone, Debug)]
+pub struct Entries<'s, 'a> {
+    elements: Elements<'s, 'a>,
+}
+
+impl<'s, 'a> Iterator for Entries<'s, 'a> {
+    type Item = (ValueSlice<'s, 'a>, ValueSlice<'s, 'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.elements.next()?;
+        let value = self.elements.next()?;
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.elements.remaining / 2;
+        (len, Some(len))
+    }
+}
+
+/// Stores the nodes while they fit, and keeps counting them once the storage is full.
+struct Builder<'s, 'a> {
+    nodes: &'s mut [Node<'a>],
+    len: usize,
+}
+
+impl<'s, 'a> Builder<'s, 'a> {
+    fn push(&mut self, node: Node<'a>) -> usize {
+        self.set(self.len, node);
+        self.len += 1;
+        self.len - 1
+    }
+
+    fn set(&mut self, index: usize, node: Node<'a>) {
+        if let Some(slot) = self.nodes.get_mut(index) {
+            *slot = node;
+        }
+    }
+}
+
+struct NodeSeed<'b, 's, 'a>(&'b mut Builder<'s, 'a>);
+
+impl<'b, 's, 'a> de::DeserializeSeed<'a> for NodeSeed<'b, 's, 'a> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> core::result::Result<(), D::Error>
+    where
+        D: de::Deserializer<'a>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'b, 's, 'a> de::Visitor<'a> for NodeSeed<'b, 's, 'a> {
+    type Value = ();
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("any valid CBOR value with borrowed strings")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> core::result::Result<(), E> {
+        self.0.push(Node::Bool(v));
+        Ok(())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> core::result::Result<(), E> {
+        self.0.push(Node::Integer(v.into()));
+        Ok(())
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> core::result::Result<(), E> {
+        self.0.push(Node::Integer(v));
+        Ok(())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> core::result::Result<(), E> {
+        self.0.push(Node::Integer(v.into()));
+        Ok(())
+    }
+
+    fn visit_f32<E: de::Error>(self, v: f32) -> core::result::Result<(), E> {
+        self.0.push(Node::Float(v.into()));
+        Ok(())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> core::result::Result<(), E> {
+        self.0.push(Node::Float(v));
+        Ok(())
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'a str) -> core::result::Result<(), E> {
+        self.0.push(Node::Text(v));
+        Ok(())
+    }
+
+    fn visit_borrowed_bytes<E: de::Error>(self, v: &'a [u8]) -> core::result::Result<(), E> {
+        self.0.push(Node::Bytes(v));
+        Ok(())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> core::result::Result<(), E> {
+        self.0.push(Node::Null);
+        Ok(())
+    }
+
+    fn visit_none<E: de::Error>(self) -> core::result::Result<(), E> {
+        self.0.push(Node::Null);
+        Ok(())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> core::result::Result<(), A::Error>
+    where
+        A: de::SeqAccess<'a>,
+    {
+        let index = self.0.push(Node::Null);
+        let mut len = 0;
+        while seq.next_element_seed(NodeSeed(&mut *self.0))?.is_some() {
+            len += 1;
+        }
+        let descendants = self.0.len - index - 1;
+        self.0.set(index, Node::Array { len, descendants });
+        Ok(())
+    }
+
+    fn visit_map<A>(self, mut map: A) -> core::result::Result<(), A::Error>
+    where
+        A: de::MapAccess<'a>,
+    {
+        let index = self.0.push(Node::Null);
+        let mut len = 0;
+        while map.next_key_seed(NodeSeed(&mut *self.0))?.is_some() {
+            map.next_value_seed(NodeSeed(&mut *self.0))?;
+            len += 1;
+        }
+        let descendants = self.0.len - index - 1;
+        self.0.set(index, Node::Map { len, descendants });
+        Ok(())
+    }
+
+    #[cfg(feature = "tags")]
+    fn visit_newtype_struct<D>(self, deserializer: D) -> core::result::Result<(), D::Error>
+    where
+        D: de::Deserializer<'a>,
+    {
+        if let Some(tag) = crate::tags::get_tag() {
+            self.0.push(Node::Tag(tag));
+        }
+        deserializer.deserialize_any(self)
+    }
+}
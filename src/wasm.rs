@@ -0,0 +1,66 @@
+//! Entry points for JavaScript through `wasm-bindgen`.
+//!
+//! Code running in browsers passes bytes around as `Uint8Array`s and expects errors as
+//! `JsValue`s. [`from_uint8_array`] and [`to_uint8_array`] decode from and encode into such
+//! arrays, and an [`Error`](../error/struct.Error.html) converts into a JavaScript `Error` named
+//! `CborError`, with an `offset` property when the error has one. With that `?` works in
+//! `#[wasm_bindgen]` functions returning `Result<_, JsValue>`, no wrapper crate needed.
+//!
+//! ```no_run
+//! use js_sys::Uint8Array;
+//! use serde_derive::{Deserialize, Serialize};
+//! use wasm_bindgen::prelude::*;
+//!
+//! #[derive(Deserialize, Serialize)]
+//! struct Settings {
+//!     version: u32,
+//!     theme: String,
+//! }
+//!
+//! #[wasm_bindgen]
+//! pub fn next_version(input: &Uint8Array) -> Result<Uint8Array, JsValue> {
+//!     let mut settings: Settings = serde_cbor::wasm::from_uint8_array(input)?;
+//!     settings.version += 1;
+//!     serde_cbor::wasm::to_uint8_array(&settings)
+//! }
+//! ```
+
+use js_sys::{Reflect, Uint8Array};
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use wasm_bindgen::JsValue;
+
+use crate::error::Error;
+
+/// Decodes a value from the bytes of a `Uint8Array`.
+///
+/// The bytes are copied out of the JavaScript memory first, so the value can't borrow from them.
+pub fn from_uint8_array<T>(input: &Uint8Array) -> Result<T, JsValue>
+where
+    T: DeserializeOwned,
+{
+    let bytes = input.to_vec();
+    Ok(crate::from_slice(&bytes)?)
+}
+
+/// Encodes a value into a new `Uint8Array`.
+pub fn to_uint8_array<T>(value: &T) -> Result<Uint8Array, JsValue>
+where
+    T: Serialize,
+{
+    let bytes = crate::to_vec(value)?;
+    Ok(Uint8Array::from(&bytes[..]))
+}
+
+impl From<Error> for JsValue {
+    fn from(error: Error) -> JsValue {
+        let js_error = js_sys::Error::new(&error.to_string());
+        js_error.set_name("CborError");
+        if let Some(offset) = error.try_offset() {
+            let offset = JsValue::from_f64(offset as f64);
+            // setting a property of a new object can't fail
+            let _ = Reflect::set(&js_error, &JsValue::from_str("offset"), &offset);
+        }
+        js_error.into()
+    }
+}
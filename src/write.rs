@@ -3,7 +3,7 @@ use alloc::vec::Vec;
 #[cfg(not(feature = "std"))]
 use core::fmt;
 #[cfg(feature = "std")]
-use std::io;
+use std::{io, mem, ptr};
 
 use crate::error;
 
@@ -24,6 +24,24 @@ pub trait Write: private::Sealed {
     /// Attempts to write an entire buffer into this write.
     #[doc(hidden)]
     fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error>;
+    /// Hints that `additional` more bytes are about to be written.
+    ///
+    /// The default implementation does nothing.
+    #[doc(hidden)]
+    fn reserve(&mut self, additional: usize) {
+        let _ = additional;
+    }
+
+    /// Attempts to write several buffers one after the other.
+    ///
+    /// The default implementation calls `write_all` for every buffer.
+    #[doc(hidden)]
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<(), Self::Error> {
+        for buf in bufs {
+            self.write_all(buf)?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(feature = "unsealed_read_write")]
@@ -44,6 +62,22 @@ pub trait Write {
 
     /// Attempts to write an entire buffer into this write.
     fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error>;
+    /// Hints that `additional` more bytes are about to be written.
+    ///
+    /// The default implementation does nothing.
+    fn reserve(&mut self, additional: usize) {
+        let _ = additional;
+    }
+
+    /// Attempts to write several buffers one after the other.
+    ///
+    /// The default implementation calls `write_all` for every buffer.
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<(), Self::Error> {
+        for buf in bufs {
+            self.write_all(buf)?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(not(feature = "unsealed_read_write"))]
@@ -60,6 +94,14 @@ where
     fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
         (*self).write_all(buf)
     }
+
+    fn reserve(&mut self, additional: usize) {
+        (*self).reserve(additional)
+    }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<(), Self::Error> {
+        (*self).write_vectored(bufs)
+    }
 }
 
 #[cfg(not(feature = "unsealed_read_write"))]
@@ -69,6 +111,10 @@ impl<W> private::Sealed for &mut W where W: Write {}
 /// A wrapper for types that implement
 /// [`std::io::Write`](https://doc.rust-lang.org/std/io/trait.Write.html) to implement the local
 /// [`Write`](trait.Write.html) trait.
+///
+/// Every item is passed on as soon as it is encoded, which costs a system call per few bytes
+/// for a raw `File` or `TcpStream`. A [`BufferedIoWrite`](struct.BufferedIoWrite.html) collects
+/// the output in a buffer instead.
 #[derive(Debug)]
 pub struct IoWrite<W>(W);
 
@@ -87,11 +133,198 @@ impl<W: io::Write> Write for IoWrite<W> {
     fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
         self.0.write_all(buf)
     }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<(), Self::Error> {
+        write_vectored_io(&mut self.0, bufs)
+    }
 }
 
 #[cfg(all(feature = "std", not(feature = "unsealed_read_write")))]
 impl<W> private::Sealed for IoWrite<W> where W: io::Write {}
 
+#[cfg(feature = "std")]
+/// A wrapper for types that implement `std::io::Write` that passes the output on in chunks.
+///
+/// The buffered output is written by [`flush`](#method.flush) and
+/// [`into_inner`](#method.into_inner). Like a `BufWriter`, a `BufferedIoWrite` that is dropped
+/// writes the rest of its output and ignores any errors, so these should be called to see them.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_cbor::ser::{BufferedIoWrite, Serializer};
+///
+/// let mut writer = BufferedIoWrite::with_capacity(8 * 1024, Vec::new());
+/// for i in 0..3 {
+///     (i, "item").serialize(&mut Serializer::new(&mut writer)).unwrap();
+/// }
+/// let vec = writer.into_inner().unwrap();
+/// assert_eq!(vec.len(), 21);
+/// ```
+#[derive(Debug)]
+pub struct BufferedIoWrite<W: io::Write> {
+    writer: W,
+    buf: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> BufferedIoWrite<W> {
+    /// Wraps an `io::Write` writer with a buffer of 8 KiB.
+    pub fn new(w: W) -> BufferedIoWrite<W> {
+        BufferedIoWrite::with_capacity(8 * 1024, w)
+    }
+
+    /// Wraps an `io::Write` writer, passing the output on in chunks of `capacity` bytes.
+    pub fn with_capacity(capacity: usize, w: W) -> BufferedIoWrite<W> {
+        BufferedIoWrite {
+            writer: w,
+            buf: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Writes the buffered output and flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.flush_buf()?;
+        self.writer.flush()
+    }
+
+    /// Writes the buffered output and returns the underlying writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush_buf()?;
+        let this = mem::ManuallyDrop::new(self);
+        // SAFETY: the fields are read once and `this` is not dropped, so they are not dropped
+        // twice. The buffer is empty and dropped right away.
+        unsafe {
+            drop(ptr::read(&this.buf));
+            Ok(ptr::read(&this.writer))
+        }
+    }
+
+    /// Writes the buffered output. The bytes that could not be written stay in the buffer.
+    fn flush_buf(&mut self) -> io::Result<()> {
+        let mut written = 0;
+        let mut result = Ok(());
+        while written < self.buf.len() {
+            match self.writer.write(&self.buf[written..]) {
+                Ok(0) => {
+                    result = Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "failed to write the buffered data",
+                    ));
+                    break;
+                }
+                Ok(n) => written += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        self.buf.drain(..written);
+        result
+    }
+
+    /// Buffers `len` more bytes if they fit, otherwise writes the buffer to make room.
+    ///
+    /// Returns whether the bytes have to be written directly.
+    fn make_room(&mut self, len: usize) -> io::Result<bool> {
+        if self.buf.len() + len <= self.buf.capacity() {
+            return Ok(false);
+        }
+        self.flush_buf()?;
+        Ok(len > self.buf.capacity())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> Drop for BufferedIoWrite<W> {
+    fn drop(&mut self) {
+        // errors can't be reported here, `flush` and `into_inner` return them
+        let _ = self.flush_buf();
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> Write for BufferedIoWrite<W> {
+    type Error = io::Error;
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
+        if self.make_room(buf.len())? {
+            self.writer.write_all(buf)
+        } else {
+            self.buf.extend_from_slice(buf);
+            Ok(())
+        }
+    }
+
+    fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<(), Self::Error> {
+        let len = bufs.iter().map(|buf| buf.len()).sum();
+        if self.make_room(len)? {
+            return write_vectored_io(&mut self.writer, bufs);
+        }
+        for buf in bufs {
+            self.buf.extend_from_slice(buf);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "std", not(feature = "unsealed_read_write")))]
+impl<W> private::Sealed for BufferedIoWrite<W> where W: io::Write {}
+
+/// The number of bytes up to which the buffers of a vectored write are joined before they are
+/// passed to an `io::Write`, enough for a string of 23 bytes and its header.
+#[cfg(feature = "std")]
+const SHORT_VECTORED: usize = 24;
+
+/// Writes several buffers one after the other to an `io::Write`.
+#[cfg(feature = "std")]
+fn write_vectored_io<W: io::Write>(writer: &mut W, mut bufs: &[&[u8]]) -> io::Result<()> {
+    let len: usize = bufs.iter().map(|buf| buf.len()).sum();
+    if len <= SHORT_VECTORED {
+        // Mostly a short string and its header. Most writers handle a single call better
+        // than a vectored write, which they often split into one call per buffer.
+        let mut joined = [0; SHORT_VECTORED];
+        let mut pos = 0;
+        for buf in bufs {
+            joined[pos..pos + buf.len()].copy_from_slice(buf);
+            pos += buf.len();
+        }
+        return writer.write_all(&joined[..len]);
+    }
+    // Number of bytes of the first buffer that have been written already.
+    let mut skip = 0;
+    loop {
+        while let Some((first, rest)) = bufs.split_first() {
+            if skip < first.len() {
+                break;
+            }
+            skip -= first.len();
+            bufs = rest;
+        }
+        if bufs.is_empty() {
+            return Ok(());
+        }
+        let mut slices = [io::IoSlice::new(&[]); 4];
+        let count = bufs.len().min(slices.len());
+        for (slice, buf) in slices.iter_mut().zip(bufs) {
+            *slice = io::IoSlice::new(buf);
+        }
+        slices[0] = io::IoSlice::new(&bufs[0][skip..]);
+        match writer.write_vectored(&slices[..count]) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ))
+            }
+            Ok(n) => skip += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
 #[cfg(any(feature = "std", feature = "alloc"))]
 impl Write for Vec<u8> {
     type Error = error::Error;
@@ -100,6 +333,10 @@ impl Write for Vec<u8> {
         self.extend_from_slice(buf);
         Ok(())
     }
+
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional)
+    }
 }
 
 #[cfg(all(
@@ -108,6 +345,71 @@ impl Write for Vec<u8> {
 ))]
 impl private::Sealed for Vec<u8> {}
 
+#[cfg(feature = "bytes")]
+impl Write for ::bytes::BytesMut {
+    type Error = error::Error;
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        ::bytes::BytesMut::reserve(self, additional)
+    }
+}
+
+#[cfg(all(feature = "bytes", not(feature = "unsealed_read_write")))]
+impl private::Sealed for ::bytes::BytesMut {}
+
+#[cfg(feature = "zstd")]
+impl<W: io::Write> Write for crate::zstd::ZstdWriter<W> {
+    type Error = io::Error;
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
+        io::Write::write_all(&mut self.encoder, buf)
+    }
+}
+
+#[cfg(all(feature = "zstd", not(feature = "unsealed_read_write")))]
+impl<W> private::Sealed for crate::zstd::ZstdWriter<W> where W: io::Write {}
+
+#[cfg(feature = "embedded")]
+impl<'a> Write for crate::embedded::RingBuffer<'a> {
+    type Error = error::Error;
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
+        if self.capacity() - self.len() < buf.len() {
+            return Err(error::Error::scratch_overflow(
+                self.len() + buf.len(),
+                self.capacity(),
+                self.len() as u64,
+            ));
+        }
+        self.push(buf);
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "embedded", not(feature = "unsealed_read_write")))]
+impl<'a> private::Sealed for crate::embedded::RingBuffer<'a> {}
+
+#[cfg(feature = "embedded")]
+impl<'a, F> Write for crate::embedded::ChunkWrite<'a, F>
+where
+    F: FnMut(&[u8]),
+{
+    type Error = error::Error;
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
+        self.write_bytes(buf);
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "embedded", not(feature = "unsealed_read_write")))]
+impl<'a, F> private::Sealed for crate::embedded::ChunkWrite<'a, F> where F: FnMut(&[u8]) {}
+
 #[cfg(not(feature = "std"))]
 #[derive(Debug)]
 pub struct FmtWrite<'a, W: Write>(&'a mut W);
@@ -154,6 +456,23 @@ impl<'a> SliceWrite<'a> {
     pub fn into_inner(self) -> &'a mut [u8] {
         self.slice
     }
+
+    /// Returns the bytes written so far.
+    pub fn written(&self) -> &[u8] {
+        &self.slice[..self.index]
+    }
+
+    /// Returns the part of the underlying slice that has been written to.
+    pub fn into_written(self) -> &'a [u8] {
+        &self.slice[..self.index]
+    }
+
+    /// Starts writing at the beginning of the slice again.
+    ///
+    /// The previously written bytes are not cleared, they are overwritten by later writes.
+    pub fn reset(&mut self) {
+        self.index = 0;
+    }
 }
 
 impl<'a> Write for SliceWrite<'a> {
@@ -162,7 +481,11 @@ impl<'a> Write for SliceWrite<'a> {
     fn write_all(&mut self, buf: &[u8]) -> Result<(), Self::Error> {
         if self.slice.len() - self.index < buf.len() {
             // This buffer will not fit in our slice
-            return Err(error::Error::scratch_too_small(self.index as u64));
+            return Err(error::Error::scratch_overflow(
+                self.index + buf.len(),
+                self.slice.len(),
+                self.index as u64,
+            ));
         }
         let end = self.index + buf.len();
         self.slice[self.index..end].copy_from_slice(buf);
@@ -0,0 +1,112 @@
+//! Compression of whole payloads with zstd.
+//!
+//! A [`ZstdWriter`] compresses everything a `Serializer` writes to it and passes the compressed
+//! stream on to an `io::Write` as encoding goes on, so a large value is never held in memory in
+//! either form. Decoding reads the compressed stream through zstd's streaming decoder the same
+//! way.
+//!
+//! ```
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, PartialEq, Deserialize, Serialize)]
+//! struct Log {
+//!     lines: Vec<String>,
+//! }
+//!
+//! let log = Log { lines: vec!["all systems nominal".into(); 100] };
+//! let compressed = serde_cbor::zstd::to_vec(&log, 0).unwrap();
+//! assert!(compressed.len() < serde_cbor::to_vec(&log).unwrap().len() / 10);
+//!
+//! let decoded: Log = serde_cbor::zstd::from_reader(&compressed[..]).unwrap();
+//! assert_eq!(decoded, log);
+//! ```
+//!
+//! Wrapping an `io::Write` compressor like `zstd::Encoder` in an `IoWrite` works as well, but
+//! the compressor has to be finished afterwards to write the end of the stream. Dropping it
+//! instead either loses the end or ignores the errors of writing it; [`ZstdWriter::finish`] and
+//! the functions of this module report them.
+
+use std::io;
+
+use ::zstd::stream::read::Decoder;
+use ::zstd::stream::write::Encoder;
+use serde::de;
+use serde::ser;
+
+use crate::de::Deserializer;
+use crate::error::{Error, Result};
+use crate::ser::Serializer;
+
+/// A [`Write`](../ser/trait.Write.html) that compresses the encoded CBOR with zstd and passes it
+/// on to an `io::Write`.
+///
+/// The compressed stream is only complete after [`finish`](#method.finish) is called.
+///
+/// ```
+/// use serde::Serialize;
+/// use serde_cbor::zstd::ZstdWriter;
+/// use serde_cbor::Serializer;
+///
+/// let mut serializer = Serializer::new(ZstdWriter::new(Vec::new(), 3).unwrap()).packed_format();
+/// (1, "one").serialize(&mut serializer).unwrap();
+/// let compressed = serializer.into_inner().finish().unwrap();
+///
+/// let value: (u8, String) = serde_cbor::zstd::from_reader(&compressed[..]).unwrap();
+/// assert_eq!(value, (1, "one".to_string()));
+/// ```
+pub struct ZstdWriter<W: io::Write> {
+    pub(crate) encoder: Encoder<'static, W>,
+}
+
+impl<W: io::Write> ZstdWriter<W> {
+    /// Compresses into `writer` at the given compression level, where 0 stands for zstd's
+    /// default level.
+    pub fn new(writer: W, level: i32) -> Result<ZstdWriter<W>> {
+        let encoder = Encoder::new(writer, level).map_err(Error::io)?;
+        Ok(ZstdWriter { encoder })
+    }
+
+    /// Writes the end of the compressed stream and returns the underlying writer.
+    pub fn finish(self) -> Result<W> {
+        self.encoder.finish().map_err(Error::io)
+    }
+}
+
+/// Encodes a value as compressed CBOR into a writer and returns the writer.
+///
+/// `level` is the zstd compression level, 0 stands for the default level.
+pub fn to_writer<W, T>(writer: W, value: &T, level: i32) -> Result<W>
+where
+    W: io::Write,
+    T: ?Sized + ser::Serialize,
+{
+    let mut serializer = Serializer::new(ZstdWriter::new(writer, level)?);
+    value.serialize(&mut serializer)?;
+    serializer.into_inner().finish()
+}
+
+/// Encodes a value as compressed CBOR into a vector.
+///
+/// `level` is the zstd compression level, 0 stands for the default level.
+pub fn to_vec<T>(value: &T, level: i32) -> Result<Vec<u8>>
+where
+    T: ?Sized + ser::Serialize,
+{
+    to_writer(Vec::new(), value, level)
+}
+
+/// Decodes a value from compressed CBOR in a reader.
+///
+/// Fails with an I/O error if the compressed stream is corrupt, and with the usual errors if the
+/// CBOR in it is invalid or followed by trailing data.
+pub fn from_reader<T, R>(reader: R) -> Result<T>
+where
+    T: de::DeserializeOwned,
+    R: io::Read,
+{
+    let decoder = Decoder::new(reader).map_err(Error::io)?;
+    let mut deserializer = Deserializer::from_reader(decoder);
+    let value = de::Deserialize::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
@@ -0,0 +1,40 @@
+#![cfg(feature = "bumpalo")]
+
+use bumpalo::Bump;
+use serde_cbor::bump::{from_reader, from_slice};
+
+#[test]
+fn test_from_slice() {
+    let bump = Bump::new();
+    // [h'0102', null, h'']
+    let input = b"\x83\x42\x01\x02\xf6\x40";
+    let value: &[Option<&[u8]>] = from_slice(input, &bump).unwrap();
+    assert_eq!(value, [Some(&[1, 2][..]), None, Some(&[][..])]);
+
+    // (_ "ab", "c") is copied into the arena like any other string
+    let value: &str = from_slice(b"\x7f\x62ab\x61c\xff", &bump).unwrap();
+    assert_eq!(value, "abc");
+    assert!(bump.allocated_bytes() > 0);
+
+    let value: &[u32] = from_slice(b"\x82\x01\x19\x01\x00", &bump).unwrap();
+    assert_eq!(value, [1, 256]);
+
+    assert!(from_slice::<&str>(b"\x41\x01", &bump).is_err());
+    assert!(from_slice::<&str>(b"\x60\x00", &bump)
+        .unwrap_err()
+        .is_syntax());
+}
+
+#[test]
+fn test_from_reader() {
+    let mut bump = Bump::new();
+    let input: &[u8] = b"\x82\x63one\x63two";
+    for _ in 0..2 {
+        let words: &[&str] = from_reader(input, &bump).unwrap();
+        assert_eq!(words, ["one", "two"]);
+        bump.reset();
+    }
+    assert!(from_reader::<&[&str], _>(&input[..6], &bump)
+        .unwrap_err()
+        .is_eof());
+}
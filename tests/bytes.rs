@@ -0,0 +1,78 @@
+#[cfg(feature = "bytes")]
+mod bytes_tests {
+    use bytes::{Bytes, BytesMut};
+    use serde_cbor::bytes::SharedBytes;
+    use serde_cbor::de::from_bytes;
+    use serde_cbor::{from_slice, Serializer};
+    use serde_derive::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Frame {
+        #[serde(with = "serde_cbor::bytes")]
+        header: Bytes,
+        #[serde(with = "serde_cbor::bytes")]
+        body: Bytes,
+    }
+
+    fn frame() -> Frame {
+        Frame {
+            header: Bytes::from_static(b"head"),
+            body: Bytes::from(vec![7; 100]),
+        }
+    }
+
+    #[test]
+    fn test_write_bytes_mut() {
+        let mut buf = BytesMut::with_capacity(4);
+        serde::Serialize::serialize(&frame(), &mut Serializer::new(&mut buf)).unwrap();
+        assert_eq!(buf, serde_cbor::to_vec(&frame()).unwrap());
+    }
+
+    #[test]
+    fn test_from_bytes_shares_buffer() {
+        let input = Bytes::from(serde_cbor::to_vec(&frame()).unwrap());
+        let decoded: Frame = from_bytes(input.clone()).unwrap();
+        assert_eq!(decoded, frame());
+        let range = input.as_ptr_range();
+        assert!(range.contains(&decoded.header.as_ptr()));
+        assert!(range.contains(&decoded.body.as_ptr()));
+    }
+
+    #[test]
+    fn test_copies_without_source() {
+        let input = serde_cbor::to_vec(&frame()).unwrap();
+        let decoded: Frame = from_slice(&input).unwrap();
+        assert_eq!(decoded, frame());
+        assert!(!input.as_ptr_range().contains(&decoded.body.as_ptr()));
+
+        // Indefinite byte strings have to be copied.
+        let input = Bytes::from_static(b"\xa2\x66header\x5f\x41a\x41b\xff\x64body\x40");
+        let decoded: Frame = from_bytes(input).unwrap();
+        assert_eq!(decoded.header, Bytes::from_static(b"ab"));
+    }
+
+    #[test]
+    fn test_shared_bytes() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Batch {
+            items: Vec<SharedBytes>,
+            trailer: Option<SharedBytes>,
+        }
+
+        let batch = Batch {
+            items: vec![Bytes::from_static(b"one").into(), Bytes::new().into()],
+            trailer: Some(Bytes::from_static(b"end").into()),
+        };
+        let decoded: Batch = {
+            // The decoded batch keeps the buffer alive after the input is gone.
+            let input = Bytes::from(serde_cbor::to_vec(&batch).unwrap());
+            let decoded: Batch = from_bytes(input.clone()).unwrap();
+            let range = input.as_ptr_range();
+            assert!(range.contains(&decoded.items[0].as_ptr()));
+            assert!(range.contains(&decoded.trailer.as_ref().unwrap().as_ptr()));
+            decoded
+        };
+        assert_eq!(decoded, batch);
+        assert_eq!(serde_cbor::to_vec(&decoded.items[0]).unwrap(), b"\x43one");
+    }
+}
@@ -101,4 +101,139 @@ mod std_tests {
         sorted.sort();
         assert_eq!(expected, sorted);
     }
+
+    #[test]
+    fn test_canonicalize() {
+        use serde_cbor::canonical::{canonicalize, canonicalize_with_order};
+        use serde_cbor::ser::KeyOrder;
+
+        let vectors: &[(&[u8], &[u8])] = &[
+            (b"\x18\x17", b"\x17"),
+            (b"\x1a\x00\x00\x01\x00", b"\x19\x01\x00"),
+            (b"\x38\x00", b"\x20"),
+            (b"\xfa\x3f\xc0\x00\x00", b"\xf9\x3e\x00"),
+            (
+                b"\xfb\x3f\xb9\x99\x99\x99\x99\x99\x9a",
+                b"\xfb\x3f\xb9\x99\x99\x99\x99\x99\x9a",
+            ),
+            (b"\xfb\x7f\xf8\x00\x00\x00\x00\x00\x01", b"\xf9\x7e\x00"),
+            (b"\xfa\xff\x80\x00\x00", b"\xf9\xfc\x00"),
+            (b"\x5f\x41\x01\x40\x58\x01\x02\xff", b"\x42\x01\x02"),
+            (b"\x7f\xff", b"\x60"),
+            (b"\x9f\x01\x9f\xff\xff", b"\x82\x01\x80"),
+            (b"\xd9\x00\x01\x00", b"\xc1\x00"),
+            // unknown tags and simple values are kept
+            (
+                b"\xda\x00\x01\x00\x00\xf8\xff",
+                b"\xda\x00\x01\x00\x00\xf8\xff",
+            ),
+            // {_ "b": {"y": 0, "x": 1}, "a": 2}
+            (
+                b"\xbf\x61b\xa2\x61y\x00\x61x\x01\x61a\x02\xff",
+                b"\xa2\x61a\x02\x61b\xa2\x61x\x01\x61y\x00",
+            ),
+        ];
+        for &(input, expected) in vectors {
+            assert_eq!(canonicalize(input).unwrap(), expected, "{:02x?}", input);
+        }
+
+        // {[1]: 0, "aa": 0, -1: 0, 1000: 0}
+        let input = b"\xa4\x81\x01\x00\x62aa\x00\x20\x00\x19\x03\xe8\x00";
+        let length_first = b"\xa4\x20\x00\x81\x01\x00\x19\x03\xe8\x00\x62aa\x00";
+        assert_eq!(canonicalize(input).unwrap(), length_first);
+        let bytewise = b"\xa4\x19\x03\xe8\x00\x20\x00\x62aa\x00\x81\x01\x00";
+        assert_eq!(
+            canonicalize_with_order(input, KeyOrder::Bytewise).unwrap(),
+            bytewise
+        );
+
+        let mut map = std::collections::BTreeMap::new();
+        map.insert(Value::Text("key".to_owned()), Value::Float(1.5));
+        map.insert(Value::Integer(-300), Value::Array(vec![Value::Null]));
+        map.insert(Value::Bytes(vec![0; 30]), Value::Integer(1 << 40));
+        let value = Value::Map(map);
+        let expected = serde_cbor::ser::to_vec_canonical(&value).unwrap();
+        let input = serde_cbor::to_vec(&value).unwrap();
+        assert_eq!(canonicalize(&input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_canonical_unknown_lengths() {
+        use serde_cbor::canonical::canonicalize;
+        use serde_derive::Serialize;
+
+        #[derive(Serialize)]
+        struct Inner {
+            b: u32,
+            a: u32,
+        }
+
+        #[derive(Serialize)]
+        struct Outer {
+            zz: u32,
+            #[serde(flatten)]
+            inner: Inner,
+        }
+
+        let value = Outer {
+            zz: 3,
+            inner: Inner { b: 2, a: 1 },
+        };
+        let output = serde_cbor::ser::to_vec_canonical(&value).unwrap();
+        assert_eq!(output, b"\xa3\x61a\x01\x61b\x02\x62zz\x03");
+        let input = serde_cbor::to_vec(&value).unwrap();
+        assert_eq!(input[0], 0xbf);
+        assert_eq!(canonicalize(&input).unwrap(), output);
+
+        // sequences of unknown length as the values of a map of unknown length
+        let value = UnknownLengths(vec![vec![0, 1], vec![]]);
+        let output = serde_cbor::ser::to_vec_canonical(&value).unwrap();
+        assert_eq!(output, b"\xa2\x00\x82\x00\x01\x01\x80");
+        let input = serde_cbor::to_vec(&value).unwrap();
+        assert_eq!(canonicalize(&input).unwrap(), output);
+    }
+
+    /// Serializes as a map from index to sequence without passing the lengths.
+    struct UnknownLengths(Vec<Vec<u8>>);
+
+    struct UnknownLength<'a>(&'a [u8]);
+
+    impl serde::Serialize for UnknownLengths {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let entries = self.0.iter().enumerate().filter(|_| true);
+            serializer.collect_map(entries.map(|(i, items)| (i, UnknownLength(items))))
+        }
+    }
+
+    impl<'a> serde::Serialize for UnknownLength<'a> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.0.iter().filter(|_| true))
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_errors() {
+        use serde_cbor::canonical::canonicalize;
+
+        let message = |input: &[u8]| canonicalize(input).unwrap_err().to_string();
+        // {1: 0, 1: 1} with the second key written in two bytes
+        assert_eq!(message(b"\xa2\x01\x00\x18\x01\x01"), "duplicate map key");
+        assert_eq!(message(b"\x01\x02"), "trailing data at offset 1");
+        assert_eq!(
+            message(b"\x7f\x61a\x62\xc3\x28\xff"),
+            "invalid UTF-8 at offset 4"
+        );
+        assert_eq!(
+            message(b"\x82\x01"),
+            "EOF while parsing a value at offset 2"
+        );
+        assert!(canonicalize(b"\xf8\x10").is_err());
+        assert!(canonicalize(b"\xff").is_err());
+        // {_ 1}
+        assert!(canonicalize(b"\xbf\x01\xff").is_err());
+        // (_ "a" h'00')
+        assert!(canonicalize(b"\x7f\x61a\x41\x00\xff").is_err());
+        let nested = [0x81; 200];
+        assert!(canonicalize(&nested).unwrap_err().is_syntax());
+    }
 }
@@ -0,0 +1,113 @@
+#![cfg(feature = "std")]
+
+use serde_cbor::codegen::key_tables;
+
+const SCHEMA: &str = "
+; readings are sent every second
+SensorReading = {
+    temperature: 1,  ; in tenths of a degree
+    humidity: 2
+}
+Ack = { id: 0 }
+";
+
+#[test]
+fn test_key_tables() {
+    let code = key_tables(SCHEMA).unwrap();
+    assert_eq!(
+        code,
+        r#"// Generated by serde_cbor::codegen::key_tables, do not edit.
+
+/// The packed keys of `SensorReading`.
+#[allow(dead_code)]
+pub mod sensor_reading {
+    /// The key of `temperature`.
+    pub const TEMPERATURE: u64 = 1;
+    /// The key of `humidity`.
+    pub const HUMIDITY: u64 = 2;
+
+    /// Maps the keys of `SensorReading` to its field names.
+    pub const KEY_RENAMES: &[(serde_cbor::de::WireKey<'static>, &str)] = &[
+        (serde_cbor::de::WireKey::Integer(1), "temperature"),
+        (serde_cbor::de::WireKey::Integer(2), "humidity"),
+    ];
+}
+
+/// The packed keys of `Ack`.
+#[allow(dead_code)]
+pub mod ack {
+    /// The key of `id`.
+    pub const ID: u64 = 0;
+
+    /// Maps the keys of `Ack` to its field names.
+    pub const KEY_RENAMES: &[(serde_cbor::de::WireKey<'static>, &str)] = &[
+        (serde_cbor::de::WireKey::Integer(0), "id"),
+    ];
+}
+"#
+    );
+}
+
+// The output of `key_tables(SCHEMA)` for `SensorReading`, as a build script would include it.
+#[allow(dead_code)]
+pub mod sensor_reading {
+    pub const TEMPERATURE: u64 = 1;
+    pub const HUMIDITY: u64 = 2;
+
+    pub const KEY_RENAMES: &[(serde_cbor::de::WireKey<'static>, &str)] = &[
+        (serde_cbor::de::WireKey::Integer(1), "temperature"),
+        (serde_cbor::de::WireKey::Integer(2), "humidity"),
+    ];
+}
+
+#[test]
+fn test_key_tables_renames() {
+    use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead};
+    use serde_derive::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct SensorReading {
+        humidity: u8,
+        temperature: i16,
+    }
+
+    let options = CustomDeserializerOptions::new().set_key_renames(sensor_reading::KEY_RENAMES);
+    // {1: 215, 2: 40}
+    let input = b"\xa2\x01\x18\xd7\x02\x18\x28";
+    let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+    let reading: SensorReading = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    assert_eq!(
+        reading,
+        SensorReading {
+            humidity: 40,
+            temperature: 215
+        }
+    );
+}
+
+#[test]
+fn test_key_tables_errors() {
+    let err = |schema: &str| key_tables(schema).unwrap_err().to_string();
+    assert_eq!(
+        err("A = {\n  x: 1,\n  y: 1\n}"),
+        "schema line 3: key 1 of `A` is used twice"
+    );
+    assert_eq!(
+        err("A = { x: 1, x: 2 }"),
+        "schema line 1: field `x` of `A` is defined twice"
+    );
+    assert_eq!(
+        err("A = { x: -1 }"),
+        "schema line 1: expected an unsigned integer key for `x`"
+    );
+    assert_eq!(err("A { x: 1 }"), "schema line 1: expected `=`, found `{`");
+    assert_eq!(
+        err("A = { x: 1"),
+        "schema line 1: expected a field name or `}`, found the end"
+    );
+    assert_eq!(
+        err("A = {} A = {}"),
+        "schema line 1: struct `A` is defined twice"
+    );
+    assert_eq!(key_tables("; nothing\n").unwrap().lines().count(), 1);
+}
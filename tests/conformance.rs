@@ -0,0 +1,57 @@
+#[cfg(feature = "conformance")]
+mod conformance_tests {
+    use serde_cbor::conformance::{self, diagnostic};
+    use serde_cbor::ser::{CustomSerializerOptions, KeyOrder, Serializer};
+    use serde_cbor::value::Value;
+
+    #[test]
+    fn test_default() {
+        assert_eq!(conformance::run(), vec![]);
+    }
+
+    #[test]
+    fn test_options() {
+        let failures = conformance::run_with(
+            |input| serde_cbor::from_slice(input),
+            |value| {
+                let mut vec = Vec::new();
+                let options =
+                    CustomSerializerOptions::new().set_key_order(Some(KeyOrder::Bytewise));
+                serde::Serialize::serialize(
+                    value,
+                    &mut Serializer::new_with_options(&mut vec, options),
+                )?;
+                Ok(vec)
+            },
+        );
+        assert_eq!(failures, vec![]);
+    }
+
+    #[test]
+    fn test_failures() {
+        let failures = conformance::run_with(
+            |input| serde_cbor::from_slice(input),
+            |value| serde_cbor::ser::to_vec_packed(&Value::Array(vec![value.clone()])),
+        );
+        assert!(!failures.is_empty());
+        assert_eq!(failures[0].input, "00");
+        assert_eq!(failures[0].message, "encoded 0 as 8100, expected 00");
+    }
+
+    #[test]
+    fn test_diagnostic() {
+        let value = Value::Tag(
+            1,
+            Box::new(Value::Array(vec![
+                Value::Text("a\u{1}".to_string()),
+                Value::Float(-f64::INFINITY),
+                Value::Bytes(vec![0xab]),
+                Value::Null,
+            ])),
+        );
+        assert_eq!(
+            diagnostic(&value),
+            "1([\"a\\u0001\", -Infinity, h'ab', null])"
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use serde_cbor::constant::ConstEncoder;
+
+#[test]
+fn test_const_encoder() {
+    const ITEM: [u8; 18] = ConstEncoder::new()
+        .tag(1000)
+        .map(2)
+        .int(-1)
+        .unsigned(24)
+        .int(i64::MIN)
+        .array(0)
+        .null()
+        .finish();
+    assert_eq!(
+        ITEM,
+        *b"\xd9\x03\xe8\xa2\x20\x18\x18\x3b\x7f\xff\xff\xff\xff\xff\xff\xff\x80\xf6"
+    );
+}
+
+#[test]
+fn test_const_encoder_lengths() {
+    let encoder = ConstEncoder::<9>::new();
+    assert!(encoder.is_empty());
+    assert_eq!(encoder.unsigned(0xff).len(), 2);
+    assert_eq!(encoder.unsigned(0x100).len(), 3);
+    assert_eq!(encoder.unsigned(0x1_0000).len(), 5);
+    assert_eq!(
+        encoder.unsigned(u64::MAX).finish(),
+        [0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+    );
+
+    let text = ConstEncoder::<24>::new().text("a string of 23 bytes...");
+    assert_eq!(text.len(), 24);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_const_encoder_matches_serializer() {
+    use std::collections::BTreeMap;
+
+    const ENCODED: [u8; 19] = ConstEncoder::new()
+        .map(2)
+        .text("id")
+        .int(-300)
+        .text("tags")
+        .array(2)
+        .text("a")
+        .bytes(&[0; 3])
+        .finish();
+    let mut map = BTreeMap::new();
+    map.insert("id", serde_cbor::Value::Integer(-300));
+    let tags = vec![
+        serde_cbor::Value::Text("a".into()),
+        serde_cbor::Value::Bytes(vec![0; 3]),
+    ];
+    map.insert("tags", serde_cbor::Value::Array(tags));
+    assert_eq!(&ENCODED[..], &serde_cbor::to_vec(&map).unwrap()[..]);
+}
+
+#[test]
+#[should_panic(expected = "do not fit")]
+fn test_const_encoder_overflow() {
+    ConstEncoder::<2>::new().text("ab");
+}
@@ -18,6 +18,43 @@ fn test_bytes() {
     assert_eq!(s, b"foobar");
 }
 
+#[test]
+fn test_single_chunk_indefinite() {
+    // (_ "foo"), borrowed from the input without a scratch buffer
+    let s: &str = de::from_slice_with_scratch(b"\x7f\x63foo\xff", &mut []).unwrap();
+    assert_eq!(s, "foo");
+    let s: &[u8] = de::from_slice_with_scratch(b"\x5f\x43foo\xff", &mut []).unwrap();
+    assert_eq!(s, b"foo");
+    // (_ "", "foo")
+    let s: &str = de::from_slice_with_scratch(b"\x7f\x60\x63foo\xff", &mut []).unwrap();
+    assert_eq!(s, "foo");
+
+    // (_ "fo", "o") has to be copied
+    let input = b"\x7f\x62fo\x61o\xff";
+    let err = de::from_slice_with_scratch::<&str>(input, &mut []).unwrap_err();
+    assert!(err.is_scratch_too_small());
+    let err = de::from_slice_with_scratch::<&str>(input, &mut [0; 3]).unwrap_err();
+    assert!(err.is_data());
+
+    let err = de::from_slice_with_scratch::<&str>(b"\x7f\x63foo", &mut [0; 3]).unwrap_err();
+    assert!(err.is_eof());
+}
+
+#[test]
+fn test_options_are_plain_data() {
+    fn assert_plain<T: Copy + Send + Sync + core::fmt::Debug + PartialEq + 'static>() {}
+    assert_plain::<de::DefaultDeserializerOptions>();
+    assert_plain::<de::CustomDeserializerOptions>();
+
+    let options = de::CustomDeserializerOptions::new().set_accept_named_format(false);
+    let copy = options;
+    assert_eq!(copy, options);
+    assert_ne!(copy, de::CustomDeserializerOptions::new());
+    let resolving = options.set_variant_resolver(de::resolve_variant_by_name);
+    assert_eq!(resolving, resolving.clone());
+    assert_ne!(resolving, options);
+}
+
 #[test]
 fn test_int() {
     let num: i64 = de::from_slice_with_scratch(&[0x39, 0x07, 0xde], &mut []).unwrap();
@@ -43,8 +80,98 @@ fn test_indefinite_object() {
     assert_eq!(expected, actual);
 }
 
+#[test]
+fn test_remaining() {
+    // (_ "foo" "bar"), 7
+    let input = b"\x7f\x63foo\x63bar\xff\x07";
+    let mut buf = *input;
+    let mut deserializer = de::Deserializer::from_mut_slice(&mut buf);
+    let s: &str = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    assert_eq!(s, "foobar");
+    assert_eq!(deserializer.remaining(), b"\x07");
+    assert_eq!(deserializer.consumed(), 10);
+
+    let mut deserializer = de::Deserializer::from_slice(input);
+    let s: String = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    assert_eq!(s, "foobar");
+    assert_eq!(deserializer.remaining(), b"\x07");
+
+    let mut deserializer = de::Deserializer::from_slice_with_scratch(b"\x06\x07", &mut []);
+    let n: u8 = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    assert_eq!(n, 6);
+    assert_eq!(deserializer.remaining(), b"\x07");
+}
+
+#[test]
+fn test_mut_slice_iter() {
+    // "a", (_ "b" "c"), (_ "d" and the end of the input
+    let mut input = *b"\x61a\x7f\x61b\x61c\xff\x7f\x61d";
+    let mut items = de::from_mut_slice_iter::<&str>(&mut input);
+    let a = items.next().unwrap().unwrap();
+    let bc = items.next().unwrap().unwrap();
+    assert!(items.next().unwrap().unwrap_err().is_eof());
+    assert_eq!((a, bc), ("a", "bc"));
+}
+
+#[test]
+fn test_split_items() {
+    // 1, "\xff" (not validated), [_ {1: h'00'}], 2(h'0102')
+    let input = b"\x01\x61\xff\x9f\xa1\x01\x41\x00\xff\xc2\x42\x01\x02";
+    let mut items = de::split_items(input);
+    assert_eq!(items.next().unwrap().unwrap(), b"\x01");
+    assert_eq!(items.byte_offset(), 1);
+    assert_eq!(items.next().unwrap().unwrap(), b"\x61\xff");
+    assert_eq!(items.next().unwrap().unwrap(), b"\x9f\xa1\x01\x41\x00\xff");
+    assert_eq!(items.next().unwrap().unwrap(), b"\xc2\x42\x01\x02");
+    assert!(items.next().is_none());
+
+    let mut items = de::split_items(b"\x01\x82\x01");
+    assert_eq!(items.next().unwrap().unwrap(), b"\x01");
+    assert!(items.next().unwrap().unwrap_err().is_eof());
+    assert!(items.next().is_none());
+
+    let mut items = de::split_items(b"\x1c\x01");
+    assert!(items.next().unwrap().unwrap_err().is_syntax());
+    assert!(items.next().is_none());
+}
+
+#[test]
+fn test_heapless() {
+    #[derive(Debug, Deserialize)]
+    struct Reading {
+        unit: heapless::String<4>,
+        values: heapless::Vec<i8, 2>,
+    }
+
+    // {"unit": (_ "de" "g"), "values": [-1, 1]}
+    let input = b"\xa2\x64unit\x7f\x62de\x61g\xff\x66values\x82\x20\x01";
+    let mut scratch = [0; 4];
+    let reading: Reading = de::from_slice_with_scratch(input, &mut scratch).unwrap();
+    assert_eq!(reading.unit, "deg");
+    assert_eq!(reading.values, [-1, 1]);
+
+    // {"unit": "kelvin", ...
+    let input = b"\xa2\x64unit\x66kelvin";
+    let err = de::from_slice_with_scratch::<Reading>(input, &mut []).unwrap_err();
+    assert!(err.is_data());
+    assert_eq!(err.invalid_length(), Some(6));
+    assert_eq!(err.offset(), 6);
+
+    // {"unit": "K", "values": [_ 1, 2, 3]}
+    let input = b"\xa2\x64unit\x61K\x66values\x9f\x01\x02\x03\xff";
+    let err = de::from_slice_with_scratch::<Reading>(input, &mut []).unwrap_err();
+    assert_eq!(err.invalid_length(), Some(3));
+    assert_eq!(err.offset(), 15);
+    #[cfg(feature = "std")]
+    assert_eq!(
+        err.to_string(),
+        "invalid length 3, expected a sequence at offset 15"
+    );
+}
+
 #[cfg(feature = "std")]
 mod std_tests {
+    use std::borrow::Cow;
     use std::collections::BTreeMap;
 
     use serde::de as serde_de;
@@ -409,7 +536,7 @@ mod std_tests {
         assert_eq!(rest, &[0x66, 0x66, 0x6f, 0x6f, 0x62, 0x61, 0x72]);
         let (rest, value): (&[u8], String) = from_slice_stream(rest).unwrap();
         assert_eq!(value, "foobar");
-        assert_eq!(rest, &[]);
+        assert_eq!(rest, &[] as &[u8]);
     }
 
     #[derive(Debug, Copy, Clone)]
@@ -708,6 +835,46 @@ mod std_tests {
         );
     }
 
+    #[test]
+    fn test_packed_structs_deserialization() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Hot {
+            a: u8,
+            b: u8,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Envelope {
+            id: u8,
+            hot: Hot,
+        }
+
+        let decode = |v: &[u8]| {
+            let options = CustomDeserializerOptions::new()
+                .set_accept_packed_format(false)
+                .set_packed_structs(&["Hot"]);
+            let mut deserializer = Deserializer::new_with_options(de::SliceRead::new(v), options);
+            serde_de::Deserialize::deserialize(&mut deserializer)
+        };
+
+        // {"id": 1, "hot": {0: 2, 1: 3}}
+        let envelope: Envelope = decode(b"\xa2\x62id\x01\x63hot\xa2\x00\x02\x01\x03").unwrap();
+        assert_eq!(
+            envelope,
+            Envelope {
+                id: 1,
+                hot: Hot { a: 2, b: 3 }
+            }
+        );
+
+        // {"id": 1, "hot": {"a": 2, "b": 3}}
+        let err = decode(b"\xa2\x62id\x01\x63hot\xa2\x61a\x02\x61b\x03").unwrap_err();
+        assert!(err.is_syntax());
+        // {0: 1, 1: {0: 2, 1: 3}}
+        let err = decode(b"\xa2\x00\x01\x01\xa2\x00\x02\x01\x03").unwrap_err();
+        assert!(err.is_syntax());
+    }
+
     use serde_cbor::{de::from_slice, ser::to_vec_packed};
     use std::net::{IpAddr, Ipv4Addr};
     #[test]
@@ -732,4 +899,1228 @@ mod std_tests {
         let err = serde_cbor::from_slice::<serde_cbor::Value>(&input).expect_err("recursion limit");
         assert!(err.is_syntax());
     }
+
+    #[test]
+    fn test_array_iter() {
+        let mut de = Deserializer::from_slice(b"\x83\x01\x02\x03\x04");
+        assert_eq!(de.array_iter::<u32>().unwrap().size_hint(), (0, Some(3)));
+        let mut de = Deserializer::from_slice(b"\x83\x01\x02\x03\x04");
+        let items: Vec<u32> = de
+            .array_iter()
+            .unwrap()
+            .collect::<error::Result<_>>()
+            .unwrap();
+        assert_eq!(items, [1, 2, 3]);
+        assert_eq!(de.byte_offset(), 4);
+    }
+
+    #[test]
+    fn test_array_iter_indefinite_reader() {
+        let data = b"\x9f\x63foo\x63bar\xff\x01";
+        let mut de = Deserializer::from_reader(&data[..]);
+        let mut iter = de.array_iter::<String>().unwrap();
+        assert_eq!(iter.size_hint(), (0, None));
+        assert_eq!(iter.next().unwrap().unwrap(), "foo");
+        assert_eq!(iter.next().unwrap().unwrap(), "bar");
+        assert!(iter.next().is_none());
+        drop(iter);
+        let trailing: u8 = serde_de::Deserialize::deserialize(&mut de).unwrap();
+        assert_eq!(trailing, 1);
+        de.end().unwrap();
+    }
+
+    #[test]
+    fn test_array_iter_errors() {
+        let mut de = Deserializer::from_slice(b"\xa0");
+        assert!(de.array_iter::<u32>().unwrap_err().is_syntax());
+
+        let mut de = Deserializer::from_slice(b"\x9f\x01");
+        let mut iter = de.array_iter::<u32>().unwrap();
+        assert_eq!(iter.next().unwrap().unwrap(), 1);
+        assert!(iter.next().unwrap().unwrap_err().is_eof());
+        assert!(iter.next().is_none());
+
+        let mut de = Deserializer::from_slice(b"\x82\x01\x61a");
+        let mut iter = de.array_iter::<u32>().unwrap();
+        assert_eq!(iter.next().unwrap().unwrap(), 1);
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_map_iter() {
+        let data = b"\xbf\x61a\x01\x61b\x02\xff";
+        let mut de = Deserializer::from_slice(data);
+        let entries: Vec<(&str, u32)> = de
+            .map_iter()
+            .unwrap()
+            .collect::<error::Result<_>>()
+            .unwrap();
+        assert_eq!(entries, [("a", 1), ("b", 2)]);
+        de.end().unwrap();
+
+        let mut de = Deserializer::from_slice(b"\xa2\x61a\x01\x61b\x20");
+        let mut iter = de.map_iter::<&str, u32>().unwrap();
+        assert_eq!(iter.size_hint(), (0, Some(2)));
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_err());
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_skip_ignored_fields() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Small {
+            b: u8,
+        }
+        // {"a": [_ h'01', h'02' ], "c": 1(_ "x" "y"), "b": 5, "d": {_ 1: [1.5, null]}}
+        let data = b"\xa4\x61a\x5f\x41\x01\x41\x02\xff\x61c\xc1\x7f\x61x\x61y\xff\x61b\x05\x61d\xbf\x01\x82\xf9\x3e\x00\xf6\xff";
+        let value: Small = de::from_slice(data).unwrap();
+        assert_eq!(value, Small { b: 5 });
+        let value: Small = de::from_reader(&data[..]).unwrap();
+        assert_eq!(value, Small { b: 5 });
+
+        // An indefinite text string with a byte string chunk is malformed.
+        let data = b"\xa2\x61a\x7f\x41\x01\xff\x61b\x05";
+        assert!(de::from_slice::<Small>(data).unwrap_err().is_syntax());
+
+        // Ignored fields are validated like the others.
+        let data = b"\xa2\x61a\x61\xff\x61b\x05";
+        let err = de::from_slice::<Small>(data).unwrap_err();
+        assert!(err.is_syntax());
+        assert_eq!(err.offset(), 4);
+        assert!(de::from_reader::<Small, _>(&data[..]).is_err());
+        let data = b"\xa2\x61a\x7f\x61x\x61\xff\xff\x61b\x05";
+        assert!(de::from_slice::<Small>(data).unwrap_err().is_syntax());
+        let data = b"\xa2\x61a\xf0\x61b\x05";
+        assert!(de::from_slice::<Small>(data).unwrap_err().is_syntax());
+        let data = b"\xa2\x61a\xf8\x20\x61b\x05";
+        assert!(de::from_slice::<Small>(data).unwrap_err().is_syntax());
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct CowFields<'a> {
+        #[serde(borrow)]
+        text: Cow<'a, str>,
+        #[serde(borrow)]
+        data: Cow<'a, [u8]>,
+    }
+
+    #[test]
+    fn test_cow_borrowed_from_slice() {
+        // {"text": "hi", "data": h'0102'}
+        let input = b"\xa2\x64text\x62hi\x64data\x42\x01\x02";
+        let value: CowFields = de::from_slice(input).unwrap();
+        assert!(matches!(value.text, Cow::Borrowed("hi")));
+        assert!(matches!(value.data, Cow::Borrowed(&[1, 2])));
+
+        let mut scratch = [0u8; 8];
+        let value: CowFields = de::from_slice_with_scratch(input, &mut scratch).unwrap();
+        assert!(matches!(value.text, Cow::Borrowed("hi")));
+        assert!(matches!(value.data, Cow::Borrowed(&[1, 2])));
+
+        let value: Cow<str> = de::from_slice(b"\x62hi").unwrap();
+        assert_eq!(value, "hi");
+    }
+
+    #[test]
+    fn test_cow_chunked() {
+        // {"text": (_ "h" "i"), "data": (_ h'01' h'02')}
+        let input = b"\xa2\x64text\x7f\x61h\x61i\xff\x64data\x5f\x41\x01\x41\x02\xff";
+        let value: CowFields = de::from_slice(input).unwrap();
+        assert!(matches!(value.text, Cow::Owned(ref s) if s == "hi"));
+        assert!(matches!(value.data, Cow::Owned(ref b) if b == &[1, 2]));
+
+        let mut input = input.to_vec();
+        let value: CowFields = de::from_mut_slice(&mut input).unwrap();
+        assert!(matches!(value.text, Cow::Borrowed("hi")));
+        assert!(matches!(value.data, Cow::Borrowed(&[1, 2])));
+    }
+
+    #[test]
+    fn test_cow_owned_from_reader() {
+        #[derive(Debug, Deserialize)]
+        struct Owned {
+            text: Cow<'static, str>,
+        }
+        let input = b"\xa1\x64text\x62hi";
+        let value: Owned = de::from_reader(&input[..]).unwrap();
+        assert!(matches!(value.text, Cow::Owned(ref s) if s == "hi"));
+    }
+
+    #[test]
+    fn test_from_reader_not_send() {
+        use std::cell::RefCell;
+        use std::io;
+        use std::rc::Rc;
+
+        struct SharedReader(Rc<RefCell<io::Cursor<Vec<u8>>>>);
+
+        impl io::Read for SharedReader {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.0.borrow_mut().read(buf)
+            }
+        }
+
+        let cursor = Rc::new(RefCell::new(io::Cursor::new(vec![0x82, 0x01, 0x02])));
+        let value: Vec<u8> = de::from_reader(SharedReader(cursor)).unwrap();
+        assert_eq!(value, [1, 2]);
+    }
+
+    #[test]
+    fn test_from_reader_one() {
+        use std::io::Read;
+
+        // [_ 1], {"a": null}, then a partial value
+        let mut stream: &[u8] = b"\x9f\x01\xff\xa1\x61a\xf6\x82";
+        let first: Vec<u8> = de::from_reader_one(&mut stream).unwrap();
+        assert_eq!(first, [1]);
+        let second: BTreeMap<String, Option<u8>> = de::from_reader_one(&mut stream).unwrap();
+        assert_eq!(second.get("a"), Some(&None));
+        assert_eq!(stream, b"\x82");
+
+        let err = de::from_reader_one::<Vec<u8>, _>(&mut stream).unwrap_err();
+        assert!(err.is_eof());
+        let mut rest = Vec::new();
+        stream.read_to_end(&mut rest).unwrap();
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn test_from_reader_in() {
+        use serde_cbor::arena::Arena;
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Record<'a> {
+            name: &'a str,
+            #[serde(with = "serde_bytes")]
+            data: &'a [u8],
+            owned: String,
+        }
+
+        // {"name": "n", "data": h'0102', "owned": "o", "skipped": "s"}, then {"name": ...
+        let mut stream: &[u8] = b"\xa4\x64name\x61n\x64data\x42\x01\x02\x65owned\x61o\
+                                  \x67skipped\x61s\xa1\x64name";
+        let mut arena = Arena::with_chunk_size(16);
+        let record: Record = de::from_reader_in(&mut stream, &arena).unwrap();
+        assert_eq!(
+            record,
+            Record {
+                name: "n",
+                data: &[1, 2],
+                owned: "o".into()
+            }
+        );
+        // keys and skipped strings are stored as well, owned strings are not
+        assert_eq!(arena.allocated(), 24);
+        assert_eq!(stream, b"\xa1\x64name");
+
+        arena.reset();
+        assert_eq!(arena.allocated(), 0);
+        let err = de::from_reader_in::<Record, _>(&mut stream, &arena).unwrap_err();
+        assert!(err.is_eof());
+
+        let long = "x".repeat(100);
+        let encoded = serde_cbor::to_vec(&(&long, "y")).unwrap();
+        let (x, y): (&str, &str) = de::from_reader_in(&encoded[..], &arena).unwrap();
+        assert_eq!((x, y), (&long[..], "y"));
+    }
+
+    #[test]
+    fn test_no_read_past_item() {
+        use std::io;
+
+        /// Yields `data` and then fails as a socket without further data would.
+        struct Socket<'a> {
+            data: &'a [u8],
+        }
+
+        impl<'a> io::Read for Socket<'a> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.data.is_empty() {
+                    return Err(io::ErrorKind::WouldBlock.into());
+                }
+                io::Read::read(&mut self.data, buf)
+            }
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        enum Kind {
+            A,
+            B(u8),
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Message {
+            kind: Kind,
+            tail: Option<Vec<u8>>,
+        }
+
+        // {"kind": {"B": 1}, "skip": [_ "x"], "tail": null}, then 0x07
+        let data = b"\xa3\x64kind\xa1\x61B\x01\x64skip\x9f\x61x\xff\x64tail\xf6\x07";
+        let mut de = Deserializer::from_reader(Socket { data });
+        let message: Message = serde::Deserialize::deserialize(&mut de).unwrap();
+        assert_eq!(
+            message,
+            Message {
+                kind: Kind::B(1),
+                tail: None
+            }
+        );
+        let socket = de.into_inner().into_inner();
+        assert_eq!(socket.data, b"\x07");
+
+        // Items ending in an optional value or an indefinite collection.
+        for data in &[
+            &b"\x81\xf6"[..],
+            b"\x9f\x9f\xff\xff",
+            b"\x61A",
+            b"\xbf\x01\xf6\xff",
+        ] {
+            let mut de = Deserializer::from_reader(Socket { data });
+            let _: Value = serde::Deserialize::deserialize(&mut de).unwrap();
+            assert!(de.into_inner().into_inner().data.is_empty());
+        }
+        let mut de = Deserializer::from_reader(Socket { data: b"\x61A" });
+        let _: Kind = serde::Deserialize::deserialize(&mut de).unwrap();
+        let mut de = Deserializer::from_reader(Socket {
+            data: b"\x82\xf6\xf6",
+        });
+        let _: (Option<u8>, Option<u8>) = serde::Deserialize::deserialize(&mut de).unwrap();
+    }
+
+    #[test]
+    fn test_from_reader_tagged_enums() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        #[serde(tag = "type")]
+        enum Internal {
+            Blob {
+                #[serde(with = "serde_bytes")]
+                data: Vec<u8>,
+            },
+            Text {
+                text: String,
+            },
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        #[serde(tag = "t", content = "c")]
+        enum Adjacent {
+            Pair(u8, u8),
+        }
+
+        // {"type": "Blob", "data": (_ h'01', h'02')}
+        let input = b"\xa2\x64type\x64Blob\x64data\x5f\x41\x01\x41\x02\xff";
+        let value: Internal = de::from_reader(&input[..]).unwrap();
+        assert_eq!(value, Internal::Blob { data: vec![1, 2] });
+
+        // {_ "text": "hi", 1: 2, "type": "Text"}
+        let input = b"\xbf\x64text\x62hi\x01\x02\x64type\x64Text\xff";
+        let value: Internal = de::from_reader(&input[..]).unwrap();
+        assert_eq!(
+            value,
+            Internal::Text {
+                text: "hi".to_owned()
+            }
+        );
+
+        // {"t": "Pair", "c": [1, 2]}
+        let input = b"\xa2\x61t\x64Pair\x61c\x82\x01\x02";
+        let value: Adjacent = de::from_reader(&input[..]).unwrap();
+        assert_eq!(value, Adjacent::Pair(1, 2));
+    }
+
+    #[test]
+    fn test_key_renames() {
+        use serde_cbor::de::WireKey;
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Shape {
+            name: String,
+            origin: Point,
+            labels: BTreeMap<String, u8>,
+        }
+
+        const RENAMES: &[(WireKey, &str)] = &[
+            (WireKey::Text("title"), "name"),
+            (WireKey::Text("x_pos"), "x"),
+            (WireKey::Integer(7), "y"),
+        ];
+        let options = || CustomDeserializerOptions::new().set_key_renames(RENAMES);
+        let expected = Shape {
+            name: "a".to_owned(),
+            origin: Point { x: 1, y: 2 },
+            labels: vec![("title".to_owned(), 3)].into_iter().collect(),
+        };
+
+        // {"title": "a", "origin": {"x_pos": 1, 7: 2}, "labels": {"title": 3}}
+        let input =
+            b"\xa3\x65title\x61a\x66origin\xa2\x65x_pos\x01\x07\x02\x66labels\xa1\x65title\x03";
+        let mut deserializer = Deserializer::new_with_options(de::SliceRead::new(input), options());
+        let value: Shape = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(value, expected);
+
+        let mut deserializer =
+            Deserializer::new_with_options(de::IoRead::new(&input[..]), options());
+        let value: Shape = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(value, expected);
+
+        // Keys that are not renamed keep working, in either format.
+        // {"name": "a", "origin": {0: 1, "y": 2}, "labels": {}}
+        let input = b"\xa3\x64name\x61a\x66origin\xa2\x00\x01\x61y\x02\x66labels\xa0";
+        let mut deserializer = Deserializer::new_with_options(de::SliceRead::new(input), options());
+        let value: Shape = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(value.origin, Point { x: 1, y: 2 });
+
+        // Invalid UTF-8 in a key is still reported.
+        let input = b"\xa1\x61\xff\x00";
+        let mut deserializer = Deserializer::new_with_options(de::SliceRead::new(input), options());
+        let err = <Point as serde_de::Deserialize>::deserialize(&mut deserializer).unwrap_err();
+        assert!(err.is_syntax());
+    }
+
+    #[test]
+    fn test_byte_string_keys() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        enum Unit {
+            Celsius,
+            Kelvin,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Reading {
+            id: u8,
+            unit: Unit,
+        }
+
+        let options = || CustomDeserializerOptions::new().set_accept_byte_string_keys(true);
+        let expected = Reading {
+            id: 3,
+            unit: Unit::Kelvin,
+        };
+
+        // {h'6964': 3, "extra": 0, (_ h'75', h'6e6974'): h'4b656c76696e'}
+        let input = b"\xa3\x42id\x03\x65extra\x00\x5f\x41u\x43nit\xff\x46Kelvin";
+        let mut deserializer = Deserializer::new_with_options(de::SliceRead::new(input), options());
+        let value: Reading = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(value, expected);
+        let mut deserializer =
+            Deserializer::new_with_options(de::IoRead::new(&input[..]), options());
+        let value: Reading = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(value, expected);
+
+        let err = from_slice::<Reading>(input).unwrap_err();
+        assert!(err.is_syntax());
+        let packed = options().set_accept_named_format(false);
+        let mut deserializer = Deserializer::new_with_options(de::SliceRead::new(input), packed);
+        let err = <Reading as serde_de::Deserialize>::deserialize(&mut deserializer).unwrap_err();
+        assert_eq!(err.to_string(), "wrong struct format at offset 1");
+    }
+
+    #[test]
+    fn test_key_normalization() {
+        use serde_cbor::de::{KeyNormalization, WireKey};
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Limits {
+            max_size: u8,
+            min_size: u8,
+            labels: BTreeMap<String, u8>,
+        }
+
+        let decode = |input: &[u8], options: CustomDeserializerOptions| {
+            let mut deserializer = Deserializer::new_with_options(de::IoRead::new(input), options);
+            <Limits as serde_de::Deserialize>::deserialize(&mut deserializer)
+        };
+        let all = KeyNormalization::TRIM | KeyNormalization::LOWERCASE;
+        let all = all | KeyNormalization::KEBAB_TO_SNAKE;
+        assert!(all.contains(KeyNormalization::TRIM | KeyNormalization::LOWERCASE));
+        assert!(!KeyNormalization::TRIM.contains(all));
+
+        // {" MAX-size": 8, "min_size": 1, "labels": {"A-b": 2}}
+        let input = b"\xa3\x69 MAX-size\x08\x68min_size\x01\x66labels\xa1\x63A-b\x02";
+        let value = decode(
+            input,
+            CustomDeserializerOptions::new().set_key_normalization(all),
+        );
+        let value = value.unwrap();
+        assert_eq!((value.max_size, value.min_size), (8, 1));
+        // only the fields of structs are normalized
+        assert_eq!(value.labels.keys().collect::<Vec<_>>(), ["A-b"]);
+
+        let options = CustomDeserializerOptions::new()
+            .set_key_normalization(KeyNormalization::LOWERCASE | KeyNormalization::KEBAB_TO_SNAKE);
+        assert!(decode(input, options).is_err());
+
+        // Renamed keys are matched before they are normalized.
+        const RENAMES: &[(WireKey, &str)] = &[(WireKey::Text("MIN"), "min_size")];
+        let options = CustomDeserializerOptions::new()
+            .set_key_renames(RENAMES)
+            .set_key_normalization(all);
+        // {"Max-Size": 8, "MIN": 1, "labels": {}}
+        let input = b"\xa3\x68Max-Size\x08\x63MIN\x01\x66labels\xa0";
+        assert_eq!(decode(input, options).unwrap().min_size, 1);
+    }
+
+    #[test]
+    fn test_key_order() {
+        use serde_cbor::ser::KeyOrder;
+
+        #[derive(Debug, PartialEq, Deserialize, Serialize)]
+        struct Point {
+            x: u8,
+            yy: u8,
+        }
+
+        fn decode(input: &[u8], order: KeyOrder) -> error::Result<Value> {
+            let options = CustomDeserializerOptions::new().set_key_order(Some(order));
+            let mut deserializer =
+                Deserializer::new_with_options(de::SliceRead::new(input), options);
+            serde_de::Deserialize::deserialize(&mut deserializer)
+        }
+
+        // {256: 1, "a": 2} is ordered bytewise but not length first
+        let input = b"\xa2\x19\x01\x00\x01\x61a\x02";
+        assert!(decode(input, KeyOrder::Bytewise).is_ok());
+        let err = decode(input, KeyOrder::LengthFirst).unwrap_err();
+        assert_eq!(err.to_string(), "map key out of order at offset 5");
+        assert!(err.is_syntax());
+        // {10: 0, -1: 0, "a": {}, [1]: 0}, nested and of several types
+        let input = b"\xa4\x0a\x00\x20\x00\x61a\xa0\x81\x01\x00";
+        assert!(decode(input, KeyOrder::Bytewise).is_ok());
+        // {"a": {"b": 0, "a": 0}}
+        let input = b"\xa1\x61a\xa2\x61b\x00\x61a\x00";
+        let err = decode(input, KeyOrder::Bytewise).unwrap_err();
+        assert_eq!(err.offset(), 7);
+        // {"a": 0, "a": 1}
+        let input = b"\xa2\x61a\x00\x61a\x01";
+        assert!(decode(input, KeyOrder::LengthFirst).is_err());
+        // {_ "a": 0, "b": 1}
+        let input = b"\xbf\x61a\x00\x61b\x01\xff";
+        assert!(decode(input, KeyOrder::Bytewise).is_ok());
+
+        let options = CustomDeserializerOptions::new().set_key_order(Some(KeyOrder::LengthFirst));
+        // {"yy": 2, "x": 1}
+        let input = b"\xa2\x62yy\x02\x61x\x01";
+        let mut deserializer = Deserializer::new_with_options(de::SliceRead::new(input), options);
+        assert!(<Point as serde_de::Deserialize>::deserialize(&mut deserializer).is_err());
+        let value = serde_cbor::ser::to_vec_canonical(&Point { x: 1, yy: 2 }).unwrap();
+        let mut deserializer = Deserializer::new_with_options(de::SliceRead::new(&value), options);
+        let point: Point = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(point, Point { x: 1, yy: 2 });
+        // readers of streams copy the keys
+        let mut deserializer = Deserializer::new_with_options(de::IoRead::new(&value[..]), options);
+        let point: Point = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(point, Point { x: 1, yy: 2 });
+        let input = b"\xa2\x62yy\x02\x61x\x01";
+        let mut deserializer = Deserializer::new_with_options(de::IoRead::new(&input[..]), options);
+        let err = <Point as serde_de::Deserialize>::deserialize(&mut deserializer).unwrap_err();
+        assert_eq!(err.to_string(), "map key out of order at offset 5");
+
+        // maps in skipped values are checked as well
+        #[derive(Debug, Deserialize)]
+        struct Small {
+            #[allow(dead_code)]
+            b: u8,
+        }
+        // {"a": {"bb": 1, "c": 2}, "b": 5}
+        let input = b"\xa2\x61a\xa2\x62bb\x01\x61c\x02\x61b\x05";
+        let mut deserializer = Deserializer::new_with_options(de::SliceRead::new(input), options);
+        let err = <Small as serde_de::Deserialize>::deserialize(&mut deserializer).unwrap_err();
+        assert_eq!(err.to_string(), "map key out of order at offset 8");
+        let mut deserializer = Deserializer::new_with_options(de::IoRead::new(&input[..]), options);
+        assert!(<Small as serde_de::Deserialize>::deserialize(&mut deserializer).is_err());
+        // {"a": {_ "c": 2, "bb": 1}, "b": 5} with a map as the key of a map
+        let input = b"\xa2\x61a\xbf\x61c\x02\x62bb\x01\xff\x61b\x05";
+        let mut deserializer = Deserializer::new_with_options(de::IoRead::new(&input[..]), options);
+        let value: Small = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(value.b, 5);
+        // {{"c": 0}: 2, {"a": 0, "bb": 0}: 1}, maps as keys of a map
+        let input = b"\xa2\xa1\x61c\x00\x02\xa2\x61a\x00\x62bb\x00\x01";
+        let mut deserializer = Deserializer::new_with_options(de::IoRead::new(&input[..]), options);
+        assert!(
+            <serde_de::IgnoredAny as serde_de::Deserialize>::deserialize(&mut deserializer).is_ok()
+        );
+        let mut deserializer = Deserializer::new_with_options(de::IoRead::new(&input[..]), options);
+        assert!(<Value as serde_de::Deserialize>::deserialize(&mut deserializer).is_ok());
+        // {{"c": 0}: 2, {"bb": 0, "a": 0}: 1}
+        let input = b"\xa2\xa1\x61c\x00\x02\xa2\x62bb\x00\x61a\x00\x01";
+        let mut deserializer = Deserializer::new_with_options(de::IoRead::new(&input[..]), options);
+        assert!(
+            <serde_de::IgnoredAny as serde_de::Deserialize>::deserialize(&mut deserializer)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_undefined_and_tagged_null_as_none() {
+        fn decode(
+            input: &[u8],
+            options: CustomDeserializerOptions,
+        ) -> error::Result<Vec<Option<u8>>> {
+            let mut deserializer =
+                Deserializer::new_with_options(de::SliceRead::new(input), options);
+            serde_de::Deserialize::deserialize(&mut deserializer)
+        }
+
+        // [undefined]
+        let input = b"\x81\xf7";
+        assert!(decode(input, CustomDeserializerOptions::new()).is_err());
+        let options = CustomDeserializerOptions::new().set_undefined_as_none(true);
+        assert_eq!(decode(input, options).unwrap(), [None]);
+
+        // [1(null), 55799(1(undefined)), 2]
+        let input = b"\x83\xc1\xf6\xd9\xd9\xf7\xc1\xf7\x02";
+        let options = CustomDeserializerOptions::new().set_tagged_null_as_none(true);
+        assert!(decode(input, options).is_err());
+        let options = options.set_undefined_as_none(true);
+        assert_eq!(decode(input, options).unwrap(), [None, None, Some(2)]);
+        let mut input = input.to_vec();
+        let values: Vec<Option<u8>> =
+            Deserializer::new_with_options(de::MutSliceRead::new(&mut input), options)
+                .into_iter()
+                .next()
+                .unwrap()
+                .unwrap();
+        assert_eq!(values, [None, None, Some(2)]);
+
+        // readers can't look past the tags
+        let mut deserializer =
+            Deserializer::new_with_options(de::IoRead::new(&b"\xc1\xf6"[..]), options);
+        assert!(<Option<u8> as serde_de::Deserialize>::deserialize(&mut deserializer).is_err());
+    }
+
+    #[test]
+    fn test_simple_values() {
+        use serde_cbor::de::SimpleValues;
+
+        fn decode<T: serde_de::DeserializeOwned>(
+            input: &[u8],
+            policy: SimpleValues,
+        ) -> error::Result<T> {
+            let options = CustomDeserializerOptions::new().set_simple_values(policy);
+            let mut deserializer =
+                Deserializer::new_with_options(de::SliceRead::new(input), options);
+            serde_de::Deserialize::deserialize(&mut deserializer)
+        }
+
+        // [simple(0), simple(19), simple(32), simple(255)]
+        let input = b"\x84\xe0\xf3\xf8\x20\xf8\xff";
+        let err = decode::<Value>(input, SimpleValues::Reject).unwrap_err();
+        assert!(err.is_syntax());
+        assert_eq!(err.offset(), 2);
+        let value = decode::<Value>(input, SimpleValues::Number).unwrap();
+        let simple = [0, 19, 32, 255].iter().map(|&v| Value::Simple(v)).collect();
+        assert_eq!(value, Value::Array(simple));
+        assert_eq!(to_vec(&value).unwrap(), input);
+        assert_eq!(
+            decode::<Vec<u8>>(input, SimpleValues::Number).unwrap(),
+            [0, 19, 32, 255]
+        );
+        assert!(decode::<Vec<String>>(input, SimpleValues::Number).is_err());
+
+        // two byte forms below 32 are not well-formed
+        assert!(decode::<Value>(b"\xf8\x10", SimpleValues::Number).is_err());
+        assert!(decode::<Value>(b"\xf8\x1f", SimpleValues::Number).is_err());
+        assert!(to_vec(&Value::Simple(24)).is_err());
+
+        fn map(value: u8) -> Option<u8> {
+            match value {
+                0 => Some(21),
+                19 => Some(22),
+                32 => Some(7),
+                _ => None,
+            }
+        }
+        let policy = SimpleValues::Map(map);
+        assert!(decode::<Value>(input, policy).is_err());
+        let value = decode::<Value>(b"\x83\xe0\xf3\xf8\x20", policy).unwrap();
+        let expected = vec![Value::Bool(true), Value::Null, Value::Simple(7)];
+        assert_eq!(value, Value::Array(expected));
+        let value: (bool, Option<u8>, u8) = decode(b"\x83\xe0\xf3\xf8\x20", policy).unwrap();
+        assert_eq!(value, (true, None, 7));
+        assert_eq!(policy, SimpleValues::Map(map));
+        assert_ne!(policy, SimpleValues::Number);
+
+        // skipped items follow the same policy
+        let value: Vec<serde_de::IgnoredAny> = decode(input, SimpleValues::Number).unwrap();
+        assert_eq!(value.len(), 4);
+        assert!(decode::<Vec<serde_de::IgnoredAny>>(input, SimpleValues::Reject).is_err());
+    }
+
+    #[test]
+    fn test_max_indefinite_chunks() {
+        fn decode<T: serde_de::DeserializeOwned>(input: &[u8], max: usize) -> error::Result<T> {
+            let options = CustomDeserializerOptions::new().set_max_indefinite_chunks(max);
+            let mut deserializer =
+                Deserializer::new_with_options(de::SliceRead::new(input), options);
+            serde_de::Deserialize::deserialize(&mut deserializer)
+        }
+
+        // (_ "a", "b", "")
+        let input = b"\x7f\x61a\x61b\x60\xff";
+        assert_eq!(decode::<String>(input, 3).unwrap(), "ab");
+        let err = decode::<String>(input, 2).unwrap_err();
+        assert!(err.is_syntax());
+        assert_eq!(err.offset(), 6);
+        // (_ h'01', h'02')
+        let input = b"\x5f\x41\x01\x41\x02\xff";
+        assert_eq!(decode::<Value>(input, 2).unwrap(), Value::Bytes(vec![1, 2]));
+        assert!(decode::<Value>(input, 1).is_err());
+
+        // [_ 1, 2, 3] and {_ 1: 2, 3: 4}
+        let array = b"\x9f\x01\x02\x03\xff";
+        let map = b"\xbf\x01\x02\x03\x04\xff";
+        assert_eq!(decode::<Vec<u8>>(array, 3).unwrap(), [1, 2, 3]);
+        assert_eq!(decode::<Vec<u8>>(array, 2).unwrap_err().offset(), 3);
+        assert_eq!(
+            decode::<Value>(map, 2).unwrap(),
+            decode::<Value>(map, 3).unwrap()
+        );
+        assert!(decode::<Value>(map, 1).is_err());
+        assert!(decode::<serde_de::IgnoredAny>(array, 2).is_err());
+        assert!(decode::<serde_de::IgnoredAny>(map, 1).is_err());
+        assert!(decode::<serde_de::IgnoredAny>(map, 2).is_ok());
+
+        // definite length items are not limited
+        assert_eq!(
+            decode::<Vec<u8>>(b"\x83\x01\x02\x03", 0).unwrap(),
+            [1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_strict_lengths() {
+        /// The first entry of a map.
+        #[derive(Debug)]
+        struct First(String, u8);
+
+        impl<'de> serde_de::Deserialize<'de> for First {
+            fn deserialize<D: serde_de::Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<First, D::Error> {
+                struct Visitor;
+
+                impl<'de> serde_de::Visitor<'de> for Visitor {
+                    type Value = First;
+
+                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        f.write_str("a map")
+                    }
+
+                    fn visit_map<A: serde_de::MapAccess<'de>>(
+                        self,
+                        mut map: A,
+                    ) -> Result<First, A::Error> {
+                        let (key, value) = map.next_entry()?.unwrap();
+                        Ok(First(key, value))
+                    }
+                }
+
+                deserializer.deserialize_map(Visitor)
+            }
+        }
+
+        fn decode<'a, T: serde_de::Deserialize<'a>>(
+            input: &'a [u8],
+            strict: bool,
+        ) -> error::Result<T> {
+            let options = CustomDeserializerOptions::new().set_strict_lengths(strict);
+            let mut deserializer =
+                Deserializer::new_with_options(de::SliceRead::new(input), options);
+            serde_de::Deserialize::deserialize(&mut deserializer)
+        }
+
+        // [{"a": 1, "b": 2}]
+        let input = b"\x81\xa2\x61a\x01\x61b\x02";
+        let err = decode::<(First,)>(input, false).unwrap_err();
+        assert!(err.is_syntax());
+        assert_eq!(err.unread_element(), None);
+        assert_eq!(err.to_string(), "trailing data at offset 5");
+        let err = decode::<(First,)>(input, true).unwrap_err();
+        assert!(err.is_syntax());
+        assert_eq!(err.unread_element(), Some(1));
+        assert_eq!(err.declared_elements(), Some(2));
+        assert_eq!(
+            err.to_string(),
+            "map of 2 entries has entry 1 left unread at offset 5"
+        );
+
+        // collections read to the end and indefinite lengths are unaffected
+        assert_eq!(decode::<(u8, u8)>(b"\x82\x01\x02", true).unwrap(), (1, 2));
+        let first = decode::<First>(b"\xa1\x61a\x01", true).unwrap();
+        assert_eq!((first.0.as_str(), first.1), ("a", 1));
+        let err = decode::<(u8,)>(b"\x9f\x01\x02\xff", true).unwrap_err();
+        assert_eq!(err.unread_element(), None);
+        assert!(err.is_syntax());
+    }
+
+    #[test]
+    fn test_deny_unknown_fields() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Point {
+            x: i8,
+            y: i8,
+        }
+
+        let decode = |input: &[u8], options: CustomDeserializerOptions| {
+            let mut deserializer =
+                Deserializer::new_with_options(de::SliceRead::new(input), options);
+            <Point as serde_de::Deserialize>::deserialize(&mut deserializer)
+        };
+        let deny = || CustomDeserializerOptions::new().set_deny_unknown_fields(true);
+
+        // {"x": 1, "z": [1, 2], "y": 3}
+        let input = b"\xa3\x61x\x01\x61z\x82\x01\x02\x61y\x03";
+        assert_eq!(
+            decode(input, CustomDeserializerOptions::new()).unwrap(),
+            Point { x: 1, y: 3 }
+        );
+        let err = decode(input, deny()).unwrap_err();
+        assert_eq!(err.offset(), 4);
+        assert_eq!(
+            err.to_string(),
+            "unknown field `z` in struct `Point` at offset 4"
+        );
+
+        // [1, 3, 5] in the packed format, the third field does not exist
+        let input = b"\xa3\x00\x01\x01\x03\x02\x05";
+        assert_eq!(
+            decode(input, CustomDeserializerOptions::new()).unwrap(),
+            Point { x: 1, y: 3 }
+        );
+        let err = decode(input, deny()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown field 2 in struct `Point` at offset 5"
+        );
+
+        // {"x": 1, (_ "z"): 2, "y": 3}, indefinite length keys are looked up as well
+        let input = b"\xa3\x61x\x01\x7f\x61z\xff\x02\x61y\x03";
+        let err = decode(input, deny()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown field `z` in struct `Point` at offset 4"
+        );
+        // {"x": 1, (_ "y"): 3}
+        let input = b"\xa2\x61x\x01\x7f\x61y\xff\x03";
+        assert_eq!(decode(input, deny()).unwrap(), Point { x: 1, y: 3 });
+
+        // {"x": 1, h'7a': 2, "y": 3} and the same with the key h'ff'
+        let bytes = || deny().set_accept_byte_string_keys(true);
+        let input = b"\xa3\x61x\x01\x41z\x02\x61y\x03";
+        let err = decode(input, bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown field `z` in struct `Point` at offset 4"
+        );
+        let input = b"\xa3\x61x\x01\x41\xff\x02\x61y\x03";
+        let err = decode(input, bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown byte string field in struct `Point` at offset 4"
+        );
+        // {h'78': 1, (_ h'79'): 3}
+        let input = b"\xa2\x41x\x01\x5f\x41y\xff\x03";
+        assert_eq!(decode(input, bytes()).unwrap(), Point { x: 1, y: 3 });
+
+        // Known fields and maps that are not structs are unaffected.
+        let input = b"\xa2\x61y\x03\x61x\x01";
+        assert_eq!(decode(input, deny()).unwrap(), Point { x: 1, y: 3 });
+        let input = b"\xa1\x61z\x01";
+        let mut deserializer = Deserializer::new_with_options(de::SliceRead::new(input), deny());
+        let map: BTreeMap<String, u8> =
+            serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(map["z"], 1);
+    }
+
+    #[test]
+    fn test_lenient_numbers() {
+        fn decode<'a, T: serde_de::Deserialize<'a>>(
+            input: &'a [u8],
+            options: CustomDeserializerOptions,
+        ) -> error::Result<T> {
+            let mut deserializer =
+                Deserializer::new_with_options(de::SliceRead::new(input), options);
+            serde_de::Deserialize::deserialize(&mut deserializer)
+        }
+        let floats = || CustomDeserializerOptions::new().set_accept_integral_floats(true);
+        let strings = || CustomDeserializerOptions::new().set_accept_numeric_strings(true);
+        let bytes = || CustomDeserializerOptions::new().set_accept_integer_bytes(true);
+
+        // All coercions are off by default.
+        assert!(decode::<u8>(b"\xf9\x42\x00", CustomDeserializerOptions::new()).is_err());
+        assert!(decode::<u8>(b"\x62\x34\x32", CustomDeserializerOptions::new()).is_err());
+        assert!(decode::<u8>(b"\x41\x01", CustomDeserializerOptions::new()).is_err());
+
+        // 3.0, -2.0, 1.5, 256.0
+        assert_eq!(decode::<u8>(b"\xf9\x42\x00", floats()).unwrap(), 3);
+        assert_eq!(
+            decode::<i32>(b"\xfb\xc0\x00\x00\x00\x00\x00\x00\x00", floats()).unwrap(),
+            -2
+        );
+        assert!(decode::<u8>(b"\xf9\x3e\x00", floats())
+            .unwrap_err()
+            .is_data());
+        assert!(decode::<u8>(b"\xf9\x5c\x00", floats())
+            .unwrap_err()
+            .is_data());
+        assert_eq!(decode::<u8>(b"\x07", floats()).unwrap(), 7);
+
+        // "42", " -7", "1.5", "x"
+        assert_eq!(decode::<u64>(b"\x62\x34\x32", strings()).unwrap(), 42);
+        assert_eq!(decode::<i8>(b"\x63\x20\x2d\x37", strings()).unwrap(), -7);
+        assert_eq!(decode::<f32>(b"\x63\x31\x2e\x35", strings()).unwrap(), 1.5);
+        assert!(decode::<u64>(b"\x63\x31\x2e\x35", strings())
+            .unwrap_err()
+            .is_data());
+        assert!(decode::<u64>(b"\x61x", strings()).unwrap_err().is_data());
+        assert_eq!(decode::<String>(b"\x62\x34\x32", strings()).unwrap(), "42");
+
+        // h'0102', h'', h'010203040506070809'
+        assert_eq!(decode::<u16>(b"\x42\x01\x02", bytes()).unwrap(), 0x0102);
+        assert_eq!(decode::<u16>(b"\x40", bytes()).unwrap(), 0);
+        assert!(decode::<u8>(b"\x42\x01\x02", bytes())
+            .unwrap_err()
+            .is_data());
+        assert!(decode::<u64>(b"\x49\x01\x02\x03\x04\x05\x06\x07\x08\x09", bytes()).is_err());
+        assert!(decode::<f64>(b"\x41\x01", bytes()).is_err());
+    }
+
+    #[test]
+    fn test_integer_out_of_range() {
+        #[derive(Debug, Deserialize)]
+        struct Pixel {
+            #[allow(dead_code)]
+            red: u8,
+        }
+
+        // {"red": 300}
+        let err = de::from_slice::<Pixel>(b"\xa1\x63red\x19\x01\x2c").unwrap_err();
+        assert!(err.is_data());
+        assert_eq!(err.offset(), 5);
+        assert_eq!(
+            err.to_string(),
+            "integer 300 out of range for u8 at offset 5"
+        );
+
+        // [-129]
+        let err = de::from_slice::<Vec<i8>>(b"\x81\x38\x80").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "integer -129 out of range for i8 at offset 1"
+        );
+        // [-18446744073709551616]
+        let err =
+            de::from_slice::<Vec<i16>>(b"\x81\x3b\xff\xff\xff\xff\xff\xff\xff\xff").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "integer -18446744073709551616 out of range for i16 at offset 1"
+        );
+
+        assert_eq!(de::from_slice::<i16>(b"\x39\x7f\xff").unwrap(), i16::MIN);
+        assert_eq!(
+            de::from_slice::<u32>(b"\x1a\xff\xff\xff\xff").unwrap(),
+            u32::MAX
+        );
+        assert!(de::from_slice::<u8>(b"\x61a").unwrap_err().is_syntax());
+
+        // [-1], negative integers for unsigned types
+        let err = de::from_slice::<Vec<u8>>(b"\x81\x20").unwrap_err();
+        assert!(err.is_data());
+        assert_eq!(
+            err.to_string(),
+            "integer -1 out of range for u8 at offset 1"
+        );
+        let err = de::from_slice::<u64>(b"\x3b\xff\xff\xff\xff\xff\xff\xff\xff").unwrap_err();
+        // Data errors at the start of the input are shown without an offset, EOF errors with one.
+        assert_eq!(err.try_offset(), Some(0));
+        assert_eq!(
+            err.to_string(),
+            "integer -18446744073709551616 out of range for u64"
+        );
+        let err = de::from_slice::<u64>(b"").unwrap_err();
+        assert_eq!(err.to_string(), "EOF while parsing a value at offset 0");
+        assert_eq!(
+            de::from_slice::<u64>(b"\x1b\xff\xff\xff\xff\xff\xff\xff\xff").unwrap(),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_custom_error_offset() {
+        #[derive(Debug, Deserialize)]
+        struct Inner {
+            #[allow(dead_code)]
+            x: u8,
+        }
+
+        // [{"x": 1}, {}]
+        let err = de::from_slice::<Vec<Inner>>(b"\x82\xa1\x61x\x01\xa0").unwrap_err();
+        assert!(err.is_data());
+        assert_eq!(err.offset(), 5);
+        assert_eq!(err.to_string(), "missing field `x` at offset 5");
+
+        let err = de::from_reader::<u8, _>(&b""[..]).unwrap_err();
+        assert_eq!(err.try_offset(), Some(0));
+        let err = serde_cbor::to_vec(&serde_cbor::value::Value::Integer(1 << 70)).unwrap_err();
+        assert_eq!(err.try_offset(), None);
+        assert_eq!(err.offset(), 0);
+    }
+
+    #[test]
+    fn test_char() {
+        assert_eq!(de::from_slice::<char>(b"\x61a").unwrap(), 'a');
+        assert_eq!(
+            de::from_slice::<char>(b"\x7f\x62\xc3\xa9\xff").unwrap(),
+            '\u{e9}'
+        );
+
+        // ["ab"]
+        let err = de::from_slice::<Vec<char>>(b"\x81\x62ab").unwrap_err();
+        assert!(err.is_data());
+        assert_eq!(
+            err.to_string(),
+            "expected a single character, found 2 characters at offset 1"
+        );
+        assert!(de::from_slice::<char>(b"\x60").unwrap_err().is_data());
+        assert!(de::from_slice::<char>(b"\x18\x61").unwrap_err().is_syntax());
+
+        fn decode(input: &[u8]) -> error::Result<char> {
+            let options = CustomDeserializerOptions::new().set_accept_code_point_chars(true);
+            let mut deserializer =
+                Deserializer::new_with_options(de::SliceRead::new(input), options);
+            serde_de::Deserialize::deserialize(&mut deserializer)
+        }
+        assert_eq!(decode(b"\x18\x61").unwrap(), 'a');
+        assert_eq!(decode(b"\x1a\x00\x01\xf4\x94").unwrap(), '\u{1f494}');
+        assert_eq!(decode(b"\x61a").unwrap(), 'a');
+        // A surrogate is not a valid char.
+        assert_eq!(
+            decode(b"\x19\xd8\x00").unwrap_err().to_string(),
+            "integer 55296 out of range for char"
+        );
+    }
+
+    #[test]
+    fn test_byte_array() {
+        let mut input = vec![0x58, 0x20];
+        input.extend(0..32);
+        let hash: [u8; 32] = de::from_slice(&input).unwrap();
+        assert_eq!(hash[31], 31);
+
+        // (_ h'0102', h'03')
+        let array: [u8; 3] = de::from_slice(b"\x5f\x42\x01\x02\x41\x03\xff").unwrap();
+        assert_eq!(array, [1, 2, 3]);
+        // Arrays of integers are still accepted.
+        let array: [u8; 3] = de::from_slice(b"\x83\x01\x02\x03").unwrap();
+        assert_eq!(array, [1, 2, 3]);
+
+        // [h'010203']
+        let err = de::from_slice::<Vec<[u8; 4]>>(b"\x81\x43\x01\x02\x03").unwrap_err();
+        assert!(err.is_data());
+        assert_eq!(err.to_string(), "expected 4 bytes, got 3 at offset 1");
+
+        // Only arrays and tuples of u8 are taken from byte strings.
+        let value: (u8, u8) = de::from_slice(b"\x42\x01\x02").unwrap();
+        assert_eq!(value, (1, 2));
+        let err = de::from_slice::<(u16, u16)>(b"\x42\x01\x02").unwrap_err();
+        assert!(err.is_data());
+        assert_eq!(
+            err.to_string(),
+            "a byte string only decodes into an array or tuple of u8"
+        );
+        assert!(de::from_slice::<(u8, char)>(b"\x42\x01\x02").is_err());
+        assert!(de::from_slice::<[Value; 2]>(b"\x42\x01\x02").is_err());
+    }
+
+    #[test]
+    fn test_human_readable() {
+        use std::net::{IpAddr, Ipv4Addr};
+
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let mut vec = Vec::new();
+        let mut serializer = serde_cbor::Serializer::new(&mut vec).human_readable();
+        serde::Serialize::serialize(&ip, &mut serializer).unwrap();
+        assert_eq!(vec, b"\x6810.0.0.1");
+
+        let mut deserializer = Deserializer::from_slice(&vec).human_readable();
+        let decoded: IpAddr = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(decoded, ip);
+        // The binary representation is used by default.
+        assert!(de::from_slice::<IpAddr>(&vec).is_err());
+        assert_eq!(de::from_slice::<IpAddr>(&to_vec(&ip).unwrap()).unwrap(), ip);
+    }
+
+    #[test]
+    fn test_utf8_validation() {
+        use serde_cbor::de::Utf8Validation;
+
+        fn decode<'a, T>(input: &'a [u8], utf8: Utf8Validation) -> error::Result<T>
+        where
+            T: serde_de::Deserialize<'a>,
+        {
+            let options = CustomDeserializerOptions::new().set_utf8_validation(utf8);
+            let mut deserializer =
+                Deserializer::new_with_options(de::SliceRead::new(input), options);
+            serde_de::Deserialize::deserialize(&mut deserializer)
+        }
+
+        // ["ok", (_ "\xc3" "\xa9\xff")]
+        let input = b"\x82\x62ok\x7f\x61\xc3\x62\xa9\xff\xff";
+        let err = decode::<Vec<String>>(input, Utf8Validation::STRICT).unwrap_err();
+        assert_eq!(err.to_string(), "invalid UTF-8 at offset 5");
+        let texts: Vec<String> = decode(input, Utf8Validation::LOSSY).unwrap();
+        assert_eq!(texts, ["ok", "\u{e9}\u{fffd}"]);
+
+        // {"caf\xc3\xa9": "\xc3\xa9"}
+        let input = b"\xa1\x65caf\xc3\xa9\x62\xc3\xa9";
+        let utf8 = unsafe { Utf8Validation::trusted() };
+        let map: BTreeMap<&str, &str> = decode(input, utf8).unwrap();
+        assert_eq!(map["caf\u{e9}"], "\u{e9}");
+    }
+
+    #[test]
+    fn test_stats() {
+        #[derive(Debug, serde_derive::Deserialize)]
+        struct Point {
+            x: u8,
+        }
+
+        // {"x": 1, "extra": [_ h'0102', {"a": [[]]}]}
+        let input = b"\xa2\x61x\x01\x65extra\x9f\x42\x01\x02\xa1\x61a\x81\x80\xff";
+        let (point, stats) = de::from_slice_with_stats::<Point>(input).unwrap();
+        assert_eq!(point.x, 1);
+        assert_eq!(stats.major_types, [1, 0, 1, 3, 3, 2, 0, 0]);
+        assert_eq!(stats.max_depth, 4);
+        assert_eq!(stats.largest_string, 5);
+        assert_eq!(stats.largest_indefinite_string, 0);
+        assert_eq!(stats.indefinite_items, 1);
+
+        // Indefinite strings count with their total length.
+        let (_, stats) = de::from_slice_with_stats::<String>(b"\x7f\x63abc\x63def\xff").unwrap();
+        assert_eq!(stats.major_types[3], 1);
+        assert_eq!(stats.largest_string, 6);
+        assert_eq!(stats.largest_indefinite_string, 6);
+
+        let mut deserializer = Deserializer::from_slice(b"\x01");
+        assert!(deserializer.stats().is_none());
+        deserializer.enable_stats();
+        let _: u8 = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(deserializer.stats().unwrap().major_types[0], 1);
+    }
+
+    #[test]
+    fn test_snapshot_rewind() {
+        use std::io::{Cursor, Seek, SeekFrom};
+
+        // [[[1]]] followed by "abc"
+        let mut input = Cursor::new(b"xx\x81\x81\x81\x01\x63abc".to_vec());
+        input.seek(SeekFrom::Start(2)).unwrap();
+        let mut deserializer = Deserializer::from_reader(input);
+        let snapshot = deserializer.snapshot();
+        assert_eq!(snapshot.offset(), 0);
+        let wrong: Result<Vec<Vec<String>>, _> =
+            serde_de::Deserialize::deserialize(&mut deserializer);
+        assert!(wrong.is_err());
+        deserializer.rewind(snapshot).unwrap();
+        let nested: Vec<Vec<Vec<u8>>> =
+            serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(nested, [[[1]]]);
+
+        let snapshot = deserializer.snapshot();
+        assert_eq!(snapshot.offset(), 4);
+        let text: String = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(text, "abc");
+        deserializer.rewind(snapshot).unwrap();
+        let value: Value = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(value, Value::Text("abc".to_string()));
+        deserializer.end().unwrap();
+
+        assert_eq!(deserializer.into_inner().into_inner().position(), 10);
+    }
+
+    #[test]
+    fn test_stream_budget() {
+        // 1, "abcd", 2, 3
+        let input: &[u8] = b"\x01\x64abcd\x02\x03";
+        let mut items = Deserializer::from_reader(input)
+            .into_iter::<Value>()
+            .max_bytes(2);
+        // the second item crosses the budget and is still decoded
+        assert_eq!(items.by_ref().count(), 2);
+        assert!(items.budget_exhausted());
+        assert_eq!(items.byte_offset(), 6);
+
+        items.take_items(1);
+        assert_eq!(items.next().unwrap().unwrap(), Value::Integer(2));
+        assert!(items.next().is_none());
+        items.reset_budget();
+        assert_eq!(items.next().unwrap().unwrap(), Value::Integer(3));
+        items.reset_budget();
+        assert!(items.next().is_none());
+        assert!(!items.budget_exhausted());
+    }
+
+    #[test]
+    fn test_owned_strings_from_reader() {
+        use serde_bytes::ByteBuf;
+        use serde_cbor::de::Utf8Validation;
+
+        // ["text", h'0102', (_ "a" "b")]
+        let input: &[u8] = b"\x83\x64text\x42\x01\x02\x7f\x61a\x61b\xff";
+        let value: (String, ByteBuf, String) = de::from_reader(input).unwrap();
+        assert_eq!(
+            value,
+            (
+                "text".to_owned(),
+                ByteBuf::from(vec![1, 2]),
+                "ab".to_owned()
+            )
+        );
+
+        // a text string that is cut short
+        let err = de::from_reader::<String, _>(&b"\x64te"[..]).unwrap_err();
+        assert!(err.is_eof());
+
+        // ["ok", "\xc3\xa9\xff"]
+        let input: &[u8] = b"\x82\x62ok\x63\xc3\xa9\xff";
+        let err = de::from_reader::<Vec<String>, _>(input).unwrap_err();
+        assert_eq!(err.to_string(), "invalid UTF-8 at offset 5");
+        let options = CustomDeserializerOptions::new().set_utf8_validation(Utf8Validation::LOSSY);
+        let mut deserializer = Deserializer::new_with_options(de::IoRead::new(input), options);
+        let texts: Vec<String> = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(texts, ["ok", "\u{e9}\u{fffd}"]);
+    }
+
+    #[test]
+    fn test_scratch_capacity() {
+        // (_ "aaa..." "b"), "c", then the same again
+        let mut item = vec![0x7f, 0x79, 0x10, 0x00];
+        item.resize(4 + 4096, b'a');
+        item.extend_from_slice(b"\x61b\xff\x61c");
+        let input = item.repeat(2);
+        let values: Vec<Value> = Deserializer::from_reader(&input[..])
+            .into_iter()
+            .scratch_capacity(16)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(values.len(), 4);
+        assert_eq!(values[2], Value::Text("a".repeat(4096) + "b"));
+        assert_eq!(values[3], Value::Text("c".to_owned()));
+
+        let mut deserializer = Deserializer::from_slice(&input);
+        let value: Value = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(value, values[0]);
+        deserializer.shrink_to(0);
+        let value: Value = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(value, values[1]);
+    }
 }
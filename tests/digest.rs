@@ -0,0 +1,48 @@
+#![cfg(feature = "std")]
+
+use std::io::Read;
+
+use serde_cbor::digest::{from_reader, DigestReader};
+use serde_cbor::Value;
+
+#[test]
+fn test_digest_covers_one_item() {
+    // [_ "a", (_ "b" "c")], 1(h'00'), then 7
+    let first = b"\x9f\x61a\x7f\x61b\x61c\xff\xff";
+    let second = b"\xc1\x41\x00";
+    let mut stream = [&first[..], &second[..], b"\x07"].concat();
+    let mut reader = &stream[..];
+
+    let mut bytes = Vec::new();
+    let (value, _): (Value, _) =
+        from_reader(&mut reader, |b: &[u8]| bytes.extend_from_slice(b)).unwrap();
+    assert_eq!(bytes, first);
+    assert_eq!(
+        value,
+        Value::Array(vec![Value::Text("a".into()), Value::Text("bc".into())])
+    );
+
+    let mut bytes = Vec::new();
+    let (_, _): (Value, _) =
+        from_reader(&mut reader, |b: &[u8]| bytes.extend_from_slice(b)).unwrap();
+    assert_eq!(bytes, second);
+    assert_eq!(reader, b"\x07");
+
+    // a truncated item
+    stream.truncate(4);
+    let err = from_reader::<Value, _, _>(&stream[..], |_: &[u8]| {})
+        .err()
+        .unwrap();
+    assert!(err.is_eof());
+}
+
+#[test]
+fn test_digest_reader() {
+    let mut count = 0;
+    let mut reader = DigestReader::new(&b"abc"[..], |b: &[u8]| count += b.len());
+    let mut buf = [0; 2];
+    reader.read_exact(&mut buf).unwrap();
+    let (rest, _) = reader.into_parts();
+    assert_eq!(rest, b"c");
+    assert_eq!(count, 2);
+}
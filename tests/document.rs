@@ -0,0 +1,131 @@
+use serde_cbor::document::Document;
+use serde_cbor::header::MajorType;
+
+// {"a": [1, {"b": "x"}], 1: "packed", "s": (_ "ab" "c"), "t": 1("t")}, trailed by another item
+const INPUT: &[u8] = b"\xa4\x61a\x82\x01\xa1\x61b\x61x\x01\x66packed\
+    \x61s\x7f\x62ab\x61c\xff\x61t\xc1\x61t\x00";
+
+#[test]
+fn test_navigate() {
+    let doc = Document::new(INPUT);
+    let a = doc.get("a").unwrap();
+    assert_eq!(a.offset(), 3);
+    assert_eq!(a.header().unwrap().major, MajorType::Array);
+    assert_eq!(a.as_bytes().unwrap(), b"\x82\x01\xa1\x61b\x61x");
+    assert_eq!(
+        a.get(1)
+            .unwrap()
+            .get("b")
+            .unwrap()
+            .decode::<&str>()
+            .unwrap(),
+        "x"
+    );
+    assert_eq!(doc.get(1).unwrap().decode::<&str>().unwrap(), "packed");
+    assert_eq!(doc.get("t").unwrap().header().unwrap().tag(), Some(1));
+    assert_eq!(doc.as_bytes().unwrap().len(), INPUT.len() - 1);
+}
+
+#[test]
+fn test_navigate_errors() {
+    let doc = Document::new(INPUT);
+    let a = doc.get("a").unwrap();
+    let err = a.get(2).unwrap_err();
+    assert_eq!(err.offset(), 3);
+    let err = a.get("b").unwrap_err();
+    assert_eq!(err.offset(), 3);
+    assert!(doc.get("missing").is_err());
+    assert!(doc.get("a").unwrap().get(0).unwrap().get(0).is_err());
+
+    // a chunked string is skipped, but can't be borrowed
+    assert!(doc.get("s").unwrap().decode::<&str>().is_err());
+
+    // errors of decoding are placed in the whole input
+    let err = doc.get("a").unwrap().decode::<Vec<u8>>().unwrap_err();
+    assert!(err.offset() > 3);
+
+    let err = Document::new(b"\xa1\x61a").get("b").unwrap_err();
+    assert!(err.is_eof());
+    let err = Document::new(b"\xa1\x61a\xff").get("b").unwrap_err();
+    assert!(err.is_syntax());
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[test]
+fn test_index() {
+    use serde_cbor::document::DocumentIndex;
+
+    let index = DocumentIndex::new(INPUT).unwrap();
+    assert_eq!(index.len(), 8);
+    assert_eq!(index.range(""), Some(0..INPUT.len() - 1));
+    assert_eq!(index.range("a"), Some(3..10));
+    assert_eq!(index.range("a/1/b"), Some(8..10));
+    assert_eq!(index.get("1").unwrap().decode::<&str>().unwrap(), "packed");
+    assert_eq!(index.get("t").unwrap().header().unwrap().tag(), Some(1));
+    assert_eq!(
+        index.get("s").unwrap().as_bytes().unwrap(),
+        b"\x7f\x62ab\x61c\xff"
+    );
+
+    let shallow = DocumentIndex::with_max_depth(INPUT, 1).unwrap();
+    assert_eq!(shallow.len(), 5);
+    assert_eq!(shallow.range("a"), index.range("a"));
+    assert_eq!(shallow.range("a/1"), None);
+    let b = shallow.get("a").unwrap().get(1).unwrap().get("b").unwrap();
+    assert_eq!(b.offset(), 8);
+
+    // {h'00': 1, "x/y": 2, "z": 3}
+    let index = DocumentIndex::new(b"\xa3\x41\x00\x01\x63x/y\x02\x61z\x03").unwrap();
+    assert_eq!(index.len(), 2);
+    assert_eq!(index.range("z"), Some(11..12));
+
+    assert!(DocumentIndex::new(b"\x82\x01").unwrap_err().is_eof());
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[test]
+fn test_patch() {
+    use serde_cbor::document::Patch;
+
+    let mut patch = Patch::new();
+    assert!(patch.is_empty());
+    patch
+        .set("a/1/b", &[true])
+        .unwrap()
+        .set("1", &())
+        .unwrap()
+        .set_encoded("t", b"\xc1\x00".to_vec());
+    assert_eq!(patch.len(), 3);
+    let output = patch.apply(INPUT).unwrap();
+    // the indefinite string and the trailing item are copied as they are
+    assert_eq!(
+        output,
+        &b"\xa4\x61a\x82\x01\xa1\x61b\x81\xf5\x01\xf6\
+            \x61s\x7f\x62ab\x61c\xff\x61t\xc1\x00\x00"[..]
+    );
+
+    // the whole document
+    let mut patch = Patch::new();
+    patch.set("", &7).unwrap();
+    assert_eq!(patch.apply(INPUT).unwrap(), [0x07, 0x00]);
+
+    // a text key made of digits
+    let mut patch = Patch::new();
+    patch.set("7", &0).unwrap();
+    assert_eq!(patch.apply(b"\xa1\x617\x01").unwrap(), b"\xa1\x617\x00");
+
+    let mut patch = Patch::new();
+    patch.set("a/2", &0).unwrap();
+    let err = patch.apply(INPUT).unwrap_err();
+    assert_eq!(err.offset(), 3);
+
+    patch = Patch::new();
+    patch.set("a", &0).unwrap().set("a/0", &0).unwrap();
+    let err = patch.apply(INPUT).unwrap_err();
+    assert_eq!(err.offset(), 4);
+    #[cfg(feature = "std")]
+    assert_eq!(
+        err.to_string(),
+        "replaced items `a` and `a/0` overlap at offset 4"
+    );
+}
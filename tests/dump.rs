@@ -0,0 +1,66 @@
+#[cfg(feature = "std")]
+mod std_tests {
+    use serde_cbor::{de, dump};
+
+    #[test]
+    fn test_dump() {
+        // [_ h'0102', 1.5, 32("abc"), {}]
+        let input = b"\x9f\x5f\x42\x01\x02\xff\xf9\x3e\x00\xd8\x20\x63abc\xa0\xff";
+        assert_eq!(
+            dump(input),
+            "00000000  9f              # array(*)\n\
+             00000001    5f            #   bytes(*)\n\
+             00000002      42          #     bytes(2)\n\
+             00000003        01 02\n\
+             00000005    ff            #   break\n\
+             00000006    f9 3e 00      #   float(1.5)\n\
+             00000009    d8 20         #   tag(32)\n\
+             0000000b      63          #     text(3)\n\
+             0000000c        61 62 63  #       \"abc\"\n\
+             0000000f    a0            #   map(0)\n\
+             00000010  ff              # break\n"
+        );
+    }
+
+    #[test]
+    fn test_dump_malformed() {
+        assert_eq!(
+            dump(b"\x82\x01\x1c\x02"),
+            "00000000  82       # array(2)\n\
+             00000001    01     #   unsigned(1)\n\
+             00000002    1c 02  #   reserved additional information\n"
+        );
+        assert_eq!(
+            dump(b"\x43\x01"),
+            "00000000  43    # bytes(3)\n\
+             00000001    01  #   truncated string\n"
+        );
+        assert_eq!(dump(b""), "");
+    }
+
+    #[test]
+    fn test_verbose_errors() {
+        let input: Vec<u8> = [0x8a]
+            .iter()
+            .chain(&[0x01; 9])
+            .chain(b"\x61a")
+            .cloned()
+            .collect();
+        let err = de::from_slice_verbose::<Vec<u8>>(&input).unwrap_err();
+        assert_eq!(err.offset(), 11);
+        assert_eq!(
+            err.to_string(),
+            "expected {IntPos,} found byte 0x61 at offset 11\n  ...\n\
+             \x20 00000007    01    #   unsigned(1)\n\
+             \x20 00000008    01    #   unsigned(1)\n\
+             \x20 00000009    01    #   unsigned(1)\n\
+             \x20 0000000a    61    #   text(1)\n\
+             > 0000000b      61  #     \"a\""
+        );
+        // Errors of other functions carry no dump.
+        assert!(de::from_slice::<Vec<u8>>(&input)
+            .unwrap_err()
+            .dump()
+            .is_none());
+    }
+}
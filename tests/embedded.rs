@@ -0,0 +1,102 @@
+#![cfg(feature = "embedded")]
+
+use serde_cbor::embedded::{self, ChunkWrite, RingBuffer, RingRead};
+use serde_cbor::{Deserializer, Serializer};
+
+#[test]
+fn test_ring_buffer_wraps() {
+    let mut storage = [0; 8];
+    let mut ring = RingBuffer::new(&mut storage);
+    assert_eq!(ring.push(b"abcdef"), 6);
+    let mut buf = [0; 4];
+    assert_eq!(ring.pop(&mut buf), 4);
+    assert_eq!(&buf, b"abcd");
+
+    // the free space is at the end of the storage first, then at its start
+    assert_eq!(ring.writable().len(), 2);
+    assert_eq!(ring.push(b"ghijklm"), 6);
+    assert_eq!(ring.len(), 8);
+    assert!(ring.writable().is_empty());
+    assert_eq!(ring.readable(), b"efgh");
+    ring.consume(4);
+    assert_eq!(ring.readable(), b"ijkl");
+    assert_eq!(ring.get(3), Some(b'l'));
+    assert_eq!(ring.get(4), None);
+    ring.consume(4);
+    assert!(ring.is_empty());
+    assert_eq!(ring.writable().len(), 8);
+}
+
+#[test]
+fn test_from_ring() {
+    let mut storage = [0; 16];
+    let mut ring = RingBuffer::new(&mut storage);
+    let mut scratch = [0; 8];
+    // move the start so the items wrap around the end of the storage
+    ring.push(&[0; 12]);
+    ring.consume(12);
+
+    // "hello", [1, 2]
+    ring.push(b"\x65hel");
+    assert_eq!(
+        embedded::from_ring::<String>(&mut ring, &mut scratch).unwrap(),
+        None
+    );
+    assert_eq!(ring.len(), 4);
+    ring.push(b"lo\x82\x01\x02");
+    assert_eq!(
+        embedded::from_ring::<String>(&mut ring, &mut scratch).unwrap(),
+        Some("hello".to_owned())
+    );
+    assert_eq!(
+        embedded::from_ring::<Vec<u8>>(&mut ring, &mut scratch).unwrap(),
+        Some(vec![1, 2])
+    );
+    assert!(ring.is_empty());
+
+    // a string longer than the scratch buffer
+    ring.push(b"\x69abcdefghi");
+    let err = embedded::from_ring::<String>(&mut ring, &mut scratch).unwrap_err();
+    assert!(err.is_scratch_too_small());
+    assert_eq!(ring.len(), 10);
+}
+
+#[test]
+fn test_ring_read() {
+    let mut storage = [0; 16];
+    let mut ring = RingBuffer::new(&mut storage);
+    let mut scratch = [0; 8];
+    // 1, 2
+    ring.push(b"\x01\x02");
+    let mut deserializer = Deserializer::new(RingRead::new(&mut ring, &mut scratch));
+    let first: u8 = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    assert_eq!(first, 1);
+    deserializer.into_inner().commit();
+    assert_eq!(ring.readable(), b"\x02");
+}
+
+#[test]
+fn test_to_ring() {
+    let mut storage = [0; 8];
+    let mut ring = RingBuffer::new(&mut storage);
+    embedded::to_ring(&mut ring, "abc").unwrap();
+    assert_eq!(ring.readable(), b"\x63abc");
+
+    // an item that does not fit is not added at all
+    let err = embedded::to_ring(&mut ring, "abcde").unwrap_err();
+    assert!(err.is_scratch_too_small());
+    assert_eq!(ring.len(), 4);
+}
+
+#[test]
+fn test_chunk_write() {
+    let mut chunks = Vec::new();
+    let mut buf = [0; 3];
+    let mut writer = ChunkWrite::new(&mut buf, |chunk: &[u8]| chunks.push(chunk.to_vec()));
+    serde::Serialize::serialize(&[1u32, 1000], &mut Serializer::new(&mut writer)).unwrap();
+    writer.flush();
+    writer.flush();
+    serde::Serialize::serialize(&true, &mut Serializer::new(&mut writer)).unwrap();
+    let _ = writer.finish();
+    assert_eq!(chunks, [&[0x82, 0x01, 0x19][..], &[0x03, 0xe8], &[0xf5]]);
+}
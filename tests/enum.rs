@@ -247,4 +247,129 @@ mod std_tests {
         let point_map_ds = from_slice(&point_map_s).unwrap();
         assert_eq!(Bar::Point { x: 5, y: -5 }, point_map_ds);
     }
+
+    #[test]
+    fn test_packed_variant_keys() {
+        let to_vec_packed_keys = |value: &Bar| {
+            let mut vec = Vec::new();
+            let mut serializer = Serializer::new(&mut vec)
+                .packed_format()
+                .packed_variant_keys();
+            serde::Serialize::serialize(value, &mut serializer).unwrap();
+            vec
+        };
+
+        // Without the option only unit and struct variants use the index.
+        assert_eq!(
+            serde_cbor::ser::to_vec_packed(&Bar::Number(42)).unwrap(),
+            b"\xa1\x66Number\x18\x2a"
+        );
+
+        let values = [
+            (Bar::Empty, &b"\x00"[..]),
+            (Bar::Number(42), b"\xa1\x01\x18\x2a"),
+            (Bar::Flag("a".to_string(), true), b"\xa1\x02\x82\x61a\xf5"),
+            (Bar::Point { x: 5, y: -5 }, b"\xa1\x03\xa2\x00\x05\x01\x24"),
+        ];
+        for (value, expected) in values.iter() {
+            let encoded = to_vec_packed_keys(value);
+            assert_eq!(encoded, *expected);
+            assert_eq!(from_slice::<Bar>(&encoded).unwrap(), *value);
+        }
+    }
+
+    #[test]
+    fn test_variant_key_formats() {
+        fn decode(input: &[u8], options: CustomDeserializerOptions) -> serde_cbor::Result<Bar> {
+            let options = options.set_accept_legacy_enums(true);
+            let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+            Deserialize::deserialize(&mut deserializer)
+        }
+        let packed_only = || CustomDeserializerOptions::new().set_accept_named_format(false);
+        let named_only = || CustomDeserializerOptions::new().set_accept_packed_format(false);
+
+        let named: [&[u8]; 3] = [
+            b"\x65Empty",
+            b"\xa1\x66Number\x01",
+            b"\x83\x64Flag\x61a\xf5",
+        ];
+        let packed: [&[u8]; 3] = [b"\x00", b"\xa1\x01\x01", b"\x83\x02\x61a\xf5"];
+        for (named, packed) in named.iter().zip(packed.iter()) {
+            let value = decode(named, named_only()).unwrap();
+            assert_eq!(decode(packed, packed_only()).unwrap(), value);
+            assert_eq!(
+                decode(named, CustomDeserializerOptions::new()).unwrap(),
+                value
+            );
+
+            let err = decode(named, packed_only()).unwrap_err();
+            assert_eq!(
+                err.to_string().split(" at ").next(),
+                Some("wrong enum format")
+            );
+            let err = decode(packed, named_only()).unwrap_err();
+            assert_eq!(
+                err.to_string().split(" at ").next(),
+                Some("wrong enum format")
+            );
+        }
+    }
+
+    #[test]
+    fn test_variant_resolver() {
+        use serde_cbor::de::{self, VariantResolver, WireKey};
+
+        fn decode(input: &[u8], resolver: VariantResolver) -> serde_cbor::Result<Bar> {
+            let options = CustomDeserializerOptions::new().set_variant_resolver(resolver);
+            let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+            Deserialize::deserialize(&mut deserializer)
+        }
+        fn aliases(variants: &'static [&'static str], key: WireKey) -> Option<&'static str> {
+            match key {
+                WireKey::Text("Nothing") => Some("Empty"),
+                key => de::resolve_variant_by_index(variants, key),
+            }
+        }
+
+        // {3: {"x": 1, "y": 2}}
+        let input = b"\xa1\x03\xa2\x61x\x01\x61y\x02";
+        let point = Bar::Point { x: 1, y: 2 };
+        assert_eq!(decode(input, de::resolve_variant_by_index).unwrap(), point);
+        assert!(decode(input, de::resolve_variant_by_name).is_err());
+        // {"Point": {"x": 1, "y": 2}}
+        let input = b"\xa1\x65Point\xa2\x61x\x01\x61y\x02";
+        assert_eq!(decode(input, de::resolve_variant_by_name).unwrap(), point);
+
+        assert_eq!(decode(b"\x67Nothing", aliases).unwrap(), Bar::Empty);
+        assert_eq!(decode(b"\xa1\x01\x05", aliases).unwrap(), Bar::Number(5));
+        let err = decode(b"\x04", aliases).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid value: integer `4`, expected a known variant index"
+        );
+        let err = decode(b"\x64None", aliases).unwrap_err();
+        assert!(err.to_string().starts_with("unknown variant `None`"));
+    }
+
+    #[test]
+    fn test_from_value_mixed_enum_formats() {
+        use serde_cbor::value::{from_value, from_value_with_options};
+
+        let values = [
+            Bar::Empty,
+            Bar::Number(42),
+            Bar::Flag("foo".to_string(), true),
+            Bar::Point { x: 5, y: -5 },
+        ];
+        for value in values.iter() {
+            let legacy: Value = from_slice(&to_vec_legacy(value).unwrap()).unwrap();
+            let standard: Value = from_slice(&to_vec(value).unwrap()).unwrap();
+            assert_eq!(from_value::<Bar>(legacy).unwrap(), *value);
+            assert_eq!(from_value::<Bar>(standard).unwrap(), *value);
+        }
+
+        let legacy: Value = from_slice(&to_vec_legacy(&Bar::Number(1)).unwrap()).unwrap();
+        let options = CustomDeserializerOptions::new().set_accept_legacy_enums(false);
+        assert!(from_value_with_options::<Bar, _>(legacy, options).is_err());
+    }
 }
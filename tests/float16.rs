@@ -0,0 +1,74 @@
+#![cfg(feature = "f16")]
+
+use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead};
+use serde_cbor::float16::f16;
+use serde_cbor::value::{to_value, FloatWidth};
+use serde_cbor::Value;
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Half(#[serde(with = "serde_cbor::float16")] f16);
+
+#[test]
+fn test_round_trip() {
+    for &(value, bytes) in &[
+        (f16::from_f32(1.0), &b"\xf9\x3c\x00"[..]),
+        (f16::from_f32(-0.0), b"\xf9\x80\x00"),
+        (f16::INFINITY, b"\xf9\x7c\x00"),
+        (f16::MAX, b"\xf9\x7b\xff"),
+    ] {
+        assert_eq!(serde_cbor::to_vec(&Half(value)).unwrap(), bytes);
+        assert_eq!(serde_cbor::from_slice::<Half>(bytes).unwrap(), Half(value));
+    }
+    let nan: Half = serde_cbor::from_slice(b"\xfb\x7f\xf8\x00\x00\x00\x00\x00\x00").unwrap();
+    assert!(nan.0.is_nan());
+    assert_eq!(
+        to_value(Half(f16::from_f32(1.0))).unwrap(),
+        Value::SizedFloat(1.0, FloatWidth::Half)
+    );
+
+    // wider floats are accepted if a half holds them
+    assert_eq!(
+        serde_cbor::from_slice::<Half>(b"\xfa\x3f\xc0\x00\x00").unwrap(),
+        Half(f16::from_f32(1.5))
+    );
+    assert!(serde_cbor::from_slice::<Half>(b"\xfa\x47\x7f\xf0\x00").is_err());
+    assert!(serde_cbor::from_slice::<Half>(b"\x01").is_err());
+}
+
+#[test]
+fn test_half_floats_as_f32() {
+    use serde::de;
+    use std::fmt;
+
+    /// Records which method a float was visited with.
+    struct Which;
+
+    impl<'de> de::Visitor<'de> for Which {
+        type Value = (&'static str, f64);
+
+        fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt.write_str("a float")
+        }
+
+        fn visit_f32<E>(self, v: f32) -> Result<Self::Value, E> {
+            Ok(("f32", v.into()))
+        }
+
+        fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+            Ok(("f64", v))
+        }
+    }
+
+    let visit = |input: &[u8], options| {
+        let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+        de::Deserializer::deserialize_any(&mut deserializer, Which).unwrap()
+    };
+    let options = CustomDeserializerOptions::new();
+    assert_eq!(visit(b"\xf9\x3e\x00", options), ("f64", 1.5));
+    let options = options.set_half_floats_as_f32(true);
+    assert_eq!(visit(b"\xf9\x3e\x00", options), ("f32", 1.5));
+    // the smallest subnormal half
+    assert_eq!(visit(b"\xf9\x00\x01", options), ("f32", 2f64.powi(-24)));
+    assert_eq!(visit(b"\xfa\x3f\xc0\x00\x00", options), ("f64", 1.5));
+}
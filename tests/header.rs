@@ -0,0 +1,53 @@
+use serde_cbor::header::{peek_header, Header, MajorType};
+
+#[test]
+fn test_peek_header() {
+    let header = |input: &[u8]| peek_header(input).unwrap();
+
+    assert_eq!(
+        header(b"\x17"),
+        Header {
+            major: MajorType::Unsigned,
+            info: 23,
+            argument: Some(23),
+            size: 1,
+        }
+    );
+    // the contents of the string are not needed
+    let text = header(b"\x7a\x00\x01\x00\x00");
+    assert_eq!(
+        (text.major, text.length(), text.size),
+        (MajorType::Text, Some(65536), 5)
+    );
+    let negative = header(b"\x3b\xff\xff\xff\xff\xff\xff\xff\xff");
+    assert_eq!((negative.argument, negative.size), (Some(u64::MAX), 9));
+    assert_eq!(negative.length(), None);
+
+    let map = header(b"\xbf\x61a\x01\xff");
+    assert_eq!(
+        (map.major, map.length(), map.size),
+        (MajorType::Map, None, 1)
+    );
+    assert!(map.is_indefinite());
+    let stop = header(b"\xff");
+    assert_eq!((stop.major, stop.argument), (MajorType::Simple, None));
+    assert!(!stop.is_indefinite());
+
+    let float = header(b"\xf9\x3e\x00");
+    assert_eq!(
+        (float.major, float.argument),
+        (MajorType::Simple, Some(0x3e00))
+    );
+}
+
+#[test]
+fn test_peek_header_errors() {
+    let err = peek_header(b"").unwrap_err();
+    assert!(err.is_eof());
+    let err = peek_header(b"\x19\x01").unwrap_err();
+    assert!(err.is_eof());
+    assert_eq!(err.offset(), 2);
+    assert!(peek_header(b"\x1c").unwrap_err().is_syntax());
+    assert!(peek_header(b"\x1f").unwrap_err().is_syntax());
+    assert!(peek_header(b"\xdf").unwrap_err().is_syntax());
+}
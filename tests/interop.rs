@@ -0,0 +1,62 @@
+#[cfg(feature = "std")]
+mod std_tests {
+    use std::collections::HashMap;
+    use std::net::Ipv4Addr;
+
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use serde_cbor::de::{Deserializer, SliceRead};
+    use serde_cbor::interop::Ecosystem;
+    use serde_cbor::Serializer;
+
+    fn encode<T: Serialize>(ecosystem: Ecosystem, value: &T) -> Vec<u8> {
+        let mut vec = Vec::new();
+        let options = ecosystem.serializer_options();
+        let mut serializer = Serializer::new_with_options(&mut vec, options);
+        value.serialize(&mut serializer).unwrap();
+        vec
+    }
+
+    fn decode<T: DeserializeOwned>(ecosystem: Ecosystem, input: &[u8]) -> serde_cbor::Result<T> {
+        let options = ecosystem.deserializer_options();
+        let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+        let value = T::deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(value)
+    }
+
+    #[test]
+    fn test_addresses_as_text() {
+        let addr = Ipv4Addr::new(127, 0, 0, 1);
+        for &ecosystem in &[Ecosystem::Cbor2, Ecosystem::Fxamacker, Ecosystem::CborX] {
+            let data = encode(ecosystem, &addr);
+            assert_eq!(data, b"\x69127.0.0.1");
+            assert_eq!(decode::<Ipv4Addr>(ecosystem, &data).unwrap(), addr);
+        }
+    }
+
+    #[test]
+    fn test_fxamacker_sorted_keys() {
+        let map: HashMap<String, u8> = ["bb", "a", "c"]
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (k.to_string(), i as u8))
+            .collect();
+        // {"a": 1, "c": 2, "bb": 0}
+        let data = encode(Ecosystem::Fxamacker, &map);
+        assert_eq!(data, b"\xa3\x61a\x01\x61c\x02\x62bb\x00");
+    }
+
+    #[test]
+    fn test_cbor_x_large_integers() {
+        // 1.6e12 as a double
+        let input = b"\xfb\x42\x77\x48\x76\xe8\x00\x00\x00";
+        assert_eq!(
+            decode::<u64>(Ecosystem::CborX, input).unwrap(),
+            1_600_000_000_000
+        );
+        assert!(decode::<u64>(Ecosystem::Cbor2, input).is_err());
+        // 1.5 is not an integer
+        assert!(decode::<u64>(Ecosystem::CborX, b"\xf9\x3e\x00").is_err());
+    }
+}
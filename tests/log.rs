@@ -0,0 +1,92 @@
+#[cfg(feature = "std")]
+mod std_tests {
+    use serde_cbor::log::{LogReader, LogWriter, Record};
+
+    fn write_log(values: &[u32], checksums: bool) -> Vec<u8> {
+        let mut writer = LogWriter::new(Vec::new()).checksums(checksums);
+        for value in values {
+            writer.append(value).unwrap();
+        }
+        writer.into_inner()
+    }
+
+    fn read_log(log: &[u8], checksums: bool) -> (Vec<Record<u32>>, Option<u64>) {
+        let mut reader = LogReader::new(log).checksums(checksums).chunk_size(1);
+        let records = reader.by_ref().collect::<serde_cbor::Result<_>>().unwrap();
+        (records, reader.torn_tail())
+    }
+
+    #[test]
+    fn test_offsets() {
+        let mut writer = LogWriter::new(Vec::new());
+        assert_eq!(writer.append(&1u32).unwrap(), 0);
+        assert_eq!(writer.append(&1000u32).unwrap(), 1);
+        assert_eq!(writer.offset(), 4);
+        let log = writer.into_inner();
+        assert_eq!(log, b"\x01\x19\x03\xe8");
+
+        let mut writer = LogWriter::at_offset(log, 4);
+        assert_eq!(writer.append(&2u32).unwrap(), 4);
+        let log = writer.into_inner();
+
+        let (records, torn) = read_log(&log, false);
+        let offsets: Vec<_> = records.iter().map(|r| (r.offset, r.value)).collect();
+        assert_eq!(offsets, [(0, 1), (1, 1000), (4, 2)]);
+        assert_eq!(torn, None);
+    }
+
+    #[test]
+    fn test_checksum_format() {
+        let log = write_log(&[1], true);
+        // 1 followed by its CRC-32
+        assert_eq!(log, b"\x01\x1a\xa5\x05\xdf\x1b");
+        let values: Vec<u32> = serde_cbor::Deserializer::from_slice(&log)
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(values, [1, 0xa505_df1b]);
+    }
+
+    #[test]
+    fn test_torn_tail() {
+        for &checksums in &[false, true] {
+            let log = write_log(&[1, 100_000], checksums);
+            let second = if checksums { 6 } else { 1 };
+            for cut in second + 1..log.len() {
+                let (records, torn) = read_log(&log[..cut], checksums);
+                assert_eq!(records.len(), 1);
+                assert_eq!(torn, Some(second as u64));
+            }
+            let (records, torn) = read_log(&log, checksums);
+            assert_eq!(records.len(), 2);
+            assert_eq!(torn, None);
+        }
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let mut log = write_log(&[1, 2, 3], true);
+        // a corrupted final record is a torn tail
+        log[12] = 4;
+        let (records, torn) = read_log(&log, true);
+        assert_eq!(records.len(), 2);
+        assert_eq!(torn, Some(12));
+
+        // a corrupted record in the middle is an error
+        log[12] = 3;
+        log[6] = 5;
+        let mut reader = LogReader::<_, u32>::new(&log[..]).checksums(true);
+        assert_eq!(reader.next().unwrap().unwrap().value, 1);
+        let err = reader.next().unwrap().unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+        assert!(reader.next().is_none());
+        assert_eq!(reader.torn_tail(), None);
+    }
+
+    #[test]
+    fn test_missing_checksum() {
+        let log = write_log(&[1, 2], false);
+        let mut reader = LogReader::<_, u32>::new(&log[..]).checksums(true);
+        assert!(reader.next().unwrap().is_err());
+    }
+}
@@ -0,0 +1,79 @@
+#[cfg(feature = "msgpack")]
+mod msgpack_tests {
+    use serde_cbor::msgpack::{cbor_to_msgpack, msgpack_to_cbor};
+
+    fn to_msgpack(cbor: &[u8]) -> serde_cbor::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        cbor_to_msgpack(cbor, &mut out)?;
+        Ok(out)
+    }
+
+    fn to_cbor(msgpack: &[u8]) -> serde_cbor::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        msgpack_to_cbor(msgpack, &mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_integer_widths() {
+        let cases: &[(&[u8], &[u8])] = &[
+            (b"\x05", b"\x05"),
+            (b"\x18\x05", b"\xcc\x05"),
+            (b"\x19\x00\x05", b"\xcd\x00\x05"),
+            (b"\x1a\x00\x00\x00\x05", b"\xce\x00\x00\x00\x05"),
+            (
+                b"\x1b\x00\x00\x00\x00\x00\x00\x00\x05",
+                b"\xcf\0\0\0\0\0\0\0\x05",
+            ),
+            (b"\x20", b"\xff"),
+            (b"\x38\x00", b"\xd0\xff"),
+            (b"\x39\x00\x00", b"\xd1\xff\xff"),
+            (b"\x3a\x00\x00\x00\x00", b"\xd2\xff\xff\xff\xff"),
+            (
+                b"\x3b\0\0\0\0\0\0\0\0",
+                b"\xd3\xff\xff\xff\xff\xff\xff\xff\xff",
+            ),
+        ];
+        for &(cbor, msgpack) in cases {
+            assert_eq!(to_msgpack(cbor).unwrap(), msgpack);
+            assert_eq!(to_cbor(msgpack).unwrap(), cbor);
+        }
+    }
+
+    #[test]
+    fn test_negative_widening() {
+        // -200 does not fit into an int8
+        assert_eq!(to_msgpack(b"\x38\xc7").unwrap(), b"\xd1\xff\x38");
+        assert!(to_msgpack(b"\x3b\xff\xff\xff\xff\xff\xff\xff\xff").is_err());
+    }
+
+    #[test]
+    fn test_indefinite_and_tags() {
+        // 1(_ [_ h'01' h'02'], {_ "a": 1.5})
+        let cbor = b"\xc1\x9f\x5f\x41\x01\x41\x02\xff\xbf\x61a\xf9\x3e\x00\xff\xff";
+        assert_eq!(
+            to_msgpack(cbor).unwrap(),
+            b"\x92\xc4\x02\x01\x02\x81\xa1a\xca\x3f\xc0\x00\x00"
+        );
+    }
+
+    #[test]
+    fn test_map_order_and_sequence() {
+        // {"b": null, "a": true} false
+        let msgpack = b"\x82\xa1b\xc0\xa1a\xc3\xc2";
+        let cbor = to_cbor(msgpack).unwrap();
+        assert_eq!(cbor, b"\xa2\x61b\xf6\x61a\xf5\xf4");
+        assert_eq!(to_msgpack(&cbor).unwrap(), msgpack);
+    }
+
+    #[test]
+    fn test_errors() {
+        // simple(16), truncated array, extension type, invalid UTF-8
+        assert!(to_msgpack(b"\xf0").is_err());
+        assert!(to_msgpack(b"\x82\x01").unwrap_err().is_eof());
+        assert!(to_cbor(b"\xd4\x01\x00").is_err());
+        assert!(to_cbor(b"\xa1\xff").unwrap_err().is_syntax());
+        let deep = vec![0x91; 200];
+        assert!(to_cbor(&deep).unwrap_err().is_syntax());
+    }
+}
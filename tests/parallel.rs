@@ -0,0 +1,73 @@
+#[cfg(feature = "rayon")]
+mod parallel_tests {
+    use serde_cbor::parallel::{decode_parallel, decode_sequence_parallel};
+    use serde_cbor::{to_vec, Value};
+    use serde_derive::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record<'a> {
+        id: u32,
+        name: &'a str,
+    }
+
+    #[test]
+    fn test_decode_parallel() {
+        let encoded: Vec<Vec<u8>> = (0..1000)
+            .map(|id| to_vec(&Record { id, name: "record" }).unwrap())
+            .collect();
+        let segments: Vec<&[u8]> = encoded.iter().map(Vec::as_slice).collect();
+        let records: Vec<serde_cbor::Result<Record>> = decode_parallel(&segments);
+        for (id, record) in records.into_iter().enumerate() {
+            assert_eq!(
+                record.unwrap(),
+                Record {
+                    id: id as u32,
+                    name: "record"
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_parallel_errors() {
+        let segments: Vec<&[u8]> = vec![b"\x01", b"\x01\x02", b"\x18", b"\x61a"];
+        let values: Vec<serde_cbor::Result<u8>> = decode_parallel(&segments);
+        assert_eq!(*values[0].as_ref().unwrap(), 1);
+        assert!(values[1].as_ref().unwrap_err().is_syntax());
+        assert!(values[2].as_ref().unwrap_err().is_eof());
+        assert_eq!(values[3].as_ref().unwrap_err().offset(), 1);
+    }
+
+    #[test]
+    fn test_decode_sequence_parallel() {
+        let mut input = Vec::new();
+        for id in 0..500 {
+            input.extend(to_vec(&Record { id, name: "x" }).unwrap());
+        }
+        let records: Vec<serde_cbor::Result<Record>> = decode_sequence_parallel(&input).unwrap();
+        assert_eq!(records.len(), 500);
+        assert!(records
+            .into_iter()
+            .enumerate()
+            .all(|(id, r)| r.unwrap().id == id as u32));
+    }
+
+    #[test]
+    fn test_decode_sequence_parallel_errors() {
+        // 1, "\xff" (invalid UTF-8 only fails its own item), [_ 2]
+        let input = b"\x01\x61\xff\x9f\x02\xff";
+        let values: Vec<serde_cbor::Result<Value>> = decode_sequence_parallel(input).unwrap();
+        assert_eq!(*values[0].as_ref().unwrap(), Value::Integer(1));
+        let err = values[1].as_ref().unwrap_err();
+        assert!(err.is_syntax());
+        assert_eq!(err.offset(), 2);
+        assert_eq!(
+            *values[2].as_ref().unwrap(),
+            Value::Array(vec![Value::Integer(2)])
+        );
+
+        // A truncated item makes the whole sequence fail.
+        let err = decode_sequence_parallel::<Value>(b"\x01\x82\x01").unwrap_err();
+        assert!(err.is_eof());
+    }
+}
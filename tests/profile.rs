@@ -0,0 +1,76 @@
+#![cfg(any(feature = "std", feature = "alloc"))]
+
+use serde_cbor::profile::{FieldSize, Profile};
+
+#[test]
+fn test_profile() {
+    let mut profile = Profile::new();
+    assert!(profile.is_empty());
+    // {1: 1(h'0102'), h'00': 0, "a": [_ "x", [true]]}
+    profile
+        .add(b"\xa3\x01\xc1\x42\x01\x02\x41\x00\x00\x61a\x9f\x61x\x81\xf5\xff")
+        .unwrap();
+    // {1: h''}, trailed by another item
+    profile.add(b"\xa1\x01\x40\x00").unwrap();
+
+    let size = |path| *profile.get(path).unwrap();
+    assert_eq!(
+        size(""),
+        FieldSize {
+            count: 2,
+            key_bytes: 0,
+            value_bytes: 20
+        }
+    );
+    assert_eq!(
+        size("1"),
+        FieldSize {
+            count: 2,
+            key_bytes: 2,
+            value_bytes: 5
+        }
+    );
+    assert_eq!(size("?").total(), 3);
+    assert_eq!(size("a").total(), 8);
+    // the string and the inner array are both elements of `a`
+    assert_eq!(size("a/*").count, 2);
+    assert_eq!(size("a/*").value_bytes, 4);
+    assert_eq!(size("a/*/*").value_bytes, 1);
+    assert_eq!(profile.len(), 6);
+    assert_eq!(
+        profile.iter().map(|(path, _)| path).collect::<Vec<_>>(),
+        ["", "1", "?", "a", "a/*", "a/*/*"]
+    );
+}
+
+#[test]
+fn test_profile_errors() {
+    let mut profile = Profile::new();
+    let err = profile.add(b"\x82\x01").unwrap_err();
+    assert!(err.is_eof());
+    let err = profile.add(b"\xa1\x01\x1c").unwrap_err();
+    assert_eq!(err.offset(), 2);
+
+    let mut nested = vec![0x81; 300];
+    nested.push(0x00);
+    assert!(profile.add(&nested).unwrap_err().is_syntax());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_profile_report() {
+    let mut profile = Profile::new();
+    // {"body": "0123456789", "id": 1}
+    profile
+        .add(b"\xa2\x64body\x6a0123456789\x62id\x01")
+        .unwrap();
+    assert_eq!(
+        profile.to_string(),
+        "     bytes  share   count  path
+        21 100.0%       1  .
+        16  76.2%       1  body
+         4  19.0%       1  id
+"
+    );
+    assert_eq!(Profile::new().to_string().lines().count(), 1);
+}
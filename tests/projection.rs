@@ -0,0 +1,115 @@
+#[macro_use]
+extern crate serde_derive;
+
+#[cfg(feature = "std")]
+mod std_tests {
+    use serde_cbor::projection::{from_reader_paths, from_slice_paths, Projected, Selection};
+    use serde_cbor::value::Value;
+    use serde_cbor::{to_vec, Deserializer};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Header {
+        id: u32,
+        #[serde(default)]
+        route: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Message {
+        #[serde(default)]
+        header: Option<Header>,
+        #[serde(default)]
+        body: Option<Vec<u8>>,
+        #[serde(default)]
+        tag: Option<String>,
+    }
+
+    fn message() -> Vec<u8> {
+        to_vec(&Message {
+            header: Some(Header {
+                id: 7,
+                route: Some("a/b".to_owned()),
+            }),
+            body: Some(vec![1, 2, 3]),
+            tag: Some("x".to_owned()),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_select_top_level() {
+        let data = message();
+        let value: Message = from_slice_paths(&data, &["tag"]).unwrap();
+        assert_eq!(
+            value,
+            Message {
+                header: None,
+                body: None,
+                tag: Some("x".to_owned()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_select_nested() {
+        let data = message();
+        let value: Message = from_slice_paths(&data, &["header/id", "body"]).unwrap();
+        assert_eq!(
+            value,
+            Message {
+                header: Some(Header { id: 7, route: None }),
+                body: Some(vec![1, 2, 3]),
+                tag: None,
+            }
+        );
+        let value: Message = from_reader_paths(&data[..], &["header/id", "header"]).unwrap();
+        assert_eq!(value.header.unwrap().route.unwrap(), "a/b");
+    }
+
+    #[test]
+    fn test_select_into_value() {
+        let data = message();
+        let value: Value = from_slice_paths(&data, &["header/route"]).unwrap();
+        let mut header = BTreeMap::new();
+        header.insert(
+            Value::Text("route".to_owned()),
+            Value::Text("a/b".to_owned()),
+        );
+        let mut expected = BTreeMap::new();
+        expected.insert(Value::Text("header".to_owned()), Value::Map(header));
+        assert_eq!(value, Value::Map(expected));
+    }
+
+    #[test]
+    fn test_select_packed_keys() {
+        let data = serde_cbor::ser::to_vec_packed(&Message {
+            header: Some(Header { id: 3, route: None }),
+            body: None,
+            tag: Some("t".to_owned()),
+        })
+        .unwrap();
+        let value: Message = from_slice_paths(&data, &["0/0"]).unwrap();
+        assert_eq!(value.header, Some(Header { id: 3, route: None }));
+        assert_eq!(value.tag, None);
+    }
+
+    #[test]
+    fn test_reuse_selection() {
+        let data = message();
+        let selection = Selection::new(&["tag"]);
+        let mut de = Deserializer::from_slice(&data);
+        let value: Message =
+            serde::Deserialize::deserialize(Projected::new(&mut de, &selection)).unwrap();
+        de.end().unwrap();
+        assert_eq!(value.tag.unwrap(), "x");
+    }
+
+    #[test]
+    fn test_skipped_entries_are_well_formed() {
+        // {"a": 1, "b": <truncated array>}
+        let data = b"\xa2\x61a\x01\x61b\x83\x01";
+        let err = from_slice_paths::<Value>(data, &["a"]).unwrap_err();
+        assert!(err.is_eof());
+    }
+}
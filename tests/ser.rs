@@ -41,6 +41,87 @@ fn serialize_and_compare<T: Serialize>(value: T, expected: &[u8]) {
     assert_eq!(&slice[..end], expected);
 }
 
+#[test]
+fn test_slice_write_reuse() {
+    let mut slice = [0u8; 4];
+    let mut serializer = Serializer::new(SliceWrite::new(&mut slice));
+    "ab".serialize(&mut serializer).unwrap();
+    assert_eq!(serializer.written(), b"\x62ab");
+
+    serializer.reset();
+    assert_eq!(serializer.bytes_written(), 0);
+    1u8.serialize(&mut serializer).unwrap();
+    assert_eq!(serializer.written(), b"\x01");
+
+    // A value that does not fit does not spoil later iterations.
+    serializer.reset();
+    let err = "abcd".serialize(&mut serializer).unwrap_err();
+    assert!(err.is_scratch_too_small());
+    if cfg!(all(feature = "compact_errors", not(feature = "std"))) {
+        // only the kind of the error is kept
+        assert_eq!(err.scratch_needed(), None);
+        assert_eq!(err.to_string(), "Scratch buffer too small at offset 1");
+        assert!(core::mem::size_of::<serde_cbor::Error>() <= 24);
+    } else {
+        assert_eq!(err.scratch_needed(), Some(5));
+        assert_eq!(err.scratch_available(), Some(4));
+    }
+    serializer.reset();
+    [1u8, 2].serialize(&mut serializer).unwrap();
+    let writer = serializer.into_inner();
+    assert_eq!(writer.into_written(), b"\x82\x01\x02");
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[test]
+fn test_option_helpers() {
+    use serde_cbor::ser::{to_vec_canonical, to_vec_packed};
+
+    #[derive(serde_derive::Serialize)]
+    struct Point {
+        yy: u8,
+        x: u8,
+    }
+
+    let point = Point { yy: 1, x: 2 };
+    assert_eq!(to_vec_packed(&point).unwrap(), b"\xa2\x00\x01\x01\x02");
+    assert_eq!(
+        to_vec_canonical(&point).unwrap(),
+        b"\xa2\x61x\x02\x62yy\x01"
+    );
+}
+
+#[test]
+fn test_options_are_plain_data() {
+    use serde_cbor::ser::{CustomSerializerOptions, DefaultSerializerOptions};
+    use std::thread;
+
+    fn assert_plain<T: Copy + Send + Sync + core::fmt::Debug + PartialEq + 'static>() {}
+    assert_plain::<DefaultSerializerOptions>();
+    assert_plain::<CustomSerializerOptions>();
+
+    let options = CustomSerializerOptions::new().set_packed(true);
+    let handles: Vec<_> = (0..2u8)
+        .map(|i| {
+            thread::spawn(move || {
+                let mut buf = [0u8; 8];
+                let mut serializer =
+                    Serializer::new_with_options(SliceWrite::new(&mut buf), options);
+                i.serialize(&mut serializer).unwrap();
+                serializer.into_inner().bytes_written()
+            })
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), 1);
+    }
+    assert_ne!(options, CustomSerializerOptions::new());
+    assert_eq!(
+        format!("{:?}", DefaultSerializerOptions),
+        "DefaultSerializerOptions"
+    );
+}
+
 #[cfg(feature = "std")]
 mod std_tests {
     use serde::Serializer;
@@ -251,4 +332,684 @@ mod std_tests {
         assert_eq!(vec, b"\xF9\x51\x50");
         assert_eq!(from_slice::<f32>(&vec[..]).unwrap(), 42.5f32);
     }
+
+    #[test]
+    fn test_pre_encoded() {
+        use serde_cbor::ser::{Item, PreEncoded, SerializerHook};
+        use serde_cbor::Value;
+        use std::collections::BTreeMap;
+
+        struct TagPreEncoded;
+
+        impl SerializerHook for TagPreEncoded {
+            fn item(&mut self, item: Item<'_>) -> Option<u64> {
+                match item {
+                    Item::PreEncoded(_) => Some(24),
+                    _ => None,
+                }
+            }
+        }
+
+        // 1.0 written as a double, and [_ 1]
+        let float = PreEncoded::new_checked(b"\xfb\x3f\xf0\0\0\0\0\0\0").unwrap();
+        let array = PreEncoded::new(b"\x9f\x01\xff");
+        let mut map = BTreeMap::new();
+        map.insert(array, float);
+        let vec = to_vec(&(array, map, b"\x01")).unwrap();
+        assert_eq!(
+            vec,
+            b"\x83\x9f\x01\xff\xa1\x9f\x01\xff\xfb\x3f\xf0\0\0\0\0\0\0\x81\x01"
+        );
+
+        let mut vec = Vec::new();
+        serde::Serialize::serialize(
+            &[float],
+            &mut serde_cbor::Serializer::new(&mut vec).with_hook(TagPreEncoded),
+        )
+        .unwrap();
+        assert_eq!(vec, b"\x81\xd8\x18\xfb\x3f\xf0\0\0\0\0\0\0");
+
+        let value = serde_cbor::value::to_value((array, float)).unwrap();
+        assert_eq!(
+            value,
+            Value::Array(vec![
+                Value::Array(vec![Value::Integer(1)]),
+                Value::SizedFloat(1.0, serde_cbor::value::FloatWidth::Double)
+            ])
+        );
+
+        let err = PreEncoded::new_checked(b"\x01\x02").unwrap_err();
+        assert_eq!(err.offset(), 1);
+        assert!(PreEncoded::new_checked(b"").unwrap_err().is_eof());
+    }
+
+    #[test]
+    fn test_hook_counts_and_tags() {
+        use serde_cbor::ser::{Item, SerializerHook};
+
+        #[derive(Default)]
+        struct Counter {
+            items: usize,
+        }
+
+        impl SerializerHook for Counter {
+            fn item(&mut self, item: Item<'_>) -> Option<u64> {
+                self.items += 1;
+                match item {
+                    Item::Bytes(_) => Some(24),
+                    _ => None,
+                }
+            }
+        }
+
+        let value = (serde_cbor::Value::Bytes(vec![1]), 1.5f64, [1u8, 2]);
+        let mut counter = Counter::default();
+        let mut vec = Vec::new();
+        serde::Serialize::serialize(
+            &value,
+            &mut serde_cbor::Serializer::new(&mut vec).with_hook(&mut counter),
+        )
+        .unwrap();
+        assert_eq!(vec, b"\x83\xd8\x18\x41\x01\xf9\x3e\x00\x82\x01\x02");
+        assert_eq!(counter.items, 6);
+    }
+
+    #[test]
+    fn test_hook_redacts_fields() {
+        use serde_cbor::ser::{PreEncoded, SerializerHook};
+
+        #[derive(serde_derive::Serialize)]
+        struct Login<'a> {
+            user: &'a str,
+            secret: &'a str,
+        }
+
+        struct Redact;
+
+        impl SerializerHook for Redact {
+            fn redact(&mut self, key: &str) -> bool {
+                key == "secret"
+            }
+        }
+
+        let login = Login {
+            user: "a",
+            secret: "b",
+        };
+        let mut vec = Vec::new();
+        serde::Serialize::serialize(
+            &login,
+            &mut serde_cbor::Serializer::new(&mut vec).with_hook(Redact),
+        )
+        .unwrap();
+        assert_eq!(vec, b"\xa2\x64user\x61a\x66secret\xf6");
+
+        let mut vec = Vec::new();
+        serde::Serialize::serialize(
+            &login,
+            &mut serde_cbor::Serializer::new(&mut vec)
+                .packed_format()
+                .with_hook(Redact),
+        )
+        .unwrap();
+        assert_eq!(vec, b"\xa2\x00\x61a\x01\xf6");
+
+        let mut map = BTreeMap::new();
+        map.insert("secret", vec!["x"]);
+        map.insert("z", vec!["y"]);
+        let mut vec = Vec::new();
+        serde::Serialize::serialize(
+            &map,
+            &mut serde_cbor::Serializer::new(&mut vec).with_hook(Redact),
+        )
+        .unwrap();
+        assert_eq!(vec, b"\xa2\x66secret\xf6\x61z\x81\x61y");
+
+        // keys behind tags and pre-encoded text keys are passed without their tags
+        #[cfg(feature = "tags")]
+        {
+            let mut vec = Vec::new();
+            serde::Serializer::collect_map(
+                &mut serde_cbor::Serializer::new(&mut vec).with_hook(Redact),
+                vec![(serde_cbor::tags::Tagged::new(Some(32), "secret"), 1)],
+            )
+            .unwrap();
+            assert_eq!(vec, b"\xa1\xd8\x20\x66secret\xf6");
+        }
+
+        let mut map = BTreeMap::new();
+        map.insert(PreEncoded::new(b"\xc1\x66secret"), 1);
+        map.insert(PreEncoded::new(b"\x46secret"), 2);
+        let mut vec = Vec::new();
+        serde::Serialize::serialize(
+            &map,
+            &mut serde_cbor::Serializer::new(&mut vec).with_hook(Redact),
+        )
+        .unwrap();
+        assert_eq!(vec, b"\xa2\x46secret\x02\xc1\x66secret\xf6");
+    }
+
+    #[test]
+    fn test_packed_structs() {
+        #[derive(serde_derive::Serialize)]
+        struct Hot {
+            a: u8,
+            b: u8,
+        }
+
+        #[derive(serde_derive::Serialize)]
+        struct Envelope {
+            id: u8,
+            hot: Hot,
+        }
+
+        let envelope = Envelope {
+            id: 1,
+            hot: Hot { a: 2, b: 3 },
+        };
+        let mut vec = Vec::new();
+        let options = ser::CustomSerializerOptions::new().set_packed_structs(&["Hot"]);
+        serde::Serialize::serialize(
+            &envelope,
+            &mut serde_cbor::Serializer::new_with_options(&mut vec, options),
+        )
+        .unwrap();
+        assert_eq!(vec, b"\xa2\x62id\x01\x63hot\xa2\x00\x02\x01\x03");
+
+        let mut vec = Vec::new();
+        let options = ser::CustomSerializerOptions::new()
+            .set_packed(true)
+            .set_named_structs(&["Hot"]);
+        serde::Serialize::serialize(
+            &envelope,
+            &mut serde_cbor::Serializer::new_with_options(&mut vec, options),
+        )
+        .unwrap();
+        assert_eq!(vec, b"\xa2\x00\x01\x01\xa2\x61a\x02\x61b\x03");
+    }
+
+    #[test]
+    fn test_writer_helpers() {
+        use serde_derive::Serialize;
+        use std::collections::HashMap;
+
+        #[derive(Serialize)]
+        struct Point {
+            x: u8,
+            y: u8,
+        }
+
+        let mut vec = Vec::new();
+        ser::to_writer_packed(&mut vec, &Point { x: 1, y: 2 }).unwrap();
+        assert_eq!(vec, ser::to_vec_packed(&Point { x: 1, y: 2 }).unwrap());
+        assert_eq!(vec, b"\xa2\x00\x01\x01\x02");
+
+        let map: HashMap<Vec<u8>, u8> = [(vec![0; 2], 0), (vec![], 1), (vec![1], 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let canonical = ser::to_vec_canonical(&map).unwrap();
+        assert_eq!(canonical, b"\xa3\x80\x01\x81\x01\x02\x82\x00\x00\x00");
+        let mut vec = Vec::new();
+        ser::to_writer_canonical(&mut vec, &map).unwrap();
+        assert_eq!(vec, canonical);
+
+        /// A map with the same key twice.
+        struct Twice;
+        impl serde::Serialize for Twice {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("a", &1)?;
+                map.serialize_entry("a", &2)?;
+                map.end()
+            }
+        }
+        assert!(ser::to_vec_canonical(&Twice).is_err());
+        assert!(ser::to_writer_canonical(Vec::new(), &Twice).is_err());
+        assert!(ser::to_writer_packed(Vec::new(), &Twice).is_ok());
+    }
+
+    #[test]
+    fn test_packed_index_base() {
+        use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead, WireKey};
+
+        #[derive(Debug, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+        struct Hot {
+            a: u8,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            b: Option<u8>,
+            c: u8,
+        }
+
+        #[derive(Debug, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+        struct Envelope {
+            id: u8,
+            hot: Hot,
+        }
+
+        let envelope = Envelope {
+            id: 1,
+            hot: Hot {
+                a: 2,
+                b: None,
+                c: 3,
+            },
+        };
+        let mut vec = Vec::new();
+        let options = ser::CustomSerializerOptions::new()
+            .set_packed(true)
+            .set_packed_index_base(1)
+            .set_packed_index_offsets(&[("Hot", 10)]);
+        serde::Serialize::serialize(
+            &envelope,
+            &mut serde_cbor::Serializer::new_with_options(&mut vec, options),
+        )
+        .unwrap();
+        // the skipped field keeps its index
+        assert_eq!(vec, b"\xa2\x01\x01\x02\xa2\x0a\x02\x0c\x03");
+
+        const RENAMES: &[(WireKey<'static>, &str)] = &[
+            (WireKey::Integer(1), "id"),
+            (WireKey::Integer(2), "hot"),
+            (WireKey::Integer(10), "a"),
+            (WireKey::Integer(11), "b"),
+            (WireKey::Integer(12), "c"),
+        ];
+        let options = CustomDeserializerOptions::new().set_key_renames(RENAMES);
+        let mut deserializer = Deserializer::new_with_options(SliceRead::new(&vec), options);
+        let decoded: Envelope = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(decoded, envelope);
+
+        // the named format is unaffected
+        let options = ser::CustomSerializerOptions::new().set_packed_index_base(7);
+        let mut vec = Vec::new();
+        serde::Serialize::serialize(
+            &envelope.hot,
+            &mut serde_cbor::Serializer::new_with_options(&mut vec, options),
+        )
+        .unwrap();
+        assert_eq!(vec, b"\xa2\x61a\x02\x61c\x03");
+    }
+
+    /// Accepts at most three bytes per call and records how often it was called.
+    #[derive(Default)]
+    struct Trickle {
+        data: Vec<u8>,
+        calls: usize,
+    }
+
+    impl std::io::Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.write_vectored(&[std::io::IoSlice::new(buf)])
+        }
+
+        fn write_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) -> std::io::Result<usize> {
+            self.calls += 1;
+            let mut n = 0;
+            for buf in bufs {
+                let len = buf.len().min(3 - n);
+                self.data.extend_from_slice(&buf[..len]);
+                n += len;
+            }
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_vectored_string_write() {
+        let mut writer = Trickle::default();
+        serde_cbor::to_writer(&mut writer, &"a").unwrap();
+        assert_eq!(writer.data, b"\x61a");
+        assert_eq!(writer.calls, 1);
+
+        let text = "abcdefghijklmnopqrstuvwxyz";
+        let mut writer = Trickle::default();
+        serde_cbor::to_writer(&mut writer, &[text]).unwrap();
+        assert_eq!(writer.data, to_vec(&[text]).unwrap());
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        use serde_cbor::ser::KeyOrder;
+        use serde_derive::Serialize;
+        use std::collections::HashMap;
+
+        #[derive(Serialize)]
+        struct Inner {
+            zz: u8,
+            b: f32,
+        }
+
+        #[derive(Serialize)]
+        struct Outer {
+            list: Vec<HashMap<u32, u8>>,
+            inner: Inner,
+            a: (),
+        }
+
+        let mut map = HashMap::new();
+        map.insert(1000, 0);
+        map.insert(10, 1);
+        map.insert(24, 2);
+        let value = Outer {
+            list: vec![map],
+            inner: Inner { zz: 1, b: 1.5 },
+            a: (),
+        };
+        let encode = |order| {
+            let mut vec = Vec::new();
+            let mut serializer = ser::Serializer::new(&mut vec).sort_keys(order);
+            serde::Serialize::serialize(&value, &mut serializer).unwrap();
+            vec
+        };
+
+        // {"a": null, "list": [{10: 1, 24: 2, 1000: 0}], "inner": {"b": 1.5, "zz": 1}}
+        let length_first: &[u8] =
+            b"\xa3\x61a\xf6\x64list\x81\xa3\x0a\x01\x18\x18\x02\x19\x03\xe8\x00\
+            \x65inner\xa2\x61b\xf9\x3e\x00\x62zz\x01";
+        assert_eq!(encode(KeyOrder::LengthFirst), length_first);
+        // Text keys are ordered by length first in both orders.
+        assert_eq!(encode(KeyOrder::Bytewise), length_first);
+    }
+
+    #[test]
+    fn test_sort_keys_indefinite_map() {
+        use serde::ser::SerializeMap;
+        use serde_cbor::ser::KeyOrder;
+
+        let encode = |order| {
+            let mut vec = Vec::new();
+            let mut serializer = ser::Serializer::new(&mut vec).sort_keys(order);
+            let mut map = (&mut serializer).serialize_map(None).unwrap();
+            map.serialize_entry("b", &[1]).unwrap();
+            map.serialize_entry(&1000, &2).unwrap();
+            map.serialize_entry("a", &3).unwrap();
+            map.end().unwrap();
+            vec
+        };
+        // {_ 1000: 2, "a": 3, "b": [1]}
+        assert_eq!(
+            encode(KeyOrder::Bytewise),
+            b"\xbf\x19\x03\xe8\x02\x61a\x03\x61b\x81\x01\xff"
+        );
+        // {_ "a": 3, "b": [1], 1000: 2}
+        assert_eq!(
+            encode(KeyOrder::LengthFirst),
+            b"\xbf\x61a\x03\x61b\x81\x01\x19\x03\xe8\x02\xff"
+        );
+    }
+
+    #[test]
+    fn test_sort_keys_after_error() {
+        use serde::Serialize;
+        use serde_cbor::ser::KeyOrder;
+
+        let mut failing = BTreeMap::new();
+        failing.insert("a", vec![1i128 << 100]);
+        let mut vec = Vec::new();
+        let mut serializer = ser::Serializer::new(&mut vec).sort_keys(KeyOrder::Bytewise);
+        assert!(failing.serialize(&mut serializer).is_err());
+        5u8.serialize(&mut serializer).unwrap();
+        // The buffered entries of the failed map are dropped, the next value is written after the
+        // map header.
+        assert_eq!(vec, b"\xa1\x05");
+    }
+
+    #[test]
+    fn test_deny_duplicate_keys() {
+        use serde::ser::SerializeMap;
+        use serde_cbor::ser::{CustomSerializerOptions, KeyOrder};
+
+        let encode = |options: CustomSerializerOptions, keys: &[&str]| {
+            let mut vec = Vec::new();
+            let mut serializer = ser::Serializer::new_with_options(&mut vec, options);
+            let mut map = (&mut serializer).serialize_map(Some(keys.len()))?;
+            for key in keys {
+                map.serialize_entry(key, &0)?;
+            }
+            map.end().map(|_| vec)
+        };
+        let deny = || CustomSerializerOptions::new().set_deny_duplicate_keys(true);
+
+        // Without an order the entries are written as given.
+        assert_eq!(
+            encode(deny(), &["b", "a"]).unwrap(),
+            b"\xa2\x61b\x00\x61a\x00"
+        );
+        let err = encode(deny(), &["a", "b", "a"]).unwrap_err();
+        assert!(err.is_data());
+        assert_eq!(err.to_string(), "duplicate map key");
+        let sorted = deny().set_key_order(Some(KeyOrder::Bytewise));
+        assert!(encode(sorted, &["b", "a", "b"]).is_err());
+        // Duplicates are allowed by default.
+        assert!(encode(CustomSerializerOptions::new(), &["a", "a"]).is_ok());
+
+        // A char and a string with the same text have the same encoding.
+        let mut vec = Vec::new();
+        let mut serializer = ser::Serializer::new_with_options(&mut vec, deny());
+        let mut map = (&mut serializer).serialize_map(None).unwrap();
+        map.serialize_entry(&'x', &1).unwrap();
+        map.serialize_entry("x", &2).unwrap();
+        assert!(map.end().is_err());
+    }
+
+    #[test]
+    fn test_error_paths() {
+        use serde::Serialize;
+        use serde_cbor::ser::CustomSerializerOptions;
+
+        #[derive(Serialize)]
+        struct Inventory {
+            name: &'static str,
+            items: BTreeMap<&'static str, Vec<(u8, i128)>>,
+            totals: BTreeMap<u32, i128>,
+        }
+
+        let encode = |value: &Inventory, paths: bool| {
+            let options = CustomSerializerOptions::new().set_error_paths(paths);
+            let mut vec = Vec::new();
+            value.serialize(&mut ser::Serializer::new_with_options(&mut vec, options))
+        };
+        let mut value = Inventory {
+            name: "store",
+            items: BTreeMap::new(),
+            totals: BTreeMap::new(),
+        };
+        value.items.insert("apples", vec![(1, 2), (3, 1 << 100)]);
+        let err = encode(&value, true).unwrap_err();
+        assert_eq!(err.path(), Some("items.apples[1][1]"));
+        assert!(err.to_string().ends_with(" at `items.apples[1][1]`"));
+        assert_eq!(encode(&value, false).unwrap_err().path(), None);
+
+        value.items.clear();
+        value.totals.insert(7, -(1 << 100));
+        assert_eq!(encode(&value, true).unwrap_err().path(), Some("totals[7]"));
+        value.totals.clear();
+        assert!(encode(&value, true).is_ok());
+    }
+
+    #[test]
+    fn test_fail_safe() {
+        use serde::Serialize;
+        use serde_cbor::ser::{CustomSerializerOptions, KeyOrder};
+
+        #[derive(Serialize)]
+        enum Event {
+            Count(i128),
+            Range { from: i128, to: i128 },
+        }
+
+        let events = [
+            Event::Count(1),
+            Event::Range {
+                from: 0,
+                to: 1 << 100,
+            },
+            Event::Range { from: 2, to: 3 },
+            Event::Count(-(1 << 100)),
+        ];
+        let encode = |options: CustomSerializerOptions| {
+            let mut vec = Vec::new();
+            let mut serializer = ser::Serializer::new_with_options(&mut vec, options);
+            let failed = events
+                .iter()
+                .filter(|event| event.serialize(&mut serializer).is_err())
+                .count();
+            5u8.serialize(&mut serializer).unwrap();
+            assert_eq!(failed, 2);
+            vec
+        };
+
+        let complete = b"\xa1\x65Count\x01\xa1\x65Range\xa2\x64from\x02\x62to\x03\x05";
+        let fail_safe = CustomSerializerOptions::new().set_fail_safe(true);
+        assert_eq!(encode(fail_safe), complete);
+        let sorted = CustomSerializerOptions::new()
+            .set_fail_safe(true)
+            .set_key_order(Some(KeyOrder::LengthFirst));
+        assert_eq!(
+            encode(sorted),
+            b"\xa1\x65Count\x01\xa1\x65Range\xa2\x62to\x03\x64from\x02\x05"
+        );
+        // Without it the failed items are cut off in the middle.
+        assert!(encode(CustomSerializerOptions::new()).len() > complete.len());
+    }
+
+    /// Records every write.
+    #[derive(Default)]
+    struct Writes(Vec<Vec<u8>>);
+
+    impl std::io::Write for Writes {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_to_writer_buffered() {
+        let value = (1, "two", [3.5]);
+        let mut writes = Writes::default();
+        ser::to_writer(&mut writes, &value).unwrap();
+        assert!(writes.0.len() > 1);
+        let mut buffered = Writes::default();
+        ser::to_writer_buffered(&mut buffered, &value).unwrap();
+        assert_eq!(buffered.0, [writes.0.concat()]);
+    }
+
+    #[test]
+    fn test_short_strings_single_write() {
+        let long = "a string longer than 23 bytes";
+        let mut writes = Writes::default();
+        ser::to_writer(
+            &mut writes,
+            &("key", serde_bytes::Bytes::new(b"\x00\x01"), long),
+        )
+        .unwrap();
+        assert_eq!(
+            writes.0,
+            [
+                &b"\x83"[..],
+                b"\x63key",
+                b"\x42\x00\x01",
+                b"\x78\x1d",
+                long.as_bytes()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_buffered_io_write() {
+        use serde::Serialize;
+        use serde_cbor::ser::BufferedIoWrite;
+
+        let items = [vec![1u8; 3], vec![2; 30], vec![3; 5], vec![4; 6]]
+            .iter()
+            .map(|bytes| serde_bytes::ByteBuf::from(bytes.clone()))
+            .collect::<Vec<_>>();
+        let mut direct = Writes::default();
+        let mut buffered = Writes::default();
+        let mut writer = BufferedIoWrite::with_capacity(16, &mut buffered);
+        for item in &items {
+            ser::to_writer(&mut direct, item).unwrap();
+            item.serialize(&mut ser::Serializer::new(&mut writer))
+                .unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+        // 4 + 32 bytes do not fit, the second item is written directly, then 6 + 7 bytes
+        let lengths: Vec<_> = buffered.0.iter().map(|write| write.len()).collect();
+        assert_eq!(lengths, [4, 2, 30, 13]);
+        assert_eq!(buffered.0.concat(), direct.0.concat());
+    }
+
+    #[test]
+    fn test_buffered_io_write_drop_and_errors() {
+        use serde::Serialize;
+        use serde_cbor::ser::BufferedIoWrite;
+
+        // the output still buffered is written on drop
+        let mut buffered = Writes::default();
+        let mut writer = BufferedIoWrite::with_capacity(16, &mut buffered);
+        "abc"
+            .serialize(&mut ser::Serializer::new(&mut writer))
+            .unwrap();
+        drop(writer);
+        assert_eq!(buffered.0, [b"\x63abc"]);
+
+        /// Accepts two bytes per write and fails every other write.
+        #[derive(Default)]
+        struct Flaky {
+            written: Vec<u8>,
+            calls: usize,
+        }
+
+        impl std::io::Write for Flaky {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.calls += 1;
+                if self.calls % 2 == 0 {
+                    return Err(std::io::Error::other("busy"));
+                }
+                let n = buf.len().min(2);
+                self.written.extend_from_slice(&buf[..n]);
+                Ok(n)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        // bytes that could not be written are kept for the next try
+        let mut writer = BufferedIoWrite::with_capacity(16, Flaky::default());
+        "abcde"
+            .serialize(&mut ser::Serializer::new(&mut writer))
+            .unwrap();
+        assert!(writer.flush().is_err());
+        while writer.flush().is_err() {}
+        let flaky = writer.into_inner().unwrap();
+        assert_eq!(flaky.written, b"\x65abcde");
+    }
+
+    #[test]
+    fn test_io_write_borrow_ends_after_use() {
+        use serde::Serialize;
+        use serde_cbor::ser::IoWrite;
+
+        // an unbuffered `IoWrite` has no drop glue that keeps the borrow alive
+        let mut vec = Vec::new();
+        let mut writer = IoWrite::new(&mut vec);
+        "abc"
+            .serialize(&mut ser::Serializer::new(&mut writer))
+            .unwrap();
+        assert_eq!(vec, b"\x63abc");
+    }
 }
@@ -0,0 +1,141 @@
+#[cfg(feature = "futures")]
+mod stream_tests {
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use futures::executor::block_on;
+    use futures::io::AsyncRead;
+    use futures::StreamExt;
+    use serde_cbor::stream::into_stream;
+    use serde_cbor::Value;
+
+    /// Hands out at most `chunk` bytes per read and is pending before every read.
+    struct Trickle {
+        data: Vec<u8>,
+        pos: usize,
+        chunk: usize,
+        ready: bool,
+    }
+
+    impl Trickle {
+        fn new(data: &[u8], chunk: usize) -> Trickle {
+            Trickle {
+                data: data.to_vec(),
+                pos: 0,
+                chunk,
+                ready: false,
+            }
+        }
+    }
+
+    impl AsyncRead for Trickle {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            if !self.ready {
+                self.ready = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.ready = false;
+            let n = buf.len().min(self.chunk).min(self.data.len() - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    #[test]
+    fn test_values_across_reads() {
+        // "foo", [1, 2], {"a": h'0102'}
+        let input = b"\x63foo\x82\x01\x02\xa1\x61a\x42\x01\x02";
+        let mut stream = into_stream::<Value, _>(Trickle::new(input, 3));
+        block_on(async {
+            assert_eq!(
+                stream.next().await.unwrap().unwrap(),
+                Value::Text("foo".into())
+            );
+            assert_eq!(stream.byte_offset(), 4);
+            assert_eq!(
+                stream.next().await.unwrap().unwrap(),
+                Value::Array(vec![Value::Integer(1), Value::Integer(2)])
+            );
+            assert_eq!(stream.byte_offset(), 7);
+            assert!(stream.next().await.unwrap().is_ok());
+            assert_eq!(stream.byte_offset(), 13);
+            assert!(stream.next().await.is_none());
+        });
+    }
+
+    #[test]
+    fn test_values_byte_by_byte() {
+        // 1(h'01'), [_ 1, {_ "a": []}, (_ "b" "c")], {}, 2("x")
+        let input = b"\xc1\x41\x01\x9f\x01\xbf\x61a\x80\xff\x7f\x61b\x61c\xff\xff\xa0\xc2\x61x";
+        let stream = into_stream::<Value, _>(Trickle::new(input, 1)).chunk_size(1);
+        let values: Vec<Value> = block_on(stream.map(Result::unwrap).collect());
+        let expected = serde_cbor::Deserializer::from_slice(input).into_iter::<Value>();
+        assert_eq!(values, expected.collect::<Result<Vec<_>, _>>().unwrap());
+        assert_eq!(values.len(), 4);
+    }
+
+    #[test]
+    fn test_small_chunk_size() {
+        let input: Vec<u8> = (0..100u8).flat_map(|i| vec![0x18, i + 24]).collect();
+        let stream = into_stream::<u8, _>(&input[..]).chunk_size(3);
+        let values: Vec<u8> = block_on(stream.map(Result::unwrap).collect());
+        assert!(values.into_iter().eq(24..124));
+    }
+
+    #[test]
+    fn test_truncated_item() {
+        let input: &[u8] = b"\x01\x82\x01";
+        let mut stream = into_stream::<Value, _>(input);
+        block_on(async {
+            assert_eq!(stream.next().await.unwrap().unwrap(), Value::Integer(1));
+            assert!(stream.next().await.unwrap().unwrap_err().is_eof());
+            assert!(stream.next().await.is_none());
+        });
+    }
+
+    #[test]
+    fn test_error_offset() {
+        // 1, 2, then an array containing a reserved additional information value
+        let input: &[u8] = b"\x01\x02\x82\x01\x1c";
+        let mut stream = into_stream::<Value, _>(input);
+        block_on(async {
+            stream.next().await.unwrap().unwrap();
+            stream.next().await.unwrap().unwrap();
+            let err = stream.next().await.unwrap().unwrap_err();
+            assert!(err.is_syntax());
+            assert_eq!(err.offset(), 5);
+            assert!(stream.next().await.is_none());
+        });
+    }
+
+    #[test]
+    fn test_max_item_len() {
+        // A byte string announcing 1000 bytes.
+        let mut input = b"\x59\x03\xe8".to_vec();
+        input.extend_from_slice(&[0; 100]);
+        let mut stream = into_stream::<Value, _>(Trickle::new(&input, 16)).max_item_len(64);
+        block_on(async {
+            let err = stream.next().await.unwrap().unwrap_err();
+            assert!(err.is_syntax());
+            assert!(stream.next().await.is_none());
+        });
+    }
+
+    #[test]
+    fn test_into_inner() {
+        let input: &[u8] = b"\x01\x02\x03";
+        let mut stream = into_stream::<u8, _>(input);
+        block_on(async {
+            assert_eq!(stream.next().await.unwrap().unwrap(), 1);
+        });
+        let (_, rest) = stream.into_inner();
+        assert_eq!(rest, b"\x02\x03");
+    }
+}
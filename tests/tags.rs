@@ -45,4 +45,345 @@ C1                   # tag(1)
         assert_eq!(bytes1, bytes2);
         assert_eq!(value1, value2);
     }
+
+    #[test]
+    fn test_expected_encoding_tag() {
+        use serde_cbor::tags::ExpectedEncoding;
+
+        let bytes = serde_bytes::Bytes::new(b"\x01\x02");
+        let data = to_vec(&ExpectedEncoding::Base64Url.tagged(bytes)).unwrap();
+        assert_eq!(data, b"\xd5\x42\x01\x02");
+        let value: Value = from_slice(&data).unwrap();
+        let (encoding, inner) = value.expected_encoding().unwrap();
+        assert_eq!(encoding, ExpectedEncoding::Base64Url);
+        assert_eq!(*inner, Value::Bytes(vec![1, 2]));
+    }
+}
+
+#[cfg(feature = "std")]
+mod text_tag_tests {
+    use serde_cbor::tags::{CborMime, CborRegex, CborUri};
+    use serde_cbor::{from_slice, to_vec};
+
+    #[test]
+    fn test_validation() {
+        assert!(CborUri::new("http://www.example.com").is_ok());
+        assert!(CborUri::new("urn:isbn:0451450523").is_ok());
+        assert!(CborUri::new("www.example.com").is_err());
+        assert!(CborUri::new("http://example.com/a b").is_err());
+        assert!(CborUri::new("1http://example.com").is_err());
+
+        assert!(CborRegex::new("^(a|[b)])*\\)$").is_ok());
+        assert!(CborRegex::new("(a").is_err());
+        assert!(CborRegex::new("a)").is_err());
+        assert!(CborRegex::new("[a").is_err());
+        assert!(CborRegex::new("a\\").is_err());
+
+        assert!(CborMime::new("text/plain; charset=utf-8\n\nhello").is_ok());
+        assert!(CborMime::new("MIME-Version: 1.0\nContent-Type: text/plain\n\nhi").is_ok());
+        assert!(CborMime::new("Subject: hi\n\nContent-Type: text/plain").is_err());
+        assert!(CborMime::new("plain text").is_err());
+    }
+
+    #[test]
+    fn test_decode_validates() {
+        let uri = CborUri::new("http://www.example.com").unwrap();
+        let decoded: CborUri = from_slice(&to_vec(&uri).unwrap()).unwrap();
+        assert_eq!(decoded.as_str(), "http://www.example.com");
+
+        // "(a"
+        let err = from_slice::<CborRegex>(b"\x62(a").unwrap_err();
+        assert!(err.is_data());
+        assert_eq!(err.to_string(), "invalid a regular expression: \"(a\"");
+    }
+
+    #[cfg(feature = "tags")]
+    #[test]
+    fn test_tags() {
+        let uri = CborUri::new("a:b").unwrap();
+        assert_eq!(to_vec(&uri).unwrap(), b"\xd8\x20\x63a:b");
+        let regex = CborRegex::new("a+").unwrap();
+        assert_eq!(to_vec(&regex).unwrap(), b"\xd8\x23\x62a+");
+        let mime = CborMime::new("text/plain").unwrap();
+        assert_eq!(to_vec(&mime).unwrap(), b"\xd8\x24\x6atext/plain");
+
+        assert_eq!(from_slice::<CborUri>(b"\xd8\x20\x63a:b").unwrap(), uri);
+        let err = from_slice::<CborUri>(b"\xd8\x23\x63a:b").unwrap_err();
+        assert_eq!(err.to_string(), "expected tag 32 for a URI, found tag 35");
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn test_url() {
+        let url = url::Url::parse("https://example.com/path?q=1").unwrap();
+        let uri = CborUri::from(url.clone());
+        assert_eq!(uri.to_url().unwrap(), url);
+    }
+}
+
+#[cfg(feature = "std")]
+mod expected_encoding_tests {
+    use serde_bytes::ByteBuf;
+    use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead};
+    use serde_cbor::tags::ExpectedEncoding;
+
+    #[test]
+    fn test_encode_decode() {
+        let cases: &[(&[u8], &str, &str, &str)] = &[
+            (b"", "", "", ""),
+            (b"f", "Zg", "Zg==", "66"),
+            (b"fo", "Zm8", "Zm8=", "666f"),
+            (b"foo", "Zm9v", "Zm9v", "666f6f"),
+            (b"\xfb\xff", "-_8", "+/8=", "fbff"),
+        ];
+        for &(bytes, url, base64, base16) in cases {
+            assert_eq!(ExpectedEncoding::Base64Url.encode(bytes), url);
+            assert_eq!(ExpectedEncoding::Base64.encode(bytes), base64);
+            assert_eq!(ExpectedEncoding::Base16.encode(bytes), base16);
+            assert_eq!(ExpectedEncoding::Base64Url.decode(url).unwrap(), bytes);
+            assert_eq!(ExpectedEncoding::Base64.decode(base64).unwrap(), bytes);
+            assert_eq!(ExpectedEncoding::Base16.decode(base16).unwrap(), bytes);
+        }
+        assert_eq!(ExpectedEncoding::Base64.decode("Zm8").unwrap(), b"fo");
+        assert_eq!(
+            ExpectedEncoding::Base16.decode("FBFF").unwrap(),
+            b"\xfb\xff"
+        );
+        assert!(ExpectedEncoding::Base64.decode("-_8=").is_none());
+        assert!(ExpectedEncoding::Base64.decode("Z===").is_none());
+        assert!(ExpectedEncoding::Base64.decode("Zg=a").is_none());
+        assert!(ExpectedEncoding::Base16.decode("abc").is_none());
+    }
+
+    #[test]
+    fn test_decode_expected_encodings() {
+        fn decode(input: &[u8], enabled: bool) -> serde_cbor::Result<ByteBuf> {
+            let options = CustomDeserializerOptions::new().set_decode_expected_encodings(enabled);
+            let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+            serde::Deserialize::deserialize(&mut deserializer)
+        }
+
+        // 21("Zm9v"), 22("Zm9v"), 23("666f6f")
+        assert_eq!(decode(b"\xd5\x64Zm9v", true).unwrap(), b"foo".to_vec());
+        assert_eq!(decode(b"\xd6\x64Zm9v", true).unwrap(), b"foo".to_vec());
+        assert_eq!(decode(b"\xd7\x66666f6f", true).unwrap(), b"foo".to_vec());
+        // 23(h'0102') keeps its bytes.
+        assert_eq!(decode(b"\xd7\x42\x01\x02", true).unwrap(), vec![1, 2]);
+        assert_ne!(
+            decode(b"\xd5\x64Zm9v", false).ok(),
+            Some(ByteBuf::from(b"foo".to_vec()))
+        );
+
+        let err = decode(b"\xd7\x63abc", true).unwrap_err();
+        assert!(err.is_data());
+        assert_eq!(err.offset(), 1);
+    }
+}
+
+#[cfg(feature = "std")]
+mod set_tests {
+    use serde_cbor::{from_slice, to_vec};
+    use serde_derive::{Deserialize, Serialize};
+    use std::collections::{BTreeSet, HashSet};
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Sets {
+        #[serde(with = "serde_cbor::tags::set")]
+        tagged: BTreeSet<u8>,
+        plain: HashSet<u8>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Unique {
+        #[serde(deserialize_with = "serde_cbor::tags::set::deserialize_unique")]
+        set: BTreeSet<u8>,
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let sets = Sets {
+            tagged: vec![1, 2].into_iter().collect(),
+            plain: vec![3].into_iter().collect(),
+        };
+        let data = to_vec(&sets).unwrap();
+        assert_eq!(from_slice::<Sets>(&data).unwrap(), sets);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        // {"set": [1, 1]}
+        let data = b"\xa1\x63set\x82\x01\x01";
+        let err = from_slice::<Unique>(data).unwrap_err();
+        assert_eq!(err.to_string(), "duplicate element in set at offset 5");
+        // {"set": [1, 2]}
+        let unique: Unique = from_slice(b"\xa1\x63set\x82\x01\x02").unwrap();
+        assert_eq!(unique.set, vec![1, 2].into_iter().collect());
+        // Plain sets drop duplicates.
+        let set: HashSet<u8> = from_slice(b"\x82\x01\x01").unwrap();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[cfg(feature = "tags")]
+    #[test]
+    fn test_tags() {
+        let sets = Sets {
+            tagged: vec![1].into_iter().collect(),
+            plain: vec![2].into_iter().collect(),
+        };
+        // {"tagged": 258([1]), "plain": [2]}
+        let data = b"\xa2\x66tagged\xd9\x01\x02\x81\x01\x65plain\x81\x02";
+        assert_eq!(to_vec(&sets).unwrap(), data);
+
+        // Set types accept the tag without the helper module.
+        let set: BTreeSet<u8> = from_slice(b"\xd9\x01\x02\x82\x01\x02").unwrap();
+        assert_eq!(set, vec![1, 2].into_iter().collect());
+        let set: Vec<u8> = from_slice(b"\xd9\x01\x02\x81\x01").unwrap();
+        assert_eq!(set, vec![1]);
+
+        // 259([1])
+        let data = b"\xa2\x66tagged\xd9\x01\x03\x81\x01\x65plain\x80";
+        let err = from_slice::<Sets>(data).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected tag 258 for a set, found tag 259 at offset 8"
+        );
+    }
+}
+
+#[cfg(feature = "std")]
+mod cbor2_tests {
+    use serde_cbor::{from_slice, to_vec};
+    use serde_derive::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Payment {
+        #[serde(with = "serde_cbor::tags::datetime")]
+        at: String,
+        #[serde(with = "serde_cbor::tags::decimal")]
+        amount: f64,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Amount(#[serde(with = "serde_cbor::tags::decimal")] f64);
+
+    #[test]
+    fn test_roundtrip() {
+        let payment = Payment {
+            at: "2013-03-21T20:04:00Z".to_string(),
+            amount: -0.05,
+        };
+        let data = to_vec(&payment).unwrap();
+        assert_eq!(from_slice::<Payment>(&data).unwrap(), payment);
+    }
+
+    #[test]
+    fn test_decimal_forms() {
+        // [-2, 5], [2, -3], [0, 7]
+        assert_eq!(from_slice::<Amount>(b"\x82\x21\x05").unwrap().0, 0.05);
+        assert_eq!(from_slice::<Amount>(b"\x82\x02\x22").unwrap().0, -300.0);
+        assert_eq!(from_slice::<Amount>(b"\x82\x00\x07").unwrap().0, 7.0);
+        // "1.25"
+        assert_eq!(from_slice::<Amount>(b"\x641.25").unwrap().0, 1.25);
+
+        // [5000, 1]
+        let err = from_slice::<Amount>(b"\x82\x19\x13\x88\x01").unwrap_err();
+        assert_eq!(err.to_string(), "decimal exponent 5000 is out of range");
+        assert!(to_vec(&Amount(f64::NAN)).is_err());
+    }
+
+    #[cfg(feature = "tags")]
+    #[test]
+    fn test_tags() {
+        let payment = Payment {
+            at: "2013-03-21T20:04:00Z".to_string(),
+            amount: 12.5,
+        };
+        // {"at": 0("2013-03-21T20:04:00Z"), "amount": 4([-1, 125])}
+        let data = b"\xa2\x62at\xc0\x742013-03-21T20:04:00Z\x66amount\xc4\x82\x20\x18\x7d";
+        assert_eq!(to_vec(&payment).unwrap(), &data[..]);
+        assert_eq!(from_slice::<Payment>(data).unwrap(), payment);
+
+        // 5([-1, 125])
+        let err = from_slice::<Amount>(b"\xc5\x82\x20\x18\x7d").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected tag 4 for a decimal fraction, found tag 5"
+        );
+        // 4("1.25")
+        assert!(from_slice::<Amount>(b"\xc4\x641.25").is_err());
+    }
+}
+
+#[cfg(feature = "std")]
+mod network_tests {
+    use serde_cbor::{from_slice, to_vec};
+    use serde_derive::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Network {
+        #[serde(with = "serde_cbor::tags::network_address")]
+        addr: IpAddr,
+        #[serde(with = "serde_cbor::tags::network_prefix")]
+        prefix: (IpAddr, u8),
+        #[serde(with = "serde_cbor::tags::map")]
+        ports: BTreeMap<u16, String>,
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let network = Network {
+            addr: IpAddr::V6(Ipv6Addr::LOCALHOST),
+            prefix: (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)), 8),
+            ports: vec![(80, "http".to_string())].into_iter().collect(),
+        };
+        let data = to_vec(&network).unwrap();
+        assert_eq!(from_slice::<Network>(&data).unwrap(), network);
+    }
+
+    #[test]
+    fn test_invalid() {
+        #[derive(Debug, Deserialize)]
+        struct Addr(
+            #[allow(dead_code)]
+            #[serde(with = "serde_cbor::tags::network_address")]
+            IpAddr,
+        );
+        #[derive(Debug, Deserialize)]
+        struct Prefix(
+            #[allow(dead_code)]
+            #[serde(with = "serde_cbor::tags::network_prefix")]
+            (IpAddr, u8),
+        );
+
+        // A MAC address
+        assert!(from_slice::<Addr>(b"\x46\x01\x02\x03\x04\x05\x06").is_err());
+        // {33: h'0a000000'}
+        let err = from_slice::<Prefix>(b"\xa1\x18\x21\x44\x0a\x00\x00\x00").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "prefix length 33 is longer than the address"
+        );
+    }
+
+    #[cfg(feature = "tags")]
+    #[test]
+    fn test_tags() {
+        let network = Network {
+            addr: IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)),
+            prefix: (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)), 8),
+            ports: BTreeMap::new(),
+        };
+        // {"addr": 260(h'c0a80001'), "prefix": 261({8: h'0a000000'}), "ports": 259({})}
+        let data: &[u8] = b"\xa3\x64addr\xd9\x01\x04\x44\xc0\xa8\x00\x01\
+            \x66prefix\xd9\x01\x05\xa1\x08\x44\x0a\x00\x00\x00\
+            \x65ports\xd9\x01\x03\xa0";
+        assert_eq!(to_vec(&network).unwrap(), data);
+        assert_eq!(from_slice::<Network>(data).unwrap(), network);
+
+        // Map types accept the tag without the helper module.
+        let map: BTreeMap<u8, u8> = from_slice(b"\xd9\x01\x03\xa1\x01\x02").unwrap();
+        assert_eq!(map, vec![(1, 2)].into_iter().collect());
+    }
 }
@@ -0,0 +1,100 @@
+#[cfg(feature = "tracing")]
+mod tracing_tests {
+    use std::fmt;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use serde_cbor::value::Value;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Records span names and event fields as strings.
+    #[derive(Clone, Default)]
+    struct Recorder {
+        lines: Arc<Mutex<Vec<String>>>,
+        next_id: Arc<AtomicU64>,
+    }
+
+    struct Line<'a>(&'a mut String);
+
+    impl<'a> Visit for Line<'a> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut line = span.metadata().name().to_owned();
+            span.record(&mut Line(&mut line));
+            self.lines.lock().unwrap().push(line);
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut line = "event".to_owned();
+            event.record(&mut Line(&mut line));
+            self.lines.lock().unwrap().push(line);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    fn record<F: FnOnce()>(f: F) -> Vec<String> {
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), f);
+        let lines = recorder.lines.lock().unwrap().clone();
+        lines
+    }
+
+    #[test]
+    fn test_trace_values_and_collections() {
+        let lines = record(|| {
+            let value: Value = serde_cbor::from_slice(b"\x82\x01\x02").unwrap();
+            assert_eq!(
+                value,
+                Value::Array(vec![Value::Integer(1), Value::Integer(2)])
+            );
+        });
+        assert_eq!(
+            lines,
+            vec![
+                "parse_value offset=0 byte=130",
+                "event message=array begin offset=1 len=Some(2)",
+                "parse_value offset=1 byte=1",
+                "parse_value offset=2 byte=2",
+                "event message=array end offset=3",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trace_errors() {
+        let lines = record(|| {
+            serde_cbor::from_slice::<Value>(b"\xa1\x01\x1c").unwrap_err();
+        });
+        assert!(lines
+            .last()
+            .unwrap()
+            .starts_with("event message=CBOR syntax error offset=3 code=expected"));
+
+        let lines = record(|| {
+            serde_cbor::from_slice::<(u8, u8)>(b"\x81\x01").unwrap_err();
+        });
+        assert_eq!(
+            lines.last().unwrap(),
+            "event message=CBOR data error error=invalid length 1, expected a tuple of size 2"
+        );
+    }
+}
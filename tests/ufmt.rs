@@ -0,0 +1,26 @@
+#[cfg(feature = "ufmt")]
+mod ufmt_tests {
+    use serde_cbor::value::Value;
+
+    fn format(err: &serde_cbor::Error) -> String {
+        let mut s = String::new();
+        ufmt::uwrite!(s, "{}", err).unwrap();
+        s
+    }
+
+    #[test]
+    fn test_error_display() {
+        let err = serde_cbor::from_slice::<Value>(b"\x82\x01").unwrap_err();
+        assert_eq!(format(&err), "EOF while parsing a value at offset 2");
+
+        let err = serde_cbor::from_slice::<(u8, u8)>(b"\x81\x01").unwrap_err();
+        assert_eq!(format(&err), err.to_string());
+
+        let err = serde_cbor::from_slice::<Value>(b"\x1c").unwrap_err();
+        assert_eq!(
+            format(&err),
+            "expected {String,Bytes,IntPos,IntNeg,Float,Array,Map,Bool,Null,} found byte 0x1C \
+             at offset 1"
+        );
+    }
+}
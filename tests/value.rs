@@ -97,4 +97,492 @@ mod std_tests {
         let reference = b"\xa2\x00\x11\x01\x18\x2a";
         assert_eq!(data, reference);
     }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    struct Known {
+        id: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    }
+
+    #[test]
+    fn with_unknown_captures_entries() {
+        use serde_cbor::value::WithUnknown;
+
+        let mut input = BTreeMap::new();
+        input.insert(Value::Text("id".to_owned()), Value::Integer(7));
+        input.insert(
+            Value::Text("extra".to_owned()),
+            Value::Array(vec![Value::Null]),
+        );
+        input.insert(Value::Integer(-3), Value::Bool(true));
+        input.insert(Value::Float(1.5), Value::Text("f".to_owned()));
+        let data = serde_cbor::to_vec(&Value::Map(input.clone())).unwrap();
+
+        let value: WithUnknown<Known> = serde_cbor::from_slice(&data).unwrap();
+        assert_eq!(value.value, Known { id: 7, name: None });
+        let mut unknown = input.clone();
+        unknown.remove(&Value::Text("id".to_owned()));
+        assert_eq!(value.unknown, unknown);
+
+        let reencoded: Value =
+            serde_cbor::from_slice(&serde_cbor::to_vec(&value).unwrap()).unwrap();
+        assert_eq!(reencoded, Value::Map(input));
+
+        let value: WithUnknown<Known> = serde_cbor::from_reader(&data[..]).unwrap();
+        assert_eq!(value.unknown.len(), 3);
+    }
+
+    #[test]
+    fn with_unknown_packed() {
+        use serde_cbor::value::WithUnknown;
+
+        // {0: 17, 2: "new"}
+        let data = b"\xa2\x00\x11\x02\x63new";
+        let value: WithUnknown<Known> = serde_cbor::from_slice(data).unwrap();
+        assert_eq!(value.value, Known { id: 17, name: None });
+        assert_eq!(
+            value.unknown.get(&Value::Integer(2)),
+            Some(&Value::Text("new".to_owned()))
+        );
+        assert!(serde_cbor::from_slice::<WithUnknown<Known>>(b"\x82\x00\x01").is_err());
+    }
+
+    #[test]
+    fn with_unknown_keeps_order_and_keys() {
+        use serde_cbor::value::WithUnknown;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Reversed {
+            zz: u8,
+            a: u8,
+        }
+
+        // {"zz": 1, 3: "x", "a": 2, h'6b': true}
+        let data = b"\xa4\x62zz\x01\x03\x61x\x61a\x02\x41k\xf5";
+        let value: WithUnknown<Reversed> = serde_cbor::from_slice(data).unwrap();
+        assert_eq!(value.value, Reversed { zz: 1, a: 2 });
+        assert_eq!(value.unknown.len(), 2);
+        // the fields in declaration order, then the unknown entries with their own keys
+        let encoded = serde_cbor::to_vec(&value).unwrap();
+        assert_eq!(encoded, b"\xa4\x62zz\x01\x61a\x02\x03\x61x\x41k\xf5");
+
+        let value = WithUnknown::new(Known { id: 1, name: None });
+        assert_eq!(serde_cbor::to_vec(&value).unwrap(), b"\xa1\x62id\x01");
+        assert!(serde_cbor::to_vec(&WithUnknown::new(5u8)).is_err());
+    }
+
+    #[test]
+    fn test_untagged() {
+        use serde_cbor::value::{Untagged, Value};
+
+        #[derive(Debug, PartialEq)]
+        enum Id {
+            Number(u8),
+            Name(String),
+            Pair(u8, u8),
+        }
+
+        let decode = |value: Value| {
+            Untagged::new("Id", value)
+                .arm("Number", Id::Number)
+                .arm("Name", Id::Name)
+                .arm("Pair", |(a, b)| Id::Pair(a, b))
+        };
+
+        let value: Value = serde_cbor::from_reader(&b"\x82\x01\x02"[..]).unwrap();
+        let untagged = decode(value);
+        assert_eq!(untagged.errors().len(), 2);
+        assert_eq!(untagged.errors()[0].0, "Number");
+        assert_eq!(untagged.finish().unwrap(), Id::Pair(1, 2));
+
+        // The first matching arm wins.
+        let untagged = decode(Value::Integer(7));
+        assert!(untagged.errors().is_empty());
+        assert_eq!(untagged.finish().unwrap(), Id::Number(7));
+
+        let untagged = decode(Value::Integer(300));
+        let arms: Vec<_> = untagged.errors().iter().map(|(arm, _)| *arm).collect();
+        assert_eq!(arms, ["Number", "Name", "Pair"]);
+        let err = untagged.finish().unwrap_err();
+        assert!(err.is_data());
+        assert!(err
+            .to_string()
+            .starts_with("data did not match any variant of untagged enum Id (Number: "));
+    }
+
+    #[test]
+    fn test_flatten() {
+        use serde_cbor::value::{Flatten, Value};
+
+        #[derive(Debug, PartialEq, Deserialize, Serialize)]
+        struct Header {
+            id: u32,
+        }
+
+        // {"id": 1, 5: h'00', 7: "x"}
+        let data = b"\xa3\x62id\x01\x05\x41\x00\x07\x61x";
+        let decoded: Flatten<Header, BTreeMap<u32, Value>> = serde_cbor::from_slice(data).unwrap();
+        assert_eq!(decoded.outer, Header { id: 1 });
+        assert_eq!(decoded.inner[&5], Value::Bytes(vec![0]));
+        assert_eq!(decoded.inner[&7], Value::Text("x".to_owned()));
+        assert_eq!(serde_cbor::to_vec(&decoded).unwrap(), &data[..]);
+
+        let decoded: Flatten<Header, BTreeMap<u32, Value>> =
+            serde_cbor::from_reader(&data[..]).unwrap();
+        assert_eq!(decoded.inner.len(), 2);
+
+        assert!(serde_cbor::from_slice::<Flatten<Header, Header>>(data).is_err());
+        let not_a_map = Flatten {
+            outer: Header { id: 1 },
+            inner: 2,
+        };
+        assert!(serde_cbor::to_vec(&not_a_map).is_err());
+    }
+
+    #[test]
+    fn test_flatten_serialize_order_and_options() {
+        use serde_cbor::value::Flatten;
+
+        #[derive(Debug, PartialEq, Deserialize, Serialize)]
+        struct Header {
+            z: u32,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize, Serialize)]
+        struct Body {
+            y: Header,
+            a: u32,
+        }
+
+        let message = Flatten {
+            outer: Header { z: 1 },
+            inner: Body {
+                y: Header { z: 2 },
+                a: 3,
+            },
+        };
+        // {"z": 1, "y": {"z": 2}, "a": 3}
+        let data = serde_cbor::to_vec(&message).unwrap();
+        assert_eq!(data, b"\xa3\x61z\x01\x61y\xa1\x61z\x02\x61a\x03");
+        let decoded: Flatten<Header, Body> = serde_cbor::from_slice(&data).unwrap();
+        assert_eq!(decoded, message);
+
+        // the flattened entries keep their names, but nested structs are packed:
+        // {"z": 1, "y": {0: 2}, "a": 3}
+        let data = serde_cbor::ser::to_vec_packed(&message).unwrap();
+        assert_eq!(data, b"\xa3\x61z\x01\x61y\xa1\x00\x02\x61a\x03");
+    }
+
+    #[test]
+    fn test_preserve_float_widths() {
+        use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead};
+        use serde_cbor::value::{to_value, FloatWidth, Value};
+
+        fn decode<T: serde::de::DeserializeOwned>(input: &[u8]) -> T {
+            let options = CustomDeserializerOptions::new().set_preserve_float_widths(true);
+            let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options);
+            T::deserialize(&mut deserializer).unwrap()
+        }
+
+        // [1.5 as f32, 1.5 as f16, 1.1 as f64, NaN as f64, Infinity as f32]
+        let input = b"\x85\xfa\x3f\xc0\x00\x00\xf9\x3e\x00\xfb\x3f\xf1\x99\x99\x99\x99\x99\x9a\
+            \xfb\x7f\xf8\x00\x00\x00\x00\x00\x00\xfa\x7f\x80\x00\x00";
+        let value: Value = decode(input);
+        assert_eq!(
+            value,
+            Value::Array(vec![
+                Value::SizedFloat(1.5, FloatWidth::Single),
+                Value::Float(1.5),
+                Value::Float(1.1),
+                Value::SizedFloat(f64::NAN, FloatWidth::Double),
+                Value::SizedFloat(f64::INFINITY, FloatWidth::Single),
+            ])
+        );
+        assert_eq!(serde_cbor::to_vec(&value).unwrap(), &input[..]);
+        assert_eq!(to_value(&value).unwrap(), value);
+
+        // without the option the shortest width is used
+        let value: Value = serde_cbor::from_slice(input).unwrap();
+        match value {
+            Value::Array(items) => assert!(items.iter().all(|v| matches!(v, Value::Float(_)))),
+            _ => panic!("expected an array"),
+        }
+
+        // other types are not affected
+        let floats: Vec<f64> = decode(input);
+        assert_eq!(floats[0], 1.5);
+
+        // a value that does not fit into the width is written with the shortest one
+        let value = Value::SizedFloat(1.1, FloatWidth::Half);
+        assert_eq!(
+            serde_cbor::to_vec(&value).unwrap(),
+            b"\xfb\x3f\xf1\x99\x99\x99\x99\x99\x9a"
+        );
+    }
+
+    #[test]
+    fn test_preserve_integer_widths() {
+        use serde_cbor::de::{CustomDeserializerOptions, Deserializer, SliceRead};
+        use serde_cbor::value::{to_value, IntegerWidth, Value};
+
+        fn options() -> CustomDeserializerOptions {
+            CustomDeserializerOptions::new().set_preserve_integer_widths(true)
+        }
+
+        // {24: -2^64, 1 with a 2 byte argument: -1 with a 1 byte argument}, keys sorted by their
+        // encoding like a `Value::Map` does
+        let input = b"\xa2\x18\x18\x3b\xff\xff\xff\xff\xff\xff\xff\xff\x19\x00\x01\x38\x00";
+        let mut deserializer = Deserializer::new_with_options(SliceRead::new(input), options());
+        let value: Value = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+        let mut expected = BTreeMap::new();
+        expected.insert(
+            Value::SizedInteger(1, IntegerWidth::Two),
+            Value::SizedInteger(-1, IntegerWidth::One),
+        );
+        expected.insert(Value::Integer(24), Value::Integer(-(1 << 64)));
+        assert_eq!(value, Value::Map(expected));
+        assert_eq!(serde_cbor::to_vec(&value).unwrap(), &input[..]);
+        assert_eq!(to_value(&value).unwrap(), value);
+
+        // other types are not affected
+        let mut deserializer =
+            Deserializer::new_with_options(SliceRead::new(b"\x19\x00\x01"), options());
+        let n: u8 = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(n, 1);
+
+        // a value that does not fit into the width is written with the shortest one
+        let value = Value::SizedInteger(300, IntegerWidth::One);
+        assert_eq!(serde_cbor::to_vec(&value).unwrap(), b"\x19\x01\x2c");
+    }
+
+    #[test]
+    fn test_owned_iterators() {
+        let array = Value::Array(vec![Value::Text("a".into()), Value::Null]);
+        let mut iter = array.into_iter();
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next_back(), Some((Value::Integer(1), Value::Null)));
+        assert_eq!(
+            iter.collect::<Vec<_>>(),
+            vec![(Value::Integer(0), Value::Text("a".into()))]
+        );
+        assert_eq!(Value::Bool(true).into_iter().next(), None);
+        assert_eq!(Value::Tag(1, Box::new(Value::Null)).into_iter().len(), 0);
+
+        // Entries with equal keys keep the last value.
+        let map: Value = vec![
+            (Value::Integer(1), Value::Integer(10)),
+            (Value::Integer(1), Value::Integer(11)),
+        ]
+        .into_iter()
+        .collect();
+        let entries: Vec<_> = map.into_iter().collect();
+        assert_eq!(entries, vec![(Value::Integer(1), Value::Integer(11))]);
+
+        let mut value = Value::Null;
+        value.extend(vec![Value::Integer(1)]);
+        value.extend((2..4).map(Value::from));
+        assert_eq!(value, (1..4).map(Value::from).collect());
+
+        let mut value = Value::Null;
+        value.extend(Some((Value::Text("k".into()), Value::Null)));
+        assert_eq!(
+            value,
+            Value::Map(BTreeMap::from_iter(Some((
+                Value::Text("k".into()),
+                Value::Null
+            ))))
+        );
+    }
+
+    #[test]
+    fn test_number() {
+        use serde_cbor::value::{FloatWidth, IntegerWidth, Number};
+
+        let number = |value: Value| value.as_number().unwrap();
+        assert_eq!(number(Value::Integer(-1)).as_u64_checked(), None);
+        assert_eq!(number(Value::Integer(-1)).as_i64_checked(), Some(-1));
+        let max = number(Value::Integer(u64::MAX.into()));
+        assert_eq!(max.as_u64_checked(), Some(u64::MAX));
+        assert_eq!(max.as_i64_checked(), None);
+        assert_eq!(max, Number::from(u64::MAX));
+
+        let sized = number(Value::SizedInteger(5, IntegerWidth::Four));
+        assert_eq!(sized.as_i64_checked(), Some(5));
+        let float = number(Value::SizedFloat(2.0, FloatWidth::Single));
+        assert!(float.is_float() && !float.is_integer());
+        assert_eq!(float.as_i64_checked(), None);
+        assert_eq!(float.as_f64_lossy(), 2.0);
+        assert_eq!(float.to_string(), "2");
+        assert_eq!(Value::from(float), Value::Float(2.0));
+
+        assert_eq!(Value::Text("1".into()).as_number(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "only an array can be extended with elements, not Bool(true)")]
+    fn test_extend_scalar() {
+        Value::Bool(true).extend(vec![Value::Null]);
+    }
+
+    #[test]
+    fn test_value_ref() {
+        use serde_cbor::value::ValueRef;
+        use std::borrow::Cow;
+
+        // {"b": [_ h'01', h'02'], "a": 6(h'03'), "b": "x"}, the key repeats
+        let input = b"\xa3\x61b\x5f\x41\x01\x41\x02\xff\x61a\xc6\x41\x03\x61b\x61x";
+        let value: ValueRef = serde_cbor::from_slice(input).unwrap();
+        let entries = match &value {
+            ValueRef::Map(entries) => entries,
+            other => panic!("not a map: {:?}", other),
+        };
+        assert_eq!(entries[0].0, ValueRef::Text(Cow::Borrowed("b")));
+        // chunks are joined into a copy
+        assert!(matches!(entries[0].1, ValueRef::Bytes(Cow::Owned(_))));
+        assert_eq!(entries[0].1, ValueRef::Bytes(Cow::Borrowed(b"\x01\x02")));
+        assert!(matches!(entries[2].1, ValueRef::Text(Cow::Borrowed("x"))));
+
+        // the order and the repeated key are kept, unlike for a `Value`
+        let output = serde_cbor::to_vec(&value).unwrap();
+        let mut expected = b"\xa3\x61b\x42\x01\x02\x61a".to_vec();
+        if cfg!(feature = "tags") {
+            expected.push(0xc6);
+        }
+        expected.extend_from_slice(b"\x41\x03\x61b\x61x");
+        assert_eq!(output, expected);
+
+        let owned = value.clone().into_owned();
+        let map = match &owned {
+            Value::Map(map) => map,
+            other => panic!("not a map: {:?}", other),
+        };
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&Value::Text("b".into())], Value::Text("x".into()));
+        let borrowed = ValueRef::from(&owned);
+        assert_eq!(borrowed.into_owned(), owned);
+    }
+
+    #[test]
+    fn test_redact() {
+        use serde_cbor::value::redact_slice;
+
+        // {"a": 1("secret"), "b/c": "kept", h'00': "kept", "list": [_ {"pin": 1234}, "x"]}
+        let input = b"\xa4\x61a\xc1\x66secret\x63b/c\x64kept\x41\x00\x64kept\
+                      \x64list\x9f\xa1\x63pin\x19\x04\xd2\x61x\xff";
+        let value: Value = serde_cbor::from_slice(input).unwrap();
+
+        let mut visited = Vec::new();
+        let mut raw = Vec::new();
+        redact_slice(&input[..], &mut raw, |path: &str| {
+            visited.push(path.to_string());
+            path == "list/0"
+        })
+        .unwrap();
+        assert_eq!(visited, ["", "a", "list", "list/0", "list/1"]);
+        // the indefinite length array and the tag are copied as they are
+        assert_eq!(
+            raw,
+            &b"\xa4\x61a\xc1\x66secret\x63b/c\x64kept\x41\x00\x64kept\
+               \x64list\x9f\x6a[redacted]\x61x\xff"[..]
+        );
+        let redacted = value.redact(["list/0"]);
+        assert_eq!(redacted, serde_cbor::from_slice::<Value>(&raw).unwrap());
+
+        let redacted = value.redact(["a", "list/*/pin", "b/c"]);
+        let mut raw = Vec::new();
+        redact_slice(&input[..], &mut raw, ["a", "list/*/pin", "b/c"]).unwrap();
+        assert_eq!(redacted, serde_cbor::from_slice::<Value>(&raw).unwrap());
+        assert_eq!(value.redact([""]), Value::Text("[redacted]".into()));
+
+        let err = redact_slice(b"\x01\x02", Vec::new(), ["x"]).unwrap_err();
+        assert!(err.is_syntax());
+        assert_eq!(err.offset(), 1);
+        let err = redact_slice(b"\x82\x01", Vec::new(), ["0"]).unwrap_err();
+        assert!(err.is_eof());
+    }
+}
+
+#[cfg(feature = "json")]
+mod json_tests {
+    use std::collections::BTreeMap;
+    use std::convert::TryFrom;
+
+    use serde_cbor::value::{JsonBytes, JsonIntegers, JsonKeys, JsonOptions, Value};
+    use serde_json::json;
+
+    fn map(entries: Vec<(Value, Value)>) -> Value {
+        Value::Map(entries.into_iter().collect::<BTreeMap<_, _>>())
+    }
+
+    #[test]
+    fn test_from_json() {
+        let json = json!({"a": [1, -2, 1.5, null, true, "x"], "b": 18446744073709551615u64});
+        let value = Value::try_from(json).unwrap();
+        let expected = map(vec![
+            (
+                Value::Text("a".into()),
+                Value::Array(vec![
+                    Value::Integer(1),
+                    Value::Integer(-2),
+                    Value::Float(1.5),
+                    Value::Null,
+                    Value::Bool(true),
+                    Value::Text("x".into()),
+                ]),
+            ),
+            (Value::Text("b".into()), Value::Integer(u64::MAX.into())),
+        ]);
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let value = map(vec![
+            (Value::Integer(1), Value::Bytes(vec![0xff, 0xfe])),
+            (
+                Value::Array(vec![Value::Integer(2)]),
+                Value::Tag(1, Box::new(Value::Float(f64::NAN))),
+            ),
+            (Value::Text("big".into()), Value::Integer(-(1 << 70))),
+        ]);
+        let json = serde_json::Value::try_from(value.clone()).unwrap();
+        assert_eq!(
+            json,
+            json!({"1": "__4", "[2]": null, "big": "-1180591620717411303424"})
+        );
+
+        let options = JsonOptions::new()
+            .set_bytes(JsonBytes::Array)
+            .set_integers(JsonIntegers::LossyFloat);
+        let json = value.to_json_with(options).unwrap();
+        assert_eq!(
+            json,
+            json!({"1": [255, 254], "[2]": null, "big": -1180591620717411303424.0})
+        );
+
+        let err = value
+            .to_json_with(JsonOptions::new().set_keys(JsonKeys::Error))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "map key Integer(1) is not a string");
+
+        // keys that are not strings are written as JSON text
+        let key = Value::Array(vec![Value::Text("a\"b".into()), Value::Float(1.0)]);
+        let value = map(vec![(key, Value::Null)]);
+        let json = serde_json::Value::try_from(value).unwrap();
+        assert_eq!(json, json!({"[\"a\\\"b\",1.0]": null}));
+    }
+
+    #[test]
+    fn test_to_json_key_collision() {
+        let value = map(vec![
+            (Value::Integer(1), Value::Text("a".into())),
+            (Value::Text("1".into()), Value::Text("b".into())),
+        ]);
+        let err = serde_json::Value::try_from(value).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "map keys convert to the same JSON key \"1\""
+        );
+    }
 }
@@ -0,0 +1,81 @@
+use serde_cbor::value_slice::{from_mut_slice, from_slice, Node, ValueBuf};
+
+#[test]
+fn test_nested() {
+    // {"a": [1, -2, 1.5], "b": {_ "c": h'00ff'}, "d": null, "e": true}
+    let input =
+        b"\xa4\x61a\x83\x01\x21\xf9\x3e\x00\x61b\xbf\x61c\x42\x00\xff\xff\x61d\xf6\x61e\xf5";
+    let mut storage = [Node::Null; 16];
+    let value = from_slice(input, &mut storage).unwrap();
+    assert_eq!(value.nodes().len(), 14);
+    assert_eq!(value.len(), Some(4));
+
+    let a = value.get_key("a").unwrap();
+    let mut elements = a.elements().unwrap();
+    assert_eq!(elements.size_hint(), (3, Some(3)));
+    assert_eq!(elements.next().unwrap().as_integer(), Some(1));
+    assert_eq!(elements.next().unwrap().as_integer(), Some(-2));
+    assert_eq!(elements.next().unwrap().as_float(), Some(1.5));
+    assert!(elements.next().is_none());
+
+    let b = value.get_key("b").unwrap();
+    assert_eq!(b.get_key("c").unwrap().as_bytes(), Some(&[0x00, 0xff][..]));
+    assert!(value.get_key("d").unwrap().is_null());
+    assert_eq!(value.get_key("e").unwrap().as_bool(), Some(true));
+    assert!(value.get_key("f").is_none());
+
+    let keys = value.entries().unwrap().map(|(k, _)| k.as_text().unwrap());
+    assert!(keys.eq(["a", "b", "d", "e"].iter().cloned()));
+}
+
+#[test]
+fn test_storage_full() {
+    let input = b"\x83\x01\x02\x03";
+    let mut storage = [Node::Null; 3];
+    let err = from_slice(input, &mut storage).unwrap_err();
+    assert!(err.is_scratch_too_small());
+    assert_eq!(err.scratch_needed(), Some(4));
+    assert_eq!(err.scratch_available(), Some(3));
+
+    // [[1, 2], {"a": [3]}] has 8 nodes
+    let nested = b"\x82\x82\x01\x02\xa1\x61a\x81\x03";
+    let mut storage = [Node::Null; 2];
+    let err = from_slice(nested, &mut storage).unwrap_err();
+    assert_eq!(err.scratch_needed(), Some(8));
+    let mut storage = [Node::Null; 8];
+    assert_eq!(from_slice(nested, &mut storage).unwrap().nodes().len(), 8);
+
+    let buf: ValueBuf<'_, 4> = ValueBuf::from_slice(input).unwrap();
+    assert_eq!(buf.value().get(2).unwrap().node(), Node::Integer(3));
+    assert!(buf.value().get(3).is_none());
+}
+
+#[test]
+fn test_indefinite_strings() {
+    let mut input = *b"\x82\x7f\x61a\x61b\xff\x5f\x41\x01\xff";
+    let mut storage = [Node::Null; 4];
+    assert!(from_slice(&input, &mut [Node::Null; 4]).is_err());
+
+    let value = from_mut_slice(&mut input, &mut storage).unwrap();
+    assert_eq!(value.get(0).unwrap().as_text(), Some("ab"));
+    assert_eq!(value.get(1).unwrap().as_bytes(), Some(&[1][..]));
+}
+
+#[test]
+fn test_trailing_data() {
+    let mut storage = [Node::Null; 4];
+    assert!(from_slice(b"\x01\x02", &mut storage).is_err());
+}
+
+#[cfg(feature = "tags")]
+#[test]
+fn test_tags() {
+    // [1(2), 3]
+    let input = b"\x82\xc1\x02\x03";
+    let buf: ValueBuf<'_, 4> = ValueBuf::from_slice(input).unwrap();
+    let value = buf.value();
+    let (tag, inner) = value.get(0).unwrap().as_tagged().unwrap();
+    assert_eq!(tag, 1);
+    assert_eq!(inner.as_integer(), Some(2));
+    assert_eq!(value.get(1).unwrap().as_integer(), Some(3));
+}
@@ -0,0 +1,25 @@
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use js_sys::{Reflect, Uint8Array};
+use serde_cbor::wasm::{from_uint8_array, to_uint8_array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn test_uint8_array_roundtrip() {
+    let array = to_uint8_array(&(1u8, "a")).unwrap();
+    assert_eq!(array.to_vec(), b"\x82\x01\x61a");
+    let value: (u8, String) = from_uint8_array(&array).unwrap();
+    assert_eq!(value, (1, "a".to_owned()));
+}
+
+#[wasm_bindgen_test]
+fn test_js_error() {
+    // [1, "a" cut short
+    let array = Uint8Array::from(&b"\x82\x01\x62a"[..]);
+    let err = from_uint8_array::<(u8, String)>(&array).unwrap_err();
+    let js_error: js_sys::Error = err.dyn_into().unwrap();
+    assert_eq!(js_error.name(), "CborError");
+    let offset = Reflect::get(&js_error, &JsValue::from_str("offset")).unwrap();
+    assert_eq!(offset.as_f64(), Some(4.0));
+}
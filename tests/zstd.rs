@@ -0,0 +1,63 @@
+#![cfg(feature = "zstd")]
+
+use std::io;
+
+use serde::Serialize;
+use serde_cbor::zstd::{from_reader, to_vec, to_writer, ZstdWriter};
+use serde_cbor::{Serializer, Value};
+
+#[test]
+fn test_round_trip() {
+    let value = Value::Array(vec![Value::Text("repeat".into()); 1000]);
+    let compressed = to_vec(&value, 19).unwrap();
+    assert!(compressed.len() < 100);
+    assert_eq!(from_reader::<Value, _>(&compressed[..]).unwrap(), value);
+
+    let mut serializer = Serializer::new(ZstdWriter::new(Vec::new(), 0).unwrap());
+    value.serialize(&mut serializer).unwrap();
+    1u8.serialize(&mut serializer).unwrap();
+    let compressed = serializer.into_inner().finish().unwrap();
+    let err = from_reader::<Value, _>(&compressed[..]).unwrap_err();
+    assert!(err.is_syntax());
+}
+
+#[test]
+fn test_corrupt_stream() {
+    let mut compressed = to_vec(&"payload", 0).unwrap();
+    let len = compressed.len();
+    compressed.truncate(len - 2);
+    let err = from_reader::<String, _>(&compressed[..]).unwrap_err();
+    assert!(err.is_io() || err.is_eof());
+
+    let err = from_reader::<String, _>(&b"not zstd"[..]).unwrap_err();
+    assert!(err.is_io());
+}
+
+/// Accepts `limit` bytes and fails afterwards.
+struct Limited {
+    limit: usize,
+}
+
+impl io::Write for Limited {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() > self.limit {
+            return Err(io::Error::other("full"));
+        }
+        self.limit -= buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_write_errors() {
+    // the compressor buffers the small payload, the error only shows up when finishing
+    let err = to_writer(Limited { limit: 4 }, &"payload", 0)
+        .err()
+        .unwrap();
+    assert!(err.is_io());
+    assert!(to_writer(Limited { limit: 1024 }, &"payload", 0).is_ok());
+}